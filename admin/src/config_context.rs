@@ -1,9 +1,9 @@
 use std::{collections::HashSet, rc::Rc};
 
 use fuente::models::{
-    AdminConfigurationType, CommerceProfile, DriverProfile, DRIVER_HUB_PRIV_KEY,
-    DRIVER_HUB_PUB_KEY, NOSTR_KIND_COMMERCE_PROFILE, NOSTR_KIND_COURIER_PROFILE,
-    NOSTR_KIND_SERVER_CONFIG,
+    AdminConfigurationType, CommerceProfile, Currency, DriverProfile, ExchangeRates, Promotion,
+    DRIVER_HUB_PRIV_KEY, DRIVER_HUB_PUB_KEY, NOSTR_KIND_COMMERCE_PROFILE,
+    NOSTR_KIND_COURIER_PROFILE, NOSTR_KIND_SERVER_CONFIG,
 };
 use nostr_minions::{key_manager::NostrIdStore, relay_pool::NostrProps};
 use nostro2::{
@@ -22,6 +22,8 @@ pub struct ServerConfigs {
     consumer_blacklist: Vec<String>,
     user_registrations: Vec<String>,
     exchange_rate: f64,
+    exchange_rates: ExchangeRates,
+    promotions: Vec<Promotion>,
     loaded: bool,
 }
 
@@ -38,6 +40,16 @@ impl ServerConfigs {
     pub fn set_exchange_rate(&mut self, rate: f64) {
         self.exchange_rate = rate;
     }
+    pub fn get_exchange_rates(&self) -> ExchangeRates {
+        let mut rates = self.exchange_rates.clone();
+        if rates.rate(Currency::SRD).is_none() {
+            rates.set_rate(Currency::SRD, self.exchange_rate);
+        }
+        rates
+    }
+    pub fn get_promotions(&self) -> Vec<Promotion> {
+        self.promotions.clone()
+    }
     pub fn get_unregistered_commerces(&self) -> Vec<NostrNote> {
         let mut unregistered_users = vec![];
         for note in self.commerces.iter() {
@@ -100,6 +112,8 @@ impl ServerConfigs {
 pub enum ServerConfigsAction {
     FinishLoading,
     UpdateExchangeRate(f64),
+    UpdateExchangeRates(ExchangeRates),
+    UpdatePromotions(Vec<Promotion>),
     UpdateCommerceWhitelist(Vec<String>),
     UpdateCouriersWhitelist(Vec<String>),
     AddCommerce(NostrNote),
@@ -121,6 +135,16 @@ impl Reducible for ServerConfigs {
                 new_state.set_exchange_rate(rate);
                 Rc::new(new_state)
             }
+            ServerConfigsAction::UpdateExchangeRates(rates) => {
+                let mut new_state = (*self).clone();
+                new_state.exchange_rates = rates;
+                Rc::new(new_state)
+            }
+            ServerConfigsAction::UpdatePromotions(promotions) => {
+                let mut new_state = (*self).clone();
+                new_state.promotions = promotions;
+                Rc::new(new_state)
+            }
             ServerConfigsAction::UpdateCommerceWhitelist(whitelist) => {
                 let mut new_state = (*self).clone();
                 new_state.commerce_whitelist = whitelist;
@@ -165,6 +189,8 @@ pub fn key_handler(props: &ServerConfigsChildren) -> Html {
         consumer_blacklist: vec![],
         user_registrations: vec![],
         exchange_rate: 0.0,
+        exchange_rates: ExchangeRates::default(),
+        promotions: vec![],
         loaded: false,
     });
 
@@ -248,6 +274,20 @@ pub fn key_handler(props: &ServerConfigsChildren) -> Html {
                                 ctx_clone.dispatch(ServerConfigsAction::UpdateExchangeRate(rate));
                             }
                         }
+                        AdminConfigurationType::ExchangeRates => {
+                            if let Ok(rates) = serde_json::from_str::<ExchangeRates>(&note.content)
+                            {
+                                ctx_clone.dispatch(ServerConfigsAction::UpdateExchangeRates(rates));
+                            }
+                        }
+                        AdminConfigurationType::Promotions => {
+                            if let Ok(promotions) =
+                                serde_json::from_str::<Vec<Promotion>>(&note.content)
+                            {
+                                ctx_clone
+                                    .dispatch(ServerConfigsAction::UpdatePromotions(promotions));
+                            }
+                        }
                         AdminConfigurationType::CommerceWhitelist => {
                             if let Ok(whitelist) =
                                 serde_json::from_str::<Vec<String>>(&note.content)
@@ -1,6 +1,7 @@
 use fuente::{
     contexts::LanguageConfigsStore,
-    models::{AdminConfigurationType, AdminServerRequest},
+    mass::SimpleSelect,
+    models::{AdminConfigurationType, AdminServerRequest, Currency},
 };
 use nostr_minions::{browser_api::HtmlForm, key_manager::NostrIdStore, relay_pool::NostrProps};
 use yew::prelude::*;
@@ -35,13 +36,15 @@ pub fn exchange_rate_page() -> Html {
 #[function_component(ExchangeRateDisplay)]
 pub fn exchange_rate_display() -> Html {
     let server_ctx = use_context::<ServerConfigsStore>().expect("ServerConfigsStore not found");
-    let exchange_rate = server_ctx.get_exchange_rate();
+    let exchange_rates = server_ctx.get_exchange_rates();
     // let sat_rate = exchange_rate / 100_000_000.0;
     html! {
         <div class="flex flex-col gap-5 p-5 md:max-w-sm lg:max-w-xs mx-auto">
             <div class="space-y-2">
                 <p class="text-gray-500 text-lg font-bold">{"Dollar Exchange"}</p>
-                <p class="text-fuente font-bold text-2xl">{format!("1 USD = SRD {}", exchange_rate)}</p>
+                {exchange_rates.rates().into_iter().filter(|(currency, _)| *currency != Currency::USD).map(|(currency, rate)| html! {
+                    <p class="text-fuente font-bold text-2xl">{format!("1 USD = {} {}", currency, rate)}</p>
+                }).collect::<Html>()}
             </div>
             // <div class="space-y-2">
             //     <p class="text-gray-500 text-md"><span class="font-bold">{"Sat Price: "}</span>{ format!("1 SAT = SRD {}", sat_rate) }</p>
@@ -61,29 +64,58 @@ pub fn exchange_rate_form() -> Html {
     let user_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
     let keys = user_ctx.clone();
 
-    let onsubmit = Callback::from(move |e: SubmitEvent| {
-        e.prevent_default();
-        let keys = keys.clone();
-        let form_element = HtmlForm::new(e).expect("Failed to get form element");
-        let exchange_rate = form_element
-            .input_value("exchange_rate")
-            .expect("Failed to get exchange rate");
-        let admin_request =
-            AdminServerRequest::new(AdminConfigurationType::ExchangeRate, exchange_rate);
-        let sender = sender.clone();
-        yew::platform::spawn_local(async move {
-            let signed_request = admin_request
-                .sign_data(&keys.get_identity().expect("No identity found"))
-                .await
-                .expect("Failed to sign request");
-            sender.emit(signed_request);
-        });
-    });
+    let server_ctx = use_context::<ServerConfigsStore>().expect("ServerConfigsStore not found");
+    let exchange_rates = server_ctx.get_exchange_rates();
+    let rate_error = use_state(|| None::<String>);
+
+    let invalid_rate = translations["admin_settings_invalid_rate"].clone();
+    let onsubmit = {
+        let rate_error = rate_error.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let keys = keys.clone();
+            let form_element = HtmlForm::new(e).expect("Failed to get form element");
+            let exchange_rate = form_element
+                .input_value("exchange_rate")
+                .ok()
+                .and_then(|value| value.trim().parse::<f64>().ok())
+                .filter(|rate| rate.is_finite() && *rate > 0.0);
+            let Some(exchange_rate) = exchange_rate else {
+                rate_error.set(Some(invalid_rate.clone()));
+                return;
+            };
+            rate_error.set(None);
+            let currency = form_element
+                .select_value("currency")
+                .ok()
+                .and_then(|code| Currency::try_from(code.as_str()).ok())
+                .unwrap_or_default();
+            let mut exchange_rates = exchange_rates.clone();
+            exchange_rates.set_rate(currency, exchange_rate);
+            let admin_request = AdminServerRequest::new(
+                AdminConfigurationType::ExchangeRates,
+                serde_json::to_string(&exchange_rates).expect("Failed to serialize rates"),
+            );
+            let sender = sender.clone();
+            yew::platform::spawn_local(async move {
+                let signed_request = admin_request
+                    .sign_data(&keys.get_identity().expect("No identity found"))
+                    .await
+                    .expect("Failed to sign request");
+                sender.emit(signed_request);
+            });
+        })
+    };
 
     html! {
         <form {onsubmit}
             class="rounded-2xl bg-white p-5 md:max-w-sm lg:max-w-xs mx-auto">
             <div class="space-y-2">
+                <SimpleSelect id="currency" name="currency" label="Currency">
+                    {Currency::ALL.iter().filter(|currency| **currency != Currency::USD).map(|currency| html! {
+                        <option value={currency.code()}>{currency.code()}</option>
+                    }).collect::<Html>()}
+                </SimpleSelect>
                 <label for="exchange_rate" class="text-gray-500 font-light text-sm">{"Change the Exchange"}</label>
                 <input
                     type="number"
@@ -91,6 +123,9 @@ pub fn exchange_rate_form() -> Html {
                     placeholder={translations["admin_settings_btc_srd"].clone()}
                     class="w-full rounded-lg border-2 border-fuente p-2"
                     step="0.01" value="" required={true} />
+                if let Some(error) = (*rate_error).clone() {
+                    <p class="text-red-500 text-sm">{error}</p>
+                }
                 <div class="flex justify-center">
                     <input
                         type="submit"
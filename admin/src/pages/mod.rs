@@ -3,10 +3,12 @@ mod couriers;
 mod commerces;
 mod home;
 mod finance;
+mod promotions;
 mod settings;
 pub use home::*;
 pub use couriers::*;
 // pub use consumers::*;
 pub use commerces::*;
 pub use finance::*;
+pub use promotions::*;
 pub use settings::*;
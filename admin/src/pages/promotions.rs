@@ -0,0 +1,96 @@
+use fuente::{
+    contexts::LanguageConfigsStore,
+    mass::{PopupSection, PromotionCard, PromotionForm},
+    models::{AdminConfigurationType, AdminServerRequest, Promotion},
+};
+use lucide_yew::Plus;
+use nostr_minions::{key_manager::NostrIdStore, relay_pool::NostrProps};
+use yew::prelude::*;
+
+use crate::{ServerConfigsAction, ServerConfigsStore};
+
+#[function_component(PromotionsPage)]
+pub fn promotions_page() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let server_ctx = use_context::<ServerConfigsStore>().expect("ServerConfigsStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
+    let user_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let new_promotion = use_state(|| false);
+
+    let publish = {
+        let server_ctx = server_ctx.clone();
+        let sender = relay_ctx.send_note.clone();
+        let keys = user_ctx.clone();
+        Callback::from(move |promotions: Vec<Promotion>| {
+            let admin_request = AdminServerRequest::new(
+                AdminConfigurationType::Promotions,
+                serde_json::to_string(&promotions).expect("Failed to serialize promotions"),
+            );
+            let sender = sender.clone();
+            let keys = keys.clone();
+            yew::platform::spawn_local(async move {
+                let signed_request = admin_request
+                    .sign_data(&keys.get_identity().expect("No identity found"))
+                    .await
+                    .expect("Failed to sign request");
+                sender.emit(signed_request);
+            });
+            server_ctx.dispatch(ServerConfigsAction::UpdatePromotions(promotions));
+        })
+    };
+    let on_save = {
+        let publish = publish.clone();
+        let server_ctx = server_ctx.clone();
+        let new_promotion = new_promotion.clone();
+        Callback::from(move |promotion: Promotion| {
+            let mut promotions = server_ctx.get_promotions();
+            promotions.push(promotion);
+            publish.emit(promotions);
+            new_promotion.set(false);
+        })
+    };
+    let on_delete = {
+        let server_ctx = server_ctx.clone();
+        Callback::from(move |promotion_id: String| {
+            let mut promotions = server_ctx.get_promotions();
+            promotions.retain(|promotion| promotion.id != promotion_id);
+            publish.emit(promotions);
+        })
+    };
+    let promotions = server_ctx.get_promotions();
+    html! {
+        <main class="container mx-auto overflow-hidden">
+            <div class="flex flex-col h-full">
+                <div class="flex flex-row justify-between items-center p-4 lg:py-10">
+                    <h1 class="text-fuente text-4xl text-center lg:text-left py-4 lg:py-0 lg:text-6xl tracking-tighter font-bold font-mplus">
+                        {&translations["promotions_heading"]}
+                    </h1>
+                    <button
+                        onclick={{
+                            let new_promotion = new_promotion.clone();
+                            Callback::from(move |_| new_promotion.set(true))
+                        }}
+                        class="flex items-center gap-2 bg-fuente text-white font-bold py-3 px-5 rounded-full">
+                        <Plus class="w-5 h-5" />
+                        {&translations["promotions_new"]}
+                    </button>
+                </div>
+                <div class="flex flex-col gap-4 overflow-y-auto p-4 w-full lg:max-w-5xl mx-auto">
+                    {if promotions.is_empty() {
+                        html! {
+                            <p class="text-gray-500">{&translations["promotions_empty"]}</p>
+                        }
+                    } else {
+                        promotions.iter().map(|promotion| html! {
+                            <PromotionCard promotion={promotion.clone()} on_delete={on_delete.clone()} />
+                        }).collect::<Html>()
+                    }}
+                </div>
+            </div>
+            <PopupSection close_handle={new_promotion.clone()}>
+                <PromotionForm {on_save} />
+            </PopupSection>
+        </main>
+    }
+}
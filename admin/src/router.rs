@@ -1,10 +1,11 @@
 use fuente::mass::{templates::FuenteSidebarTemplate, AppLink};
-use lucide_yew::{Bitcoin, Cog, House, Store, Truck};
+use lucide_yew::{Bitcoin, Cog, House, Percent, Store, Truck};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
 use crate::pages::{
-    CommercesPage, CourierWhitelistPage, ExchangeRatePage, HomePage, SettingsPageComponent,
+    CommercesPage, CourierWhitelistPage, ExchangeRatePage, HomePage, PromotionsPage,
+    SettingsPageComponent,
 };
 
 #[derive(Clone, Routable, PartialEq)]
@@ -17,6 +18,8 @@ pub enum AdminPanelRoute {
     Commerces,
     #[at("/couriers")]
     Couriers,
+    #[at("/promotions")]
+    Promotions,
     #[at("/consumers")]
     Consumers,
     #[at("/settings")]
@@ -45,6 +48,9 @@ pub fn consumer_pages() -> Html {
                         AdminPanelRoute::Couriers => html! {
                             <CourierWhitelistPage />
                         },
+                        AdminPanelRoute::Promotions => html! {
+                            <PromotionsPage />
+                        },
                         AdminPanelRoute::Consumers => html!{
                             <></>
                         },
@@ -78,6 +84,11 @@ pub fn home_footer() -> Html {
                 route={AdminPanelRoute::Couriers}>
                 <Truck class="w-8 h-8 text-fuente" />
             </AppLink<AdminPanelRoute>>
+            <AppLink<AdminPanelRoute>
+                class="" selected_class=""
+                route={AdminPanelRoute::Promotions}>
+                <Percent class="w-8 h-8 text-fuente" />
+            </AppLink<AdminPanelRoute>>
             <AppLink<AdminPanelRoute>
                 class="" selected_class=""
                 route={AdminPanelRoute::Settings}>
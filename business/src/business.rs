@@ -4,7 +4,9 @@ use business::{
     router::CommercePages,
 };
 use fuente::{
-    contexts::{AdminConfigsProvider, AdminConfigsStore, LanguageConfigsProvider},
+    contexts::{
+        AdminConfigsProvider, AdminConfigsStore, LanguageConfigsProvider, OrderChatProvider,
+    },
     mass::{LoadingScreen, LoginPage, ToastProvider,  PwaInstall},
     models::{init_commerce_db, init_consumer_db},
 };
@@ -81,7 +83,9 @@ fn app_context(props: &ChildrenProps) -> Html {
                 <CommerceDataProvider>
                     <ConsumerDataProvider >
                         <OrderDataProvider>
-                            {props.children.clone()}
+                            <OrderChatProvider>
+                                {props.children.clone()}
+                            </OrderChatProvider>
                         </OrderDataProvider>
                     </ConsumerDataProvider>
                 </CommerceDataProvider>
@@ -1,11 +1,18 @@
 use fuente::models::{
-    CommerceProfile, CommerceProfileIdb, ProductMenu, ProductMenuIdb, NOSTR_KIND_COMMERCE_PRODUCTS,
-    NOSTR_KIND_COMMERCE_PROFILE, NOSTR_KIND_PRESIGNED_URL_RESP, TEST_PUB_KEY,
+    has_geohash_tags, CommerceProfile, CommerceProfileIdb, MenuDraftIdb, MenuVersionIdb,
+    ProductMenu, ProductMenuIdb, PromotionList, MENU_HISTORY_LIMIT,
+    NOSTR_KIND_COMMERCE_MENU_CATEGORY, NOSTR_KIND_COMMERCE_PRODUCTS, NOSTR_KIND_COMMERCE_PROFILE,
+    NOSTR_KIND_COMMERCE_PROMOTIONS, NOSTR_KIND_PRESIGNED_URL_RESP, TEST_PUB_KEY,
 };
 use nostr_minions::{
-    browser_api::IdbStoreManager, key_manager::NostrIdStore, relay_pool::NostrProps,
+    browser_api::IdbStoreManager,
+    key_manager::{NostrIdStore, UserIdentity},
+    relay_pool::NostrProps,
+};
+use nostro2::{
+    notes::NostrNote,
+    relays::{NostrSubscription, RelayEvent},
 };
-use nostro2::relays::{NostrSubscription, RelayEvent};
 use std::rc::Rc;
 use web_sys::wasm_bindgen::JsValue;
 use yew::{platform::spawn_local, prelude::*};
@@ -16,6 +23,9 @@ pub struct CommerceData {
     checked_relay: bool,
     profile: Option<CommerceProfileIdb>,
     menu: Option<ProductMenuIdb>,
+    draft: Option<MenuDraftIdb>,
+    history: Vec<MenuVersionIdb>,
+    promotions: PromotionList,
 }
 
 impl CommerceData {
@@ -39,6 +49,78 @@ impl CommerceData {
             None
         }
     }
+    /// The menu being edited, the published one until a change is saved to the draft.
+    pub fn draft(&self) -> Option<ProductMenu> {
+        match &self.draft {
+            Some(draft) => Some(draft.menu()),
+            None => self.menu(),
+        }
+    }
+    /// Prices are kept in the pricing currency, so it can't change while the
+    /// menu, its draft or the order minimum still hold any.
+    pub fn has_prices(&self) -> bool {
+        let menu_has_products = |menu: Option<ProductMenu>| {
+            menu.is_some_and(|menu| {
+                menu.categories()
+                    .iter()
+                    .any(|category| !category.products().is_empty())
+            })
+        };
+        menu_has_products(self.menu())
+            || menu_has_products(self.draft())
+            || self
+                .profile()
+                .is_some_and(|profile| profile.order_minimum.minimum.is_some())
+    }
+    pub fn has_unpublished_changes(&self) -> bool {
+        self.draft.is_some() && self.draft() != self.menu()
+    }
+    /// Previously published menus, newest first.
+    pub fn history(&self) -> Vec<MenuVersionIdb> {
+        self.history.clone()
+    }
+    /// Whether the published profile or menu lacks the geohash tags consumers
+    /// look commerces up by, while the profile has a location to tag.
+    pub fn missing_geohash_tags(&self) -> (bool, bool) {
+        let Some(profile) = &self.profile else {
+            return (false, false);
+        };
+        if profile.profile().geolocation.geohash(1).is_none() {
+            return (false, false);
+        }
+        let menu_untagged = self
+            .menu
+            .as_ref()
+            .is_some_and(|menu| !has_geohash_tags(&menu.note()));
+        (!has_geohash_tags(profile.signed_note()), menu_untagged)
+    }
+    pub fn promotions(&self) -> PromotionList {
+        self.promotions.clone()
+    }
+    /// Signs the edited menu and returns the events to publish,
+    /// categories left untouched keep their published event.
+    pub async fn sign_menu(
+        &self,
+        menu: ProductMenu,
+        keys: &UserIdentity,
+    ) -> (ProductMenuIdb, Vec<NostrNote>) {
+        let location = self
+            .profile()
+            .map(|profile| profile.geolocation)
+            .unwrap_or_default();
+        match &self.menu {
+            Some(previous) => {
+                let entry = previous.update(menu, &location, keys).await;
+                let notes = entry.changed_notes(Some(previous));
+                (entry, notes)
+            }
+            None => {
+                let entry = ProductMenuIdb::new(menu, &location, keys).await;
+                let notes = entry.notes();
+                (entry, notes)
+            }
+        }
+    }
 }
 
 pub enum CommerceDataAction {
@@ -48,6 +130,13 @@ pub enum CommerceDataAction {
     LoadProductData(ProductMenuIdb),
     UpdateCommerceProfile(CommerceProfileIdb),
     UpdateProductList(ProductMenuIdb),
+    LoadMenuDraft(MenuDraftIdb),
+    LoadMenuHistory(Vec<MenuVersionIdb>),
+    UpdateMenuDraft(ProductMenu),
+    DiscardMenuDraft,
+    MenuPublished(ProductMenuIdb),
+    ApplyMenuNote(NostrNote),
+    UpdatePromotions(PromotionList),
 }
 
 impl Reducible for CommerceData {
@@ -65,6 +154,9 @@ impl Reducible for CommerceData {
                     checked_relay: self.checked_relay,
                     profile: Some(profile),
                     menu: self.menu.clone(),
+                    draft: self.draft.clone(),
+                    history: self.history.clone(),
+                    promotions: self.promotions.clone(),
                 })
             }
             CommerceDataAction::UpdateProductList(list) => {
@@ -77,6 +169,98 @@ impl Reducible for CommerceData {
                     checked_relay: self.checked_relay,
                     profile: self.profile.clone(),
                     menu: Some(list),
+                    draft: self.draft.clone(),
+                    history: self.history.clone(),
+                    promotions: self.promotions.clone(),
+                })
+            }
+            CommerceDataAction::LoadMenuDraft(draft) => Rc::new(CommerceData {
+                draft: Some(draft),
+                ..(*self).clone()
+            }),
+            CommerceDataAction::LoadMenuHistory(mut history) => {
+                history.sort_by(|a, b| b.published_at().cmp(&a.published_at()));
+                Rc::new(CommerceData {
+                    history,
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::UpdateMenuDraft(menu) => {
+                // Drafts belong to the commerce profile, there is none to edit before it exists
+                let Some(profile) = &self.profile else {
+                    return self;
+                };
+                let draft = MenuDraftIdb::new(profile.id(), menu);
+                let db_entry = draft.clone();
+                spawn_local(async move {
+                    db_entry.save_to_store().await.expect("Failed to save");
+                });
+                Rc::new(CommerceData {
+                    draft: Some(draft),
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::DiscardMenuDraft => {
+                if let Some(draft) = self.draft.clone() {
+                    spawn_local(async move {
+                        let _ = draft.delete_from_store().await;
+                    });
+                }
+                Rc::new(CommerceData {
+                    draft: None,
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::MenuPublished(list) => {
+                let mut added = vec![MenuVersionIdb::from(&list)];
+                // The menu live before the first publish from here is worth going back to
+                if let (true, Some(previous)) = (self.history.is_empty(), &self.menu) {
+                    added.push(MenuVersionIdb::from(previous));
+                }
+                let mut history = added.clone();
+                history.extend(self.history.iter().cloned());
+                let dropped = history.split_off(MENU_HISTORY_LIMIT.min(history.len()));
+                let db_entry = list.clone();
+                let draft = self.draft.clone();
+                spawn_local(async move {
+                    db_entry.save_to_store().await.expect("Failed to save");
+                    for version in added {
+                        version.save_to_store().await.expect("Failed to save");
+                    }
+                    for version in dropped {
+                        let _ = version.delete_from_store().await;
+                    }
+                    if let Some(draft) = draft {
+                        let _ = draft.delete_from_store().await;
+                    }
+                });
+                Rc::new(CommerceData {
+                    menu: Some(list),
+                    draft: None,
+                    history,
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::ApplyMenuNote(note) => {
+                let menu = match self.menu.clone() {
+                    Some(mut menu) => menu.apply_note(note).map(|_| menu),
+                    None => ProductMenuIdb::try_from(note),
+                };
+                let Ok(menu) = menu else {
+                    return self;
+                };
+                let db_entry = menu.clone();
+                spawn_local(async move {
+                    db_entry.save_to_store().await.expect("Failed to save");
+                });
+                Rc::new(CommerceData {
+                    checked_db: self.checked_db,
+                    checked_relay: self.checked_relay,
+                    profile: self.profile.clone(),
+                    menu: Some(menu),
+                    draft: self.draft.clone(),
+                    history: self.history.clone(),
+                    promotions: self.promotions.clone(),
                 })
             }
             CommerceDataAction::LoadCommerceData(db_entries) => Rc::new(CommerceData {
@@ -84,24 +268,45 @@ impl Reducible for CommerceData {
                 checked_relay: self.checked_relay,
                 profile: Some(db_entries),
                 menu: self.menu.clone(),
+                draft: self.draft.clone(),
+                history: self.history.clone(),
+                promotions: self.promotions.clone(),
             }),
             CommerceDataAction::LoadProductData(db_entries) => Rc::new(CommerceData {
                 checked_db: self.checked_db,
                 checked_relay: self.checked_relay,
                 profile: self.profile.clone(),
                 menu: Some(db_entries),
+                draft: self.draft.clone(),
+                history: self.history.clone(),
+                promotions: self.promotions.clone(),
+            }),
+            CommerceDataAction::UpdatePromotions(promotions) => Rc::new(CommerceData {
+                checked_db: self.checked_db,
+                checked_relay: self.checked_relay,
+                profile: self.profile.clone(),
+                menu: self.menu.clone(),
+                draft: self.draft.clone(),
+                history: self.history.clone(),
+                promotions,
             }),
             CommerceDataAction::CheckedDb => Rc::new(CommerceData {
                 checked_db: true,
                 checked_relay: self.checked_relay,
                 profile: self.profile.clone(),
                 menu: self.menu.clone(),
+                draft: self.draft.clone(),
+                history: self.history.clone(),
+                promotions: self.promotions.clone(),
             }),
             CommerceDataAction::CheckedRelay => Rc::new(CommerceData {
                 checked_db: self.checked_db,
                 checked_relay: true,
                 profile: self.profile.clone(),
                 menu: self.menu.clone(),
+                draft: self.draft.clone(),
+                history: self.history.clone(),
+                promotions: self.promotions.clone(),
             }),
         }
     }
@@ -121,6 +326,9 @@ pub fn key_handler(props: &CommerceDataChildren) -> Html {
         checked_db: false,
         profile: None,
         menu: None,
+        draft: None,
+        history: vec![],
+        promotions: PromotionList::default(),
     });
 
     let ctx_clone = ctx.clone();
@@ -138,6 +346,18 @@ pub fn key_handler(props: &CommerceDataChildren) -> Html {
                 {
                     ctx_clone.dispatch(CommerceDataAction::LoadProductData(products));
                 }
+                if let Ok(draft) =
+                    MenuDraftIdb::retrieve_from_store(&JsValue::from_str(&pubkey)).await
+                {
+                    ctx_clone.dispatch(CommerceDataAction::LoadMenuDraft(draft));
+                }
+                if let Ok(history) = MenuVersionIdb::retrieve_all_from_store().await {
+                    let history = history
+                        .into_iter()
+                        .filter(|version| version.pubkey() == pubkey)
+                        .collect();
+                    ctx_clone.dispatch(CommerceDataAction::LoadMenuHistory(history));
+                }
                 ctx_clone.dispatch(CommerceDataAction::CheckedDb);
             });
         }
@@ -162,6 +382,7 @@ pub fn commerce_data_sync() -> Html {
     let subscriber = relay_ctx.subscribe;
     let unique_notes = relay_ctx.unique_notes.clone();
     let relay_events = relay_ctx.relay_events.clone();
+    let keys = key_ctx.get_identity().cloned();
 
     let id_handle = sub_id.clone();
     use_effect_with(key_ctx, move |key_ctx| {
@@ -170,6 +391,8 @@ pub fn commerce_data_sync() -> Html {
                 kinds: Some(vec![
                     NOSTR_KIND_COMMERCE_PROFILE,
                     NOSTR_KIND_COMMERCE_PRODUCTS,
+                    NOSTR_KIND_COMMERCE_MENU_CATEGORY,
+                    NOSTR_KIND_COMMERCE_PROMOTIONS,
                 ]),
                 authors: Some(vec![pubkey]),
                 ..Default::default()
@@ -207,9 +430,12 @@ pub fn commerce_data_sync() -> Html {
                         ctx_clone.dispatch(CommerceDataAction::UpdateCommerceProfile(profile));
                     }
                 }
-                NOSTR_KIND_COMMERCE_PRODUCTS => {
-                    if let Ok(products) = note.clone().try_into() {
-                        ctx_clone.dispatch(CommerceDataAction::UpdateProductList(products));
+                NOSTR_KIND_COMMERCE_PRODUCTS | NOSTR_KIND_COMMERCE_MENU_CATEGORY => {
+                    ctx_clone.dispatch(CommerceDataAction::ApplyMenuNote(note.clone()));
+                }
+                NOSTR_KIND_COMMERCE_PROMOTIONS => {
+                    if let Ok(promotions) = PromotionList::try_from(note) {
+                        ctx_clone.dispatch(CommerceDataAction::UpdatePromotions(promotions));
                     }
                 }
                 _ => {}
@@ -217,6 +443,47 @@ pub fn commerce_data_sync() -> Html {
         }
         || {}
     });
+    // Profiles and menus published before geohash tags are not found by
+    // consumers subscribing to their area
+    let sender = relay_ctx.send_note.clone();
+    let untagged_keys = keys.clone();
+    use_effect_with(ctx.clone(), move |ctx| {
+        let (profile_untagged, menu_untagged) = ctx.missing_geohash_tags();
+        let untagged = profile_untagged || menu_untagged;
+        if let (true, true, Some(keys)) = (ctx.checked_relay(), untagged, untagged_keys) {
+            let ctx = ctx.clone();
+            spawn_local(async move {
+                if let (true, Some(profile)) = (profile_untagged, ctx.profile()) {
+                    if let Ok(db_entry) = CommerceProfileIdb::new(profile, &keys).await {
+                        sender.emit(db_entry.signed_note().clone());
+                        ctx.dispatch(CommerceDataAction::UpdateCommerceProfile(db_entry));
+                    }
+                }
+                if let (true, Some(menu)) = (menu_untagged, ctx.menu()) {
+                    let (db_entry, notes) = ctx.sign_menu(menu, &keys).await;
+                    notes.into_iter().for_each(|note| sender.emit(note));
+                    ctx.dispatch(CommerceDataAction::UpdateProductList(db_entry));
+                }
+            });
+        }
+        || {}
+    });
+
+    // Menus published before product ids were random can hold the same id twice
+    let sender = relay_ctx.send_note.clone();
+    use_effect_with(ctx.clone(), move |ctx| {
+        if let (true, Some(mut menu), Some(keys)) = (ctx.checked_relay(), ctx.menu(), keys) {
+            if menu.migrate_ids() {
+                let ctx = ctx.clone();
+                spawn_local(async move {
+                    let (db_entry, notes) = ctx.sign_menu(menu, &keys).await;
+                    notes.into_iter().for_each(|note| sender.emit(note));
+                    ctx.dispatch(CommerceDataAction::UpdateProductList(db_entry));
+                });
+            }
+        }
+        || {}
+    });
 
     html! {}
 }
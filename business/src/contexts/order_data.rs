@@ -1,6 +1,6 @@
 use fuente::models::{
-    OrderInvoiceState, OrderPaymentStatus, OrderStateIdb, OrderStatus, NOSTR_KIND_ORDER_STATE,
-    TEST_PUB_KEY,
+    OrderInvoiceState, OrderPaymentStatus, OrderStateIdb, OrderStatus, PrepTime,
+    NOSTR_KIND_ORDER_STATE, TEST_PUB_KEY,
 };
 use nostr_minions::{
     browser_api::IdbStoreManager, key_manager::NostrIdStore, relay_pool::NostrProps,
@@ -50,6 +50,11 @@ impl OrderData {
     pub fn order_history(&self) -> Vec<OrderInvoiceState> {
         self.order_history.clone()
     }
+    /// Prep time to offer for the next order, given how busy the kitchen is.
+    pub fn suggested_prep_minutes(&self, lead_minutes: u32) -> u32 {
+        let preparing = self.filter_by_order_status(OrderStatus::Preparing).len();
+        PrepTime::suggested_minutes(lead_minutes, preparing)
+    }
 }
 
 pub enum OrderDataAction {
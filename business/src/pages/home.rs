@@ -53,7 +53,7 @@ pub fn home_page() -> Html {
     }
 }
 
-fn respond_to_order(
+pub(crate) fn respond_to_order(
     nostr_keys: UserIdentity,
     send_note: Callback<NostrNote>,
     order: NostrNote,
@@ -80,10 +80,13 @@ fn respond_to_order(
             }
         }
 
-        let new_request = OrderUpdateRequest {
-            order: order.clone(),
-            status_update,
-        };
+        let mut new_request = OrderUpdateRequest::new(order.clone(), status_update);
+        if status_update == OrderStatus::Preparing {
+            new_request.prep_minutes = form_data
+                .get("prep_minutes")
+                .as_string()
+                .and_then(|minutes| minutes.parse().ok());
+        }
         let send_note = send_note.clone();
         let nostr_keys = nostr_keys.clone();
         yew::platform::spawn_local(async move {
@@ -188,6 +191,10 @@ pub fn order_dashboard(props: &MobileDashboardOptionsProps) -> Html {
 #[function_component(OrderDashboardMobile)]
 pub fn order_dashboard() -> Html {
     let commerce_ctx = use_context::<OrderDataStore>().expect("No commerce ctx");
+    let commerce_data = use_context::<CommerceDataStore>().expect("No commerce data ctx");
+    let suggested_prep_minutes = commerce_data
+        .profile()
+        .map(|profile| commerce_ctx.suggested_prep_minutes(profile.availability.lead_minutes));
     let send_note = use_context::<NostrProps>().expect("Nostr context not found");
     let update_kind = NOSTR_KIND_COMMERCE_UPDATE;
     let key_ctx = use_context::<NostrIdStore>().expect("Nostr context not found");
@@ -206,7 +213,7 @@ pub fn order_dashboard() -> Html {
                             respond_to_order(nostr_keys.clone(), send_note.send_note.clone(), order.1.clone(), update_kind)
                         };
                         html! {
-                            <OrderCard order={order.0.clone()} on_click={on_click} order_note={order.1.clone()} />
+                            <OrderCard order={order.0.clone()} on_click={on_click} order_note={order.1.clone()} {suggested_prep_minutes} />
                         }
                     }).collect::<Html>()}
                 </OrderList>
@@ -217,6 +224,10 @@ pub fn order_dashboard() -> Html {
 #[function_component(OrderDashboardDesktop)]
 pub fn order_dashboard() -> Html {
     let commerce_ctx = use_context::<OrderDataStore>().expect("No commerce ctx");
+    let commerce_data = use_context::<CommerceDataStore>().expect("No commerce data ctx");
+    let suggested_prep_minutes = commerce_data
+        .profile()
+        .map(|profile| commerce_ctx.suggested_prep_minutes(profile.availability.lead_minutes));
     let send_note = use_context::<NostrProps>().expect("Nostr context not found");
     let key_ctx = use_context::<NostrIdStore>().expect("Nostr context not found");
     let update_kind = NOSTR_KIND_COMMERCE_UPDATE;
@@ -233,7 +244,7 @@ pub fn order_dashboard() -> Html {
                             respond_to_order(nostr_keys.clone(), send_note.send_note.clone(), order.1.clone(), update_kind)
                         };
                         html! {
-                            <OrderCard order={order.0.clone()} on_click={on_click} order_note={order.1.clone()} />
+                            <OrderCard order={order.0.clone()} on_click={on_click} order_note={order.1.clone()} {suggested_prep_minutes} />
                         }
                     }).collect::<Html>()}
                 </OrderList>
@@ -3,12 +3,15 @@ mod home;
 mod new_user;
 mod orders;
 mod products;
+mod promotions;
 mod settings;
 pub use history::*;
 pub use home::*;
 use lucide_yew::ArrowLeft;
 pub use new_user::*;
+pub use orders::*;
 pub use products::*;
+pub use promotions::*;
 pub use settings::*;
 
 use fuente::mass::AppLink;
@@ -1 +1,64 @@
+use super::home::respond_to_order;
+use crate::contexts::OrderDataStore;
+use fuente::{
+    contexts::LanguageConfigsStore,
+    mass::{OrderCard, OrderList},
+    models::{OrderStatus, NOSTR_KIND_COMMERCE_UPDATE},
+};
+use nostr_minions::{key_manager::NostrIdStore, relay_pool::NostrProps};
+use yew::prelude::*;
 
+#[function_component(OrdersPage)]
+pub fn orders_page() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx");
+    let translations = language_ctx.translations();
+    let order_ctx = use_context::<OrderDataStore>().expect("No order ctx");
+    let send_note = use_context::<NostrProps>().expect("Nostr context not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("Nostr context not found");
+    let nostr_keys = key_ctx
+        .get_identity()
+        .cloned()
+        .expect("Nostr key not found");
+    let mut scheduled = order_ctx.filter_by_order_status(OrderStatus::Scheduled);
+    scheduled.sort_by_key(|(order, _)| {
+        order
+            .get_order_request()
+            .scheduled_for
+            .map(|slot| slot.start)
+            .unwrap_or_default()
+    });
+    html! {
+        <main class="flex-1 overflow-hidden">
+            <div class="flex flex-col h-full container mx-auto">
+                <h1 class="text-fuente font-mplus text-4xl text-center lg:text-left py-4 lg:py-10 lg:text-6xl tracking-tighter font-bold">
+                    {&translations["orders_scheduled_heading"]}
+                </h1>
+                <div class="flex flex-1 overflow-hidden p-4">
+                    <OrderList title={OrderStatus::Scheduled}>
+                        {scheduled.iter().map(|order| {
+                            let on_click = respond_to_order(
+                                nostr_keys.clone(),
+                                send_note.send_note.clone(),
+                                order.1.clone(),
+                                NOSTR_KIND_COMMERCE_UPDATE,
+                            );
+                            let slot = order.0.get_order_request().scheduled_for;
+                            html! {
+                                <div class="flex flex-col gap-1 mb-2">
+                                    {if let Some(slot) = slot {
+                                        html! {
+                                            <p class="text-sm font-bold text-purple-500 px-2">{slot.locale_display()}</p>
+                                        }
+                                    } else {
+                                        html! {}
+                                    }}
+                                    <OrderCard order={order.0.clone()} on_click={on_click} order_note={order.1.clone()} />
+                                </div>
+                            }
+                        }).collect::<Html>()}
+                    </OrderList>
+                </div>
+            </div>
+        </main>
+    }
+}
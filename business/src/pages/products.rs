@@ -3,15 +3,20 @@ use fuente::{
     contexts::LanguageConfigsStore,
     mass::{
         templates::SettingsPageTemplate, CardComponent, DrawerSection, LoadingScreen, MoneyInput,
-        PopupSection, SimpleInput, SimpleTextArea, Toast, ToastAction, ToastContext, ToastType,
+        PopupSection, ProductTagPicker, SimpleInput, SimpleTextArea, Toast, ToastAction,
+        ToastContext, ToastType,
+    },
+    models::{
+        parse_discount, parse_menu_csv, parse_menu_json, parse_price, CommerceProfileIdb,
+        MenuChange, MenuImport, ModifierGroup, Money, OpeningHours, ProductCategory, ProductItem,
+        ProductMenu, ProductSide, ProductTag, Weekday,
     },
-    models::{CommerceProfileIdb, ProductCategory, ProductItem, ProductMenu, ProductMenuIdb},
 };
 
 use fuente::mass::ImageUploadInput;
-use lucide_yew::{Library, Shirt, Trash};
+use lucide_yew::{Library, ListPlus, Shirt, Trash};
 use nostr_minions::{browser_api::HtmlForm, key_manager::NostrIdStore, relay_pool::NostrProps};
-use web_sys::HtmlInputElement;
+use web_sys::{wasm_bindgen::JsCast, HtmlInputElement};
 use yew::prelude::*;
 
 #[derive(Clone, PartialEq)]
@@ -19,6 +24,9 @@ pub enum ProductPageSection {
     Products,
     OnSale,
     Banner,
+    Spreadsheet,
+    Schedule,
+    Publish,
 }
 
 #[function_component(ProductsPage)]
@@ -46,6 +54,21 @@ pub fn home_page() -> Html {
         let page = current_page.clone();
         Callback::from(move |_| page.set(ProductPageSection::Banner))
     };
+    let go_to_spreadsheet = {
+        let page = current_page.clone();
+        Callback::from(move |_| page.set(ProductPageSection::Spreadsheet))
+    };
+    let go_to_schedule = {
+        let page = current_page.clone();
+        Callback::from(move |_| page.set(ProductPageSection::Schedule))
+    };
+    let go_to_publish = {
+        let page = current_page.clone();
+        Callback::from(move |_| page.set(ProductPageSection::Publish))
+    };
+    let unpublished_changes = commerce_ctx
+        .as_ref()
+        .is_some_and(|ctx| ctx.has_unpublished_changes());
     let onclick_new_product = {
         let modal = add_product_modal.clone();
         Callback::from(move |_| {
@@ -100,18 +123,31 @@ pub fn home_page() -> Html {
             modal.set(true);
         })
     };
+    let mut options = vec![new_product_category, new_product_button];
+    if unpublished_changes {
+        options.insert(
+            0,
+            html! {
+                <button
+                    type="button" onclick={go_to_publish.clone()}
+                    class="bg-fuente-orange text-white px-6 py-3 rounded-full font-bold text-sm md:text-md lg:text-lg">
+                    {&translations["store_products_draft_publish"]}
+                </button>
+            },
+        );
+    }
     html! {
         <>
         <SettingsPageTemplate
             heading={translations["admin_store_new_products_heading"].clone()}
-            options={ vec![
-                new_product_category,
-                new_product_button,
-            ]}
+            {options}
             sidebar_options={ vec![
                 (translations["admin_store_new_products_button"].clone(), go_to_products, if *current_page == ProductPageSection::Products { true } else { false }),
                 // (translations["admin_store_sale_products_button"].clone(), go_to_on_sale, if *current_page == ProductPageSection::OnSale { true } else { false }),
                 (translations["admin_store_banner_button"].clone(), go_to_banner, if *current_page == ProductPageSection::Banner { true } else { false }),
+                (translations["store_products_sheet_button"].clone(), go_to_spreadsheet, *current_page == ProductPageSection::Spreadsheet),
+                (translations["store_products_schedule_button"].clone(), go_to_schedule, *current_page == ProductPageSection::Schedule),
+                (translations["store_products_draft_button"].clone(), go_to_publish, *current_page == ProductPageSection::Publish),
             ]}
             content_button={None} >
             <>
@@ -130,6 +166,21 @@ pub fn home_page() -> Html {
                             <BannerDetailsSection onclick={onclick_new_banner} />
                         }
                     }
+                    ProductPageSection::Spreadsheet => {
+                        html! {
+                            <MenuSheetSection />
+                        }
+                    }
+                    ProductPageSection::Schedule => {
+                        html! {
+                            <MenuScheduleSection />
+                        }
+                    }
+                    ProductPageSection::Publish => {
+                        html! {
+                            <MenuDraftSection />
+                        }
+                    }
                 }}
             </>
         </SettingsPageTemplate>
@@ -170,15 +221,11 @@ pub fn add_category_form(props: &AddCategoryFormProps) -> Html {
         use_context::<LanguageConfigsStore>().expect("No LanguageConfigsStore found");
     let translations = language_ctx.translations();
     let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
-    let key_ctx = use_context::<NostrIdStore>().expect("No NostrIdStore found");
-    let relay_ctx = use_context::<NostrProps>().expect("No RelayProps found");
-    let menu = commerce_ctx.menu();
+    let menu = commerce_ctx.draft();
 
     let toast_ctx = use_context::<ToastContext>().expect("No toast context found");
 
     let onsubmit = {
-        let sender = relay_ctx.send_note.clone();
-        let key = key_ctx.get_identity().cloned().expect("No user keys found");
         let handle = commerce_ctx.clone();
         let close_modal = close_modal.clone();
         let toast_ctx = toast_ctx.clone();
@@ -188,34 +235,10 @@ pub fn add_category_form(props: &AddCategoryFormProps) -> Html {
             let category_name = form
                 .input_value("category_name")
                 .expect("Failed to get category name");
-            match menu.clone() {
-                Some(mut menu) => {
-                    let new_category =
-                        ProductCategory::new(menu.categories().len(), category_name.clone());
-                    menu.update_category_name(new_category);
-                    let sender = sender.clone();
-                    let handle = handle.clone();
-                    let key = key.clone();
-                    yew::platform::spawn_local(async move {
-                        let db_entry = ProductMenuIdb::new(menu, &key).await;
-                        sender.emit(db_entry.note());
-                        handle.dispatch(CommerceDataAction::UpdateProductList(db_entry));
-                    });
-                }
-                None => {
-                    let mut new_menu = ProductMenu::new();
-                    let new_category = ProductCategory::new(0, category_name.clone());
-                    new_menu.add_category(new_category);
-                    let sender = sender.clone();
-                    let handle = handle.clone();
-                    let key = key.clone();
-                    yew::platform::spawn_local(async move {
-                        let db_entry = ProductMenuIdb::new(new_menu, &key).await;
-                        sender.emit(db_entry.note());
-                        handle.dispatch(CommerceDataAction::UpdateProductList(db_entry));
-                    });
-                }
-            }
+            let mut menu = menu.clone().unwrap_or_default();
+            let new_category = ProductCategory::new(menu.categories().len(), category_name.clone());
+            menu.update_category_name(new_category);
+            handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu));
 
             let success_message = format!("Category \"{}\" added successfully!", category_name);
             toast_ctx.dispatch(ToastAction::Show(Toast {
@@ -321,24 +344,29 @@ pub struct AddProductFormProps {
 #[function_component(AddProductForm)]
 pub fn add_product_form(props: &AddProductFormProps) -> Html {
     let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
-    let menu = commerce_ctx.menu();
+    let menu = commerce_ctx.draft();
+    let currency = commerce_ctx
+        .profile()
+        .map(|profile| profile.currency)
+        .unwrap_or_default();
 
     let image_url = use_state(|| None::<String>);
     let key_ctx = use_context::<NostrIdStore>().expect("No NostrIdStore found");
-    let relay_ctx = use_context::<NostrProps>().expect("No RelayProps found");
     let language_ctx = use_context::<LanguageConfigsStore>().expect("No LanguageStore found");
     let toast_ctx = use_context::<ToastContext>().expect("No toast context found");
     let nostr_keys = key_ctx.get_identity().expect("No user keys found");
     let thumbnail_url = use_state(|| None::<String>);
     let discount_enabled = use_state(|| false);
+    let tags = use_state(Vec::<ProductTag>::new);
+    let spice_level = use_state(|| 0u8);
     let translations = language_ctx.translations();
 
     let onsubmit = {
         let image_url = image_url.clone();
         let thumbnail_url = thumbnail_url.clone();
         let discount_enabled = discount_enabled.clone();
-        let sender = relay_ctx.send_note.clone();
-        let nostr_keys = key_ctx.get_identity().cloned().expect("No user keys found");
+        let tags = tags.clone();
+        let spice_level = spice_level.clone();
         let handle = commerce_ctx.clone();
         let menu = menu.clone();
         let toast_ctx = toast_ctx.clone();
@@ -354,19 +382,38 @@ pub fn add_product_form(props: &AddProductFormProps) -> Html {
             let product_name = form
                 .input_value("product_name")
                 .expect("Failed to get name");
-            let product_price = form
-                .input_value("product_price")
-                .expect("Failed to get price");
+            let show_error = |message: String| {
+                toast_ctx.dispatch(ToastAction::Show(Toast {
+                    message,
+                    toast_type: ToastType::Error,
+                }));
+            };
+            // The discount is stored apart, so the product keeps its original price
+            let price = form.input_value("price").unwrap_or_default();
+            let product_price = match parse_price(&price, currency, false) {
+                Ok(price) => price,
+                Err(e) => return show_error(e.to_string()),
+            };
             let description = form
                 .textarea_value("description")
                 .expect("Failed to get description");
             let details = form
                 .textarea_value("details")
                 .expect("Failed to get details");
-            let discount = form
-                .input_value("discount")
+            let discount = match form.input_value("discount") {
+                Ok(discount) if *discount_enabled && !discount.trim().is_empty() => {
+                    match parse_discount(&discount, product_price) {
+                        Ok(discount) => Some(discount),
+                        Err(e) => return show_error(e.to_string()),
+                    }
+                }
+                _ => None,
+            };
+            // Left empty, the product is never counted as sold out
+            let stock = form
+                .input_value("stock")
                 .ok()
-                .filter(|_| *discount_enabled);
+                .and_then(|stock| stock.trim().parse::<u32>().ok());
 
             match (menu).clone() {
                 Some(mut menu) => {
@@ -392,25 +439,18 @@ pub fn add_product_form(props: &AddProductFormProps) -> Html {
                     }
                     product.set_details(details);
                     product.set_discount(discount);
+                    product.set_stock(stock);
+                    product.set_tags((*tags).clone());
+                    product.set_spice_level(*spice_level);
 
                     menu.add_product(category.id(), product);
+                    handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu));
 
-                    let sender = sender.clone();
-                    let handle = handle.clone();
-                    let toast_ctx = toast_ctx.clone();
-                    let close_modal = close_modal.clone();
-                    let key = nostr_keys.clone();
-                    yew::platform::spawn_local(async move {
-                        let db_entry = ProductMenuIdb::new(menu.clone(), &key).await;
-                        sender.emit(db_entry.note());
-                        handle.dispatch(CommerceDataAction::UpdateProductList(db_entry));
-
-                        toast_ctx.dispatch(ToastAction::Show(Toast {
-                            message: "Product added successfully!".into(),
-                            toast_type: ToastType::Success,
-                        }));
-                        close_modal.set(false);
-                    });
+                    toast_ctx.dispatch(ToastAction::Show(Toast {
+                        message: "Product added successfully!".into(),
+                        toast_type: ToastType::Success,
+                    }));
+                    close_modal.set(false);
                 }
                 None => {
                     toast_ctx.dispatch(ToastAction::Show(Toast {
@@ -541,6 +581,13 @@ pub fn add_product_form(props: &AddProductFormProps) -> Html {
                                 <input step={"0.01"} type="number" id="product_price"  disabled={true}
                                     class="border-2 border-fuente rounded-xl p-2 max-w-32" required={true} />
                             </div>
+
+                            <div class="w-full flex justify-between">
+                                <label for="stock" class="text-gray-400 font-semibold">{&translations["store_products_form_label_stock"]}</label>
+                                <input step={"1"} type="number" id="stock" name="stock" min={"0"} class="border-2 border-fuente rounded-xl p-2 max-w-32" />
+                            </div>
+
+                            <ProductTagPicker tags={tags.clone()} spice_level={spice_level.clone()} />
                         </div>
 
                     <button
@@ -586,35 +633,542 @@ pub fn banner_details_section(props: &BannerDetailsProps) -> Html {
     }
 }
 
+fn download_sheet(content: String, file_name: &str, mime_type: &str) {
+    let blob = gloo::file::Blob::new_with_options(content.as_str(), Some(mime_type));
+    let url = gloo::file::ObjectUrl::from(blob);
+    let Ok(link) = gloo::utils::document().create_element("a") else {
+        return;
+    };
+    let _ = link.set_attribute("href", &url);
+    let _ = link.set_attribute("download", file_name);
+    if let Ok(link) = link.dyn_into::<web_sys::HtmlElement>() {
+        link.click();
+    }
+}
+
+#[function_component(MenuSheetSection)]
+pub fn menu_sheet_section() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+    let toast_ctx = use_context::<ToastContext>().expect("No toast context found");
+    let menu = commerce_ctx.draft().unwrap_or_default();
+    let currency = commerce_ctx
+        .profile()
+        .map(|profile| profile.currency)
+        .unwrap_or_default();
+    let preview = use_state(|| None::<MenuImport>);
+    let errors = use_state(Vec::<String>::new);
+    let reader = use_mut_ref(|| None::<gloo::file::callbacks::FileReader>);
+
+    let onclick_csv = {
+        let menu = menu.clone();
+        Callback::from(move |_: MouseEvent| {
+            download_sheet(menu.to_csv(), "menu.csv", "text/csv");
+        })
+    };
+    let onclick_json = {
+        let menu = menu.clone();
+        Callback::from(move |_: MouseEvent| {
+            download_sheet(menu.to_sheet_json(), "menu.json", "application/json");
+        })
+    };
+    let onchange = {
+        let preview = preview.clone();
+        let errors = errors.clone();
+        let reader = reader.clone();
+        Callback::from(move |e: Event| {
+            let input = e.target_unchecked_into::<HtmlInputElement>();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            let is_json = file.name().to_lowercase().ends_with(".json");
+            let menu = menu.clone();
+            let preview = preview.clone();
+            let errors = errors.clone();
+            let file = gloo::file::File::from(file);
+            *reader.borrow_mut() = Some(gloo::file::callbacks::read_as_text(&file, move |text| {
+                let rows = text.map_err(|e| e.to_string()).and_then(|text| {
+                    if is_json {
+                        parse_menu_json(&text)
+                    } else {
+                        parse_menu_csv(&text)
+                    }
+                    .map_err(|e| e.to_string())
+                });
+                let import = rows.map_err(|e| vec![e]).and_then(|rows| {
+                    menu.import_sheet(&rows, currency)
+                        .map_err(|errors| errors.iter().map(|e| e.to_string()).collect())
+                });
+                match import {
+                    Ok(import) => {
+                        errors.set(vec![]);
+                        preview.set(Some(import));
+                    }
+                    Err(import_errors) => {
+                        preview.set(None);
+                        errors.set(import_errors);
+                    }
+                }
+            }));
+        })
+    };
+    let onclick_apply = {
+        let preview = preview.clone();
+        let handle = commerce_ctx.clone();
+        let toast_ctx = toast_ctx.clone();
+        let success_message = translations["store_products_sheet_applied"].clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(import) = (*preview).clone() else {
+                return;
+            };
+            handle.dispatch(CommerceDataAction::UpdateMenuDraft(import.menu));
+            toast_ctx.dispatch(ToastAction::Show(Toast {
+                message: success_message.clone(),
+                toast_type: ToastType::Success,
+            }));
+            preview.set(None);
+        })
+    };
+    let onclick_discard = {
+        let preview = preview.clone();
+        Callback::from(move |_: MouseEvent| preview.set(None))
+    };
+    html! {
+        <div class="flex flex-col gap-5 p-2">
+            <div class="flex flex-wrap gap-3">
+                <button type="button" onclick={onclick_csv}
+                    class="border-2 border-fuente text-fuente font-bold rounded-full px-5 py-2">
+                    {&translations["store_products_sheet_export_csv"]}
+                </button>
+                <button type="button" onclick={onclick_json}
+                    class="border-2 border-fuente text-fuente font-bold rounded-full px-5 py-2">
+                    {&translations["store_products_sheet_export_json"]}
+                </button>
+            </div>
+            <div class="space-y-2">
+                <label for="menu_sheet" class="text-gray-400 font-light block text-md">
+                    {&translations["store_products_sheet_import"]}
+                </label>
+                <input type="file" id="menu_sheet" accept=".csv,.json,text/csv,application/json"
+                    {onchange} class="border-2 border-fuente rounded-xl p-2 w-full" />
+                <p class="text-sm text-gray-500">{&translations["store_products_sheet_columns"]}</p>
+            </div>
+            {for errors.iter().map(|error| html! {
+                <p class="text-sm text-red-500">{error}</p>
+            })}
+            {match preview.as_ref() {
+                Some(import) => html! {
+                    <div class="flex flex-col gap-3">
+                        <h3 class="text-fuente font-semibold text-lg">{&translations["store_products_sheet_preview"]}</h3>
+                        if import.changes.is_empty() {
+                            <p class="text-gray-500">{&translations["store_products_sheet_no_changes"]}</p>
+                        }
+                        <ul class="flex flex-col gap-1">
+                            {for import.changes.iter().map(change_line)}
+                        </ul>
+                        <div class="flex gap-3">
+                            <button type="button" onclick={onclick_apply}
+                                class="bg-fuente-orange text-white font-semibold rounded-full py-2 px-5">
+                                {&translations["store_products_sheet_apply"]}
+                            </button>
+                            <button type="button" onclick={onclick_discard}
+                                class="border-2 border-fuente text-fuente font-semibold rounded-full py-2 px-5">
+                                {&translations["store_products_sheet_discard"]}
+                            </button>
+                        </div>
+                    </div>
+                },
+                None => html! {},
+            }}
+        </div>
+    }
+}
+
+fn change_line(change: &MenuChange) -> Html {
+    match change {
+        MenuChange::Added { category, name } => html! {
+            <li class="text-green-600">{format!("+ {} / {}", category, name)}</li>
+        },
+        MenuChange::Updated {
+            category,
+            name,
+            fields,
+        } => html! {
+            <li class="text-gray-700">
+                {format!("~ {} / {} ({})", category, name, fields.join(", "))}
+            </li>
+        },
+        MenuChange::Removed { category, name } => html! {
+            <li class="text-red-500">{format!("- {} / {}", category, name)}</li>
+        },
+        MenuChange::Hours { category } => html! {
+            <li class="text-gray-700">{format!("~ {} (hours)", category)}</li>
+        },
+    }
+}
+
+#[function_component(MenuDraftSection)]
+pub fn menu_draft_section() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("No NostrIdStore found");
+    let relay_ctx = use_context::<NostrProps>().expect("No RelayProps found");
+    let toast_ctx = use_context::<ToastContext>().expect("No toast context found");
+    let published = commerce_ctx.menu().unwrap_or_default();
+    let draft = commerce_ctx.draft().unwrap_or_default();
+    let changes = draft.changes_from(&published);
+    let publishing = use_state(|| false);
+
+    let onclick_publish = {
+        let handle = commerce_ctx.clone();
+        let keys = key_ctx.get_identity().cloned();
+        let sender = relay_ctx.send_note.clone();
+        let toast_ctx = toast_ctx.clone();
+        let publishing = publishing.clone();
+        let success_message = translations["store_products_draft_published"].clone();
+        let draft = draft.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(keys) = keys.clone() else {
+                return;
+            };
+            let handle = handle.clone();
+            let sender = sender.clone();
+            let toast_ctx = toast_ctx.clone();
+            let publishing = publishing.clone();
+            let success_message = success_message.clone();
+            let draft = draft.clone();
+            publishing.set(true);
+            yew::platform::spawn_local(async move {
+                let (db_entry, notes) = handle.sign_menu(draft, &keys).await;
+                notes.into_iter().for_each(|note| sender.emit(note));
+                handle.dispatch(CommerceDataAction::MenuPublished(db_entry));
+                publishing.set(false);
+                toast_ctx.dispatch(ToastAction::Show(Toast {
+                    message: success_message,
+                    toast_type: ToastType::Success,
+                }));
+            });
+        })
+    };
+    let onclick_discard = {
+        let handle = commerce_ctx.clone();
+        Callback::from(move |_: MouseEvent| handle.dispatch(CommerceDataAction::DiscardMenuDraft))
+    };
+    html! {
+        <div class="flex flex-col gap-8 p-2">
+            <div class="flex flex-col gap-3">
+                <h3 class="text-fuente font-semibold text-lg">{&translations["store_products_draft_changes"]}</h3>
+                if commerce_ctx.has_unpublished_changes() {
+                    <ul class="flex flex-col gap-1">
+                        {for changes.iter().map(change_line)}
+                    </ul>
+                    <div class="flex gap-3">
+                        <button type="button" onclick={onclick_publish} disabled={*publishing}
+                            class="bg-fuente-orange text-white font-semibold rounded-full py-2 px-5 disabled:opacity-50">
+                            {&translations["store_products_draft_publish"]}
+                        </button>
+                        <button type="button" onclick={onclick_discard}
+                            class="border-2 border-fuente text-fuente font-semibold rounded-full py-2 px-5">
+                            {&translations["store_products_sheet_discard"]}
+                        </button>
+                    </div>
+                } else {
+                    <p class="text-gray-500">{&translations["store_products_draft_up_to_date"]}</p>
+                }
+            </div>
+            <div class="flex flex-col gap-3">
+                <h3 class="text-fuente font-semibold text-lg">{&translations["store_products_draft_history"]}</h3>
+                if commerce_ctx.history().is_empty() {
+                    <p class="text-gray-500">{&translations["store_products_draft_no_history"]}</p>
+                }
+                {for commerce_ctx.history().into_iter().map(|version| {
+                    let published_at = web_sys::js_sys::Date::new(&web_sys::wasm_bindgen::JsValue::from_f64(
+                        (version.published_at() * 1000) as f64,
+                    ))
+                    .to_locale_string("nl-SR", &web_sys::js_sys::Object::new());
+                    let products = version
+                        .menu()
+                        .categories()
+                        .iter()
+                        .map(|category| category.products().len())
+                        .sum::<usize>();
+                    let is_live = version.menu() == published;
+                    let onclick_restore = {
+                        let handle = commerce_ctx.clone();
+                        let menu = version.menu();
+                        Callback::from(move |_: MouseEvent| {
+                            handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu.clone()))
+                        })
+                    };
+                    html! {
+                        <div key={version.id()} class="flex items-center justify-between gap-5 border-b border-gray-200 py-2">
+                            <div class="flex flex-col">
+                                <p class="font-semibold text-gray-700">{String::from(published_at)}</p>
+                                <p class="text-sm text-gray-500">
+                                    {format!("{} {}", products, translations["store_products_draft_products"])}
+                                </p>
+                            </div>
+                            if is_live {
+                                <span class="text-sm text-green-600 font-semibold">{&translations["store_products_draft_live"]}</span>
+                            } else {
+                                <button type="button" onclick={onclick_restore}
+                                    class="border-2 border-fuente text-fuente font-semibold rounded-full py-1 px-4 text-sm">
+                                    {&translations["store_products_draft_restore"]}
+                                </button>
+                            }
+                        </div>
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq)]
+enum ScheduleTarget {
+    Category(ProductCategory),
+    Discount(ProductItem),
+}
+
+fn hours_summary(hours: &[OpeningHours]) -> String {
+    hours
+        .iter()
+        .map(OpeningHours::display)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[function_component(MenuScheduleSection)]
+pub fn menu_schedule_section() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+    let editing = use_state(|| None::<ScheduleTarget>);
+    let show_popup = use_state(|| false);
+    let menu = commerce_ctx.draft().unwrap_or_default();
+
+    let edit = |target: ScheduleTarget| {
+        let editing = editing.clone();
+        let show_popup = show_popup.clone();
+        Callback::from(move |_: MouseEvent| {
+            editing.set(Some(target.clone()));
+            show_popup.set(true);
+        })
+    };
+    let on_save = {
+        let handle = commerce_ctx.clone();
+        let editing = editing.clone();
+        let show_popup = show_popup.clone();
+        let menu = menu.clone();
+        Callback::from(
+            move |(discount, hours): (Option<Money>, Vec<OpeningHours>)| {
+                let mut menu = menu.clone();
+                match (*editing).clone() {
+                    Some(ScheduleTarget::Category(category)) => {
+                        menu.set_category_hours(&category.id(), hours);
+                    }
+                    Some(ScheduleTarget::Discount(mut product)) => {
+                        product.set_discount(discount);
+                        product.set_discount_hours(hours);
+                        menu.remove_product(&product.category_id(), &product.id());
+                        menu.add_product(product.category_id(), product);
+                    }
+                    None => return,
+                }
+                handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu));
+                show_popup.set(false);
+            },
+        )
+    };
+    html! {
+        <div class="flex flex-col gap-6 p-2">
+            <p class="text-gray-500">{&translations["store_products_schedule_description"]}</p>
+            {for menu.categories().into_iter().map(|category| {
+                let hours = category.hours();
+                html! {
+                    <div key={category.id()} class="flex flex-col gap-2 border-b border-gray-200 pb-4">
+                        <div class="flex items-center justify-between gap-5">
+                            <div class="flex flex-col">
+                                <h3 class="text-fuente font-semibold text-lg">{category.name()}</h3>
+                                <p class="text-sm text-gray-500">
+                                    {if hours.is_empty() {
+                                        translations["store_products_schedule_all_day"].clone()
+                                    } else {
+                                        hours_summary(&hours)
+                                    }}
+                                </p>
+                            </div>
+                            <button type="button" onclick={edit(ScheduleTarget::Category(category.clone()))}
+                                class="border-2 border-fuente text-fuente font-semibold rounded-full py-1 px-4 text-sm">
+                                {&translations["store_products_schedule_edit_hours"]}
+                            </button>
+                        </div>
+                        {for category.products().into_iter().map(|product| {
+                            let discount = match (product.discount(), product.discount_hours()) {
+                                (None, _) => translations["store_products_schedule_no_discount"].clone(),
+                                (Some(discount), hours) if hours.is_empty() => {
+                                    format!("-{} ({})", discount, translations["store_products_schedule_always"])
+                                }
+                                (Some(discount), hours) => format!("-{} ({})", discount, hours_summary(&hours)),
+                            };
+                            html! {
+                                <div key={product.id()} class="flex items-center justify-between gap-5 pl-4">
+                                    <div class="flex flex-col">
+                                        <p class="text-gray-700">{product.name()}</p>
+                                        <p class="text-sm text-gray-500">{discount}</p>
+                                    </div>
+                                    <button type="button" onclick={edit(ScheduleTarget::Discount(product.clone()))}
+                                        class="text-fuente font-semibold text-sm">
+                                        {&translations["store_products_schedule_edit_discount"]}
+                                    </button>
+                                </div>
+                            }
+                        })}
+                    </div>
+                }
+            })}
+            <PopupSection close_handle={show_popup.clone()}>
+                {match (*editing).clone() {
+                    Some(ScheduleTarget::Category(category)) => html! {
+                        <ScheduleForm key={category.id()} hours={category.hours()} discount={None::<Option<Money>>} on_save={on_save.clone()} />
+                    },
+                    Some(ScheduleTarget::Discount(product)) => html! {
+                        <ScheduleForm key={product.id()} hours={product.discount_hours()} discount={Some(product.discount())} price={product.original_price()} on_save={on_save.clone()} />
+                    },
+                    None => html! {},
+                }}
+            </PopupSection>
+        </div>
+    }
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct ScheduleFormProps {
+    pub hours: Vec<OpeningHours>,
+    /// Set when scheduling a product discount, the inner value being the current discount.
+    pub discount: Option<Option<Money>>,
+    /// Price of the product the discount is taken off.
+    #[prop_or_default]
+    pub price: Option<Money>,
+    pub on_save: Callback<(Option<Money>, Vec<OpeningHours>)>,
+}
+
+#[function_component(ScheduleForm)]
+pub fn schedule_form(props: &ScheduleFormProps) -> Html {
+    let ScheduleFormProps {
+        hours,
+        discount,
+        price,
+        on_save,
+    } = props.clone();
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let toast_ctx = use_context::<ToastContext>().expect("No toast context found");
+
+    let onsubmit = Callback::from(move |e: SubmitEvent| {
+        e.prevent_default();
+        let form = HtmlForm::new(e).expect("Failed to get form");
+        let hours = Weekday::ALL
+            .iter()
+            .filter_map(|day| {
+                let opens = form.input_value(&format!("opens-{}", day.display())).ok()?;
+                let closes = form
+                    .input_value(&format!("closes-{}", day.display()))
+                    .ok()?;
+                let opens = OpeningHours::parse_time(&opens)?;
+                let closes = OpeningHours::parse_time(&closes)?;
+                (opens < closes).then(|| OpeningHours::new(*day, opens, closes))
+            })
+            .collect();
+        let discount = match (form.input_value("discount"), price) {
+            (Ok(discount), Some(price)) if !discount.trim().is_empty() => {
+                match parse_discount(&discount, price) {
+                    Ok(discount) => Some(discount),
+                    Err(e) => {
+                        toast_ctx.dispatch(ToastAction::Show(Toast {
+                            message: e.to_string(),
+                            toast_type: ToastType::Error,
+                        }));
+                        return;
+                    }
+                }
+            }
+            _ => None,
+        };
+        on_save.emit((discount, hours));
+    });
+    html! {
+        <form {onsubmit}
+            class="w-full h-full flex flex-col gap-4 rounded-3xl p-4 bg-white max-h-[80vh] overflow-y-auto">
+            if let Some(discount) = discount {
+                <MoneyInput
+                    label={translations["store_products_form_label_discount"].clone()}
+                    value={discount.map(|discount| discount.amount()).unwrap_or_default()}
+                    id="discount"
+                    name="discount"
+                    input_type="number"
+                    required={false}
+                />
+            }
+            <p class="text-sm text-gray-500">{&translations["store_products_schedule_hours_hint"]}</p>
+            {for Weekday::ALL.iter().map(|day| {
+                let hours = hours.iter().find(|hours| hours.day == *day);
+                let opens = hours.map(|h| OpeningHours::format_time(h.opens)).unwrap_or_default();
+                let closes = hours.map(|h| OpeningHours::format_time(h.closes)).unwrap_or_default();
+                html! {
+                    <div class="grid grid-cols-2 gap-2">
+                        <SimpleInput
+                            id={format!("opens-{}", day.display())}
+                            name={format!("opens-{}", day.display())}
+                            label={format!("{} - {}", day.display(), translations["store_products_schedule_from"])}
+                            value={opens}
+                            input_type="time"
+                            required={false}
+                        />
+                        <SimpleInput
+                            id={format!("closes-{}", day.display())}
+                            name={format!("closes-{}", day.display())}
+                            label={translations["store_products_schedule_until"].clone()}
+                            value={closes}
+                            input_type="time"
+                            required={false}
+                        />
+                    </div>
+                }
+            })}
+            <button
+                type="submit"
+                class="bg-fuente-orange text-white font-semibold rounded-full py-2 px-5"
+                >{&translations["store_products_schedule_save"]}</button>
+        </form>
+    }
+}
+
 #[function_component(AllProductsSection)]
 pub fn product_list_section() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
     let commerce_ctx = use_context::<CommerceDataStore>().expect("Commerce data context not found");
-    let key_ctx = use_context::<NostrIdStore>().expect("Nostr context not found");
-    let relay_ctx = use_context::<NostrProps>().expect("Consumer context not found");
-    let menu_state = use_state(|| commerce_ctx.menu());
+    let menu_state = use_state(|| commerce_ctx.draft());
     let editing_product = use_state(|| None::<ProductItem>); // Editing product state
     let deleting_product = use_state(|| None::<(String, String)>);
     let show_delete_popup = use_state(|| false);
+    let modifiers_product = use_state(|| None::<ProductItem>);
+    let show_modifiers_popup = use_state(|| false);
 
     let new_menu = menu_state.clone();
     let handle = commerce_ctx.clone();
-    let keys = key_ctx.get_identity().cloned();
-    let sender = relay_ctx.send_note.clone();
     let _onclick = Callback::from(move |_: MouseEvent| {
-        if let (Some(new_menu), Some(key)) = ((*new_menu).clone(), keys.clone()) {
-            let sender = sender.clone();
-            let handle = handle.clone();
-            yew::platform::spawn_local(async move {
-                let db_entry = ProductMenuIdb::new(new_menu, &key).await;
-                sender.emit(db_entry.note());
-                handle.dispatch(CommerceDataAction::UpdateProductList(db_entry));
-            });
+        if let Some(new_menu) = (*new_menu).clone() {
+            handle.dispatch(CommerceDataAction::UpdateMenuDraft(new_menu));
         }
     });
     {
         let menu_state = menu_state.clone();
         use_effect_with(commerce_ctx.clone(), move |ctx| {
-            menu_state.set(ctx.menu());
+            menu_state.set(ctx.draft());
             || {}
         });
     }
@@ -643,6 +1197,32 @@ pub fn product_list_section() -> Html {
                             })
                         };
 
+                       let on_modifiers = {
+                           let modifiers_product = modifiers_product.clone();
+                           let show_modifiers_popup = show_modifiers_popup.clone();
+                           let product_clone = product.clone();
+                           Callback::from(move |_: MouseEvent| {
+                               modifiers_product.set(Some(product_clone.clone()));
+                               show_modifiers_popup.set(true);
+                           })
+                       };
+
+                       let on_toggle_available = {
+                           let menu_handle = menu_state.clone();
+                           let handle = commerce_ctx.clone();
+                           let product = product.clone();
+                           Callback::from(move |_: MouseEvent| {
+                               if let Some(mut menu) = (*menu_handle).clone() {
+                                   let mut product = product.clone();
+                                   product.set_available(!product.is_available());
+                                   menu.remove_product(&product.category_id(), &product.id());
+                                   menu.add_product(product.category_id(), product);
+                                   handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu.clone()));
+                                   menu_handle.set(Some(menu));
+                               }
+                           })
+                       };
+
                        let _on_edit = {
                            let editing_product = editing_product.clone();
                            let product_clone = product.clone();
@@ -661,13 +1241,36 @@ pub fn product_list_section() -> Html {
                                    <p class="font-thin text-gray-500 mt-2 text-wrap line-clamp-2 sm:line-clamp-3 md:line-clamp-4">{product.details()}</p>
                                    <p class="font-bold text-gray-500">{product.sku()}</p>
                                </div>
-                               <p class="text-2xl md:text-4xl text-fuente font-bold">
-                                   {product.price()}
-                               </p>
-                               <button onclick={on_delete}
-                                   class="w-8 h-8 md:h-10 md:w-10 text-red-500">
-                                   <Trash class="cursor-pointer" />
-                               </button>
+                               <div class="flex flex-col items-center gap-2">
+                                   <p class="text-2xl md:text-4xl text-fuente font-bold">
+                                       {product.price().to_string()}
+                                   </p>
+                                   {match product.stock() {
+                                       Some(stock) => html! {
+                                           <p class="text-sm text-gray-500">{format!("{}: {}", translations["store_products_form_label_stock"], stock)}</p>
+                                       },
+                                       None => html! {},
+                                   }}
+                               </div>
+                               <div class="flex gap-4 items-center">
+                                   <button onclick={on_toggle_available}
+                                       class={classes!("text-xs", "font-semibold", "rounded-full", "px-3", "py-1", "border-2",
+                                           if product.is_sold_out() { "border-red-500 text-red-500" } else { "border-green-500 text-green-500" })}>
+                                       {if product.is_available() {
+                                           if product.is_sold_out() { &translations["product_sold_out"] } else { &translations["store_products_available"] }
+                                       } else {
+                                           &translations["store_products_unavailable"]
+                                       }}
+                                   </button>
+                                   <button onclick={on_modifiers}
+                                       class="w-8 h-8 md:h-10 md:w-10 text-fuente">
+                                       <ListPlus class="cursor-pointer" />
+                                   </button>
+                                   <button onclick={on_delete}
+                                       class="w-8 h-8 md:h-10 md:w-10 text-red-500">
+                                       <Trash class="cursor-pointer" />
+                                   </button>
+                               </div>
                            </div>
                        }
                    }).collect::<Html>()}
@@ -699,31 +1302,18 @@ pub fn product_list_section() -> Html {
                             onclick={
                                 let menu_handle = menu_state.clone();
                                 let handle = commerce_ctx.clone();
-                                let keys = key_ctx.get_identity().cloned().expect("No user keys found");
-                                let sender = relay_ctx.send_note.clone();
                                 let deleting_product = deleting_product.clone();
                                 let show_delete_popup = show_delete_popup.clone();
 
                                 Callback::from(move |_| {
                                     if let (Some((category_id, product_id)), Some(mut menu)) = ((*deleting_product).clone(), (*menu_handle).clone()) {
                                         menu.remove_product(&category_id, &product_id);
-
-                                        let sender = sender.clone();
-                                        let handle = handle.clone();
-                                        let keys = keys.clone();
-                                        let menu_handle = menu_handle.clone();
-                                        let deleting_product = deleting_product.clone();
-                                        let show_delete_popup = show_delete_popup.clone();
-                                        yew::platform::spawn_local(async move {
-                                        let db_entry = ProductMenuIdb::new(menu.clone(), &keys).await;
-                                        sender.emit(db_entry.note());
-                                        handle.dispatch(CommerceDataAction::UpdateProductList(db_entry.clone()));
+                                        handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu.clone()));
 
                                         // Update local state
                                         menu_handle.set(Some(menu));
                                         deleting_product.set(None);
                                         show_delete_popup.set(false);
-                                        });
                                     }
                                 })
                             }
@@ -734,11 +1324,202 @@ pub fn product_list_section() -> Html {
                     </div>
                 </div>
             </PopupSection>
+            <PopupSection close_handle={show_modifiers_popup.clone()}>
+                {if let Some(product) = (*modifiers_product).clone() {
+                    let on_save = {
+                        let menu_handle = menu_state.clone();
+                        let handle = commerce_ctx.clone();
+                        let show_modifiers_popup = show_modifiers_popup.clone();
+                        Callback::from(move |product: ProductItem| {
+                            if let Some(mut menu) = (*menu_handle).clone() {
+                                menu.remove_product(&product.category_id(), &product.id());
+                                menu.add_product(product.category_id(), product);
+                                handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu.clone()));
+                                menu_handle.set(Some(menu));
+                                show_modifiers_popup.set(false);
+                            }
+                        })
+                    };
+                    let key = product.id();
+                    html! {
+                        <ModifierGroupsForm {key} {product} {on_save} />
+                    }
+                } else {
+                    html! {}
+                }}
+            </PopupSection>
         </div>
 
     }
 }
 
+#[derive(Properties, Clone, PartialEq)]
+pub struct ModifierGroupsFormProps {
+    pub product: ProductItem,
+    pub on_save: Callback<ProductItem>,
+}
+
+#[function_component(ModifierGroupsForm)]
+pub fn modifier_groups_form(props: &ModifierGroupsFormProps) -> Html {
+    let ModifierGroupsFormProps { product, on_save } = props.clone();
+    let groups = use_state(|| product.modifier_groups());
+    let required = use_state(|| false);
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+    let currency = commerce_ctx
+        .profile()
+        .map(|profile| profile.currency)
+        .unwrap_or_default();
+
+    let onsubmit = {
+        let groups = groups.clone();
+        let required = required.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let form = HtmlForm::new(e).expect("Failed to get form");
+            let name = form
+                .input_value("group_name")
+                .expect("Failed to get group name");
+            let min = form
+                .input_value("group_min")
+                .ok()
+                .and_then(|min| min.parse::<u32>().ok())
+                .unwrap_or_default();
+            let max = form
+                .input_value("group_max")
+                .ok()
+                .and_then(|max| max.parse::<u32>().ok())
+                .unwrap_or(1);
+            // One option per line, with an optional price delta after the last comma
+            let options = form
+                .textarea_value("group_options")
+                .expect("Failed to get options")
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .enumerate()
+                .map(|(i, line)| match line.rsplit_once(',') {
+                    Some((name, price)) if parse_price(price, currency, true).is_ok() => {
+                        ProductSide::new(
+                            i,
+                            name.trim().to_string(),
+                            parse_price(price, currency, true).unwrap_or_default(),
+                        )
+                    }
+                    _ => ProductSide::new(i, line.to_string(), Money::zero(currency)),
+                })
+                .collect::<Vec<_>>();
+            if options.is_empty() {
+                return;
+            }
+            let mut new_groups = (*groups).clone();
+            let mut group = ModifierGroup::new(name.clone(), *required, min, max, options);
+            if let Some(previous) = new_groups.iter().find(|group| group.name() == name) {
+                group = group.replacing(previous);
+            }
+            new_groups.retain(|group| group.name() != name);
+            new_groups.push(group);
+            groups.set(new_groups);
+            required.set(false);
+        })
+    };
+    let onclick_save = {
+        let groups = groups.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut product = product.clone();
+            product.set_modifier_groups((*groups).clone());
+            on_save.emit(product);
+        })
+    };
+
+    html! {
+        <main class="bg-white rounded-2xl p-6 max-w-2xl mx-auto max-h-[80vh] overflow-y-auto">
+            <h3 class="text-fuente text-2xl font-bold mb-4">{format!("{} - Sides & options", props.product.name())}</h3>
+            <div class="flex flex-col gap-3 mb-6">
+                {groups.iter().map(|group| {
+                    let on_remove = {
+                        let groups = groups.clone();
+                        let group_id = group.id();
+                        Callback::from(move |_: MouseEvent| {
+                            let mut new_groups = (*groups).clone();
+                            new_groups.retain(|group| group.id() != group_id);
+                            groups.set(new_groups);
+                        })
+                    };
+                    html! {
+                        <div class="flex justify-between items-start border-2 border-fuente rounded-xl p-3">
+                            <div>
+                                <p class="font-bold text-gray-500">{group.name()}</p>
+                                <p class="text-sm text-gray-400">{group.rules_display()}</p>
+                                <p class="text-sm text-gray-500">
+                                    {group.options().iter().map(|side| format!("{} (+{})", side.name(), side.price())).collect::<Vec<_>>().join(", ")}
+                                </p>
+                            </div>
+                            <button type="button" onclick={on_remove} class="w-6 h-6 text-red-500">
+                                <Trash class="cursor-pointer" />
+                            </button>
+                        </div>
+                    }
+                }).collect::<Html>()}
+            </div>
+            <form {onsubmit} class="flex flex-col gap-2">
+                <SimpleInput
+                    label="Group name"
+                    value=""
+                    id="group_name"
+                    name="group_name"
+                    input_type="text"
+                    required={true}
+                />
+                <div class="grid grid-cols-2 gap-2">
+                    <SimpleInput
+                        label="Min. selections"
+                        value="0"
+                        id="group_min"
+                        name="group_min"
+                        input_type="number"
+                        required={true}
+                    />
+                    <SimpleInput
+                        label="Max. selections"
+                        value="1"
+                        id="group_max"
+                        name="group_max"
+                        input_type="number"
+                        required={true}
+                    />
+                </div>
+                <div class="flex items-center gap-2">
+                    <input
+                        type="checkbox"
+                        id="group_required"
+                        checked={*required}
+                        onclick={{
+                            let required = required.clone();
+                            Callback::from(move |_| required.set(!*required))
+                        }}
+                    />
+                    <label for="group_required">{"Required"}</label>
+                </div>
+                <SimpleTextArea
+                    label="Options (one per line, e.g. \"Extra cheese, 7.50\")"
+                    value=""
+                    id="group_options"
+                    name="group_options"
+                    input_type="text"
+                    required={true}
+                />
+                <button type="submit" class="border-2 border-fuente text-fuente rounded-lg px-4 py-2">
+                    {"Add group"}
+                </button>
+            </form>
+            <button onclick={onclick_save}
+                class="bg-fuente-orange text-white font-semibold rounded-full py-3 w-full mt-6 text-center">
+                {"Save"}
+            </button>
+        </main>
+    }
+}
+
 #[function_component(EditProductForm)]
 pub fn edit_product_form(props: &EditProductFormProps) -> Html {
     let close_handle = use_state(|| false);
@@ -750,40 +1531,70 @@ pub fn edit_product_form(props: &EditProductFormProps) -> Html {
 
     let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
     let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
-    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
 
     let image_url = use_state(|| Some(product.image_url()));
     let thumbnail_url = use_state(|| Some(product.thumbnail_url()));
     let discount_enabled = use_state(|| product.discount().is_some());
+    let tags = use_state(|| product.tags());
+    let spice_level = use_state(|| product.spice_level());
     let nostr_keys = key_ctx.get_identity().cloned().expect("No user keys found");
+    let toast_ctx = use_context::<ToastContext>().expect("No toast context found");
+    let currency = commerce_ctx
+        .profile()
+        .map(|profile| profile.currency)
+        .unwrap_or_default();
 
     let onsubmit = {
         let handle = commerce_ctx.clone();
-        let sender = relay_ctx.send_note.clone();
         let on_cancel = on_cancel.clone();
         let thumbnail_url = thumbnail_url.clone();
         let discount_enabled = discount_enabled.clone();
+        let tags = tags.clone();
+        let spice_level = spice_level.clone();
         let image_url = image_url.clone();
-        let nostr_keys = nostr_keys.clone();
         let product = product.clone();
 
         Callback::from(move |e: SubmitEvent| {
             e.prevent_default();
             let form = HtmlForm::new(e).expect("Failed to get form");
 
+            let show_error = |message: String| {
+                toast_ctx.dispatch(ToastAction::Show(Toast {
+                    message,
+                    toast_type: ToastType::Error,
+                }));
+            };
+            let price = form.input_value("product_price").expect("No price");
+            let price = match parse_price(&price, currency, false) {
+                Ok(price) => price,
+                Err(e) => return show_error(e.to_string()),
+            };
+            let discount = match form.input_value("discount") {
+                Ok(discount) if *discount_enabled && !discount.trim().is_empty() => {
+                    match parse_discount(&discount, price) {
+                        Ok(discount) => Some(discount),
+                        Err(e) => return show_error(e.to_string()),
+                    }
+                }
+                _ => None,
+            };
+
             if let Some(mut menu) = (*menu_handle).clone() {
                 let mut updated_product = product.clone();
 
                 updated_product.set_name(form.input_value("product_name").expect("No name"));
-                updated_product.set_price(form.input_value("product_price").expect("No price"));
+                updated_product.set_price(price);
                 updated_product.set_details(form.textarea_value("details").expect("No details"));
                 updated_product
                     .set_description(form.textarea_value("description").expect("No description"));
-                updated_product.set_discount(if *discount_enabled {
-                    form.input_value("discount").ok()
-                } else {
-                    None
-                });
+                updated_product.set_discount(discount);
+                updated_product.set_stock(
+                    form.input_value("stock")
+                        .ok()
+                        .and_then(|stock| stock.trim().parse::<u32>().ok()),
+                );
+                updated_product.set_tags((*tags).clone());
+                updated_product.set_spice_level(*spice_level);
 
                 if let Some(url) = (*image_url).clone() {
                     updated_product.set_image_url(url);
@@ -796,20 +1607,9 @@ pub fn edit_product_form(props: &EditProductFormProps) -> Html {
                 menu.remove_product(&updated_product.category_id(), &updated_product.id());
 
                 menu.add_product(updated_product.category_id(), updated_product);
-
-                let handle = handle.clone();
-                let sender = sender.clone();
-                let nostr_keys = nostr_keys.clone();
-                let menu_handle = menu_handle.clone();
-                let on_cancel = on_cancel.clone();
-
-                yew::platform::spawn_local(async move {
-                    let db_entry = ProductMenuIdb::new(menu.clone(), &nostr_keys).await;
-                    sender.emit(db_entry.note());
-                    handle.dispatch(CommerceDataAction::UpdateProductList(db_entry));
-                    menu_handle.set(Some(menu.clone()));
-                    on_cancel.emit(MouseEvent::new("click").unwrap());
-                });
+                handle.dispatch(CommerceDataAction::UpdateMenuDraft(menu.clone()));
+                menu_handle.set(Some(menu));
+                on_cancel.emit(MouseEvent::new("click").unwrap());
             }
         })
     };
@@ -828,7 +1628,7 @@ pub fn edit_product_form(props: &EditProductFormProps) -> Html {
                     />
                     <MoneyInput
                         label="Price"
-                        value={product.price()}
+                        value={product.original_price().amount()}
                         id="product_price"
                         name="product_price"
                         input_type="number"
@@ -892,7 +1692,7 @@ pub fn edit_product_form(props: &EditProductFormProps) -> Html {
                         html! {
                             <MoneyInput
                                 label="Discount Amount"
-                                value={product.discount().unwrap_or_default()}
+                                value={product.discount().map(|discount| discount.amount()).unwrap_or_default()}
                                 id="discount"
                                 name="discount"
                                 input_type="number"
@@ -902,6 +1702,15 @@ pub fn edit_product_form(props: &EditProductFormProps) -> Html {
                     } else {
                         html! {}
                     }}
+                    <SimpleInput
+                        label="Stock"
+                        value={product.stock().map(|stock| stock.to_string()).unwrap_or_default()}
+                        id="stock"
+                        name="stock"
+                        input_type="number"
+                        required={false}
+                    />
+                    <ProductTagPicker tags={tags.clone()} spice_level={spice_level.clone()} />
                     <div class="flex gap-2">
                         <button
                             type="submit"
@@ -0,0 +1,101 @@
+use crate::contexts::{CommerceDataAction, CommerceDataStore};
+use fuente::{
+    contexts::LanguageConfigsStore,
+    mass::{PopupSection, PromotionCard, PromotionForm},
+    models::{Promotion, PromotionList},
+};
+use lucide_yew::Plus;
+use nostr_minions::{key_manager::NostrIdStore, relay_pool::NostrProps};
+use yew::prelude::*;
+
+#[function_component(PromotionsPage)]
+pub fn promotions_page() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx");
+    let translations = language_ctx.translations();
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let key_ctx = use_context::<NostrIdStore>().expect("Nostr context not found");
+    let relay_ctx = use_context::<NostrProps>().expect("Nostr context not found");
+    let new_promotion = use_state(|| false);
+
+    let publish = {
+        let commerce_ctx = commerce_ctx.clone();
+        let sender = relay_ctx.send_note.clone();
+        let keys = key_ctx.get_identity().cloned().expect("No user keys found");
+        Callback::from(move |list: PromotionList| {
+            let commerce_ctx = commerce_ctx.clone();
+            let sender = sender.clone();
+            let keys = keys.clone();
+            yew::platform::spawn_local(async move {
+                match list.signed_note(&keys).await {
+                    Ok(note) => {
+                        sender.emit(note);
+                        commerce_ctx.dispatch(CommerceDataAction::UpdatePromotions(list));
+                    }
+                    Err(e) => gloo::console::error!("Failed to sign promotions", e.to_string()),
+                }
+            });
+        })
+    };
+    let on_save = {
+        let publish = publish.clone();
+        let commerce_ctx = commerce_ctx.clone();
+        let new_promotion = new_promotion.clone();
+        Callback::from(move |promotion: Promotion| {
+            let mut list = commerce_ctx.promotions();
+            list.add(promotion);
+            publish.emit(list);
+            new_promotion.set(false);
+        })
+    };
+    let on_delete = {
+        let commerce_ctx = commerce_ctx.clone();
+        Callback::from(move |promotion_id: String| {
+            let mut list = commerce_ctx.promotions();
+            list.remove(&promotion_id);
+            publish.emit(list);
+        })
+    };
+    let currency = commerce_ctx
+        .profile()
+        .map(|profile| profile.currency)
+        .unwrap_or_default();
+    let categories = commerce_ctx
+        .menu()
+        .map(|menu| menu.categories())
+        .unwrap_or_default();
+    let promotions = commerce_ctx.promotions().promotions;
+    html! {
+        <main class="flex-1 overflow-hidden">
+            <div class="flex flex-col h-full container mx-auto">
+                <div class="flex flex-row justify-between items-center py-4 lg:py-10">
+                    <h1 class="text-fuente font-mplus text-4xl text-center lg:text-left lg:text-6xl tracking-tighter font-bold">
+                        {&translations["promotions_heading"]}
+                    </h1>
+                    <button
+                        onclick={{
+                            let new_promotion = new_promotion.clone();
+                            Callback::from(move |_| new_promotion.set(true))
+                        }}
+                        class="flex items-center gap-2 bg-fuente text-white font-bold py-3 px-5 rounded-full">
+                        <Plus class="w-5 h-5" />
+                        {&translations["promotions_new"]}
+                    </button>
+                </div>
+                <div class="flex flex-col gap-4 overflow-y-auto p-4">
+                    {if promotions.is_empty() {
+                        html! {
+                            <p class="text-gray-500">{&translations["promotions_empty"]}</p>
+                        }
+                    } else {
+                        promotions.iter().map(|promotion| html! {
+                            <PromotionCard promotion={promotion.clone()} on_delete={on_delete.clone()} />
+                        }).collect::<Html>()
+                    }}
+                </div>
+            </div>
+            <PopupSection close_handle={new_promotion.clone()}>
+                <PromotionForm {categories} {currency} {on_save} />
+            </PopupSection>
+        </main>
+    }
+}
@@ -3,14 +3,15 @@ use crate::router::CommerceRoute;
 use fuente::contexts::LanguageConfigsStore;
 use fuente::mass::templates::KeyRecoverySection;
 use fuente::mass::{
-    templates::SettingsPageTemplate, AppLink, PopupProps, PopupSection, SimpleInput, SimpleTextArea,
+    templates::SettingsPageTemplate, AppLink, PopupProps, PopupSection, SimpleInput, SimpleSelect,
+    SimpleTextArea,
 };
 use fuente::mass::{
     CommerceProfileProps, ImageUploadInput, LanguageToggle, NewAddressForm, NewAddressProps,
 };
-use fuente::models::CommerceProfileIdb;
+use fuente::models::{CommerceProfileIdb, Currency, Money, OpeningHours, OrderMinimum, Weekday};
 use lucide_yew::{
-    Compass, Mail, MapPin, Phone, ScrollText, ShoppingBag, SquarePen, Upload, Zap, X,
+    Clock, Compass, Mail, MapPin, Phone, ScrollText, ShoppingBag, SquarePen, Upload, Zap, X,
 };
 use nostr_minions::browser_api::HtmlForm;
 use nostr_minions::key_manager::NostrIdStore;
@@ -22,6 +23,7 @@ use yew::prelude::*;
 pub enum SettingsPage {
     Profile,
     Address,
+    Availability,
     KeyRecovery,
     Language,
 }
@@ -50,6 +52,10 @@ pub fn settings_page() -> Html {
         let page = current_page.clone();
         Callback::from(move |_| page.set(SettingsPage::Address))
     };
+    let go_to_availability = {
+        let page = current_page.clone();
+        Callback::from(move |_| page.set(SettingsPage::Availability))
+    };
     let go_to_key_recovery = {
         let page = current_page.clone();
         Callback::from(move |_| page.set(SettingsPage::KeyRecovery))
@@ -100,9 +106,11 @@ pub fn settings_page() -> Html {
     };
     let profile_popup_handle = use_state(|| false);
     let address_popup_handle = use_state(|| false);
+    let availability_popup_handle = use_state(|| false);
     let edit_button = {
         let profile_popup_handle = profile_popup_handle.clone();
         let address_popup_handle = address_popup_handle.clone();
+        let availability_popup_handle = availability_popup_handle.clone();
         match *current_page {
             SettingsPage::KeyRecovery => {
                 html! {
@@ -130,6 +138,18 @@ pub fn settings_page() -> Html {
                     </button>
                 }
             }
+            SettingsPage::Availability => {
+                html! {
+                    <button onclick={Callback::from(move |_| availability_popup_handle.set(true))}
+                        type="button"
+                        class="absolute top-1 right-1 sm:top-4 sm:right-4 p-2 rounded-full transition duration-300">
+                        <span class="text-fuente font-bold text-xl flex gap-1 items-top">
+                            {&translations["profile_personal_information_edit_button"]}
+                            <SquarePen class={classes!("feather", "feather-plus", "text-fuente","w-6", "h-6")} />
+                        </span>
+                    </button>
+                }
+            }
             SettingsPage::Profile => {
                 html! {
                     <button onclick={Callback::from(move |_| profile_popup_handle.set(true))}
@@ -154,6 +174,7 @@ pub fn settings_page() -> Html {
             sidebar_options={ vec![
                 (translations["stores_settings_option_information"].clone(), go_to_profile, if *current_page == SettingsPage::Profile { true } else { false }),
                 (translations["profile_address_address_button"].clone(), go_to_address, if *current_page == SettingsPage::Address { true } else { false }),
+                (translations["store_settings_availability"].clone(), go_to_availability, if *current_page == SettingsPage::Availability { true } else { false }),
                 (translations["profile_settings_key"].clone(), go_to_key_recovery, if *current_page == SettingsPage::KeyRecovery { true } else { false }),
                 (translations["profile_settings_language"].clone(), go_to_language, if *current_page == SettingsPage::Language { true } else { false }),
             ]}
@@ -176,6 +197,14 @@ pub fn settings_page() -> Html {
                         </PopupSection>
                         </div>
                     },
+                    SettingsPage::Availability => html! {
+                        <div class="w-full">
+                        <MyOpeningHours />
+                        <PopupSection close_handle={availability_popup_handle.clone()}>
+                            <EditAvailabilityModal close_handle={availability_popup_handle.clone()} />
+                        </PopupSection>
+                        </div>
+                    },
                     SettingsPage::KeyRecovery => html! {
                         <div class="w-full">
                         <KeyRecoverySection />
@@ -368,6 +397,7 @@ pub fn edit_profile_menu(props: &PopupProps) -> Html {
     let sender = relay_pool.send_note.clone();
     let handle = props.close_handle.clone();
     let profile_clone = profile.clone();
+    let currency_locked = user_ctx.has_prices();
 
     let onsubmit = Callback::from(move |e: SubmitEvent| {
         e.prevent_default();
@@ -381,6 +411,13 @@ pub fn edit_profile_menu(props: &PopupProps) -> Html {
         new_profile.ln_address = form
             .input_value("ln_address")
             .expect("Failed to get ln_address");
+        if !currency_locked {
+            new_profile.currency = form
+                .select_value("currency")
+                .ok()
+                .and_then(|code| Currency::try_from(code.as_str()).ok())
+                .unwrap_or(new_profile.currency);
+        }
         let description_element = web_sys::window()
             .expect("no window")
             .document()
@@ -407,6 +444,11 @@ pub fn edit_profile_menu(props: &PopupProps) -> Html {
         <form {onsubmit}
             class="w-full h-full flex flex-col gap-4 rounded-3xl p-4 bg-fuente-dark">
                 <EditProfileInputs commerce_data={profile.clone()} />
+                if currency_locked {
+                    <p class="text-xs text-neutral-400 px-4">
+                        {"Remove your products and order minimum to change the pricing currency"}
+                    </p>
+                }
                 <button
                     type="submit"
                     class="text-sm bg-fuente text-white font-bold p-2 px-4 rounded-3xl"
@@ -420,7 +462,10 @@ pub fn new_address_inputs(props: &CommerceProfileProps) -> Html {
     let CommerceProfileProps {
         commerce_data,
         rating: _,
+        estimate: _,
     } = props;
+    let user_ctx = use_context::<CommerceDataStore>().expect("No user context found");
+    let currency_locked = user_ctx.has_prices();
     html! {
         <div class="flex flex-col px-4 gap-2">
             <SimpleInput
@@ -455,6 +500,13 @@ pub fn new_address_inputs(props: &CommerceProfileProps) -> Html {
                 input_type="text"
                 required={true}
             />
+            <SimpleSelect id="currency" name="currency" label="Pricing Currency">
+                {Currency::ALL.iter().filter(|currency| !currency_locked || **currency == commerce_data.currency).map(|currency| html! {
+                    <option value={currency.code()} selected={*currency == commerce_data.currency}>
+                        {currency.code()}
+                    </option>
+                }).collect::<Html>()}
+            </SimpleSelect>
             <SimpleTextArea
                 id="description"
                 name="description"
@@ -517,3 +569,210 @@ pub fn edit_profile_menu(props: &PopupProps) -> Html {
         </form>
     }
 }
+
+#[function_component(MyOpeningHours)]
+fn my_opening_hours() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No NostrProps found");
+    let translations = language_ctx.translations();
+    let user_ctx = use_context::<CommerceDataStore>().expect("No CommerceDataStore found");
+    let profile = user_ctx.profile().expect("No user profile found");
+    let availability = profile.availability;
+    let order_minimum = profile.order_minimum;
+    html! {
+        <div class="max-w-full flex flex-col p-6 rounded-lg space-y-6 overflow-hidden">
+            <h3 class="text-gray-800 text-2xl font-semibold border-b pb-2">
+              {&translations["store_settings_availability"]}
+            </h3>
+            <div class="space-y-4">
+              <div class="flex items-start space-x-3">
+                <Clock class="text-gray-500 w-5 h-5 mt-1 flex-shrink-0" />
+                <div class="flex-grow">
+                  <p class="text-gray-700 text-lg font-bold">
+                    {&translations["store_settings_opening_hours"]}
+                  </p>
+                  {if availability.accepts_scheduled_orders() {
+                      html! {
+                          {for availability.hours.iter().map(|hours| html! {
+                              <p class="text-gray-600 text-xl font-light">{hours.display()}</p>
+                          })}
+                      }
+                  } else {
+                      html! {
+                          <p class="text-gray-600 text-xl font-light">{&translations["store_settings_no_schedule"]}</p>
+                      }
+                  }}
+                </div>
+              </div>
+              <div class="flex items-start space-x-3">
+                <ScrollText class="text-gray-500 w-5 h-5 mt-1 flex-shrink-0" />
+                <div class="flex-grow">
+                  <p class="text-gray-700 text-lg font-bold">
+                    {&translations["store_settings_slot_length"]}
+                  </p>
+                  <p class="text-gray-600 text-xl font-light">
+                    {format!("{} min / {} min", availability.slot_minutes, availability.lead_minutes)}
+                  </p>
+                </div>
+              </div>
+              <div class="flex items-start space-x-3">
+                <ShoppingBag class="text-gray-500 w-5 h-5 mt-1 flex-shrink-0" />
+                <div class="flex-grow">
+                  <p class="text-gray-700 text-lg font-bold">
+                    {&translations["store_settings_minimum_order"]}
+                  </p>
+                  {match order_minimum.minimum {
+                      Some(minimum) => html! {
+                          <>
+                              <p class="text-gray-600 text-xl font-light">{language_ctx.format_money(minimum)}</p>
+                              <p class="text-gray-600 text-lg font-light">
+                                  {match order_minimum.small_order_fee {
+                                      Some(fee) => format!("{} {}", translations["store_settings_small_order_fee"], language_ctx.format_money(fee)),
+                                      None => translations["store_settings_small_orders_refused"].clone(),
+                                  }}
+                              </p>
+                          </>
+                      },
+                      None => html! {
+                          <p class="text-gray-600 text-xl font-light">{&translations["store_settings_no_minimum"]}</p>
+                      },
+                  }}
+                </div>
+              </div>
+            </div>
+        </div>
+    }
+}
+
+#[function_component(EditAvailabilityModal)]
+pub fn edit_availability_modal(props: &PopupProps) -> Html {
+    let user_ctx = use_context::<CommerceDataStore>().expect("No user context found");
+    let key_ctx = use_context::<NostrIdStore>().expect("No NostrProps found");
+    let relay_pool = use_context::<NostrProps>().expect("No RelayPool Context found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+
+    let profile = user_ctx.profile().expect("No user profile found");
+    let keys = key_ctx.get_identity().cloned().expect("No user keys found");
+    let sender = relay_pool.send_note.clone();
+    let handle = props.close_handle.clone();
+    let availability = profile.availability.clone();
+    let order_minimum = profile.order_minimum;
+    let currency = profile.currency;
+
+    let onsubmit = Callback::from(move |e: SubmitEvent| {
+        e.prevent_default();
+        let form = HtmlForm::new(e).expect("Failed to get form");
+        let mut new_profile = profile.clone();
+        new_profile.availability.hours = Weekday::ALL
+            .iter()
+            .filter_map(|day| {
+                let opens = form.input_value(&format!("opens-{}", day.display())).ok()?;
+                let closes = form
+                    .input_value(&format!("closes-{}", day.display()))
+                    .ok()?;
+                let opens = OpeningHours::parse_time(&opens)?;
+                let closes = OpeningHours::parse_time(&closes)?;
+                (opens < closes).then(|| OpeningHours::new(*day, opens, closes))
+            })
+            .collect();
+        new_profile.availability.slot_minutes = form
+            .input_value("slot_minutes")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(new_profile.availability.slot_minutes);
+        new_profile.availability.lead_minutes = form
+            .input_value("lead_minutes")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(new_profile.availability.lead_minutes);
+        let currency = new_profile.currency;
+        let money = |name: &str| {
+            form.input_value(name)
+                .ok()
+                .and_then(|value| Money::parse_in(&value, currency).ok())
+        };
+        new_profile.order_minimum =
+            OrderMinimum::new(money("minimum_order"), money("small_order_fee"));
+        let keys = keys.clone();
+        let sender = sender.clone();
+        let user_ctx = user_ctx.clone();
+        let handle = handle.clone();
+        yew::platform::spawn_local(async move {
+            let db = CommerceProfileIdb::new(new_profile.clone(), &keys)
+                .await
+                .expect("Failed to create profile");
+            let note = db.signed_note();
+            sender.emit(note.clone());
+            user_ctx.dispatch(CommerceDataAction::UpdateCommerceProfile(db));
+            handle.set(false);
+        });
+    });
+    html! {
+        <form {onsubmit}
+            class="w-full h-full flex flex-col gap-4 rounded-3xl p-4 bg-fuente-dark max-h-[80vh] overflow-y-auto">
+            <div class="flex flex-col px-4 gap-2">
+                {for Weekday::ALL.iter().map(|day| {
+                    let hours = availability.hours.iter().find(|hours| hours.day == *day);
+                    let opens = hours.map(|h| OpeningHours::format_time(h.opens)).unwrap_or_default();
+                    let closes = hours.map(|h| OpeningHours::format_time(h.closes)).unwrap_or_default();
+                    html! {
+                        <div class="grid grid-cols-2 gap-2">
+                            <SimpleInput
+                                id={format!("opens-{}", day.display())}
+                                name={format!("opens-{}", day.display())}
+                                label={format!("{} - {}", day.display(), translations["store_availability_opens"])}
+                                value={opens}
+                                input_type="time"
+                                required={false}
+                            />
+                            <SimpleInput
+                                id={format!("closes-{}", day.display())}
+                                name={format!("closes-{}", day.display())}
+                                label={translations["store_availability_closes"].clone()}
+                                value={closes}
+                                input_type="time"
+                                required={false}
+                            />
+                        </div>
+                    }
+                })}
+                <SimpleInput
+                    id="slot_minutes"
+                    name="slot_minutes"
+                    label={translations["store_availability_slot_minutes"].clone()}
+                    value={availability.slot_minutes.to_string()}
+                    input_type="number"
+                    required={true}
+                />
+                <SimpleInput
+                    id="lead_minutes"
+                    name="lead_minutes"
+                    label={translations["store_availability_lead_minutes"].clone()}
+                    value={availability.lead_minutes.to_string()}
+                    input_type="number"
+                    required={true}
+                />
+                <SimpleInput
+                    id="minimum_order"
+                    name="minimum_order"
+                    label={format!("Minimum order ({})", currency.code())}
+                    value={order_minimum.minimum.map(|minimum| minimum.amount()).unwrap_or_default()}
+                    input_type="number"
+                    required={false}
+                />
+                <SimpleInput
+                    id="small_order_fee"
+                    name="small_order_fee"
+                    label={format!("Small order fee ({}), leave empty to refuse smaller orders", currency.code())}
+                    value={order_minimum.small_order_fee.map(|fee| fee.amount()).unwrap_or_default()}
+                    input_type="number"
+                    required={false}
+                />
+            </div>
+            <button
+                type="submit"
+                class="text-sm bg-fuente text-white font-bold p-2 px-4 rounded-3xl"
+                >{&translations["store_availability_save"]}</button>
+        </form>
+    }
+}
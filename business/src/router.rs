@@ -1,9 +1,11 @@
 use fuente::mass::{AppLink, templates::FuenteSidebarTemplate};
-use lucide_yew::{BellPlus, History, Settings, Store};
+use lucide_yew::{BellPlus, CalendarClock, History, Percent, Settings, Store};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::pages::{HistoryPage, HomePage, ProductsPage, SettingsPageComponent};
+use crate::pages::{
+    HistoryPage, HomePage, OrdersPage, ProductsPage, PromotionsPage, SettingsPageComponent,
+};
 
 #[derive(Clone, Routable, PartialEq)]
 pub enum CommerceRoute {
@@ -17,6 +19,8 @@ pub enum CommerceRoute {
     Products,
     #[at("/orders")]
     Orders,
+    #[at("/promotions")]
+    Promotions,
 }
 
 #[function_component(CommercePages)]
@@ -30,7 +34,8 @@ pub fn consumer_pages() -> Html {
                         CommerceRoute::History => html!{<HistoryPage />},
                         CommerceRoute::Settings => html!{<SettingsPageComponent />},
                         CommerceRoute::Products => html!{<ProductsPage />},
-                        CommerceRoute::Orders => html!{<></>},
+                        CommerceRoute::Orders => html!{<OrdersPage />},
+                        CommerceRoute::Promotions => html!{<PromotionsPage />},
                     }
                 }}
             />
@@ -46,11 +51,21 @@ pub fn home_footer() -> Html {
                 route={CommerceRoute::Home}>
                 <BellPlus class="w-8 h-8 stroke-fuente" />
             </AppLink<CommerceRoute>>
+            <AppLink<CommerceRoute>
+                class="" selected_class=""
+                route={CommerceRoute::Orders}>
+                <CalendarClock class="w-8 h-8 stroke-fuente" />
+            </AppLink<CommerceRoute>>
             <AppLink<CommerceRoute>
                 class="" selected_class=""
                 route={CommerceRoute::Products}>
                 <Store class="w-8 h-8 stroke-fuente" />
             </AppLink<CommerceRoute>>
+            <AppLink<CommerceRoute>
+                class="" selected_class=""
+                route={CommerceRoute::Promotions}>
+                <Percent class="w-8 h-8 stroke-fuente" />
+            </AppLink<CommerceRoute>>
             <AppLink<CommerceRoute>
                 class="" selected_class=""
                 route={CommerceRoute::History}>
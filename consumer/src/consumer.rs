@@ -1,12 +1,15 @@
 use consumer::{
     contexts::{
-        CartProvider, CommerceDataExt, CommerceDataProvider, CommerceDataStore,
-        ConsumerDataProvider, FavoritesProvider, LiveOrderProvider, LoginProvider, RatingsProvider,
+        CartProvider, CommerceAreaSync, CommerceDataExt, CommerceDataProvider, CommerceDataStore,
+        ConsumerDataProvider, FavoritesProvider, GroupOrderProvider, LiveOrderProvider,
+        LoginProvider, RatingsProvider,
     },
     router::ConsumerPages,
 };
 use fuente::{
-    contexts::{AdminConfigsProvider, AdminConfigsStore, LanguageConfigsProvider},
+    contexts::{
+        AdminConfigsProvider, AdminConfigsStore, LanguageConfigsProvider, OrderChatProvider,
+    },
     mass::{LoadingScreen, PwaInstall, ToastProvider},
     models::init_consumer_db,
 };
@@ -95,8 +98,11 @@ fn app_context(props: &ChildrenProps) -> Html {
 fn app_context(props: &ChildrenProps) -> Html {
     html! {
        <ConsumerDataProvider>
+          <CommerceAreaSync />
           <CartProvider>
+            <GroupOrderProvider>
               <LiveOrderProvider>
+                <OrderChatProvider>
                   <FavoritesProvider>
                         <ToastProvider>
                           <LoadingCheck>
@@ -104,7 +110,9 @@ fn app_context(props: &ChildrenProps) -> Html {
                             </LoadingCheck>
                           </ToastProvider>
                   </FavoritesProvider>
+                </OrderChatProvider>
               </LiveOrderProvider>
+            </GroupOrderProvider>
           </CartProvider>
        </ConsumerDataProvider>
     }
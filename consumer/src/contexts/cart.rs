@@ -1,134 +1,246 @@
 use std::rc::Rc;
 
 use fuente::models::{
-    ConsumerAddress, ConsumerProfile, OrderRequest, ProductItem, ProductOrder,
-    NOSTR_KIND_SERVER_REQUEST, TEST_PUB_KEY,
+    CartBasket, DeliverySlot, GroupShare, Money, OrderLine, ProductItem, ProductOrder, Reorder,
+    ReorderChange, SavedCart, SavedCartIdb, NOSTR_KIND_CONSUMER_REPLACEABLE_GIFTWRAP,
 };
-use nostr_minions::key_manager::UserIdentity;
-use nostro2::notes::NostrNote;
-use yew::prelude::*;
+use nostr_minions::{
+    browser_api::IdbStoreManager, key_manager::NostrIdStore, relay_pool::NostrProps,
+};
+use nostro2::{notes::NostrNote, relays::NostrSubscription};
+use web_sys::wasm_bindgen::JsValue;
+use yew::{platform::spawn_local, prelude::*};
+
+use super::{CommerceDataAction, CommerceDataStore};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Cart {
-    order_id: Option<String>,
-    cart_items: ProductOrder,
-    current_business: Option<String>,
+    /// Commerce and order id of each order sent at checkout.
+    sent_orders: Vec<(String, String)>,
+    baskets: Vec<CartBasket>,
+    /// Unix time of the last change, compared with copies from other devices.
+    updated_at: i64,
+    /// Set once the copy saved on this device was read, nothing is saved before.
+    has_loaded: bool,
+    sync_devices: bool,
+    /// Restored baskets not yet checked against the current menu.
+    unchecked: Vec<String>,
+    /// What changed in restored baskets since they were saved.
+    restore_changes: Vec<ReorderChange>,
 }
 
 impl Cart {
-    pub fn last_sent_order(&self) -> Option<String> {
-        self.order_id.clone()
+    /// Orders created by the last checkout, one per commerce.
+    pub fn sent_orders(&self) -> Vec<(String, String)> {
+        self.sent_orders.clone()
+    }
+    pub fn baskets(&self) -> Vec<CartBasket> {
+        self.baskets.clone()
+    }
+    pub fn basket(&self, commerce_id: &str) -> Option<CartBasket> {
+        self.baskets
+            .iter()
+            .find(|basket| basket.commerce_id() == commerce_id)
+            .cloned()
+    }
+    pub fn commerce_ids(&self) -> Vec<String> {
+        self.baskets
+            .iter()
+            .map(|basket| basket.commerce_id())
+            .collect()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.baskets.is_empty()
+    }
+    pub fn item_count(&self) -> u32 {
+        self.baskets
+            .iter()
+            .map(|basket| basket.product_order().item_count())
+            .sum()
     }
-    pub fn can_add_from_business(&self, business_id: &str) -> bool {
-        match &self.current_business {
-            None => true,
-            Some(current_id) => current_id == business_id,
+    pub fn quantity_of(&self, commerce_id: &str, product_id: &str) -> u32 {
+        self.basket(commerce_id)
+            .map(|basket| basket.quantity_of(product_id))
+            .unwrap_or_default()
+    }
+    /// Whether one more unit of the product fits in the stock the commerce published.
+    pub fn can_add_one(&self, commerce_id: &str, product: &ProductItem) -> bool {
+        product.can_supply(self.quantity_of(commerce_id, &product.id()) + 1, 0)
+    }
+    pub fn has_loaded(&self) -> bool {
+        self.has_loaded
+    }
+    pub fn sync_devices(&self) -> bool {
+        self.sync_devices
+    }
+    pub fn unchecked(&self) -> Vec<String> {
+        self.unchecked.clone()
+    }
+    pub fn restore_changes(&self) -> Vec<ReorderChange> {
+        self.restore_changes.clone()
+    }
+    pub fn saved(&self) -> SavedCart {
+        SavedCart {
+            baskets: self.baskets.clone(),
+            updated_at: self.updated_at,
         }
     }
-    pub fn cart(&self) -> Vec<ProductItem> {
-        self.cart_items.products()
-    }
-    pub async fn sign_request(
-        &self,
-        keys: &UserIdentity,
-        commerce: String,
-        profile: ConsumerProfile,
-        address: ConsumerAddress,
-    ) -> (String, NostrNote) {
-        let new_request = OrderRequest::new(commerce, profile, address, self.cart_items.clone());
-        let note = new_request.sign_request(keys).await;
-        let content = note.to_string();
-        let giftwrap = NostrNote {
-            pubkey: keys.get_pubkey().await.unwrap(),
-            kind: NOSTR_KIND_SERVER_REQUEST,
-            content,
-            ..Default::default()
+    /// Applies `update` to the commerce's basket, creating it if needed and
+    /// dropping it once it's empty.
+    fn with_basket(&self, commerce_id: String, update: impl FnOnce(&mut CartBasket)) -> Self {
+        let mut baskets = self.baskets.clone();
+        let index = match baskets
+            .iter()
+            .position(|basket| basket.commerce_id() == commerce_id)
+        {
+            Some(index) => index,
+            None => {
+                baskets.push(CartBasket::new(commerce_id));
+                baskets.len() - 1
+            }
         };
-        let giftwrap = keys.sign_nip44(giftwrap, TEST_PUB_KEY.to_string()).await.unwrap();
-        (note.id.unwrap(), giftwrap)
-    }
-    pub fn business_id(&self) -> Option<String> {
-        self.current_business.clone()
+        update(&mut baskets[index]);
+        if baskets[index].product_order().is_empty() {
+            baskets.remove(index);
+        }
+        Cart {
+            baskets,
+            updated_at: (web_sys::js_sys::Date::now() / 1000.0) as i64,
+            ..self.clone()
+        }
     }
 }
 
 pub enum CartAction {
-    SentOrder(String),
+    SentOrders(Vec<(String, String)>),
     AddProduct(ProductItem, String),
-    AddOne(ProductItem),
-    RemoveProduct(ProductItem),
-    ClearProduct(ProductItem),
+    AddOne(String, OrderLine),
+    RemoveProduct(String, OrderLine),
+    ClearProduct(String, OrderLine),
+    SetNote(String, OrderLine, Option<String>),
+    /// Replaces the lines with the same order priced for the current menu window.
+    RepriceItems(String, ProductOrder),
+    ScheduleDelivery(String, Option<DeliverySlot>),
+    SetCourierTip(String, Money),
+    ApplyPromoCode(String, Option<String>),
+    /// Starts the commerce's basket over with a past order.
+    Reorder(ProductOrder, String),
+    /// Starts the commerce's basket over with the items of a group order.
+    GroupCheckout(String, ProductOrder, Vec<GroupShare>),
+    ClearBasket(String),
     ClearCart,
+    /// Takes a saved copy of the cart if it's newer than the one in memory.
+    Restore(SavedCart),
+    /// A restored basket checked against the commerce's current menu.
+    Revalidate(String, Reorder),
+    DismissRestoreChanges,
+    SetDeviceSync(bool),
 }
 
 impl Reducible for Cart {
     type Action = CartAction;
 
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
-        match action {
-            CartAction::AddProduct(product, business_id) => {
-                // Update AddProduct to include business_id
-                let mut cart_items = self.cart_items.clone();
-
-                // If cart is empty, set the business
-                let current_business = match &self.current_business {
-                    None => Some(business_id),
-                    Some(id) => Some(id.clone()),
-                };
-
-                cart_items.add(product);
-                Rc::new(Cart {
-                    cart_items,
-                    current_business,
-                    order_id: self.order_id.clone(),
-                })
+        let updated = match action {
+            CartAction::AddProduct(product, commerce_id) => {
+                self.with_basket(commerce_id, |basket| basket.items_mut().add(&product))
             }
-            CartAction::RemoveProduct(product) => {
-                let mut cart_items = self.cart_items.clone();
-                cart_items.remove_one(product.id());
-
-                // If cart becomes empty, clear business id
-                let current_business = if cart_items.is_empty() {
-                    None
-                } else {
-                    self.current_business.clone()
-                };
-
-                Rc::new(Cart {
-                    cart_items,
-                    current_business,
-                    order_id: self.order_id.clone(),
-                })
+            CartAction::RemoveProduct(commerce_id, line) => {
+                self.with_basket(commerce_id, |basket| basket.items_mut().remove_one(&line))
+            }
+            CartAction::AddOne(commerce_id, line) => {
+                self.with_basket(commerce_id, |basket| basket.items_mut().add_one(&line))
+            }
+            CartAction::ClearProduct(commerce_id, line) => {
+                self.with_basket(commerce_id, |basket| basket.items_mut().remove_line(&line))
+            }
+            CartAction::SetNote(commerce_id, line, note) => self
+                .with_basket(commerce_id, |basket| {
+                    basket.items_mut().set_note(&line, note)
+                }),
+            CartAction::RepriceItems(commerce_id, cart_items) => {
+                self.with_basket(commerce_id, |basket| *basket.items_mut() = cart_items)
             }
-            CartAction::AddOne(product) => {
-                let mut cart_items = self.cart_items.clone();
-                cart_items.add(product);
-                Rc::new(Cart {
-                    cart_items,
-                    current_business: self.current_business.clone(),
-                    order_id: self.order_id.clone(),
+            CartAction::ScheduleDelivery(commerce_id, delivery_slot) => self
+                .with_basket(commerce_id, |basket| {
+                    basket.set_delivery_slot(delivery_slot)
+                }),
+            CartAction::SetCourierTip(commerce_id, tip) => {
+                self.with_basket(commerce_id, |basket| basket.set_courier_tip(tip))
+            }
+            CartAction::ApplyPromoCode(commerce_id, promo_code) => {
+                self.with_basket(commerce_id, |basket| basket.set_promo_code(promo_code))
+            }
+            CartAction::Reorder(cart_items, commerce_id) => {
+                self.with_basket(commerce_id.clone(), |basket| {
+                    *basket = CartBasket::new(commerce_id);
+                    *basket.items_mut() = cart_items;
                 })
             }
-            CartAction::ClearProduct(product) => {
-                let mut cart_items = self.cart_items.clone();
-                cart_items.remove_all(product.id());
-                Rc::new(Cart {
-                    cart_items,
-                    current_business: self.current_business.clone(),
-                    order_id: self.order_id.clone(),
+            CartAction::GroupCheckout(commerce_id, cart_items, group_shares) => {
+                self.with_basket(commerce_id.clone(), |basket| {
+                    *basket = CartBasket::new(commerce_id);
+                    *basket.items_mut() = cart_items;
+                    basket.set_group_shares(group_shares);
                 })
             }
-            CartAction::ClearCart => Rc::new(Cart {
-                cart_items: ProductOrder::new(vec![]),
-                current_business: None,
-                order_id: self.order_id.clone(),
-            }),
-            CartAction::SentOrder(order_id) => Rc::new(Cart {
-                order_id: Some(order_id),
-                cart_items: self.cart_items.clone(),
-                current_business: self.current_business.clone(),
-            }),
-        }
+            CartAction::ClearBasket(commerce_id) => self
+                .with_basket(commerce_id.clone(), |basket| {
+                    *basket = CartBasket::new(commerce_id)
+                }),
+            CartAction::ClearCart => Cart {
+                baskets: vec![],
+                updated_at: (web_sys::js_sys::Date::now() / 1000.0) as i64,
+                unchecked: vec![],
+                restore_changes: vec![],
+                ..(*self).clone()
+            },
+            CartAction::SentOrders(sent_orders) => Cart {
+                sent_orders,
+                ..(*self).clone()
+            },
+            CartAction::Restore(saved) => {
+                if saved.updated_at <= self.updated_at {
+                    Cart {
+                        has_loaded: true,
+                        ..(*self).clone()
+                    }
+                } else {
+                    Cart {
+                        unchecked: saved
+                            .baskets
+                            .iter()
+                            .map(|basket| basket.commerce_id())
+                            .collect(),
+                        baskets: saved.baskets,
+                        updated_at: saved.updated_at,
+                        has_loaded: true,
+                        ..(*self).clone()
+                    }
+                }
+            }
+            CartAction::Revalidate(commerce_id, reorder) => {
+                let mut cart = if reorder.changes.is_empty() {
+                    (*self).clone()
+                } else {
+                    self.with_basket(commerce_id.clone(), |basket| {
+                        *basket.items_mut() = reorder.order
+                    })
+                };
+                cart.unchecked.retain(|id| *id != commerce_id);
+                cart.restore_changes.extend(reorder.changes);
+                cart
+            }
+            CartAction::DismissRestoreChanges => Cart {
+                restore_changes: vec![],
+                ..(*self).clone()
+            },
+            CartAction::SetDeviceSync(sync_devices) => Cart {
+                sync_devices,
+                ..(*self).clone()
+            },
+        };
+        Rc::new(updated)
     }
 }
 
@@ -142,14 +254,142 @@ pub struct CartChildren {
 #[function_component(CartProvider)]
 pub fn key_handler(props: &CartChildren) -> Html {
     let ctx = use_reducer(|| Cart {
-        cart_items: ProductOrder::new(vec![]),
-        current_business: None,
-        order_id: None,
+        sent_orders: vec![],
+        baskets: vec![],
+        updated_at: 0,
+        has_loaded: false,
+        sync_devices: false,
+        unchecked: vec![],
+        restore_changes: vec![],
+    });
+
+    let ctx_clone = ctx.clone();
+    let key_ctx = use_context::<NostrIdStore>().expect("User context not found");
+    use_effect_with(key_ctx.get_pubkey(), |pubkey| {
+        if let Some(pubkey) = pubkey.clone() {
+            spawn_local(async move {
+                let saved: Result<SavedCartIdb, _> =
+                    SavedCartIdb::retrieve_from_store(&JsValue::from_str(&pubkey)).await;
+                match saved {
+                    Ok(saved) => {
+                        ctx_clone.dispatch(CartAction::SetDeviceSync(saved.sync_devices()));
+                        ctx_clone.dispatch(CartAction::Restore(saved.cart()));
+                    }
+                    Err(_) => ctx_clone.dispatch(CartAction::Restore(SavedCart::default())),
+                }
+            });
+        }
+        || {}
     });
 
     html! {
         <ContextProvider<CartStore> context={ctx}>
             {props.children.clone()}
+            <CartSync />
         </ContextProvider<CartStore>>
     }
 }
+
+/// Saves the cart on the device, shares it with the consumer's other devices
+/// when they opted in, and checks restored baskets against current menus.
+#[function_component(CartSync)]
+pub fn cart_sync() -> Html {
+    let ctx = use_context::<CartStore>().expect("CartStore not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+
+    let sender = relay_ctx.send_note.clone();
+    let keys = key_ctx.clone();
+    use_effect_with(
+        (ctx.saved(), ctx.sync_devices(), ctx.has_loaded()),
+        move |(saved, sync_devices, has_loaded)| {
+            let (saved, sync_devices) = (saved.clone(), *sync_devices);
+            if let (true, Some(pubkey)) = (*has_loaded, keys.get_pubkey()) {
+                spawn_local(async move {
+                    let entry = SavedCartIdb::new(pubkey, saved.clone(), sync_devices);
+                    if let Err(e) = entry.save_to_store().await {
+                        gloo::console::error!("Failed to save cart:", e);
+                    }
+                    let Some(identity) = keys.get_identity().cloned() else {
+                        return;
+                    };
+                    if sync_devices && saved.updated_at > 0 {
+                        match saved.giftwrapped_data(&identity).await {
+                            Ok(note) => sender.emit(note),
+                            Err(e) => gloo::console::error!("Failed to sync cart:", e),
+                        }
+                    }
+                });
+            }
+            || {}
+        },
+    );
+
+    let subscriber = relay_ctx.subscribe.clone();
+    use_effect_with(
+        (key_ctx.get_pubkey(), ctx.sync_devices()),
+        move |(pubkey, sync_devices)| {
+            if let (Some(pubkey), true) = (pubkey, sync_devices) {
+                let mut filter = NostrSubscription {
+                    kinds: Some(vec![NOSTR_KIND_CONSUMER_REPLACEABLE_GIFTWRAP]),
+                    authors: Some(vec![pubkey.clone()]),
+                    ..Default::default()
+                };
+                filter.add_tag("#d", &SavedCart::d_tag());
+                subscriber.emit(filter.into());
+            }
+            || {}
+        },
+    );
+
+    let cart = ctx.clone();
+    let keys = key_ctx.clone();
+    use_effect_with(relay_ctx.unique_notes.clone(), move |notes| {
+        if let Some(note) = notes.last().cloned() {
+            if note.kind == NOSTR_KIND_CONSUMER_REPLACEABLE_GIFTWRAP && cart.sync_devices() {
+                spawn_local(async move {
+                    let Ok(decrypted) = keys.decrypt_note(&note).await else {
+                        return;
+                    };
+                    // Profiles and addresses share the kind, only carts parse
+                    let Some(saved) = serde_json::from_str::<NostrNote>(&decrypted)
+                        .ok()
+                        .and_then(|inner| SavedCart::try_from(inner).ok())
+                    else {
+                        return;
+                    };
+                    cart.dispatch(CartAction::Restore(saved));
+                });
+            }
+        }
+        || {}
+    });
+
+    let ctx_clone = ctx.clone();
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+    let checks = ctx
+        .unchecked()
+        .into_iter()
+        .map(|commerce_id| {
+            let reorder = ctx.basket(&commerce_id).and_then(|basket| {
+                commerce_ctx.reorder(&commerce_id, &basket.product_order(), now)
+            });
+            (commerce_id, reorder)
+        })
+        .collect::<Vec<_>>();
+    use_effect_with(checks, move |checks| {
+        for (commerce_id, reorder) in checks {
+            match reorder {
+                Some(reorder) => {
+                    ctx_clone.dispatch(CartAction::Revalidate(commerce_id.clone(), reorder.clone()))
+                }
+                // Menus of commerces outside the consumer's area are only loaded on request
+                None => commerce_ctx.dispatch(CommerceDataAction::RequestMenu(commerce_id.clone())),
+            }
+        }
+        || {}
+    });
+
+    html! {}
+}
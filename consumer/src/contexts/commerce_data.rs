@@ -3,51 +3,154 @@ use std::rc::Rc;
 use fuente::{
     contexts::AdminConfigsStore,
     models::{
-        CommerceProfile, CommerceProfileIdb, ProductItem, ProductMenuIdb,
-        NOSTR_KIND_COMMERCE_PRODUCTS, NOSTR_KIND_COMMERCE_PROFILE,
+        CommerceListing, CommerceProfile, CommerceProfileIdb, CommerceStock, CoordinateStrings,
+        ProductItem, ProductMenuIdb, ProductOrder, Promotion, PromotionList, Reorder, SearchIndex,
+        TagFilter, NOSTR_KIND_COMMERCE_MENU_CATEGORY, NOSTR_KIND_COMMERCE_PRODUCTS,
+        NOSTR_KIND_COMMERCE_PROFILE, NOSTR_KIND_COMMERCE_PROMOTIONS, NOSTR_KIND_COMMERCE_STOCK,
+        TEST_PUB_KEY,
     },
 };
-use nostr_minions::relay_pool::NostrProps;
-use nostro2::relays::{NostrSubscription, RelayEvent};
+use nostr_minions::{key_manager::NostrIdStore, relay_pool::NostrProps};
+use nostro2::{
+    notes::NostrNote,
+    relays::{NostrSubscription, RelayEvent},
+};
 use yew::prelude::*;
 
+use super::{ConsumerDataStore, RatingsData};
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct CommerceData {
     has_loaded: bool,
     commerces: Vec<CommerceProfileIdb>,
     products_lists: Vec<ProductMenuIdb>,
+    promotions: Vec<(String, PromotionList)>,
+    /// Stock left per commerce as published by the server, lower than the
+    /// menus show once other consumers paid for some of it.
+    stock: Vec<CommerceStock>,
+    search_index: Rc<SearchIndex>,
+    /// Geohash cells commerces are loaded from, `None` until the consumer's
+    /// address is known and empty to load every commerce.
+    area: Option<Vec<String>>,
+    /// Commerces whose menu and promotions were asked for.
+    requested_menus: Vec<String>,
 }
 
 impl CommerceData {
     pub fn finished_loading(&self) -> bool {
         self.has_loaded
     }
+    pub fn area(&self) -> Option<Vec<String>> {
+        self.area.clone()
+    }
+    pub fn requested_menus(&self) -> Vec<String> {
+        self.requested_menus.clone()
+    }
     pub fn commerces(&self) -> Vec<CommerceProfileIdb> {
         self.commerces.clone()
     }
     pub fn products_lists(&self) -> Vec<ProductMenuIdb> {
-        self.products_lists.clone()
+        self.products_lists
+            .iter()
+            .map(|list| self.with_stock(list))
+            .collect()
+    }
+    fn with_stock(&self, list: &ProductMenuIdb) -> ProductMenuIdb {
+        match self
+            .stock
+            .iter()
+            .find(|stock| stock.commerce_id == list.id())
+        {
+            Some(stock) => list.with_remaining_stock(&stock.remaining),
+            None => list.clone(),
+        }
     }
     pub fn find_commerce_by_id(&self, id: &str) -> Option<CommerceProfileIdb> {
         self.commerces.iter().find(|p| p.id() == id).cloned()
     }
     pub fn find_product_list_by_id(&self, id: &str) -> Option<ProductMenuIdb> {
-        self.products_lists.iter().find(|p| p.id() == id).cloned()
-    }
-    pub fn find_product(&self, commerce_id: &str, product_id: &str) -> Option<ProductItem> {
         self.products_lists
             .iter()
-            .find(|p| p.id() == commerce_id)
-            .and_then(|p| {
-                p.menu().categories().iter().fold(None, |acc, c| {
-                    acc.or_else(|| {
-                        c.products()
-                            .iter()
-                            .find(|i| i.id() == product_id)
-                            .map(|i| i.clone())
-                    })
+            .find(|p| p.id() == id)
+            .map(|list| self.with_stock(list))
+    }
+    pub fn find_promotions(&self, commerce_id: &str) -> Vec<Promotion> {
+        self.promotions
+            .iter()
+            .find(|(pubkey, _)| pubkey == commerce_id)
+            .map(|(_, list)| list.promotions.clone())
+            .unwrap_or_default()
+    }
+    /// Commerces and products matching the query, grouped per commerce. Only
+    /// products that can be ordered at `now` and fit the dietary filter are kept.
+    pub fn search(
+        &self,
+        query: &str,
+        filter: &TagFilter,
+        now: i64,
+    ) -> Vec<(CommerceProfileIdb, Vec<ProductItem>)> {
+        self.search_index
+            .search(query)
+            .into_iter()
+            .filter_map(|result| {
+                let commerce = self.find_commerce_by_id(&result.commerce_id)?;
+                let utc_offset_minutes = commerce.profile().availability.utc_offset_minutes;
+                let menu = self
+                    .find_product_list_by_id(&result.commerce_id)
+                    .map(|menu| menu.menu().at(now, utc_offset_minutes))
+                    .unwrap_or_default();
+                let products: Vec<ProductItem> = result
+                    .product_ids
+                    .iter()
+                    .filter_map(|id| menu.find_product(id))
+                    .filter(|product| filter.matches(product))
+                    .collect();
+                let keep = !products.is_empty() || (result.commerce_matched && filter.is_empty());
+                keep.then_some((commerce, products))
+            })
+            .collect()
+    }
+    /// A past order rebuilt against what the commerce serves at `now`, `None`
+    /// until its profile and menu are loaded.
+    pub fn reorder(&self, commerce_id: &str, past: &ProductOrder, now: i64) -> Option<Reorder> {
+        let commerce = self.find_commerce_by_id(commerce_id)?;
+        let utc_offset_minutes = commerce.profile().availability.utc_offset_minutes;
+        let menu = self.find_product_list_by_id(commerce_id)?;
+        Some(menu.menu().at(now, utc_offset_minutes).reorder(past))
+    }
+    /// Commerces with their rating and, when the consumer's address is known,
+    /// the delivery estimate to it.
+    pub fn listings(
+        &self,
+        ratings: &RatingsData,
+        destination: Option<&CoordinateStrings>,
+        now: i64,
+    ) -> Vec<CommerceListing> {
+        self.commerces
+            .iter()
+            .map(|commerce| {
+                let rating = ratings.get_business_rating(commerce.id());
+                CommerceListing::new(
+                    commerce.id().to_string(),
+                    commerce.profile().clone(),
+                    rating.as_ref(),
+                    destination,
+                    now,
+                )
+            })
+            .collect()
+    }
+    pub fn find_product(&self, commerce_id: &str, product_id: &str) -> Option<ProductItem> {
+        self.find_product_list_by_id(commerce_id).and_then(|p| {
+            p.menu().categories().iter().fold(None, |acc, c| {
+                acc.or_else(|| {
+                    c.products()
+                        .iter()
+                        .find(|i| i.id() == product_id)
+                        .map(|i| i.clone())
                 })
             })
+        })
     }
 }
 
@@ -78,8 +181,12 @@ impl CommerceDataExt for UseReducerHandle<CommerceData> {
 pub enum CommerceDataAction {
     FinishedLoadingRelays,
     UpdateCommerceProfile(CommerceProfileIdb),
-    UpdateProductList(ProductMenuIdb),
+    ApplyMenuNote(NostrNote),
+    UpdatePromotions(String, PromotionList),
+    UpdateStock(CommerceStock),
     FilterWhiteList(Vec<String>),
+    SetArea(Vec<String>),
+    RequestMenu(String),
 }
 
 impl Reducible for CommerceData {
@@ -91,17 +198,77 @@ impl Reducible for CommerceData {
                 let mut commerces = self.commerces.clone();
                 commerces.retain(|p| p.id() != profile.id());
                 commerces.push(profile.clone());
+                let mut search_index = self.search_index.clone();
+                Rc::make_mut(&mut search_index).set_commerce(
+                    profile.id(),
+                    &profile.profile().name,
+                    &profile.profile().description,
+                );
                 Rc::new(CommerceData {
                     commerces,
+                    search_index,
                     ..(*self).clone()
                 })
             }
-            CommerceDataAction::UpdateProductList(list) => {
+            CommerceDataAction::ApplyMenuNote(note) => {
                 let mut products_lists = self.products_lists.clone();
-                products_lists.retain(|p| p.id() != list.id());
-                products_lists.push(list.clone());
+                let commerce_id = note.pubkey.clone();
+                match products_lists.iter_mut().find(|p| p.id() == note.pubkey) {
+                    Some(list) => {
+                        if let Err(e) = list.apply_note(note) {
+                            gloo::console::error!("Error in menu", format!("{:?}", e));
+                        }
+                    }
+                    None => match ProductMenuIdb::try_from(note) {
+                        Ok(list) => products_lists.push(list),
+                        Err(e) => gloo::console::error!("Error in menu", format!("{:?}", e)),
+                    },
+                }
+                let mut search_index = self.search_index.clone();
+                if let Some(list) = products_lists.iter().find(|p| p.id() == commerce_id) {
+                    Rc::make_mut(&mut search_index).set_menu(&commerce_id, &list.menu());
+                }
                 Rc::new(CommerceData {
                     products_lists,
+                    search_index,
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::UpdatePromotions(pubkey, list) => {
+                let mut promotions = self.promotions.clone();
+                promotions.retain(|(id, _)| *id != pubkey);
+                promotions.push((pubkey, list));
+                Rc::new(CommerceData {
+                    promotions,
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::UpdateStock(update) => {
+                let mut stock = self.stock.clone();
+                stock.retain(|known| known.commerce_id != update.commerce_id);
+                stock.push(update);
+                Rc::new(CommerceData {
+                    stock,
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::SetArea(area) => {
+                if self.area.as_ref() == Some(&area) {
+                    return self;
+                }
+                Rc::new(CommerceData {
+                    area: Some(area),
+                    ..(*self).clone()
+                })
+            }
+            CommerceDataAction::RequestMenu(commerce_id) => {
+                if self.requested_menus.contains(&commerce_id) {
+                    return self;
+                }
+                let mut requested_menus = self.requested_menus.clone();
+                requested_menus.push(commerce_id);
+                Rc::new(CommerceData {
+                    requested_menus,
                     ..(*self).clone()
                 })
             }
@@ -118,6 +285,13 @@ impl Reducible for CommerceData {
                     .products_lists
                     .retain(|p| wl.contains(&p.note().pubkey));
                 self_clone
+                    .promotions
+                    .retain(|(pubkey, _)| wl.contains(pubkey));
+                Rc::make_mut(&mut self_clone.search_index).retain_commerces(&wl);
+                self_clone
+                    .stock
+                    .retain(|stock| wl.contains(&stock.commerce_id));
+                self_clone
             }),
         }
     }
@@ -136,6 +310,11 @@ pub fn key_handler(props: &CommerceDataChildren) -> Html {
         has_loaded: false,
         commerces: vec![],
         products_lists: vec![],
+        promotions: vec![],
+        stock: vec![],
+        search_index: Rc::new(SearchIndex::default()),
+        area: None,
+        requested_menus: vec![],
     });
 
     html! {
@@ -158,18 +337,47 @@ pub fn commerce_data_sync() -> Html {
     let relay_events = relay_ctx.relay_events.clone();
 
     let id_handle = sub_id.clone();
-    use_effect_with(admin_configs.clone(), move |configs| {
-        if configs.is_loaded() {
-            let filter: nostro2::relays::SubscribeEvent = NostrSubscription {
+    let area_subscriber = subscriber.clone();
+    use_effect_with(
+        (admin_configs.is_loaded(), ctx.area()),
+        move |(configs_loaded, area)| {
+            if let (true, Some(area)) = (configs_loaded, area) {
+                let mut filter = NostrSubscription {
+                    kinds: Some(vec![NOSTR_KIND_COMMERCE_PROFILE]),
+                    ..Default::default()
+                };
+                area.iter().for_each(|cell| filter.add_tag("#g", cell));
+                let filter: nostro2::relays::SubscribeEvent = filter.into();
+                id_handle.set(filter.1.clone());
+                area_subscriber.emit(filter);
+            }
+            || {}
+        },
+    );
+
+    // Menus are only loaded for the commerces the consumer opens, along with
+    // the profile in case the commerce is outside the consumer's area
+    use_effect_with(ctx.requested_menus(), move |requested| {
+        if let Some(commerce_id) = requested.last() {
+            let filter = NostrSubscription {
                 kinds: Some(vec![
                     NOSTR_KIND_COMMERCE_PROFILE,
                     NOSTR_KIND_COMMERCE_PRODUCTS,
+                    NOSTR_KIND_COMMERCE_MENU_CATEGORY,
+                    NOSTR_KIND_COMMERCE_PROMOTIONS,
                 ]),
+                authors: Some(vec![commerce_id.clone()]),
                 ..Default::default()
-            }
-            .into();
-            id_handle.set(filter.1.clone());
-            subscriber.emit(filter);
+            };
+            subscriber.emit(filter.into());
+            // What other consumers already paid for is only known to the server
+            let mut stock_filter = NostrSubscription {
+                kinds: Some(vec![NOSTR_KIND_COMMERCE_STOCK]),
+                authors: Some(vec![TEST_PUB_KEY.to_string()]),
+                ..Default::default()
+            };
+            stock_filter.add_tag("#d", commerce_id);
+            subscriber.emit(stock_filter.into());
         }
         || {}
     });
@@ -208,13 +416,26 @@ pub fn commerce_data_sync() -> Html {
                             }
                         }
                     }
-                    NOSTR_KIND_COMMERCE_PRODUCTS => {
+                    NOSTR_KIND_COMMERCE_PRODUCTS | NOSTR_KIND_COMMERCE_MENU_CATEGORY => {
                         if admin_wl.contains(&note.pubkey) {
-                            if let Ok(products) = note.clone().try_into() {
-                                ctx_clone.dispatch(CommerceDataAction::UpdateProductList(products));
+                            ctx_clone.dispatch(CommerceDataAction::ApplyMenuNote(note.clone()));
+                        }
+                    }
+                    NOSTR_KIND_COMMERCE_PROMOTIONS => {
+                        if admin_wl.contains(&note.pubkey) {
+                            if let Ok(list) = PromotionList::try_from(note) {
+                                ctx_clone.dispatch(CommerceDataAction::UpdatePromotions(
+                                    note.pubkey.clone(),
+                                    list,
+                                ));
                             }
                         }
                     }
+                    NOSTR_KIND_COMMERCE_STOCK => {
+                        if let Ok(stock) = CommerceStock::try_from(note) {
+                            ctx_clone.dispatch(CommerceDataAction::UpdateStock(stock));
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -231,3 +452,24 @@ pub fn commerce_data_sync() -> Html {
     });
     html! {}
 }
+
+/// Narrows the commerce subscription to the area around the consumer's
+/// default address once it's known. Consumers without one see every commerce.
+#[function_component(CommerceAreaSync)]
+pub fn commerce_area_sync() -> Html {
+    let ctx = use_context::<CommerceDataStore>().expect("Commerce context not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let user_ctx = use_context::<ConsumerDataStore>().expect("ConsumerDataStore not found");
+    use_effect_with((key_ctx, user_ctx), move |(keys, user)| {
+        let signed_in = keys.get_pubkey().is_some();
+        if keys.loaded() && (!signed_in || user.finished_loading()) {
+            let area = user
+                .get_default_address()
+                .map(|address| address.coordinates().geohash_area())
+                .unwrap_or_default();
+            ctx.dispatch(CommerceDataAction::SetArea(area));
+        }
+        || {}
+    });
+    html! {}
+}
@@ -0,0 +1,269 @@
+use std::rc::Rc;
+
+use fuente::models::{
+    GroupContribution, GroupOrder, GroupOrderIdb, GroupShareState, NOSTR_KIND_GROUP_CONTRIBUTION,
+    NOSTR_KIND_GROUP_ORDER, NOSTR_KIND_GROUP_SHARE,
+};
+use nostr_minions::{
+    browser_api::IdbStoreManager, key_manager::NostrIdStore, relay_pool::NostrProps,
+};
+use nostro2::relays::NostrSubscription;
+use yew::{platform::spawn_local, prelude::*};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupOrders {
+    has_loaded: bool,
+    groups: Vec<GroupOrderIdb>,
+    /// Latest description of each group by its host, keyed by group id.
+    orders: Vec<(String, GroupOrder)>,
+    /// Latest items of each participant, keyed by group id and pubkey.
+    contributions: Vec<(String, String, GroupContribution)>,
+    /// Share invoices sent to this consumer, with the time they were sent.
+    shares: Vec<(i64, GroupShareState)>,
+}
+
+impl GroupOrders {
+    pub fn is_loaded(&self) -> bool {
+        self.has_loaded
+    }
+    pub fn groups(&self) -> Vec<GroupOrderIdb> {
+        self.groups.clone()
+    }
+    pub fn group(&self, group_id: &str) -> Option<GroupOrderIdb> {
+        self.groups
+            .iter()
+            .find(|group| group.group_id() == group_id)
+            .cloned()
+    }
+    pub fn group_order(&self, group_id: &str) -> Option<GroupOrder> {
+        self.orders
+            .iter()
+            .find(|(id, _)| id == group_id)
+            .map(|(_, order)| order.clone())
+    }
+    /// Everyone's items in the group, keyed by pubkey, oldest first.
+    pub fn contributions(&self, group_id: &str) -> Vec<(String, GroupContribution)> {
+        let mut contributions = self
+            .contributions
+            .iter()
+            .filter(|(id, _, _)| id == group_id)
+            .map(|(_, pubkey, contribution)| (pubkey.clone(), contribution.clone()))
+            .collect::<Vec<_>>();
+        contributions.sort_by_key(|(_, contribution)| contribution.updated_at);
+        contributions
+    }
+    /// The consumer's share of a group order, sent after the host closed it.
+    pub fn share_since(&self, closed_at: i64) -> Option<GroupShareState> {
+        self.shares
+            .iter()
+            .filter(|(sent_at, _)| *sent_at >= closed_at)
+            .max_by_key(|(sent_at, _)| *sent_at)
+            .map(|(_, share)| share.clone())
+    }
+}
+
+pub enum GroupOrderAction {
+    LoadGroups(Vec<GroupOrderIdb>),
+    JoinGroup(GroupOrderIdb),
+    LeaveGroup(String),
+    UpdateOrder(String, GroupOrder),
+    UpdateContribution(String, String, GroupContribution),
+    UpdateShare(i64, GroupShareState),
+}
+
+impl Reducible for GroupOrders {
+    type Action = GroupOrderAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut new_state = (*self).clone();
+        match action {
+            GroupOrderAction::LoadGroups(groups) => {
+                new_state.groups = groups;
+                new_state.has_loaded = true;
+            }
+            GroupOrderAction::JoinGroup(group) => {
+                if self.group(&group.group_id()).is_some() {
+                    return self;
+                }
+                let db_entry = group.clone();
+                spawn_local(async move {
+                    if let Err(e) = db_entry.save_to_store().await {
+                        gloo::console::error!("Failed to save group order:", e);
+                    }
+                });
+                new_state.groups.push(group);
+            }
+            GroupOrderAction::LeaveGroup(group_id) => {
+                if let Some(group) = self.group(&group_id) {
+                    spawn_local(async move {
+                        if let Err(e) = group.delete_from_store().await {
+                            gloo::console::error!("Failed to delete group order:", e);
+                        }
+                    });
+                }
+                new_state
+                    .groups
+                    .retain(|group| group.group_id() != group_id);
+                new_state.orders.retain(|(id, _)| *id != group_id);
+                new_state.contributions.retain(|(id, _, _)| *id != group_id);
+            }
+            GroupOrderAction::UpdateOrder(group_id, order) => {
+                if self
+                    .group_order(&group_id)
+                    .is_some_and(|known| known.updated_at > order.updated_at)
+                {
+                    return self;
+                }
+                new_state.orders.retain(|(id, _)| *id != group_id);
+                new_state.orders.push((group_id, order));
+            }
+            GroupOrderAction::UpdateContribution(group_id, pubkey, contribution) => {
+                let is_older = self.contributions.iter().any(|(id, key, known)| {
+                    *id == group_id && *key == pubkey && known.updated_at > contribution.updated_at
+                });
+                if is_older {
+                    return self;
+                }
+                new_state
+                    .contributions
+                    .retain(|(id, key, _)| !(*id == group_id && *key == pubkey));
+                new_state
+                    .contributions
+                    .push((group_id, pubkey, contribution));
+            }
+            GroupOrderAction::UpdateShare(sent_at, share) => {
+                new_state.shares.retain(|(_, known)| {
+                    !(known.order_id == share.order_id && known.share == share.share)
+                });
+                new_state.shares.push((sent_at, share));
+            }
+        }
+        Rc::new(new_state)
+    }
+}
+
+pub type GroupOrderStore = UseReducerHandle<GroupOrders>;
+
+#[derive(Clone, Debug, Properties, PartialEq)]
+pub struct GroupOrderChildren {
+    pub children: Children,
+}
+
+#[function_component(GroupOrderProvider)]
+pub fn group_order_provider(props: &GroupOrderChildren) -> Html {
+    let ctx = use_reducer(|| GroupOrders {
+        has_loaded: false,
+        groups: vec![],
+        orders: vec![],
+        contributions: vec![],
+        shares: vec![],
+    });
+
+    let ctx_clone = ctx.clone();
+    use_effect_with((), move |_| {
+        spawn_local(async move {
+            match GroupOrderIdb::retrieve_all_from_store().await {
+                Ok(groups) => ctx_clone.dispatch(GroupOrderAction::LoadGroups(groups)),
+                Err(e) => {
+                    gloo::console::error!("Failed to load group orders:", e);
+                    ctx_clone.dispatch(GroupOrderAction::LoadGroups(vec![]));
+                }
+            }
+        });
+        || {}
+    });
+
+    html! {
+        <ContextProvider<GroupOrderStore> context={ctx}>
+            {props.children.clone()}
+            <GroupOrderSync />
+        </ContextProvider<GroupOrderStore>>
+    }
+}
+
+/// Follows the groups the consumer joined and the share invoices sent to them.
+#[function_component(GroupOrderSync)]
+pub fn group_order_sync() -> Html {
+    let ctx = use_context::<GroupOrderStore>().expect("GroupOrderStore not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
+
+    let subscriber = relay_ctx.subscribe.clone();
+    let group_ids = ctx
+        .groups()
+        .iter()
+        .map(|group| group.group_id())
+        .collect::<Vec<_>>();
+    use_effect_with(group_ids, move |group_ids| {
+        if !group_ids.is_empty() {
+            let mut filter = NostrSubscription {
+                kinds: Some(vec![NOSTR_KIND_GROUP_ORDER, NOSTR_KIND_GROUP_CONTRIBUTION]),
+                ..Default::default()
+            };
+            for group_id in group_ids {
+                filter.add_tag("#d", group_id);
+            }
+            subscriber.emit(filter.into());
+        }
+        || {}
+    });
+
+    let subscriber = relay_ctx.subscribe.clone();
+    use_effect_with(key_ctx.get_pubkey(), move |pubkey| {
+        if let Some(pubkey) = pubkey {
+            let mut filter = NostrSubscription {
+                kinds: Some(vec![NOSTR_KIND_GROUP_SHARE]),
+                ..Default::default()
+            };
+            filter.add_tag("#p", pubkey.as_str());
+            subscriber.emit(filter.into());
+        }
+        || {}
+    });
+
+    let groups = ctx.groups();
+    use_effect_with(relay_ctx.unique_notes.clone(), move |notes| {
+        if let Some(note) = notes.last().cloned() {
+            match note.kind {
+                NOSTR_KIND_GROUP_ORDER | NOSTR_KIND_GROUP_CONTRIBUTION => {
+                    let group = note
+                        .tags
+                        .find_first_parameter()
+                        .and_then(|group_id| groups.iter().find(|g| g.group_id() == group_id));
+                    if let Some(group) = group {
+                        let invite = group.invite();
+                        if let Ok(order) = GroupOrder::from_giftwrap(&note, &invite) {
+                            ctx.dispatch(GroupOrderAction::UpdateOrder(group.group_id(), order));
+                        } else if let Ok(contribution) =
+                            GroupContribution::from_giftwrap(&note, &invite)
+                        {
+                            ctx.dispatch(GroupOrderAction::UpdateContribution(
+                                group.group_id(),
+                                note.pubkey.clone(),
+                                contribution,
+                            ));
+                        }
+                    }
+                }
+                NOSTR_KIND_GROUP_SHARE => {
+                    let keys = key_ctx.clone();
+                    spawn_local(async move {
+                        let Ok(decrypted) = keys.decrypt_note(&note).await else {
+                            gloo::console::error!("Failed to decrypt group share");
+                            return;
+                        };
+                        let Ok(share) = GroupShareState::try_from(decrypted) else {
+                            gloo::console::error!("Failed to parse group share");
+                            return;
+                        };
+                        ctx.dispatch(GroupOrderAction::UpdateShare(note.created_at, share));
+                    });
+                }
+                _ => {}
+            }
+        }
+        || {}
+    });
+
+    html! {}
+}
@@ -1,8 +1,8 @@
 use std::rc::Rc;
 
 use fuente::models::{
-    OrderInvoiceState, OrderPaymentStatus, OrderStateIdb, OrderStatus, NOSTR_KIND_DRIVER_STATE,
-    NOSTR_KIND_ORDER_STATE,
+    DeliveryEta, OrderInvoiceState, OrderPaymentStatus, OrderStateIdb, OrderStatus,
+    NOSTR_KIND_DELIVERY_ETA, NOSTR_KIND_DRIVER_STATE, NOSTR_KIND_ORDER_STATE, TEST_PUB_KEY,
 };
 use nostr_minions::{
     browser_api::IdbStoreManager, key_manager::NostrIdStore, relay_pool::NostrProps,
@@ -14,15 +14,25 @@ use yew::{platform::spawn_local, prelude::*};
 pub struct LiveOrder {
     pub live_orders: Vec<(NostrNote, OrderInvoiceState)>,
     pub has_loaded: bool,
+    /// Latest delivery estimate the server sent for each live order.
+    pub etas: Vec<DeliveryEta>,
 }
 
-impl LiveOrder {}
+impl LiveOrder {
+    pub fn eta(&self, order_id: &str) -> Option<DeliveryEta> {
+        self.etas
+            .iter()
+            .find(|eta| eta.order_id == order_id)
+            .cloned()
+    }
+}
 
 pub enum LiveOrderAction {
     FinishedLoadingRelay,
     UpdateOrder(NostrNote, OrderInvoiceState),
     LoadOrders(Vec<(NostrNote, OrderInvoiceState)>),
     CompleteOrder(String),
+    UpdateEta(DeliveryEta),
 }
 
 impl Reducible for LiveOrder {
@@ -38,6 +48,7 @@ impl Reducible for LiveOrder {
                     orders.push((order, state));
                     orders
                 },
+                etas: self.etas.clone(),
             }),
             LiveOrderAction::CompleteOrder(order_id) => Rc::new(LiveOrder {
                 live_orders: {
@@ -46,15 +57,40 @@ impl Reducible for LiveOrder {
                     orders
                 },
                 has_loaded: self.has_loaded,
+                etas: {
+                    let mut etas = self.etas.clone();
+                    etas.retain(|eta| eta.order_id != order_id);
+                    etas
+                },
             }),
             LiveOrderAction::FinishedLoadingRelay => Rc::new(LiveOrder {
                 has_loaded: true,
                 live_orders: self.live_orders.clone(),
+                etas: self.etas.clone(),
             }),
             LiveOrderAction::LoadOrders(orders) => Rc::new(LiveOrder {
                 has_loaded: self.has_loaded,
                 live_orders: orders,
+                etas: self.etas.clone(),
             }),
+            LiveOrderAction::UpdateEta(eta) => {
+                if self
+                    .eta(&eta.order_id)
+                    .is_some_and(|known| known.updated_at > eta.updated_at)
+                {
+                    return self;
+                }
+                Rc::new(LiveOrder {
+                    has_loaded: self.has_loaded,
+                    live_orders: self.live_orders.clone(),
+                    etas: {
+                        let mut etas = self.etas.clone();
+                        etas.retain(|known| known.order_id != eta.order_id);
+                        etas.push(eta);
+                        etas
+                    },
+                })
+            }
         }
     }
 }
@@ -71,6 +107,7 @@ pub fn key_handler(props: &LiveOrderChildren) -> Html {
     let ctx = use_reducer(|| LiveOrder {
         has_loaded: false,
         live_orders: vec![],
+        etas: vec![],
     });
 
     let order_ctx = ctx.clone();
@@ -139,6 +176,13 @@ pub fn commerce_data_sync() -> Html {
                 let sub: nostro2::relays::SubscribeEvent = filter.into();
                 id_handle.set(sub.1.clone());
                 subscriber.emit(sub);
+                let mut eta_filter = NostrSubscription {
+                    kinds: Some(vec![NOSTR_KIND_DELIVERY_ETA]),
+                    authors: Some(vec![TEST_PUB_KEY.to_string()]),
+                    ..Default::default()
+                };
+                eta_filter.add_tag("#p", keys.as_str());
+                subscriber.emit(eta_filter.into());
             });
         }
         || {}
@@ -185,8 +229,8 @@ pub fn commerce_data_sync() -> Html {
                         .await
                         .expect("Failed to save order state idb");
                     match (&order_status.payment_status, &order_status.order_status) {
-                        (OrderPaymentStatus::PaymentFailed, _) => {}
-                        (_, OrderStatus::Canceled) => {
+                        // Refused and expired orders end the wait for their invoice
+                        (OrderPaymentStatus::PaymentFailed, _) | (_, OrderStatus::Canceled) => {
                             ctx.dispatch(LiveOrderAction::UpdateOrder(order_note, order_status));
                         }
                         (OrderPaymentStatus::PaymentSuccess, OrderStatus::Completed) => {
@@ -198,6 +242,18 @@ pub fn commerce_data_sync() -> Html {
                         }
                     }
                 });
+            } else if note.kind == NOSTR_KIND_DELIVERY_ETA && note.pubkey == TEST_PUB_KEY {
+                spawn_local(async move {
+                    let Ok(decrypted) = keys.decrypt_note(&note).await else {
+                        gloo::console::error!("Failed to decrypt delivery estimate");
+                        return;
+                    };
+                    let Ok(eta) = DeliveryEta::try_from(decrypted) else {
+                        gloo::console::error!("Failed to parse delivery estimate");
+                        return;
+                    };
+                    ctx.dispatch(LiveOrderAction::UpdateEta(eta));
+                });
             }
         }
         || {}
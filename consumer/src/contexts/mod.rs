@@ -1,6 +1,7 @@
 mod cart;
 mod commerce_data;
 mod consumer_data;
+mod group_order;
 mod live_order;
 mod favorites;
 mod ratings;
@@ -8,6 +9,7 @@ pub mod auth;
 pub use cart::*;
 pub use commerce_data::*;
 pub use consumer_data::*;
+pub use group_order::*;
 pub use live_order::*;
 pub use favorites::*;
 pub use ratings::*;
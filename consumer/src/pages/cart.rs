@@ -1,15 +1,25 @@
+use std::collections::HashMap;
+
 use crate::contexts::{
-    CartAction, CartStore, CommerceDataStore, ConsumerDataStore, LiveOrderStore, LoginStateAction,
-    LoginStateStore,
+    CartAction, CartStore, CommerceDataAction, CommerceDataStore, ConsumerDataStore,
+    LiveOrderStore, LoginStateAction, LoginStateStore,
 };
-use crate::pages::OrderInvoiceComponent;
+use crate::pages::{OrderInvoiceComponent, ReorderChangeList};
 use crate::router::ConsumerRoute;
+use bright_lightning::LndHodlInvoice;
 use fuente::contexts::{AdminConfigsStore, LanguageConfigsStore};
 use fuente::mass::{AppLink, ThreeBlockSpinner, Toast, ToastAction, ToastContext, ToastType};
-use fuente::models::{OrderPaymentStatus, ProductItem, ProductOrder};
-use lucide_yew::{ArrowRight, Trash2};
+use fuente::models::{
+    AppliedPromotion, CartBasket, DeliverySlot, ExchangeRates, Money, OrderInvoiceState, OrderLine,
+    OrderPaymentStatus, OrderStatus, ProductOrder, Promotion, TEST_PUB_KEY,
+};
+use lucide_yew::{ArrowRight, CalendarClock, Trash2};
+use nostr_minions::browser_api::HtmlForm;
 use nostr_minions::key_manager::NostrIdStore;
 use nostr_minions::relay_pool::NostrProps;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::wasm_bindgen::{JsCast, JsValue};
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew_router::hooks::use_navigator;
 
@@ -21,9 +31,16 @@ pub fn cart_page() -> Html {
     let key_ctx = use_context::<NostrIdStore>().expect("No key context not found");
     let translations = language_ctx.translations();
     let login_state = use_context::<LoginStateStore>().expect("LoginStateStore not found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce context found");
+    let ctx = commerce_ctx.clone();
+    use_effect_with(cart_ctx.commerce_ids(), move |commerce_ids| {
+        for commerce_id in commerce_ids {
+            ctx.dispatch(CommerceDataAction::RequestMenu(commerce_id.clone()));
+        }
+        || {}
+    });
 
-    let cart_items = cart_ctx.cart();
-    if cart_items.is_empty() {
+    if cart_ctx.is_empty() {
         return html! {
             <EmptyCart />
         };
@@ -39,6 +56,18 @@ pub fn cart_page() -> Html {
         };
     }
 
+    let commerces_loaded = cart_ctx
+        .commerce_ids()
+        .iter()
+        .all(|id| commerce_ctx.find_commerce_by_id(id).is_some());
+    if !commerces_loaded {
+        return html! {
+            <div class="flex flex-1 items-center justify-center">
+                <ThreeBlockSpinner class="w-16 h-16 text-fuente" />
+            </div>
+        };
+    }
+
     html! {
         <main class="flex flex-col overflow-hidden container w-full mx-auto">
             <div class="flex flex-col lg:flex-row justify-between items-center gap-4 py-5">
@@ -47,7 +76,12 @@ pub fn cart_page() -> Html {
                 </h1>
             </div>
             <div class="flex-grow flex flex-col lg:flex-row overflow-hidden lg:justify-center">
-               <CartTemplate order={ProductOrder::new(cart_items)} />
+               <div class="flex flex-col w-full overflow-y-auto no-scrollbar">
+                   <CartRestoreNotice />
+                   {for cart_ctx.baskets().iter().map(|basket| html! {
+                       <CartTemplate commerce_id={basket.commerce_id()} order={basket.product_order()} />
+                   })}
+               </div>
                <CartPreTotal />
             </div>
         </main>
@@ -66,6 +100,9 @@ pub fn empty_cart() -> Html {
                     {&translations["checkout_product_empty_table_heading"]}
                 </h1>
             </div>
+            <div class="container mx-auto">
+                <CartRestoreNotice />
+            </div>
             <div class="container bg-fuente rounded-2xl p-5 flex flex-col mx-auto h-fit w-fit">
                 <div class="flex justify-between items-center lg:mb-4">
                     <h2 class="text-white text-4xl font-semibold tracking-tighter">{&translations["home_stores"]}</h2>
@@ -83,19 +120,49 @@ pub fn empty_cart() -> Html {
     }
 }
 
+/// Lists what changed on the menus since the cart was saved, until dismissed.
+#[function_component(CartRestoreNotice)]
+pub fn cart_restore_notice() -> Html {
+    let cart_ctx = use_context::<CartStore>().expect("No cart context found");
+    let language_ctx =
+        use_context::<LanguageConfigsStore>().expect("No language context not found");
+    let translations = language_ctx.translations();
+    let changes = cart_ctx.restore_changes();
+    if changes.is_empty() {
+        return html! {};
+    }
+    let onclick = Callback::from(move |_: MouseEvent| {
+        cart_ctx.dispatch(CartAction::DismissRestoreChanges);
+    });
+    html! {
+        <div class="border-2 border-fuente-orange rounded-xl p-4 my-2 flex flex-col gap-2">
+            <p class="font-bold text-fuente-dark">{&translations["cart_restore_changes"]}</p>
+            <ReorderChangeList {changes} />
+            <button {onclick} class="self-end text-fuente font-bold">
+                {&translations["cart_restore_dismiss"]}
+            </button>
+        </div>
+    }
+}
+
 #[derive(Properties, Clone, PartialEq)]
 pub struct CartTemplateProps {
+    pub commerce_id: String,
     pub order: ProductOrder,
 }
 
 #[function_component(CartTemplate)]
 pub fn checkout_cart_template(props: &CartTemplateProps) -> Html {
-    let CartTemplateProps { order } = props;
+    let CartTemplateProps { commerce_id, order } = props;
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
     let language_ctx =
         use_context::<LanguageConfigsStore>().expect("No language context not found");
     let translations = language_ctx.translations();
-    let counted_products = order.counted_products();
-    if counted_products.is_empty() {
+    let commerce_name = commerce_ctx
+        .find_commerce_by_id(commerce_id)
+        .map(|commerce| commerce.profile().name.clone())
+        .unwrap_or(translations["cart_text"].clone());
+    if order.is_empty() {
         return html! {
             <div class="border-2 border-fuente mt-10 px-5 rounded-3xl h-[550px] overflow-y-scroll">
                 <h2 class="flex text-2xl text-fuente font-bold pt-5">{&translations["checkout_product_empty_table_heading"]}</h2>
@@ -105,7 +172,7 @@ pub fn checkout_cart_template(props: &CartTemplateProps) -> Html {
     html! {
         <div class="overflow-hidden py-2 lg:py-4 w-full">
             <div class="overflow-auto border-2 border-fuente rounded-xl no-scrollbar relative p-2">
-                <h2 class="bg-white flex text-2xl text-fuente font-bold px-10 pt-5 sticky top-0">{&translations["cart_text"]}</h2>
+                <h2 class="bg-white flex text-2xl text-fuente font-bold px-10 pt-5 sticky top-0">{commerce_name}</h2>
 
                 <div class="hidden lg:flex justify-between items-center lg:mt-10 xl:mt-5">
                     <h3></h3>
@@ -114,29 +181,154 @@ pub fn checkout_cart_template(props: &CartTemplateProps) -> Html {
                     <h3 class="text-fuente lg:pr-10 xl:pr-28">{&translations["cart_table_heading_price"]}</h3>
                     <h3></h3>
                 </div>
-                {order.counted_products().iter().map(|(item, count)| {
-
+                {order.lines().iter().map(|line| {
                     html! {
-                        <CartItemDetails
-                            item={item.clone()}
-                            count={*count} />
+                        <CartItemDetails commerce_id={commerce_id.clone()} line={line.clone()} />
                     }
                 }).collect::<Html>()}
             </div>
         </div>
     }
 }
+/// What checking out one basket costs, or why it can't be ordered as it is.
+struct BasketCheckout {
+    /// The basket priced for the current menu window.
+    priced: Option<ProductOrder>,
+    total: Money,
+    error: Option<String>,
+    /// Items still to add to reach the commerce's minimum order.
+    remaining: Money,
+    /// Charged instead when the commerce takes orders under its minimum.
+    small_order_fee: Option<Money>,
+    /// No stock or price errors and, if a promo code was entered, it applies.
+    ready: bool,
+}
+
+fn basket_checkout(
+    basket: &CartBasket,
+    commerce_ctx: &CommerceDataStore,
+    admin_ctx: &AdminConfigsStore,
+    order_ctx: &LiveOrderStore,
+) -> BasketCheckout {
+    let order = basket.product_order();
+    let promotion = cart_promotion(
+        basket,
+        commerce_ctx,
+        admin_ctx,
+        order_ctx,
+        basket.promo_code().as_deref(),
+    );
+    let discount = promotion
+        .as_ref()
+        .ok()
+        .and_then(Option::as_ref)
+        .map(AppliedPromotion::amount)
+        .unwrap_or_default();
+    // Tips are picked in SRD, the order may be priced in another currency
+    let tip = admin_ctx
+        .get_exchange_rates()
+        .convert(basket.courier_tip(), order.total().currency());
+    let commerce_id = basket.commerce_id();
+    let menu = commerce_ctx
+        .find_product_list_by_id(&commerce_id)
+        .map(|menu| menu.menu());
+    let profile = commerce_ctx
+        .find_commerce_by_id(&commerce_id)
+        .map(|commerce| commerce.profile().clone());
+    let utc_offset_minutes = profile
+        .as_ref()
+        .map(|profile| profile.availability.utc_offset_minutes)
+        .unwrap_or_default();
+    let order_minimum = profile
+        .map(|profile| profile.order_minimum)
+        .unwrap_or_default();
+    let small_order_fee = order_minimum.surcharge(order.total());
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+    let priced = menu
+        .as_ref()
+        .map(|menu| menu.price_order(&order, now, utc_offset_minutes));
+    // The menu stock already leaves out what the server published as paid for
+    let error = menu
+        .as_ref()
+        .and_then(|menu| menu.check_stock(&order, &HashMap::new()).err())
+        .map(|e| e.to_string())
+        .or_else(|| {
+            priced
+                .as_ref()
+                .and_then(|priced| priced.as_ref().err())
+                .map(ToString::to_string)
+        })
+        .or_else(|| tip.as_ref().err().map(ToString::to_string));
+    let fee = small_order_fee.as_ref().copied().unwrap_or_default();
+    BasketCheckout {
+        priced: priced.as_ref().and_then(|p| p.as_ref().ok()).cloned(),
+        total: order.total() + fee - discount + tip.as_ref().copied().unwrap_or_default(),
+        remaining: order_minimum.remaining(order.total()),
+        ready: error.is_none() && promotion.is_ok() && small_order_fee.is_ok(),
+        small_order_fee: small_order_fee.ok().filter(Money::is_positive),
+        error,
+    }
+}
+
+/// Adds up amounts in the currency of the first one, failing when one of
+/// them has no exchange rate.
+fn combined_total(amounts: &[Money], rates: &ExchangeRates) -> Result<Money, String> {
+    let Some(first) = amounts.first() else {
+        return Ok(Money::default());
+    };
+    amounts
+        .iter()
+        .skip(1)
+        .try_fold(*first, |total, amount| {
+            total.checked_add(rates.convert(*amount, first.currency())?)
+        })
+        .map_err(|e| e.to_string())
+}
+
 #[function_component(CartPreTotal)]
 pub fn cart_pre_total() -> Html {
     let cart_ctx = use_context::<CartStore>().expect("No cart context found");
     let key_ctx = use_context::<NostrIdStore>().expect("No key context not found");
     let relay_ctx = use_context::<NostrProps>().expect("No relay context not found");
     let user_ctx = use_context::<ConsumerDataStore>().expect("No user context found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let order_ctx = use_context::<LiveOrderStore>().expect("LiveOrderStore not found");
     let language_ctx =
         use_context::<LanguageConfigsStore>().expect("No language context not found");
     let translations = language_ctx.translations();
-    let order = ProductOrder::new(cart_ctx.cart());
-    let id = cart_ctx.business_id().expect("No business id");
+    let baskets = cart_ctx.baskets();
+    let checkouts = baskets
+        .iter()
+        .map(|basket| basket_checkout(basket, &commerce_ctx, &admin_ctx, &order_ctx))
+        .collect::<Vec<_>>();
+    let rates = admin_ctx.get_exchange_rates();
+    let totals = checkouts
+        .iter()
+        .map(|checkout| checkout.total)
+        .collect::<Vec<_>>();
+    let can_checkout = checkouts.iter().all(|checkout| checkout.ready);
+    // Scheduled discounts may have started or ended since the items were added
+    let repriced = baskets
+        .iter()
+        .zip(checkouts.iter())
+        .filter_map(|(basket, checkout)| {
+            let priced = checkout.priced.clone()?;
+            (priced != basket.product_order()).then(|| (basket.commerce_id(), priced))
+        })
+        .collect::<Vec<_>>();
+    use_effect_with(repriced, {
+        let cart_ctx = cart_ctx.clone();
+        move |repriced| {
+            for (commerce_id, priced) in repriced {
+                cart_ctx.dispatch(CartAction::RepriceItems(
+                    commerce_id.clone(),
+                    priced.clone(),
+                ));
+            }
+            || {}
+        }
+    });
     let profile = user_ctx.get_profile();
     let address = user_ctx.get_default_address();
     let navigator = use_navigator().expect("No navigator found");
@@ -146,57 +338,362 @@ pub fn cart_pre_total() -> Html {
         let sender = relay_ctx.send_note.clone();
         Callback::from(move |e: MouseEvent| {
             e.prevent_default();
-            let keys = key_ctx.get_identity().cloned();
+            let keys = key_ctx.get_identity().cloned().expect("No user keys found");
             let cart_ctx = cart_ctx.clone();
-            let id = id.clone();
             let profile = profile.clone();
             let address = address.clone();
             let navigator = navigator.clone();
             let sender = sender.clone();
             yew::platform::spawn_local(async move {
-                let note = cart_ctx.sign_request(
-                    &keys.unwrap(),
-                    id.clone(),
-                    profile.clone().unwrap(),
-                    address.clone().unwrap(),
-                ).await;
-                sender.emit(note.1);
-                cart_ctx.dispatch(CartAction::SentOrder(note.0));
+                // Each commerce gets an order of its own, with its own invoice and courier
+                let mut sent_orders = vec![];
+                for basket in cart_ctx.baskets() {
+                    let (order_id, note) = basket
+                        .sign_request(&keys, profile.clone().unwrap(), address.clone().unwrap())
+                        .await;
+                    sender.emit(note);
+                    sent_orders.push((basket.commerce_id(), order_id));
+                }
+                cart_ctx.dispatch(CartAction::SentOrders(sent_orders));
                 navigator.push(&ConsumerRoute::Checkout);
             });
         })
     };
     html! {
         <div class="flex flex-col gap-4 mx-auto h-fit">
-            <div class="bg-gray-100 p-5 m-5 rounded-2xl flex justify-end items-center">
+            {for baskets.iter().zip(checkouts.iter()).map(|(basket, checkout)| html! {
+                <CartBasketOptions
+                    commerce_id={basket.commerce_id()}
+                    total={checkout.total}
+                    remaining={checkout.remaining}
+                    small_order_fee={checkout.small_order_fee}
+                    error={checkout.error.clone()}
+                />
+            })}
+            if baskets.len() > 1 {
+                <div class="bg-gray-100 p-5 mx-5 rounded-2xl flex justify-end items-center">
+                    <p class="text-center text-fuente text-lg flex items-center gap-5">
+                        {&translations["cart_combined_total"]}
+                        {match combined_total(&totals, &rates) {
+                            Ok(total) => html! {
+                                <span class="font-bold text-2xl md:text-3xl">{language_ctx.format_price(total, &rates)}</span>
+                            },
+                            Err(e) => html! {
+                                <span class="text-red-500 text-sm">{e}</span>
+                            },
+                        }}
+                    </p>
+                </div>
+            }
+            <div class="lg:flex lg:justify-center my-3 px-5 lg:px-12">
+                <button onclick={send_order_request} disabled={!can_checkout}
+                    class="disabled:opacity-50 bg-fuente-light text-lg w-full lg:w-fit text-nowrap py-4 px-10 rounded-full font-bold text-white">
+                    {&translations["cart_checkout"]}
+                </button>
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties, Clone, PartialEq)]
+pub struct CartBasketOptionsProps {
+    pub commerce_id: String,
+    pub total: Money,
+    pub remaining: Money,
+    pub small_order_fee: Option<Money>,
+    pub error: Option<String>,
+}
+
+/// Total, delivery time, tip and promo code of the order sent to one commerce.
+#[function_component(CartBasketOptions)]
+pub fn cart_basket_options(props: &CartBasketOptionsProps) -> Html {
+    let CartBasketOptionsProps {
+        commerce_id,
+        total,
+        remaining,
+        small_order_fee,
+        error,
+    } = props;
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx found");
+    let translations = language_ctx.translations();
+    let rates = admin_ctx.get_exchange_rates();
+    let commerce_name = commerce_ctx
+        .find_commerce_by_id(commerce_id)
+        .map(|commerce| commerce.profile().name.clone())
+        .unwrap_or_default();
+    html! {
+        <div class="flex flex-col gap-4">
+            <div class="bg-gray-100 p-5 mx-5 mt-5 rounded-2xl flex justify-between items-center gap-5">
+                <p class="text-fuente text-lg font-bold">{commerce_name}</p>
                 <p class="text-center text-fuente text-lg flex items-center gap-5">
                     {&translations["cart_pre_total"]}
-                    <span class="font-bold text-2xl md:text-3xl">{format!("SRD {:.2}", order.total())}</span>
+                    <span class="font-bold text-2xl md:text-3xl">{language_ctx.format_price(*total, &rates)}</span>
                 </p>
             </div>
+            if remaining.is_positive() {
+                <div class="bg-gray-100 p-5 mx-5 rounded-2xl flex flex-col gap-1">
+                    <p class={classes!("text-lg", "font-bold", if small_order_fee.is_some() { "text-fuente" } else { "text-red-500" })}>
+                        {format!("{} {}", translations["cart_minimum_remaining"], language_ctx.format_price(*remaining, &rates))}
+                    </p>
+                    if let Some(fee) = small_order_fee {
+                        <p class="text-gray-500 text-sm">
+                            {format!("{} {}", translations["cart_small_order_fee"], language_ctx.format_price(*fee, &rates))}
+                        </p>
+                    }
+                </div>
+            }
+            <DeliverySlotPicker commerce_id={commerce_id.clone()} />
+            <CourierTipPicker commerce_id={commerce_id.clone()} />
+            <PromoCodeForm commerce_id={commerce_id.clone()} />
+            if let Some(error) = error.as_ref() {
+                <p class="text-red-500 text-sm text-center">{error}</p>
+            }
+        </div>
+    }
+}
 
-            <div class="lg:flex lg:justify-center my-3 px-5 lg:px-12">
-                <button onclick={send_order_request}
-                    class="bg-fuente-light text-lg w-full lg:w-fit text-nowrap py-4 px-10 rounded-full font-bold text-white">
-                    {&translations["cart_checkout"]}
-                </button>
+#[derive(Properties, Clone, PartialEq)]
+pub struct CartBasketProps {
+    pub commerce_id: String,
+}
+
+#[function_component(DeliverySlotPicker)]
+pub fn delivery_slot_picker(props: &CartBasketProps) -> Html {
+    let cart_ctx = use_context::<CartStore>().expect("No cart context found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx found");
+    let translations = language_ctx.translations();
+    let commerce_id = props.commerce_id.clone();
+    let (Some(basket), Some(business)) = (
+        cart_ctx.basket(&commerce_id),
+        commerce_ctx.find_commerce_by_id(&commerce_id),
+    ) else {
+        return html! {};
+    };
+    let availability = business.profile().availability.clone();
+    if !availability.accepts_scheduled_orders() {
+        return html! {};
+    }
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+    let slots = availability.upcoming_slots(now);
+    let selected = basket.delivery_slot();
+    let onchange = {
+        let slots = slots.clone();
+        Callback::from(move |e: Event| {
+            let value = e
+                .target_unchecked_into::<web_sys::HtmlSelectElement>()
+                .value();
+            let slot = value
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| slots.get(index).cloned());
+            cart_ctx.dispatch(CartAction::ScheduleDelivery(commerce_id.clone(), slot));
+        })
+    };
+    html! {
+        <div class="bg-gray-100 p-5 mx-5 rounded-2xl flex flex-col md:flex-row md:justify-between md:items-center gap-3">
+            <p class="text-fuente text-lg font-bold flex items-center gap-2">
+                <CalendarClock class="w-6 h-6 text-fuente" />
+                {&translations["cart_delivery_time"]}
+            </p>
+            <select {onchange} class="bg-white border border-fuente rounded-xl px-4 py-2 text-fuente">
+                <option value="asap" selected={selected.is_none()}>{&translations["cart_delivery_asap"]}</option>
+                {for slots.iter().enumerate().map(|(index, slot)| html! {
+                    <option value={index.to_string()} selected={selected.as_ref() == Some(slot)}>
+                        {slot.locale_display()}
+                    </option>
+                })}
+            </select>
+        </div>
+    }
+}
+
+const COURIER_TIP_OPTIONS: [Money; 4] = [
+    Money::srd(0),
+    Money::srd(1000),
+    Money::srd(2500),
+    Money::srd(5000),
+];
+
+#[function_component(CourierTipPicker)]
+pub fn courier_tip_picker(props: &CartBasketProps) -> Html {
+    let cart_ctx = use_context::<CartStore>().expect("No cart context found");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx found");
+    let translations = language_ctx.translations();
+    let current_tip = cart_ctx
+        .basket(&props.commerce_id)
+        .map(|basket| basket.courier_tip())
+        .unwrap_or_default();
+    let rates = admin_ctx.get_exchange_rates();
+    html! {
+        <div class="bg-gray-100 p-5 mx-5 rounded-2xl flex flex-col md:flex-row md:justify-between md:items-center gap-3">
+            <p class="text-fuente text-lg font-bold">{&translations["cart_courier_tip"]}</p>
+            <div class="flex gap-2">
+                {for COURIER_TIP_OPTIONS.iter().map(|tip| {
+                    let selected = current_tip == *tip;
+                    let onclick = {
+                        let cart_ctx = cart_ctx.clone();
+                        let commerce_id = props.commerce_id.clone();
+                        let tip = *tip;
+                        Callback::from(move |_| {
+                            cart_ctx.dispatch(CartAction::SetCourierTip(commerce_id.clone(), tip))
+                        })
+                    };
+                    html! {
+                        <button {onclick} type="button"
+                            class={classes!(
+                                "px-4", "py-2", "rounded-xl", "border", "border-fuente", "font-bold",
+                                if selected { "bg-fuente text-white" } else { "bg-white text-fuente" }
+                            )}>
+                            {if tip.is_zero() {
+                                translations["cart_courier_tip_none"].clone()
+                            } else {
+                                language_ctx.format_price(*tip, &rates)
+                            }}
+                        </button>
+                    }
+                })}
             </div>
         </div>
     }
 }
 
+fn cart_promotion(
+    basket: &CartBasket,
+    commerce_ctx: &CommerceDataStore,
+    admin_ctx: &AdminConfigsStore,
+    order_ctx: &LiveOrderStore,
+    code: Option<&str>,
+) -> Result<Option<AppliedPromotion>, String> {
+    let commerce_id = basket.commerce_id();
+    let mut offered: Vec<(String, Promotion)> = admin_ctx
+        .get_promotions()
+        .into_iter()
+        .map(|promotion| (TEST_PUB_KEY.to_string(), promotion))
+        .collect();
+    offered.extend(
+        commerce_ctx
+            .find_promotions(&commerce_id)
+            .into_iter()
+            .map(|promotion| (commerce_id.clone(), promotion)),
+    );
+    let is_first_order = !order_ctx
+        .live_orders
+        .iter()
+        .any(|(_, order)| order.order_status != OrderStatus::Canceled);
+    basket.promotion(
+        code,
+        &offered,
+        is_first_order,
+        admin_ctx.get_exchange_rates(),
+    )
+}
+
+#[function_component(PromoCodeForm)]
+pub fn promo_code_form(props: &CartBasketProps) -> Html {
+    let cart_ctx = use_context::<CartStore>().expect("No cart context found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let order_ctx = use_context::<LiveOrderStore>().expect("LiveOrderStore not found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx found");
+    let translations = language_ctx.translations();
+    let code_error = use_state(|| None::<String>);
+    let Some(basket) = cart_ctx.basket(&props.commerce_id) else {
+        return html! {};
+    };
+    let promo_code = basket.promo_code();
+    let rates = admin_ctx.get_exchange_rates();
+    let applied = cart_promotion(
+        &basket,
+        &commerce_ctx,
+        &admin_ctx,
+        &order_ctx,
+        promo_code.as_deref(),
+    );
+    let onsubmit = {
+        let cart_ctx = cart_ctx.clone();
+        let basket = basket.clone();
+        let code_error = code_error.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let form = HtmlForm::new(e).expect("Failed to get form");
+            let code = form.input_value("promo_code").unwrap_or_default();
+            match cart_promotion(&basket, &commerce_ctx, &admin_ctx, &order_ctx, Some(&code)) {
+                Ok(_) => {
+                    code_error.set(None);
+                    cart_ctx.dispatch(CartAction::ApplyPromoCode(
+                        basket.commerce_id(),
+                        Some(code.trim().to_uppercase()),
+                    ));
+                }
+                Err(e) => code_error.set(Some(e)),
+            }
+        })
+    };
+    let remove_code = {
+        let cart_ctx = cart_ctx.clone();
+        let commerce_id = basket.commerce_id();
+        Callback::from(move |_: MouseEvent| {
+            cart_ctx.dispatch(CartAction::ApplyPromoCode(commerce_id.clone(), None))
+        })
+    };
+    let error = applied.as_ref().err().cloned().or((*code_error).clone());
+    html! {
+        <div class="bg-gray-100 p-5 mx-5 rounded-2xl flex flex-col gap-3">
+            {match &promo_code {
+                Some(code) => html! {
+                    <div class="flex justify-between items-center gap-3">
+                        <p class="text-fuente text-lg font-bold">{format!("{}: {}", translations["cart_promo_code"], code)}</p>
+                        <button onclick={remove_code} type="button" class="text-red-500 font-bold">
+                            {&translations["cart_promo_remove"]}
+                        </button>
+                    </div>
+                },
+                None => html! {
+                    <form {onsubmit} class="flex flex-col md:flex-row md:justify-between md:items-center gap-3">
+                        <label for="promo_code" class="text-fuente text-lg font-bold">{&translations["cart_promo_code"]}</label>
+                        <div class="flex gap-2">
+                            <input id="promo_code" name="promo_code" type="text" required={true}
+                                class="bg-white border border-fuente rounded-xl px-4 py-2 text-fuente uppercase" />
+                            <input type="submit" value={translations["cart_promo_apply"].clone()}
+                                class="bg-fuente text-white font-bold rounded-xl px-4 py-2 cursor-pointer" />
+                        </div>
+                    </form>
+                },
+            }}
+            {match applied {
+                Ok(Some(promotion)) => html! {
+                    <p class="text-green-600 font-bold">
+                        {format!("{} {} -{}", translations["cart_discount"], promotion.description, language_ctx.format_price(promotion.discount, &rates))}
+                    </p>
+                },
+                _ => html! {},
+            }}
+            {match error {
+                Some(e) => html! {
+                    <p class="text-red-500 text-sm">{format!("{} ({})", translations["cart_promo_invalid"], e)}</p>
+                },
+                None => html! {},
+            }}
+        </div>
+    }
+}
+
 #[function_component(CheckoutPage)]
 pub fn cart_page() -> Html {
     let cart_ctx = use_context::<CartStore>().expect("No cart context found");
     let language_ctx =
         use_context::<LanguageConfigsStore>().expect("No language context not found");
     let translations = language_ctx.translations();
-    let order_id = cart_ctx.last_sent_order().unwrap_or_default();
+    let sent_orders = cart_ctx.sent_orders();
 
-    let cart_items = cart_ctx.cart();
-    if cart_items.is_empty() {
+    if cart_ctx.is_empty() {
         return html! {};
     }
+    let baskets = cart_ctx.baskets();
 
     html! {
         <main class="flex flex-col h-screen overflow-hidden container mx-auto">
@@ -209,12 +706,16 @@ pub fn cart_page() -> Html {
                 <div class="grid xl:grid-cols-[3fr_1fr] gap-5 overflow-y-auto">
                     <div>
                         <CheckoutClientInfo />
-                        <CartTemplate order={ProductOrder::new(cart_items)} />
+                        {for baskets.iter().map(|basket| html! {
+                            <CartTemplate commerce_id={basket.commerce_id()} order={basket.product_order()} />
+                        })}
                     </div>
 
                     <div>
-                        <CheckoutInvoice order_id={order_id} />
-                        <CheckoutOrderSummary />
+                        <CheckoutInvoices {sent_orders} />
+                        {for baskets.iter().map(|basket| html! {
+                            <CheckoutOrderSummary commerce_id={basket.commerce_id()} />
+                        })}
                     </div>
                 </div>
             </div>
@@ -224,75 +725,117 @@ pub fn cart_page() -> Html {
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct CartItemDetailsProps {
-    pub item: ProductItem,
-    pub count: u32,
+    pub commerce_id: String,
+    pub line: OrderLine,
 }
 
 #[function_component(CartItemDetails)]
 pub fn cart_item_details(props: &CartItemDetailsProps) -> Html {
     let cart_ctx = use_context::<CartStore>().expect("No cart context found");
-    let CartItemDetailsProps { item, count } = props;
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx found");
+    let translations = language_ctx.translations();
+    let CartItemDetailsProps { commerce_id, line } = props;
+    let product = commerce_ctx
+        .find_product_list_by_id(commerce_id)
+        .and_then(|menu| menu.menu().find_product(&line.product_id()));
+    let can_add_one = product
+        .as_ref()
+        .is_none_or(|product| cart_ctx.can_add_one(commerce_id, product));
     let remove_one_item = {
         let cart_ctx = cart_ctx.clone();
-        let item_clone = item.clone();
+        let commerce_id = commerce_id.clone();
+        let line = line.clone();
         let toast_ctx = use_context::<ToastContext>().expect("No toast context");
         Callback::from(move |_: MouseEvent| {
-            cart_ctx.dispatch(CartAction::RemoveProduct(item_clone.clone()));
+            cart_ctx.dispatch(CartAction::RemoveProduct(commerce_id.clone(), line.clone()));
             toast_ctx.dispatch(ToastAction::Show(Toast {
-                message: format!("1 {} removed from cart", item_clone.name()),
+                message: format!("1 {} removed from cart", line.name()),
                 toast_type: ToastType::Error,
             }));
         })
     };
     let add_one_item = {
         let cart_ctx = cart_ctx.clone();
-        let item_clone = item.clone();
+        let commerce_id = commerce_id.clone();
+        let line = line.clone();
         let toast_ctx = use_context::<ToastContext>().expect("No toast context");
         Callback::from(move |_: MouseEvent| {
-            cart_ctx.dispatch(CartAction::AddOne(item_clone.clone()));
+            cart_ctx.dispatch(CartAction::AddOne(commerce_id.clone(), line.clone()));
             toast_ctx.dispatch(ToastAction::Show(Toast {
-                message: format!("{} added to cart", item_clone.name()),
+                message: format!("{} added to cart", line.name()),
                 toast_type: ToastType::Success,
             }));
         })
     };
     let clear_product = {
         let cart_ctx = cart_ctx.clone();
-        let item_clone = item.clone();
+        let commerce_id = commerce_id.clone();
+        let line = line.clone();
         let toast_ctx = use_context::<ToastContext>().expect("No toast context");
         Callback::from(move |_: MouseEvent| {
-            cart_ctx.dispatch(CartAction::ClearProduct(item_clone.clone()));
+            cart_ctx.dispatch(CartAction::ClearProduct(commerce_id.clone(), line.clone()));
             toast_ctx.dispatch(ToastAction::Show(Toast {
-                message: format!("All {} removed from cart", item_clone.name()),
+                message: format!("All {} removed from cart", line.name()),
                 toast_type: ToastType::Error,
             }));
         })
     };
-    let price = item.price().parse::<f64>().unwrap() * *count as f64;
+    let onchange_note = {
+        let cart_ctx = cart_ctx.clone();
+        let commerce_id = commerce_id.clone();
+        let line = line.clone();
+        Callback::from(move |e: Event| {
+            let note = e.target_unchecked_into::<HtmlInputElement>().value();
+            cart_ctx.dispatch(CartAction::SetNote(
+                commerce_id.clone(),
+                line.clone(),
+                Some(note),
+            ));
+        })
+    };
     html! {
         <div class="flex flex-col min-[450px]:flex-row justify-center sm:justify-between items-center gap-5 mt-10 py-10 px-10">
             <img
-                src={item.thumbnail_url()}
+                src={product.as_ref().map(|product| product.thumbnail_url()).unwrap_or("/public/assets/img/logo.png".to_string())}
                 alt="Product Image"
                 class="size-32 object-contain bg-gray-100 rounded-xl block"
             />
             <div class="flex items-center min-[450px]:items-start flex-col">
-                <p class="text-gray-500 font-bold">{item.name()}</p>
-                <p class="text-gray-500 font-light line-clamp-3 max-w-32">{item.details()}</p>
-                <p class="text-gray-500 font-bold uppercase">{format!("SKU: {}", item.sku())}</p>
-                <button onclick={add_one_item.clone()}
-                    class="lg:hidden border-2 border-fuente px-5 py-2 rounded-xl w-fit mt-1">{count}</button>
+                <p class="text-gray-500 font-bold">{line.name()}</p>
+                if let Some(product) = product.as_ref() {
+                    <p class="text-gray-500 font-light line-clamp-3 max-w-32">{product.details()}</p>
+                    <p class="text-gray-500 font-bold uppercase">{format!("SKU: {}", product.sku())}</p>
+                }
+                if !line.sides().is_empty() {
+                    <p class="text-fuente font-semibold text-sm max-w-32">{line.sides_display()}</p>
+                }
+                if product.as_ref().is_some_and(|product| product.is_sold_out()) {
+                    <p class="text-red-500 font-semibold text-sm">{&translations["product_sold_out"]}</p>
+                } else if product.as_ref().is_some_and(|product| !product.can_supply(cart_ctx.quantity_of(commerce_id, &product.id()), 0)) {
+                    <p class="text-red-500 font-semibold text-sm">{&translations["cart_over_stock"]}</p>
+                }
+                <input
+                    type="text"
+                    class="border border-gray-300 rounded-lg px-2 py-1 text-sm mt-1 max-w-48"
+                    placeholder={translations["cart_line_note"].clone()}
+                    value={line.note().unwrap_or_default()}
+                    onchange={onchange_note}
+                />
+                <button onclick={add_one_item.clone()} disabled={!can_add_one}
+                    class="lg:hidden border-2 border-fuente px-5 py-2 rounded-xl w-fit mt-1">{line.quantity()}</button>
             </div>
 
             <div class="hidden lg:flex items-center justify-between border-2 border-fuente rounded-xl gap-5 px-2">
                 <button onclick={remove_one_item}
                     class="text-gray-500 w-full py-3">{"-"}</button>
-                <button class="text-gray-500 w-full py-3">{count}</button>
-                <button onclick={add_one_item}
-                    class="text-gray-500 w-full py-3">{"+"}</button>
+                <button class="text-gray-500 w-full py-3">{line.quantity()}</button>
+                <button onclick={add_one_item} disabled={!can_add_one}
+                    class="text-gray-500 w-full py-3 disabled:opacity-50">{"+"}</button>
             </div>
 
-            <p class="text-2xl text-center md:text-4xl text-fuente font-bold">{format!("SRD {:.2}", price)}</p>
+            <p class="text-2xl text-center md:text-4xl text-fuente font-bold">{language_ctx.format_price(line.subtotal(), &admin_ctx.get_exchange_rates())}</p>
 
             <button onclick={clear_product} >
                 <Trash2 class="w-8 h-8 text-red-500" />
@@ -342,16 +885,36 @@ pub fn checkout_summary() -> Html {
     }
 }
 #[function_component(CheckoutOrderSummary)]
-pub fn checkout_summary() -> Html {
+pub fn checkout_summary(props: &CartBasketProps) -> Html {
     let cart_ctx = use_context::<CartStore>().expect("No cart context found");
     let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let order_ctx = use_context::<LiveOrderStore>().expect("LiveOrderStore not found");
     let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx found");
     let translations = language_ctx.translations();
-    let order = ProductOrder::new(cart_ctx.cart());
+    let Some(basket) = cart_ctx.basket(&props.commerce_id) else {
+        return html! {};
+    };
+    let order = basket.product_order();
+    let promotion = cart_promotion(
+        &basket,
+        &commerce_ctx,
+        &admin_ctx,
+        &order_ctx,
+        basket.promo_code().as_deref(),
+    )
+    .ok()
+    .flatten();
+    let rates = admin_ctx.get_exchange_rates();
     let business = commerce_ctx
-        .find_commerce_by_id(cart_ctx.business_id().expect("No business id").as_str())
+        .find_commerce_by_id(&props.commerce_id)
         .expect("No business found");
     let business = business.profile();
+    let small_order_fee = business
+        .order_minimum
+        .surcharge(order.total())
+        .ok()
+        .filter(Money::is_positive);
     html! {
         <div class="bg-zinc-100 py-7 px-10 rounded-2xl mt-7 mx-2 lg:mx-4">
             <h2 class="text-fuente text-3xl font-bold mt-7">{&translations["checkout_summary_heading"]}</h2>
@@ -359,12 +922,31 @@ pub fn checkout_summary() -> Html {
             <div class="border-y border-y-fuente mt-7 space-y-5 py-5">
                 <div class="space-y-2">
                     <h3 class="text-gray-500 font-bold text-lg">{&translations["checkout_summary_price_details_pre_total"]}</h3>
-                    <p class="text-gray-400 text-lg font-light">{format!("SRD {:.2}", order.total())}</p>
+                    <p class="text-gray-400 text-lg font-light">{language_ctx.format_price(order.total(), &rates)}</p>
                 </div>
                 <div class="space-y-2">
                     <h3 class="text-gray-500 font-bold text-lg">{&translations["checkout_summary_price_details_fee"]}</h3>
                     <p class="text-gray-400 text-lg font-light">{"Free"}</p>
                 </div>
+                <div class="space-y-2">
+                    <h3 class="text-gray-500 font-bold text-lg">{&translations["cart_courier_tip"]}</h3>
+                    <p class="text-gray-400 text-lg font-light">{language_ctx.format_price(basket.courier_tip(), &rates)}</p>
+                </div>
+                if let Some(fee) = small_order_fee {
+                    <div class="space-y-2">
+                        <h3 class="text-gray-500 font-bold text-lg">{&translations["cart_small_order_fee"]}</h3>
+                        <p class="text-gray-400 text-lg font-light">{language_ctx.format_price(fee, &rates)}</p>
+                    </div>
+                }
+                {match promotion {
+                    Some(promotion) => html! {
+                        <div class="space-y-2">
+                            <h3 class="text-gray-500 font-bold text-lg">{&translations["cart_discount"]}</h3>
+                            <p class="text-gray-400 text-lg font-light">{format!("{} -{}", promotion.description, language_ctx.format_price(promotion.discount, &rates))}</p>
+                        </div>
+                    },
+                    None => html! {},
+                }}
                 // <div class="space-y-2">
                 //     <h3 class="text-gray-500 font-bold text-lg">{"Taxes"}</h3>
                 //     <p class="text-gray-400 text-lg font-light">{"$13.00"}</p>
@@ -374,7 +956,7 @@ pub fn checkout_summary() -> Html {
             <div class="mt-7 space-y-5">
                 <div class="space-y-2">
                     <h3 class="text-gray-500 font-bold text-lg">{&translations["checkout_summary_total_products_heading"]}</h3>
-                    <p class="text-gray-400 text-lg font-light">{format!("{} products", order.products().len())}</p>
+                    <p class="text-gray-400 text-lg font-light">{format!("{} products", order.item_count())}</p>
                 </div>
 
                 <div class="space-y-2">
@@ -383,10 +965,16 @@ pub fn checkout_summary() -> Html {
                     <p class="text-gray-400 text-lg font-light line-clamp-3">{&business.lookup.display_name()}</p>
                 </div>
 
-                // <div class="space-y-2">
-                //     <h3 class="text-gray-500 font-bold text-lg">{"Pickup date"}</h3>
-                //     <p class="text-gray-400 text-lg font-light">{"12/12/24"}</p>
-                // </div>
+                <div class="space-y-2">
+                    <h3 class="text-gray-500 font-bold text-lg">{&translations["cart_delivery_time"]}</h3>
+                    <p class="text-gray-400 text-lg font-light">
+                        {basket
+                            .delivery_slot()
+                            .as_ref()
+                            .map(DeliverySlot::locale_display)
+                            .unwrap_or(translations["cart_delivery_asap"].clone())}
+                    </p>
+                </div>
             </div>
 
         </div>
@@ -394,54 +982,250 @@ pub fn checkout_summary() -> Html {
 }
 
 #[derive(Properties, Clone, PartialEq)]
-pub struct OrderInvoiceProps {
-    pub order_id: String,
+pub struct CheckoutInvoicesProps {
+    /// Commerce and order id of each order sent at checkout.
+    pub sent_orders: Vec<(String, String)>,
+}
+
+/// Where the invoice of one order sent at checkout stands.
+#[derive(Clone, PartialEq)]
+enum CheckoutInvoice {
+    /// The invoicer hasn't answered yet.
+    Waiting,
+    Unpaid(LndHodlInvoice),
+    Paid,
+    /// Refused by the invoicer or expired, with the reason when it gave one.
+    Failed(Option<String>),
+}
+impl CheckoutInvoice {
+    fn new(order: Option<&OrderInvoiceState>) -> Self {
+        let Some(order) = order else {
+            return Self::Waiting;
+        };
+        match (&order.payment_status, &order.order_status) {
+            (OrderPaymentStatus::PaymentReceived | OrderPaymentStatus::PaymentSuccess, _) => {
+                Self::Paid
+            }
+            (OrderPaymentStatus::PaymentFailed, _) | (_, OrderStatus::Canceled) => {
+                Self::Failed(order.refusal.clone())
+            }
+            _ => match order.consumer_invoice.clone() {
+                Some(invoice) => Self::Unpaid(invoice),
+                None => Self::Waiting,
+            },
+        }
+    }
+}
+
+fn has_webln() -> bool {
+    web_sys::window().is_some_and(|window| {
+        web_sys::js_sys::Reflect::has(&window, &JsValue::from_str("webln")).unwrap_or_default()
+    })
+}
+
+/// Pays the invoices one after the other through the wallet the browser
+/// exposes with WebLN, so the whole checkout is paid in one go.
+async fn pay_with_webln(invoices: Vec<String>) -> Result<(), String> {
+    use web_sys::js_sys::{Function, Promise, Reflect};
+    let js_error = |e: JsValue| e.as_string().unwrap_or("Wallet payment failed".to_string());
+    let window = web_sys::window().ok_or("No window".to_string())?;
+    let webln = Reflect::get(&window, &JsValue::from_str("webln")).map_err(js_error)?;
+    let method = |name: &str| {
+        Reflect::get(&webln, &JsValue::from_str(name))
+            .map_err(js_error)?
+            .dyn_into::<Function>()
+            .map_err(|_| format!("Wallet has no {}", name))
+    };
+    let enable = method("enable")?.call0(&webln).map_err(js_error)?;
+    JsFuture::from(Promise::from(enable))
+        .await
+        .map_err(js_error)?;
+    let send_payment = method("sendPayment")?;
+    for invoice in invoices {
+        let payment = send_payment
+            .call1(&webln, &JsValue::from_str(&invoice))
+            .map_err(js_error)?;
+        JsFuture::from(Promise::from(payment))
+            .await
+            .map_err(js_error)?;
+    }
+    Ok(())
 }
 
-#[function_component(CheckoutInvoice)]
-pub fn checkout_summary(props: &OrderInvoiceProps) -> Html {
+/// Invoices of every order sent at checkout, one per commerce, paid one
+/// after the other or all at once with a browser wallet. Paid baskets leave
+/// the cart, refused ones stay in it to be fixed and sent again.
+#[function_component(CheckoutInvoices)]
+pub fn checkout_invoices(props: &CheckoutInvoicesProps) -> Html {
     let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
     let order_ctx = use_context::<LiveOrderStore>().expect("LiveOrderStore not found");
     let cart_ctx = use_context::<CartStore>().expect("CartStore not found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce ctx");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language ctx found");
+    let translations = language_ctx.translations();
     let navigator = use_navigator().unwrap();
+    let paying = use_state(|| false);
+    let pay_error = use_state(|| None::<String>);
     let exchange_rate = admin_ctx.get_exchange_rate();
-    let order_id = props.order_id.clone();
+    let sent_orders = props.sent_orders.clone();
+    let invoices = sent_orders
+        .iter()
+        .map(|(commerce_id, order_id)| {
+            let order = order_ctx
+                .live_orders
+                .iter()
+                .find(|(_, state)| state.order_id() == *order_id)
+                .map(|(_, state)| state);
+            (commerce_id.clone(), CheckoutInvoice::new(order))
+        })
+        .collect::<Vec<_>>();
+    let paid = invoices
+        .iter()
+        .filter(|(_, invoice)| *invoice == CheckoutInvoice::Paid)
+        .map(|(commerce_id, _)| commerce_id.clone())
+        .collect::<Vec<_>>();
+    let all_paid = !invoices.is_empty() && paid.len() == invoices.len();
+    let unpaid = invoices
+        .iter()
+        .filter_map(|(_, invoice)| match invoice {
+            CheckoutInvoice::Unpaid(invoice) => Some(invoice.payment_request()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    // Only the invoice to pay next is shown, the others wait their turn
+    let next_unpaid = invoices
+        .iter()
+        .position(|(_, invoice)| matches!(invoice, CheckoutInvoice::Unpaid(_)));
+    let rates = admin_ctx.get_exchange_rates();
+    let totals = cart_ctx
+        .baskets()
+        .iter()
+        .filter(|basket| !paid.contains(&basket.commerce_id()))
+        .map(|basket| basket_checkout(basket, &commerce_ctx, &admin_ctx, &order_ctx).total)
+        .collect::<Vec<_>>();
 
+    {
+        let cart_ctx = cart_ctx.clone();
+        use_effect_with(paid, move |paid| {
+            for commerce_id in paid {
+                if cart_ctx.basket(commerce_id).is_some() {
+                    cart_ctx.dispatch(CartAction::ClearBasket(commerce_id.clone()));
+                }
+            }
+            || {}
+        });
+    }
     {
         let navigator = navigator.clone();
-        use_effect_with(order_ctx.live_orders.clone(), move |order| {
-            if let Some((_, order_state)) = order.last() {
-                match order_state.payment_status {
-                    OrderPaymentStatus::PaymentReceived => {
-                        // Changed from PaymentPending
-                        if let Some(order_id) = order_state.order.id.clone() {
-                            cart_ctx.dispatch(CartAction::ClearCart);
-                            navigator.push(&ConsumerRoute::Order { order_id });
-                        }
-                    }
-                    _ => {}
+        use_effect_with(all_paid, move |all_paid| {
+            if *all_paid {
+                match sent_orders.as_slice() {
+                    [(_, order_id)] => navigator.push(&ConsumerRoute::Order {
+                        order_id: order_id.clone(),
+                    }),
+                    _ => navigator.push(&ConsumerRoute::TrackPackages),
                 }
             }
             || {}
         });
     }
 
-    if let Some(order) = order_ctx
-        .live_orders
-        .iter()
-        .find(|o| o.1.order_id() == order_id)
-    {
-        html! {
-            <OrderInvoiceComponent
-                invoice={order.1.consumer_invoice.as_ref().cloned().unwrap()}
-                {exchange_rate}
-            />
-        }
-    } else {
-        html! {
-            <div class="bg-zinc-100 p-4 rounded-2xl flex flex-col gap-3 items-center justify-center mx-2 lg:mx-4">
-                <ThreeBlockSpinner class="w-8 h-8 text-fuente" />
-            </div>
-        }
+    let pay_all = {
+        let paying = paying.clone();
+        let pay_error = pay_error.clone();
+        let unpaid = unpaid.clone();
+        Callback::from(move |_: MouseEvent| {
+            let paying = paying.clone();
+            let pay_error = pay_error.clone();
+            let unpaid = unpaid.clone();
+            paying.set(true);
+            pay_error.set(None);
+            yew::platform::spawn_local(async move {
+                if let Err(e) = pay_with_webln(unpaid).await {
+                    pay_error.set(Some(e));
+                }
+                paying.set(false);
+            });
+        })
+    };
+    let back_to_cart = Callback::from(move |_: MouseEvent| navigator.push(&ConsumerRoute::Cart));
+
+    html! {
+        <div class="flex flex-col gap-3">
+            if invoices.len() > 1 {
+                <div class="bg-zinc-100 p-4 rounded-2xl flex flex-col gap-2 mx-2 lg:mx-4">
+                    <p class="text-fuente text-sm">{&translations["checkout_invoices_note"]}</p>
+                    {match combined_total(&totals, &rates) {
+                        Ok(total) => html! {
+                            <p class="text-fuente font-bold">
+                                {format!(
+                                    "{}: {}",
+                                    translations["cart_combined_total"],
+                                    language_ctx.format_price(total, &rates)
+                                )}
+                            </p>
+                        },
+                        Err(e) => html! {
+                            <p class="text-red-500 text-sm">{e}</p>
+                        },
+                    }}
+                    if unpaid.len() > 1 && has_webln() {
+                        <button onclick={pay_all} disabled={*paying}
+                            class="disabled:opacity-50 bg-fuente-light text-white font-bold py-3 rounded-full">
+                            {&translations["checkout_pay_all"]}
+                        </button>
+                    }
+                    if let Some(error) = (*pay_error).clone() {
+                        <p class="text-red-500 text-sm">{error}</p>
+                    }
+                </div>
+            }
+            {for invoices.iter().enumerate().map(|(index, (commerce_id, invoice))| {
+                let commerce_name = commerce_ctx
+                    .find_commerce_by_id(commerce_id)
+                    .map(|commerce| commerce.profile().name.clone())
+                    .unwrap_or_default();
+                html! {
+                    <div class="flex flex-col gap-2">
+                        if invoices.len() > 1 {
+                            <p class="text-fuente font-bold mx-2 lg:mx-4">
+                                {format!("{} {}/{}: {}", translations["checkout_invoice_progress"], index + 1, invoices.len(), commerce_name)}
+                            </p>
+                        }
+                        {match invoice {
+                            CheckoutInvoice::Waiting => html! {
+                                <div class="bg-zinc-100 p-4 rounded-2xl flex flex-col gap-3 items-center justify-center mx-2 lg:mx-4">
+                                    <ThreeBlockSpinner class="w-8 h-8 text-fuente" />
+                                </div>
+                            },
+                            CheckoutInvoice::Paid => html! {
+                                <p class="bg-zinc-100 p-4 rounded-2xl text-green-600 font-bold mx-2 lg:mx-4">
+                                    {&translations["checkout_invoice_paid"]}
+                                </p>
+                            },
+                            CheckoutInvoice::Failed(reason) => html! {
+                                <div class="bg-zinc-100 p-4 rounded-2xl flex flex-col gap-2 mx-2 lg:mx-4">
+                                    <p class="text-red-500 font-bold">{&translations["checkout_invoice_failed"]}</p>
+                                    if let Some(reason) = reason {
+                                        <p class="text-red-500 text-sm">{reason}</p>
+                                    }
+                                    <button onclick={back_to_cart.clone()} class="text-fuente font-bold text-sm self-start">
+                                        {&translations["checkout_back_to_cart"]}
+                                    </button>
+                                </div>
+                            },
+                            CheckoutInvoice::Unpaid(invoice) if Some(index) == next_unpaid => html! {
+                                <OrderInvoiceComponent invoice={invoice.clone()} {exchange_rate} />
+                            },
+                            CheckoutInvoice::Unpaid(_) => html! {
+                                <p class="bg-zinc-100 p-4 rounded-2xl text-gray-500 mx-2 lg:mx-4">
+                                    {&translations["checkout_invoice_next"]}
+                                </p>
+                            },
+                        }}
+                    </div>
+                }
+            })}
+        </div>
     }
 }
@@ -1,10 +1,11 @@
 use crate::{
-    contexts::{CartAction, CartStore, CommerceDataStore, ConsumerDataStore},
+    contexts::{CartAction, CartStore, CommerceDataAction, CommerceDataStore, ConsumerDataStore, RatingsStore},
+    pages::StartGroupOrderButton,
     router::ConsumerRoute,
 };
 
-use fuente::{contexts::LanguageConfigsStore, mass::{AppLink, Toast, ToastAction, ToastContext, ToastType}, models::ProductItem};
-use lucide_yew::{ArrowLeft, ShoppingCart};
+use fuente::{contexts::{AdminConfigsStore, LanguageConfigsStore}, mass::{AppLink, CommerceDiscoveryBar, DeliveryEstimateBadge, ModifierGroupPicker, ProductTagBadges, TagFilterPicker, ThreeBlockSpinner, Toast, ToastAction, ToastContext, ToastType}, models::{CommerceDiscovery, ProductItem, TagFilter}};
+use lucide_yew::{ArrowLeft, ShoppingCart, Star};
 use nostr_minions::key_manager::NostrIdStore;
 use yew::prelude::*;
 use yew_router::hooks::use_navigator;
@@ -20,7 +21,32 @@ pub enum ProductFilter {
     Brand(bool),
 }
 
+/// Loads the commerce's menu, and its profile when it's outside the
+/// consumer's area, before showing the storefront.
 #[function_component(CommercePage)]
+pub fn commerce_page(props: &CommercePageProps) -> Html {
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce context found");
+    let ctx = commerce_ctx.clone();
+    use_effect_with(props.commerce_id.clone(), move |commerce_id| {
+        ctx.dispatch(CommerceDataAction::RequestMenu(commerce_id.clone()));
+        || {}
+    });
+    if commerce_ctx
+        .find_commerce_by_id(&props.commerce_id)
+        .is_none()
+    {
+        return html! {
+            <div class="flex flex-1 items-center justify-center">
+                <ThreeBlockSpinner class="w-16 h-16 text-fuente" />
+            </div>
+        };
+    }
+    html! {
+        <CommerceStorefront commerce_id={props.commerce_id.clone()} />
+    }
+}
+
+#[function_component(CommerceStorefront)]
 pub fn commerce_page_template(props: &CommercePageProps) -> Html {
     let CommercePageProps { commerce_id } = props;
     let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
@@ -35,6 +61,7 @@ pub fn commerce_page_template(props: &CommercePageProps) -> Html {
 
     let product_handle = use_state(|| None::<ProductItem>);
     let product_filter = use_state(|| Option::<ProductFilter>::None);
+    let tag_filter = use_state(TagFilter::default);
     let onclick_brand_filter = {
         let product_filter = product_filter.clone();
         Callback::from(move |e: MouseEvent| {
@@ -65,9 +92,11 @@ pub fn commerce_page_template(props: &CommercePageProps) -> Html {
             }
         })
     };
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
     let mut all_products = if let Some(products) = products {
         products
             .menu()
+            .at(now, commerce_profile.availability.utc_offset_minutes)
             .categories()
             .iter()
             .flat_map(|category| {
@@ -81,11 +110,12 @@ pub fn commerce_page_template(props: &CommercePageProps) -> Html {
     } else {
         vec![]
     };
+    all_products.retain(|product| tag_filter.matches(product));
     match product_filter.as_ref() {
         Some(ProductFilter::Price(forward)) => {
             all_products.sort_by(|a, b| match forward {
-                true => a.price().partial_cmp(&b.price()).unwrap(),
-                false => b.price().partial_cmp(&a.price()).unwrap(),
+                true => a.price().cmp(&b.price()),
+                false => b.price().cmp(&a.price()),
             });
         }
         Some(ProductFilter::Brand(forward)) => {
@@ -134,6 +164,13 @@ pub fn commerce_page_template(props: &CommercePageProps) -> Html {
                                     {&translations["detail_store_filter_brand"]}
                                 </p>
                             </div>
+                            <h3 class="font-semibold text-fuente text-xl text-nowrap">{&translations["detail_store_filter_dietary"]}</h3>
+                            <div class="min-w-64 lg:min-w-0">
+                                <TagFilterPicker filter={tag_filter.clone()} />
+                            </div>
+                            <div class="min-w-64 lg:min-w-0">
+                                <StartGroupOrderButton commerce_id={commerce_id.clone()} />
+                            </div>
                         </div>
                     </aside>
 
@@ -178,6 +215,8 @@ pub fn product_item_card(props: &ProductItemProps) -> Html {
         product_handle,
     } = props;
     let cart_ctx = use_context::<CartStore>().expect("No cart context found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
     let auth_context = use_context::<NostrIdStore>().expect("No auth context found");
     let consumer_ctx = use_context::<ConsumerDataStore>().expect("No commerce context found");
     let has_address_and_profile = {
@@ -185,12 +224,11 @@ pub fn product_item_card(props: &ProductItemProps) -> Html {
     };
     let is_logged_in = auth_context.get_identity().is_some();
     let navigation = use_navigator().expect("No navigation found");
-    let show_warning = use_state(|| false);
     let onclick = {
         let commerce_id = commerce_id.clone();
         let cart_ctx = cart_ctx.clone();
-        let show_warning = show_warning.clone();
         let product = product.clone();
+        let product_handle = product_handle.clone();
         let toast_ctx = use_context::<ToastContext>().expect("No toast context");
         
         Callback::from(move |e: MouseEvent| {
@@ -203,14 +241,18 @@ pub fn product_item_card(props: &ProductItemProps) -> Html {
                 navigation.push(&ConsumerRoute::Register);
                 return;
             }
-            if !cart_ctx.can_add_from_business(&commerce_id) {
-                show_warning.set(true);
+            if !cart_ctx.can_add_one(&commerce_id, &product) {
                 toast_ctx.dispatch(ToastAction::Show(Toast {
-                    message: "Can't add items from different stores".into(),
+                    message: format!("{} is sold out", product.name()),
                     toast_type: ToastType::Error,
                 }));
                 return;
             }
+            if product.has_modifiers() {
+                // Sides have to be picked on the product page first
+                product_handle.set(Some(product.clone()));
+                return;
+            }
             cart_ctx.dispatch(CartAction::AddProduct(
                 product.clone(),
                 commerce_id.to_string(),
@@ -240,11 +282,22 @@ pub fn product_item_card(props: &ProductItemProps) -> Html {
                 </div>
                 <h2 class="font-bold text-lg text-gray-500 text-center mt-3">{product.name()}</h2>
                 <p class="text-sm text-gray-400 text-center line-clamp-2">{product.details()}</p>
+                <div class="flex justify-center mt-2 px-5">
+                    <ProductTagBadges product={product.clone()} />
+                </div>
                 <div class="flex justify-between items-center mt-3 px-5 gap-5">
-                    <p class="text-xl font-bold text-fuente">{format!("SRD {}", product.price())}</p>
-                    <button {onclick} class="bg-fuente-orange text-white py-2 px-7 rounded-full z-[500]">
-                        <ShoppingCart class="w-8 h-8" />
-                    </button>
+                    <p class="text-xl font-bold text-fuente">{language_ctx.format_price(product.price(), &admin_ctx.get_exchange_rates())}</p>
+                    {if product.is_sold_out() {
+                        html! {
+                            <p class="text-red-500 font-semibold py-2">{&language_ctx.translations()["product_sold_out"]}</p>
+                        }
+                    } else {
+                        html! {
+                            <button {onclick} class="bg-fuente-orange text-white py-2 px-7 rounded-full z-[500]">
+                                <ShoppingCart class="w-8 h-8" />
+                            </button>
+                        }
+                    }}
                 </div>
             </div>
         </div>
@@ -259,6 +312,10 @@ pub fn product_page_template(props: &ProductItemProps) -> Html {
         product_handle,
     } = props;
     let cart_ctx = use_context::<CartStore>().expect("No cart context found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let toast_ctx = use_context::<ToastContext>().expect("No toast context");
+    let selected_sides = use_state(|| Vec::<String>::new());
     let back_to_store = {
         let product_handle = product_handle.clone();
         Callback::from(move |_e: MouseEvent| {
@@ -269,10 +326,35 @@ pub fn product_page_template(props: &ProductItemProps) -> Html {
         let cart_ctx = cart_ctx.clone();
         let commerce_id = commerce_id.clone();
         let product = product.clone();
+        let selected_sides = selected_sides.clone();
+        let toast_ctx = toast_ctx.clone();
         Callback::from(move |_e: MouseEvent| {
-            cart_ctx.dispatch(CartAction::AddProduct(product.clone(), commerce_id.clone()));
+            if !cart_ctx.can_add_one(&commerce_id, &product) {
+                toast_ctx.dispatch(ToastAction::Show(Toast {
+                    message: format!("{} is sold out", product.name()),
+                    toast_type: ToastType::Error,
+                }));
+                return;
+            }
+            match product.with_sides(&selected_sides) {
+                Ok(item) => {
+                    cart_ctx.dispatch(CartAction::AddProduct(item, commerce_id.clone()));
+                    toast_ctx.dispatch(ToastAction::Show(Toast {
+                        message: format!("{} added to cart", product.name()),
+                        toast_type: ToastType::Success,
+                    }));
+                }
+                Err(e) => toast_ctx.dispatch(ToastAction::Show(Toast {
+                    message: e.to_string(),
+                    toast_type: ToastType::Error,
+                })),
+            }
         })
     };
+    let price = product
+        .with_sides(&selected_sides)
+        .map(|item| item.price_with_sides())
+        .unwrap_or(product.price());
     html! {
         <main class="flex flex-col h-screen overflow-hidden container mx-auto">
             <button onclick={back_to_store}
@@ -295,6 +377,8 @@ pub fn product_page_template(props: &ProductItemProps) -> Html {
                         <h2 class="text-gray-500 text-2xl font-bold">{product.name()}</h2>
                         <p class="font-light text-gray-500 text-xl mt-3 text-xs sm:text-sm md:text-lg line-clamp-3">{product.details()}</p>
                         <p class="font-bold text-gray-500 uppercase text-2xl">{format!("{}", product.sku())}</p>
+                        <ProductTagBadges product={product.clone()} />
+                        <ModifierGroupPicker product={product.clone()} selected={selected_sides.clone()} />
                         // <div class="flex items-center gap-1 mt-10">
                         //     <svg baseProfile="tiny" version="1.2" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg" class="w-8 h-8 ">
                         //         <path d="m9.362 9.158-5.268.584c-.19.023-.358.15-.421.343s0 .394.14.521c1.566 1.429 3.919 3.569 3.919 3.569-.002 0-.646 3.113-1.074 5.19a.496.496 0 0 0 .734.534c1.844-1.048 4.606-2.624 4.606-2.624l4.604 2.625c.168.092.378.09.541-.029a.5.5 0 0 0 .195-.505l-1.071-5.191 3.919-3.566a.499.499 0 0 0-.28-.865c-2.108-.236-5.269-.586-5.269-.586l-2.183-4.83a.499.499 0 0 0-.909 0l-2.183 4.83z" fill="#4167e8" class="fill-000000"></path>
@@ -303,11 +387,13 @@ pub fn product_page_template(props: &ProductItemProps) -> Html {
                         // </div>
 
                         <div class="flex flex-row lg:flex-col mt-5 lg:mt-10 gap-5">
-                            <p class="text-3xl md:text-4xl lg:text-5xl font-bold text-fuente">{format!("SRD {}", product.price())}</p>
-                            <button onclick={add_cart}
-                                class="bg-fuente-orange text-white p-2  lg:py-4 lg:px-10 rounded-full flex items-center justify-center gap-2 flex-1">
+                            <p class="text-3xl md:text-4xl lg:text-5xl font-bold text-fuente">{language_ctx.format_price(price, &admin_ctx.get_exchange_rates())}</p>
+                            <button onclick={add_cart} disabled={product.is_sold_out()}
+                                class="bg-fuente-orange text-white p-2  lg:py-4 lg:px-10 rounded-full flex items-center justify-center gap-2 flex-1 disabled:opacity-50">
                                 <ShoppingCart class="w-8 h-8" />
-                                <p class="hidden md:block font-semibold text-xl text-center">{"Shop Now"}</p>
+                                <p class="hidden md:block font-semibold text-xl text-center">
+                                    {if product.is_sold_out() { language_ctx.translations()["product_sold_out"].clone() } else { "Shop Now".to_string() }}
+                                </p>
                             </button>
                         </div>
                     </div>
@@ -326,7 +412,16 @@ pub fn settings_template() -> Html {
     let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
     let translations = language_ctx.translations();
     let commerce_ctx = use_context::<CommerceDataStore>().expect("Commerce context not found");
-    let businesses = commerce_ctx.commerces();
+    let ratings_ctx = use_context::<RatingsStore>().expect("RatingsStore not found");
+    let user_ctx = use_context::<ConsumerDataStore>().expect("ConsumerDataStore not found");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let rates = admin_ctx.get_exchange_rates();
+    let discovery = use_state(CommerceDiscovery::default);
+    let destination = user_ctx
+        .get_default_address()
+        .map(|address| address.coordinates());
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+    let listings = discovery.apply(commerce_ctx.listings(&ratings_ctx, destination.as_ref(), now));
     html! {
         <main class="flex flex-col h-full overflow-hidden container mx-auto">
             <div class="flex flex-col lg:flex-row justify-between items-center my-5">
@@ -334,12 +429,13 @@ pub fn settings_template() -> Html {
                     {&translations["stores_heading"]}
                 </h1>
             </div>
+            <CommerceDiscoveryBar discovery={discovery.clone()} has_location={destination.is_some()} />
 
             <div class="flex-1 w-full flex flex-col lg:flex-row overflow-hidden mt-2">
                 <div class="w-full grid grid-cols-1 md:grid-cols-2 lg:grid-cols-3 gap-5 overflow-y-auto">
-                    {businesses.iter().map(|profile| {
-                        let commerce_data = profile.profile().clone();
-                        let commerce_id = profile.id().to_string();
+                    {listings.into_iter().map(|listing| {
+                        let commerce_data = listing.profile;
+                        let commerce_id = listing.commerce_id;
                         html! {
                             <AppLink<ConsumerRoute>
                                 class="border-2 border-fuente rounded-3xl block object-contain bg-white overflow-clip p-2 h-80"
@@ -351,6 +447,18 @@ pub fn settings_template() -> Html {
                                 <div class="space-y-2 flex flex-col items-center">
                                     <h3 class="text-gray-500 text-lg font-bold tracking-wide uppercase">{&commerce_data.name}</h3>
                                     <p class="text-gray-500 font-light text-md line-clamp-3">{&commerce_data.description}</p>
+                                    <div class="flex flex-wrap items-center justify-center gap-2 text-sm text-gray-500">
+                                        if let Some(estimate) = listing.estimate {
+                                            <DeliveryEstimateBadge {estimate} />
+                                            <span>{format!("{} {}", translations["discovery_delivery_fee"], language_ctx.format_price(estimate.fee, &rates))}</span>
+                                        }
+                                        if let Some(rating) = listing.rating {
+                                            <span class="flex items-center gap-1"><Star class="w-4 h-4 text-fuente" />{format!("{:.1}", rating)}</span>
+                                        }
+                                        if !listing.open {
+                                            <span class="text-red-500 font-semibold">{&translations["discovery_closed"]}</span>
+                                        }
+                                    </div>
                                     // <div class="flex items-center gap-2">
                                     //     <Star class="w-6 h-6 text-fuente" />
                                     //     <p class="text-gray-500 font-light">{"5.0 Delivery on time"}</p>
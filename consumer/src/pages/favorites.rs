@@ -53,6 +53,7 @@ pub fn favorite_commerce_template(props: &CommerceProfileProps) -> Html {
     let CommerceProfileProps {
         commerce_data,
         rating,
+        estimate: _,
     } = props;
     html! {
        <div class="flex flex-col sm:flex-row items-center gap-5 w-fit">
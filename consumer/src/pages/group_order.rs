@@ -0,0 +1,526 @@
+use crate::{
+    contexts::{
+        CartAction, CartStore, CommerceDataAction, CommerceDataStore, ConsumerDataStore,
+        GroupOrderAction, GroupOrderStore,
+    },
+    pages::{OrderInvoiceComponent, PageHeader},
+    router::ConsumerRoute,
+};
+use fuente::{
+    contexts::{AdminConfigsStore, LanguageConfigsStore},
+    mass::ThreeBlockSpinner,
+    models::{
+        merge_group_contributions, GroupContribution, GroupInvite, GroupOrder, GroupOrderIdb,
+        GroupShareState, GroupShareStatus, OrderInvoiceState,
+    },
+};
+use lucide_yew::{Copy, Users};
+use nostr_minions::{
+    browser_api::clipboard_copy, key_manager::NostrIdStore, relay_pool::NostrProps,
+};
+use yew::{platform::spawn_local, prelude::*};
+use yew_router::hooks::use_navigator;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct StartGroupOrderProps {
+    pub commerce_id: String,
+}
+
+/// Opens a group order for the commerce and takes the host to its page.
+#[function_component(StartGroupOrderButton)]
+pub fn start_group_order_button(props: &StartGroupOrderProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let consumer_ctx = use_context::<ConsumerDataStore>().expect("ConsumerDataStore not found");
+    let group_ctx = use_context::<GroupOrderStore>().expect("GroupOrderStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
+    let navigator = use_navigator().expect("Navigator not found");
+    let split_payment = use_state(|| false);
+
+    let onclick = {
+        let split_payment = split_payment.clone();
+        let commerce_id = props.commerce_id.clone();
+        Callback::from(move |_: MouseEvent| {
+            let (Some(identity), Some(pubkey), Some(profile)) = (
+                key_ctx.get_identity().cloned(),
+                key_ctx.get_pubkey(),
+                consumer_ctx.get_profile(),
+            ) else {
+                navigator.push(&ConsumerRoute::Login);
+                return;
+            };
+            let invite = GroupInvite::new(pubkey);
+            let order = GroupOrder {
+                commerce_id: commerce_id.clone(),
+                host_name: profile.nickname,
+                split_payment: *split_payment,
+                closed: false,
+                updated_at: (web_sys::js_sys::Date::now() / 1000.0) as i64,
+            };
+            let group = GroupOrderIdb::new(invite.clone());
+            group_ctx.dispatch(GroupOrderAction::JoinGroup(group.clone()));
+            group_ctx.dispatch(GroupOrderAction::UpdateOrder(
+                group.group_id(),
+                order.clone(),
+            ));
+            let sender = relay_ctx.send_note.clone();
+            let note_invite = invite.clone();
+            spawn_local(async move {
+                match order.giftwrapped_data(&identity, &note_invite).await {
+                    Ok(note) => sender.emit(note),
+                    Err(e) => gloo::console::error!("Failed to open group order:", e),
+                }
+            });
+            navigator.push(&ConsumerRoute::GroupOrder {
+                code: invite.code(),
+            });
+        })
+    };
+    let onchange = {
+        let split_payment = split_payment.clone();
+        Callback::from(move |_: Event| split_payment.set(!*split_payment))
+    };
+    html! {
+        <div class="flex flex-col gap-2 w-full">
+            <button {onclick}
+                class="flex items-center justify-center gap-2 bg-fuente-orange text-white font-bold py-2 px-4 rounded-full">
+                <Users class="w-5 h-5" />
+                {&translations["group_order_start"]}
+            </button>
+            <label class="flex items-center gap-2 text-sm text-fuente">
+                <input type="checkbox" checked={*split_payment} {onchange} />
+                {&translations["group_order_split_payment"]}
+            </label>
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct GroupOrderPageProps {
+    pub code: String,
+}
+
+#[function_component(GroupOrderPage)]
+pub fn group_order_page(props: &GroupOrderPageProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let group_ctx = use_context::<GroupOrderStore>().expect("GroupOrderStore not found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+    let invite = GroupInvite::parse(&props.code).ok();
+
+    let ctx = group_ctx.clone();
+    use_effect_with(
+        (invite.clone(), group_ctx.is_loaded()),
+        move |(invite, is_loaded)| {
+            if let (Some(invite), true) = (invite, is_loaded) {
+                ctx.dispatch(GroupOrderAction::JoinGroup(GroupOrderIdb::new(
+                    invite.clone(),
+                )));
+            }
+            || {}
+        },
+    );
+
+    let group_order = invite
+        .as_ref()
+        .and_then(|invite| group_ctx.group_order(&invite.group_id()));
+    let commerce_id = group_order.as_ref().map(|order| order.commerce_id.clone());
+    let ctx = commerce_ctx.clone();
+    use_effect_with(commerce_id, move |commerce_id| {
+        if let Some(commerce_id) = commerce_id {
+            ctx.dispatch(CommerceDataAction::RequestMenu(commerce_id.clone()));
+        }
+        || {}
+    });
+
+    let Some(invite) = invite else {
+        return html! {
+            <>
+                <PageHeader title={translations["group_order_heading"].clone()} />
+                <p class="text-center text-red-500 font-semibold p-4">{&translations["group_order_invalid"]}</p>
+            </>
+        };
+    };
+
+    html! {
+        <>
+            <PageHeader title={translations["group_order_heading"].clone()} />
+            {match group_order {
+                Some(group_order) => html! {
+                    <GroupOrderDetails {invite} {group_order} />
+                },
+                None => html! {
+                    <div class="flex flex-col flex-1 items-center justify-center gap-4 p-4">
+                        <ThreeBlockSpinner class="w-16 h-16 text-fuente" />
+                        <p class="text-gray-500">{&translations["group_order_waiting_host"]}</p>
+                    </div>
+                },
+            }}
+        </>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct GroupOrderDetailsProps {
+    pub invite: GroupInvite,
+    pub group_order: GroupOrder,
+}
+
+#[function_component(GroupOrderDetails)]
+pub fn group_order_details(props: &GroupOrderDetailsProps) -> Html {
+    let GroupOrderDetailsProps {
+        invite,
+        group_order,
+    } = props.clone();
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let group_ctx = use_context::<GroupOrderStore>().expect("GroupOrderStore not found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+    let consumer_ctx = use_context::<ConsumerDataStore>().expect("ConsumerDataStore not found");
+    let cart_ctx = use_context::<CartStore>().expect("CartStore not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
+    let navigator = use_navigator().expect("Navigator not found");
+    let rates = admin_ctx.get_exchange_rates();
+
+    let group_id = invite.group_id();
+    let commerce_id = group_order.commerce_id.clone();
+    let commerce_name = commerce_ctx
+        .find_commerce_by_id(&commerce_id)
+        .map(|commerce| commerce.profile().name.clone())
+        .unwrap_or_default();
+    let pubkey = key_ctx.get_pubkey().unwrap_or_default();
+    let is_host = pubkey == invite.host;
+    let contributions = group_ctx.contributions(&group_id);
+    let my_items = contributions
+        .iter()
+        .find(|(key, _)| *key == pubkey)
+        .map(|(_, contribution)| contribution.items.clone())
+        .unwrap_or_default();
+    let basket = cart_ctx.basket(&commerce_id);
+
+    // Replaces the consumer's contribution and keeps the local copy in step
+    let publish_items = {
+        let group_ctx = group_ctx.clone();
+        let key_ctx = key_ctx.clone();
+        let consumer_ctx = consumer_ctx.clone();
+        let sender = relay_ctx.send_note.clone();
+        let invite = invite.clone();
+        let group_id = group_id.clone();
+        Callback::from(move |items| {
+            let (Some(identity), Some(pubkey)) =
+                (key_ctx.get_identity().cloned(), key_ctx.get_pubkey())
+            else {
+                return;
+            };
+            let contribution = GroupContribution {
+                name: consumer_ctx
+                    .get_profile()
+                    .map(|profile| profile.nickname)
+                    .unwrap_or_default(),
+                items,
+                updated_at: (web_sys::js_sys::Date::now() / 1000.0) as i64,
+            };
+            group_ctx.dispatch(GroupOrderAction::UpdateContribution(
+                group_id.clone(),
+                pubkey,
+                contribution.clone(),
+            ));
+            let sender = sender.clone();
+            let invite = invite.clone();
+            spawn_local(async move {
+                match contribution.giftwrapped_data(&identity, &invite).await {
+                    Ok(note) => sender.emit(note),
+                    Err(e) => gloo::console::error!("Failed to share items:", e),
+                }
+            });
+        })
+    };
+    let share_basket = {
+        let publish_items = publish_items.clone();
+        let cart_ctx = cart_ctx.clone();
+        let commerce_id = commerce_id.clone();
+        let basket = basket.clone();
+        let my_items = my_items.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(basket) = basket.clone() else {
+                return;
+            };
+            let mut items = my_items.clone();
+            basket
+                .product_order()
+                .lines()
+                .into_iter()
+                .for_each(|line| items.add_line(line));
+            publish_items.emit(items);
+            cart_ctx.dispatch(CartAction::ClearBasket(commerce_id.clone()));
+        })
+    };
+    let edit_items = {
+        let publish_items = publish_items.clone();
+        let cart_ctx = cart_ctx.clone();
+        let navigator = navigator.clone();
+        let commerce_id = commerce_id.clone();
+        let my_items = my_items.clone();
+        Callback::from(move |_: MouseEvent| {
+            cart_ctx.dispatch(CartAction::Reorder(my_items.clone(), commerce_id.clone()));
+            publish_items.emit(Default::default());
+            navigator.push(&ConsumerRoute::Commerce {
+                commerce_id: commerce_id.clone(),
+            });
+        })
+    };
+    let add_items = {
+        let navigator = navigator.clone();
+        let commerce_id = commerce_id.clone();
+        Callback::from(move |_: MouseEvent| {
+            navigator.push(&ConsumerRoute::Commerce {
+                commerce_id: commerce_id.clone(),
+            });
+        })
+    };
+    let checkout = {
+        let group_ctx = group_ctx.clone();
+        let key_ctx = key_ctx.clone();
+        let sender = relay_ctx.send_note.clone();
+        let navigator = navigator.clone();
+        let invite = invite.clone();
+        let group_id = group_id.clone();
+        let group_order = group_order.clone();
+        let contributions = contributions.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some(identity) = key_ctx.get_identity().cloned() else {
+                return;
+            };
+            let (items, shares) =
+                merge_group_contributions(&invite.host, group_order.split_payment, &contributions);
+            if items.is_empty() {
+                return;
+            }
+            let closed = GroupOrder {
+                closed: true,
+                updated_at: (web_sys::js_sys::Date::now() / 1000.0) as i64,
+                ..group_order.clone()
+            };
+            group_ctx.dispatch(GroupOrderAction::UpdateOrder(
+                group_id.clone(),
+                closed.clone(),
+            ));
+            let sender = sender.clone();
+            let invite = invite.clone();
+            spawn_local(async move {
+                match closed.giftwrapped_data(&identity, &invite).await {
+                    Ok(note) => sender.emit(note),
+                    Err(e) => gloo::console::error!("Failed to close group order:", e),
+                }
+            });
+            cart_ctx.dispatch(CartAction::GroupCheckout(
+                group_order.commerce_id.clone(),
+                items,
+                shares,
+            ));
+            navigator.push(&ConsumerRoute::Cart);
+        })
+    };
+    let leave = {
+        let group_ctx = group_ctx.clone();
+        let navigator = navigator.clone();
+        let group_id = group_id.clone();
+        Callback::from(move |_: MouseEvent| {
+            group_ctx.dispatch(GroupOrderAction::LeaveGroup(group_id.clone()));
+            navigator.push(&ConsumerRoute::Home);
+        })
+    };
+    let invite_link = format!(
+        "{}/group/{}",
+        web_sys::window()
+            .and_then(|window| window.location().origin().ok())
+            .unwrap_or_default(),
+        invite.code()
+    );
+    let copy_link = {
+        let invite_link = invite_link.clone();
+        Callback::from(move |_: MouseEvent| {
+            let _ = clipboard_copy(&invite_link);
+        })
+    };
+    let my_share = group_ctx.share_since(group_order.updated_at);
+
+    html! {
+        <div class="container mx-auto flex flex-col gap-5 p-4 max-w-4xl">
+            <div class="bg-zinc-100 p-4 rounded-2xl flex flex-col gap-2">
+                <h2 class="text-fuente text-2xl font-bold">{commerce_name}</h2>
+                <p class="text-gray-600">
+                    {format!("{} {}", translations["group_order_host"], group_order.host_name)}
+                </p>
+                <p class="text-gray-600">
+                    {if group_order.split_payment {
+                        &translations["group_order_split_payment"]
+                    } else {
+                        &translations["group_order_host_pays"]
+                    }}
+                </p>
+                if !group_order.closed {
+                    <div class="flex items-center gap-2">
+                        <input type="text" readonly={true} value={invite_link}
+                            class="flex-1 border border-gray-300 rounded-lg px-2 py-1 text-sm text-gray-500 truncate" />
+                        <button onclick={copy_link} class="text-fuente" title={translations["group_order_copy_link"].clone()}>
+                            <Copy class="w-5 h-5" />
+                        </button>
+                    </div>
+                }
+            </div>
+
+            <div class="flex flex-col gap-3">
+                <h3 class="text-fuente text-xl font-bold">{&translations["group_order_participants"]}</h3>
+                {if contributions.iter().all(|(_, contribution)| contribution.items.is_empty()) {
+                    html! {
+                        <p class="text-gray-500">{&translations["group_order_no_items"]}</p>
+                    }
+                } else {
+                    contributions
+                        .iter()
+                        .filter(|(_, contribution)| !contribution.items.is_empty())
+                        .map(|(_, contribution)| html! {
+                            <div class="border border-gray-200 rounded-xl p-3 flex flex-col gap-1">
+                                <div class="flex justify-between font-semibold text-fuente">
+                                    <span>{&contribution.name}</span>
+                                    <span>{language_ctx.format_price(contribution.items.total(), &rates)}</span>
+                                </div>
+                                {contribution.items.lines().iter().map(|line| html! {
+                                    <p class="text-sm text-gray-500">
+                                        {format!("{} x {}", line.quantity(), line.name())}
+                                    </p>
+                                }).collect::<Html>()}
+                            </div>
+                        })
+                        .collect::<Html>()
+                }}
+            </div>
+
+            {if group_order.closed {
+                html! {
+                    <GroupShareStatusView
+                        split_payment={group_order.split_payment && !is_host}
+                        share={my_share}
+                    />
+                }
+            } else {
+                html! {
+                    <div class="flex flex-col md:flex-row gap-3">
+                        <button onclick={add_items}
+                            class="border-2 border-fuente text-fuente font-bold py-2 px-4 rounded-full">
+                            {&translations["group_order_add_items"]}
+                        </button>
+                        if basket.is_some() {
+                            <button onclick={share_basket}
+                                class="bg-fuente-light text-white font-bold py-2 px-4 rounded-full">
+                                {&translations["group_order_share_cart"]}
+                            </button>
+                        }
+                        if !my_items.is_empty() {
+                            <button onclick={edit_items}
+                                class="border-2 border-fuente text-fuente font-bold py-2 px-4 rounded-full">
+                                {&translations["group_order_edit_items"]}
+                            </button>
+                        }
+                        if is_host {
+                            <button onclick={checkout}
+                                class="bg-fuente-orange text-white font-bold py-2 px-4 rounded-full">
+                                {&translations["group_order_checkout"]}
+                            </button>
+                        }
+                    </div>
+                }
+            }}
+
+            <button onclick={leave} class="text-red-500 font-semibold self-start">
+                {&translations["group_order_leave"]}
+            </button>
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct GroupShareStatusProps {
+    /// Whether the consumer pays for their own items.
+    pub split_payment: bool,
+    pub share: Option<GroupShareState>,
+}
+
+#[function_component(GroupShareStatusView)]
+pub fn group_share_status(props: &GroupShareStatusProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    if !props.split_payment {
+        return html! {
+            <p class="text-fuente font-semibold">{&translations["group_order_closed"]}</p>
+        };
+    }
+    match props.share.clone() {
+        Some(GroupShareState {
+            status: GroupShareStatus::Pending,
+            consumer_invoice: Some(invoice),
+            ..
+        }) => html! {
+            <div class="flex flex-col gap-2">
+                <h3 class="text-fuente text-xl font-bold">{&translations["group_order_pay_share"]}</h3>
+                <OrderInvoiceComponent {invoice} exchange_rate={admin_ctx.get_exchange_rate()} />
+            </div>
+        },
+        Some(GroupShareState {
+            status: GroupShareStatus::Paid,
+            ..
+        }) => html! {
+            <p class="text-green-500 font-bold">{&translations["group_order_share_paid"]}</p>
+        },
+        Some(GroupShareState {
+            status: GroupShareStatus::Failed,
+            ..
+        }) => html! {
+            <p class="text-red-500 font-bold">{&translations["group_order_share_failed"]}</p>
+        },
+        _ => html! {
+            <div class="flex items-center gap-4">
+                <ThreeBlockSpinner class="w-8 h-8 text-fuente" />
+                <p class="text-gray-500">{&translations["group_order_waiting_checkout"]}</p>
+            </div>
+        },
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct GroupPaymentProgressProps {
+    pub order: OrderInvoiceState,
+}
+
+/// Shown to the host while a split group order waits on its participants.
+#[function_component(GroupPaymentProgress)]
+pub fn group_payment_progress(props: &GroupPaymentProgressProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    html! {
+        <div class="flex flex-col gap-3 p-4 max-w-2xl mx-auto w-full">
+            <h3 class="text-fuente text-xl font-bold">{&translations["group_order_waiting_shares"]}</h3>
+            {props.order.group_shares.iter().map(|share| html! {
+                <div class="flex justify-between border-b border-gray-200 py-2">
+                    <span class="font-semibold">{&share.share.name}</span>
+                    {match share.status {
+                        GroupShareStatus::Paid => html! {
+                            <span class="text-green-500 font-bold">{&translations["group_order_share_paid"]}</span>
+                        },
+                        GroupShareStatus::Failed => html! {
+                            <span class="text-red-500 font-bold">{&translations["group_order_share_failed"]}</span>
+                        },
+                        GroupShareStatus::Pending => html! {
+                            <span class="text-gray-500">{language_ctx.format_money(share.amount)}</span>
+                        },
+                    }}
+                </div>
+            }).collect::<Html>()}
+        </div>
+    }
+}
@@ -1,14 +1,24 @@
+use crate::contexts::{CartAction, CartStore, CommerceDataAction, CommerceDataStore};
+use crate::pages::OrderInvoiceComponent;
+use crate::router::ConsumerRoute;
 use fuente::{
-    contexts::LanguageConfigsStore,
+    contexts::{AdminConfigsStore, LanguageConfigsStore},
     mass::{
-        templates::OrderHistoryTemplate, OrderDetails, OrderHistoryDesktop, OrderHistoryMobile,
+        templates::OrderHistoryTemplate, MoneyInput, OrderDetails, OrderHistoryDesktop,
+        OrderHistoryMobile, PopupSection, ThreeBlockSpinner,
+    },
+    models::{
+        CourierTipRequest, CourierTipState, CourierTipStatus, Money, OrderInvoiceState,
+        OrderStateIdb, OrderStatus, ReorderChange, NOSTR_KIND_COURIER_TIP,
     },
-    models::{OrderInvoiceState, OrderStateIdb, OrderStatus},
 };
-use lucide_yew::History;
+use lucide_yew::{History, ShoppingCart};
+use nostr_minions::{browser_api::HtmlForm, key_manager::NostrIdStore, relay_pool::NostrProps};
+use nostro2::relays::NostrSubscription;
 use web_sys::wasm_bindgen::JsValue;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
+use yew_router::hooks::use_navigator;
 
 #[function_component(HistoryPage2)]
 pub fn history_page() -> Html {
@@ -100,7 +110,9 @@ pub fn history_page() -> Html {
 
     if let Some(order_id) = (*selected_order).clone() {
         if let Some(order) = orders.iter().find(|o| o.order_id() == order_id) {
+            let can_tip = order.order_status == OrderStatus::Completed && order.courier.is_some();
             return html! {
+                <>
                 <OrderDetails
                     order={order.clone()}
                     on_back={Callback::from({
@@ -108,6 +120,13 @@ pub fn history_page() -> Html {
                         move |_| selected.set(None)
                     })}
                 />
+                <ReorderButton order={order.clone()} />
+                {if can_tip {
+                    html! { <CourierTipForm order={order.clone()} /> }
+                } else {
+                    html! {}
+                }}
+                </>
             };
         }
     }
@@ -182,3 +201,274 @@ pub fn history_page() -> Html {
         </>
     }
 }
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct CourierTipFormProps {
+    pub order: OrderInvoiceState,
+}
+
+#[function_component(CourierTipForm)]
+pub fn courier_tip_form(props: &CourierTipFormProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
+    let tip_state = use_state(|| None::<CourierTipState>);
+    let order_id = props.order.order_id();
+    // Tips are given in the currency the order was paid in
+    let currency = props.order.get_order_request().products.total().currency();
+
+    let subscriber = relay_ctx.subscribe.clone();
+    use_effect_with(key_ctx.get_pubkey(), move |pubkey| {
+        if let Some(pubkey) = pubkey {
+            let mut filter = NostrSubscription {
+                kinds: Some(vec![NOSTR_KIND_COURIER_TIP]),
+                ..Default::default()
+            };
+            filter.add_tag("#p", pubkey.as_str());
+            subscriber.emit(filter.into());
+        }
+        || {}
+    });
+
+    let tip_handle = tip_state.clone();
+    let keys = key_ctx.clone();
+    let tip_order_id = order_id.clone();
+    use_effect_with(relay_ctx.unique_notes.clone(), move |notes| {
+        if let Some(note) = notes.last().cloned() {
+            if note.kind == NOSTR_KIND_COURIER_TIP {
+                yew::platform::spawn_local(async move {
+                    let Ok(decrypted) = keys.decrypt_note(&note).await else {
+                        gloo::console::error!("Failed to decrypt tip");
+                        return;
+                    };
+                    let Ok(state) = CourierTipState::try_from(decrypted) else {
+                        gloo::console::error!("Failed to parse tip");
+                        return;
+                    };
+                    if state.request.order_id == tip_order_id {
+                        tip_handle.set(Some(state));
+                    }
+                });
+            }
+        }
+        || {}
+    });
+
+    let sender = relay_ctx.send_note.clone();
+    let onsubmit = Callback::from(move |e: SubmitEvent| {
+        e.prevent_default();
+        let form = HtmlForm::new(e).expect("Failed to get form");
+        let amount = form
+            .input_value("tip_amount")
+            .ok()
+            .and_then(|value| Money::parse_in(&value, currency).ok())
+            .unwrap_or_default();
+        if !amount.is_positive() {
+            return;
+        }
+        let request = CourierTipRequest::new(order_id.clone(), amount);
+        let keys = key_ctx.get_identity().cloned().expect("No user keys found");
+        let sender = sender.clone();
+        yew::platform::spawn_local(async move {
+            match request.sign_request(&keys).await {
+                Ok(note) => sender.emit(note),
+                Err(e) => gloo::console::error!("Failed to send tip", e.to_string()),
+            }
+        });
+    });
+
+    html! {
+        <div class="bg-zinc-100 p-4 rounded-2xl flex flex-col gap-3 mx-2 lg:mx-4 max-w-4xl lg:mx-auto w-full">
+            <h3 class="text-fuente text-xl font-bold">{&translations["history_tip_courier"]}</h3>
+            {match (*tip_state).clone() {
+                Some(CourierTipState { status: CourierTipStatus::Paid, .. }) => html! {
+                    <p class="text-green-500 font-bold">{&translations["history_tip_sent"]}</p>
+                },
+                Some(CourierTipState { status: CourierTipStatus::Pending, consumer_invoice: Some(invoice), .. }) => html! {
+                    <OrderInvoiceComponent {invoice} exchange_rate={admin_ctx.get_exchange_rate()} />
+                },
+                _ => html! {
+                    <form {onsubmit} class="flex flex-col md:flex-row gap-3 items-end">
+                        <MoneyInput
+                            id="tip_amount"
+                            name="tip_amount"
+                            label={currency.code()}
+                            value=""
+                            input_type="number"
+                            required={true}
+                        />
+                        <button type="submit"
+                            class="bg-fuente-light text-white font-bold py-2 px-6 rounded-full text-nowrap">
+                            {&translations["history_tip_button"]}
+                        </button>
+                    </form>
+                },
+            }}
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ReorderButtonProps {
+    pub order: OrderInvoiceState,
+}
+
+#[function_component(ReorderButton)]
+pub fn reorder_button(props: &ReorderButtonProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("CommerceDataStore not found");
+    let cart_ctx = use_context::<CartStore>().expect("CartStore not found");
+    let navigator = use_navigator().expect("Navigator not found");
+    let popup = use_state(|| false);
+    let request = props.order.get_order_request();
+    let commerce_id = request.commerce.clone();
+
+    let onclick = {
+        let popup = popup.clone();
+        let commerce_ctx = commerce_ctx.clone();
+        let commerce_id = commerce_id.clone();
+        Callback::from(move |_: MouseEvent| {
+            // Menus of commerces outside the consumer's area are only loaded on request
+            commerce_ctx.dispatch(CommerceDataAction::RequestMenu(commerce_id.clone()));
+            popup.set(true);
+        })
+    };
+
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+    let rates = admin_ctx.get_exchange_rates();
+    let popup_content = match commerce_ctx.reorder(&commerce_id, &request.products, now) {
+        None => html! {
+            <div class="flex justify-center py-8">
+                <ThreeBlockSpinner class="w-8 h-8 text-fuente" />
+            </div>
+        },
+        Some(reorder) => {
+            let replaces_cart = cart_ctx.basket(&commerce_id).is_some();
+            let add_to_cart = {
+                let cart_ctx = cart_ctx.clone();
+                let navigator = navigator.clone();
+                let order = reorder.order.clone();
+                let commerce_id = commerce_id.clone();
+                Callback::from(move |_: MouseEvent| {
+                    cart_ctx.dispatch(CartAction::Reorder(order.clone(), commerce_id.clone()));
+                    navigator.push(&ConsumerRoute::Cart);
+                })
+            };
+            html! {
+                <>
+                {if reorder.changes.is_empty() {
+                    html! {}
+                } else {
+                    html! {
+                        <>
+                        <p class="text-sm text-neutral-500">{&translations["history_reorder_changes"]}</p>
+                        <ReorderChangeList changes={reorder.changes.clone()} />
+                        </>
+                    }
+                }}
+                {if reorder.order.is_empty() {
+                    html! {
+                        <p class="text-red-500 font-bold">{&translations["history_reorder_unavailable"]}</p>
+                    }
+                } else {
+                    html! {
+                        <>
+                        <p class="flex justify-between font-bold">
+                            <span>{&translations["history_reorder_total"]}</span>
+                            <span>{language_ctx.format_price(reorder.order.total(), &rates)}</span>
+                        </p>
+                        {if replaces_cart {
+                            html! {
+                                <p class="text-sm text-neutral-500">{&translations["history_reorder_replaces_cart"]}</p>
+                            }
+                        } else {
+                            html! {}
+                        }}
+                        <button onclick={add_to_cart}
+                            class="bg-fuente-light text-white font-bold py-2 px-6 rounded-full">
+                            {&translations["history_reorder_checkout"]}
+                        </button>
+                        </>
+                    }
+                }}
+                </>
+            }
+        }
+    };
+
+    html! {
+        <>
+        <div class="flex justify-end mx-2 lg:mx-4 max-w-4xl lg:mx-auto w-full">
+            <button {onclick}
+                class="flex items-center gap-2 border-2 border-fuente text-fuente font-bold py-2 px-6 rounded-full">
+                <ShoppingCart class="w-5 h-5" />
+                {&translations["history_reorder"]}
+            </button>
+        </div>
+        <PopupSection close_handle={popup.clone()}>
+            <div class="bg-white rounded-2xl p-6 m-4 max-w-md w-full flex flex-col gap-4 max-h-[85vh] overflow-y-auto no-scrollbar">
+                <h3 class="text-fuente text-xl font-bold">{&translations["history_reorder"]}</h3>
+                {popup_content}
+            </div>
+        </PopupSection>
+        </>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ReorderChangeListProps {
+    pub changes: Vec<ReorderChange>,
+}
+
+/// Lines of a past or saved order that changed on the commerce's current menu.
+#[function_component(ReorderChangeList)]
+pub fn reorder_change_list(props: &ReorderChangeListProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let rates = admin_ctx.get_exchange_rates();
+    let changes = props
+        .changes
+        .iter()
+        .map(|change| {
+            let (name, detail) = match change {
+                ReorderChange::Removed { name } => {
+                    (name, translations["history_reorder_removed"].clone())
+                }
+                ReorderChange::OptionsChanged { name } => (
+                    name,
+                    translations["history_reorder_options_changed"].clone(),
+                ),
+                ReorderChange::SidesDropped { name, sides } => (
+                    name,
+                    format!(
+                        "{} {}",
+                        translations["history_reorder_sides_dropped"],
+                        sides.join(", ")
+                    ),
+                ),
+                ReorderChange::PriceChanged { name, was, now } => (
+                    name,
+                    format!(
+                        "{} → {}",
+                        language_ctx.format_price(*was, &rates),
+                        language_ctx.format_price(*now, &rates)
+                    ),
+                ),
+            };
+            html! {
+                <li class="flex justify-between gap-4">
+                    <span class="font-semibold">{name}</span>
+                    <span class="text-neutral-500 text-right">{detail}</span>
+                </li>
+            }
+        })
+        .collect::<Html>();
+    html! {
+        <ul class="flex flex-col gap-2 text-sm">{changes}</ul>
+    }
+}
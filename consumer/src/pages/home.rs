@@ -1,9 +1,9 @@
-use crate::contexts::{FavoritesAction, RatingsStore};
+use crate::contexts::{ConsumerDataStore, FavoritesAction, RatingsStore};
 use crate::{contexts::CommerceDataStore, contexts::FavoritesStore, router::ConsumerRoute};
 use fuente::contexts::LanguageConfigsStore;
 use fuente::mass::templates::{FuenteBenefits, FuenteBitcoinBanner, FuenteSalesPitch};
-use fuente::mass::{AppLink, CommerceProfileCard};
-use fuente::models::FavoriteStore;
+use fuente::mass::{AppLink, CommerceDiscoveryBar, CommerceProfileCard};
+use fuente::models::{CommerceDiscovery, FavoriteStore};
 use lucide_yew::{ArrowRight, Heart};
 use nostr_minions::key_manager::NostrIdStore;
 use yew::prelude::*;
@@ -43,15 +43,24 @@ pub fn home_page() -> Html {
 pub fn stores_banner() -> Html {
     let commerce_ctx = use_context::<CommerceDataStore>().expect("Commerce context not found");
     let ratings_ctx = use_context::<RatingsStore>().expect("RatingsStore not found");
+    let user_ctx = use_context::<ConsumerDataStore>().expect("ConsumerDataStore not found");
     let languages = use_context::<LanguageConfigsStore>().expect("Language context not found");
     let translations = languages.translations();
-    let businesses = commerce_ctx.commerces();
+    let discovery = use_state(CommerceDiscovery::default);
+    let destination = user_ctx
+        .get_default_address()
+        .map(|address| address.coordinates());
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+    let listings = discovery.apply(commerce_ctx.listings(&ratings_ctx, destination.as_ref(), now));
 
     html! {
         <section class="bg-fuente-light rounded-2xl py-10 mt-5 lg:mt-0">
             <div class="flex justify-between items-center w-full mx-auto">
                 <h2 class="text-white text-5xl font-semibold tracking-tighter px-5">{&translations["home_top_stores"]}</h2>
             </div>
+            <div class="bg-white rounded-xl p-3 mx-5 mt-5">
+                <CommerceDiscoveryBar discovery={discovery.clone()} has_location={destination.is_some()} />
+            </div>
 
             <div class="flex justify-center lg:justify-between items-center mt-10 px-6">
                 // <button onclick={scroll_left}>
@@ -60,11 +69,11 @@ pub fn stores_banner() -> Html {
                 // </button>
                 <div class="overflow-x-auto whitespace-nowrap no-scrollbar">
                     <div id="commerce_carousel" class="grid grid-flow-col auto-cols-max gap-10">
-                        {businesses.iter().map(|profile| {
-                            gloo::console::log!("Profile");
-                            let commerce_data = profile.profile().clone();
-                            let commerce_id = profile.id().to_string();
+                        {listings.into_iter().map(|listing| {
+                            let commerce_data = listing.profile;
+                            let commerce_id = listing.commerce_id;
                             let rating = ratings_ctx.get_business_rating(&commerce_id);
+                            let estimate = listing.estimate;
 
                             html! {
                                 <AppLink<ConsumerRoute>
@@ -72,7 +81,7 @@ pub fn stores_banner() -> Html {
                                     selected_class=""
                                     route={ConsumerRoute::Commerce { commerce_id: commerce_id.clone() }}>
                                     <div class="relative">
-                                        <CommerceProfileCard commerce_data={commerce_data.clone()} {rating} />
+                                        <CommerceProfileCard commerce_data={commerce_data.clone()} {rating} {estimate} />
                                         <FavoriteButton commerce_id={commerce_id} commerce_data={commerce_data} />
                                     </div>
                                 </AppLink<ConsumerRoute>>
@@ -1,19 +1,23 @@
 use crate::contexts::{CommerceDataExt, CommerceDataStore, LiveOrderAction, LiveOrderStore};
+use crate::pages::GroupPaymentProgress;
 use crate::router::ConsumerRoute;
 use bright_lightning::LndHodlInvoice;
 use fuente::contexts::LanguageConfigsStore;
 use fuente::mass::CheckoutBannerTemplate;
 use fuente::{
     mass::{
-        DriverDetailsComponent, OrderFailureTemplate, OrderPendingTemplate, OrderSuccessTemplate,
+        DriverDetailsComponent, OrderChatPanel, OrderFailureTemplate, OrderPendingTemplate,
+        OrderScheduledTemplate, OrderSuccessTemplate, PrepCountdown,
     },
     models::{
-        CommerceProfile, DriverProfileIdb, DriverStateUpdate, OrderInvoiceState,
+        CommerceProfile, Currency, DriverProfileIdb, DriverStateUpdate, Money, OrderInvoiceState,
         OrderPaymentStatus, OrderStatus, OrderUpdateRequest, SatisfactionRecord,
         NOSTR_KIND_CONSUMER_CANCEL, NOSTR_KIND_DRIVER_STATE, TEST_PUB_KEY,
     },
 };
+use gloo::timers::callback::Interval;
 use html::ChildrenProps;
+use lucide_yew::Clock;
 use nostr_minions::{
     browser_api::{clipboard_copy, GeolocationCoordinates},
     key_manager::NostrIdStore,
@@ -149,9 +153,21 @@ pub fn live_order_check(props: &ChildrenProps) -> Html {
                     <OrderPendingTemplate  />
                 }),
 
-                OrderPaymentStatus::PaymentReceived => Ok(html! {
-                    <OrderPendingTemplate  />
-                }),
+                OrderPaymentStatus::PaymentReceived => {
+                    if !order_state.group_paid() {
+                        Ok(html! {
+                            <GroupPaymentProgress order={order_state.clone()} />
+                        })
+                    } else if order_state.order_status == OrderStatus::Scheduled {
+                        Ok(html! {
+                            <OrderScheduledTemplate order={order_state.clone()} />
+                        })
+                    } else {
+                        Ok(html! {
+                            <OrderPendingTemplate  />
+                        })
+                    }
+                }
                 OrderPaymentStatus::PaymentFailed => {
                     let onclick = {
                         let navigator = navigator.clone();
@@ -173,7 +189,14 @@ pub fn live_order_check(props: &ChildrenProps) -> Html {
                             })
                         };
                         Ok(html! {
-                            <OrderSuccessTemplate order={order_state.clone()} onclick={onclick} />
+                            <>
+                                if let Some(preparation) = order_state.preparation {
+                                    <div class="flex justify-center mb-4">
+                                        <PrepCountdown {preparation} />
+                                    </div>
+                                }
+                                <OrderSuccessTemplate order={order_state.clone()} onclick={onclick} />
+                            </>
                         })
                     } else if status == &OrderStatus::InDelivery {
                         let commerce = commerce_ctx
@@ -220,10 +243,8 @@ pub fn live_order_check(props: &ChildrenProps) -> Html {
                 let keys = keys.clone();
                 let relay_ctx = relay_ctx.clone();
                 yew::platform::spawn_local(async move {
-                    let update_req = OrderUpdateRequest {
-                        order: order_note.clone(),
-                        status_update: OrderStatus::Canceled,
-                    };
+                    let update_req =
+                        OrderUpdateRequest::new(order_note.clone(), OrderStatus::Canceled);
                     if let Ok(signed_req) = update_req
                         .sign_update(&keys, NOSTR_KIND_CONSUMER_CANCEL)
                         .await
@@ -255,8 +276,12 @@ pub fn live_order_check(props: &ChildrenProps) -> Html {
 
             html! {
                 <>
-                    <CheckoutBannerTemplate {order} onclick={cancel_onclick} />
+                    <DeliveryEtaBanner order_id={order.order_id()} />
+                    <CheckoutBannerTemplate order={order.clone()} onclick={cancel_onclick} />
                     {inside_html}
+                    if order.payment_status != OrderPaymentStatus::PaymentPending {
+                        <OrderChatPanel {order} />
+                    }
                 </>
             }
         }
@@ -280,7 +305,10 @@ pub fn order_invoice_details(props: &OrderInvoiceComponentProps) -> Html {
     } = props.clone();
     let invoice_pr = invoice.payment_request();
     let sat_amount = invoice.sat_amount();
-    let srd_amount = sat_amount as f64 / 100_000_000.0 * exchange_rate;
+    let srd_amount = Money::from_f64(
+        sat_amount as f64 / 100_000_000.0 * exchange_rate,
+        Currency::SRD,
+    );
     let onclick_copy = {
         let pr = invoice_pr.clone();
         Callback::from(move |_| {
@@ -291,7 +319,7 @@ pub fn order_invoice_details(props: &OrderInvoiceComponentProps) -> Html {
         <div class="bg-zinc-100 p-4 rounded-2xl flex flex-col gap-3 mx-2 lg:mx-4">
             <div class="flex justify-between flex-1">
                 <h3 class="text-fuente text-xl font-bold">{&translations["checkout_summary_price_details_total"]}</h3>
-                <p class="text-gray-400 text-lg">{format!("{:.2} SRD", srd_amount)}</p>
+                <p class="text-gray-400 text-lg">{language_ctx.format_money(srd_amount)}</p>
             </div>
             <div class="flex justify-between">
                 <h3 class="text-fuente text-xl font-bold">
@@ -317,6 +345,49 @@ pub fn order_invoice_details(props: &OrderInvoiceComponentProps) -> Html {
     }
 }
 
+#[derive(Clone, PartialEq, Properties)]
+pub struct DeliveryEtaBannerProps {
+    pub order_id: String,
+}
+
+/// When the server expects the order at the consumer's door.
+#[function_component(DeliveryEtaBanner)]
+pub fn delivery_eta_banner(props: &DeliveryEtaBannerProps) -> Html {
+    let order_ctx = use_context::<LiveOrderStore>().expect("LiveOrderStore not found");
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
+    let translations = language_ctx.translations();
+    let now = use_state(|| (web_sys::js_sys::Date::now() / 1000.0) as i64);
+    {
+        let now = now.clone();
+        use_effect_with((), move |_| {
+            // Counts down between the estimates the server sends
+            let interval = Interval::new(30_000, move || {
+                now.set((web_sys::js_sys::Date::now() / 1000.0) as i64);
+            });
+            move || {
+                interval.cancel();
+            }
+        });
+    }
+    let Some(eta) = order_ctx.eta(&props.order_id) else {
+        return html! {};
+    };
+    html! {
+        <div class="bg-gray-100 p-4 mx-2 lg:mx-4 rounded-2xl flex justify-between items-center gap-3">
+            <p class="text-fuente text-lg font-bold flex items-center gap-2">
+                <Clock class="w-6 h-6 text-fuente" />
+                {&translations["live_order_eta"]}
+            </p>
+            <div class="flex flex-col items-end">
+                <p class="text-fuente text-xl font-bold">{eta.locale_arrival()}</p>
+                <p class="text-gray-500 text-sm">
+                    {format!("{} {} min", translations["live_order_eta_in"], eta.minutes_left(*now))}
+                </p>
+            </div>
+        </div>
+    }
+}
+
 #[derive(Clone, PartialEq, Properties)]
 pub struct LiveOrderTrackingProps {
     pub order: OrderInvoiceState,
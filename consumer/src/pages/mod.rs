@@ -1,20 +1,24 @@
 mod cart;
 mod commerce;
 mod favorites;
+mod group_order;
 mod history;
 mod home;
 mod live_order;
 mod new_user;
+mod search;
 mod settings;
 mod track_packages;
 pub use cart::*;
 pub use commerce::*;
 pub use favorites::*;
+pub use group_order::*;
 pub use history::*;
 pub use home::*;
 pub use live_order::*;
 use lucide_yew::ArrowLeft;
 pub use new_user::*;
+pub use search::*;
 pub use settings::*;
 pub use track_packages::*;
 
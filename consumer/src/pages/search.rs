@@ -0,0 +1,77 @@
+use crate::contexts::CommerceDataStore;
+use crate::router::ConsumerRoute;
+use fuente::contexts::{AdminConfigsStore, LanguageConfigsStore};
+use fuente::mass::{AppLink, ProductTagBadges, TagFilterPicker};
+use fuente::models::TagFilter;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+use yew_router::hooks::use_location;
+
+/// Query string of the search page, `/search?q=pizza`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+}
+
+#[function_component(SearchPage)]
+pub fn search_page() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let commerce_ctx = use_context::<CommerceDataStore>().expect("No commerce context found");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let tag_filter = use_state(TagFilter::default);
+    let query = use_location()
+        .and_then(|location| location.query::<SearchQuery>().ok())
+        .unwrap_or_default()
+        .q;
+    let rates = admin_ctx.get_exchange_rates();
+    let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+
+    let groups = commerce_ctx.search(&query, &tag_filter, now);
+
+    html! {
+        <main class="container mx-auto flex flex-col gap-5 py-5 px-5 lg:px-0">
+            <h1 class="text-3xl font-mplus lg:text-5xl text-fuente tracking-tighter font-bold">
+                {format!("{} \"{}\"", translations["search_results_heading"], query)}
+            </h1>
+            <TagFilterPicker filter={tag_filter.clone()} />
+            if groups.is_empty() {
+                <p class="text-gray-500 text-lg">{&translations["search_no_results"]}</p>
+            }
+            {groups.into_iter().map(|(commerce, products)| {
+                let commerce_id = commerce.id().to_string();
+                let profile = commerce.profile().clone();
+                html! {
+                    <section key={commerce_id.clone()} class="flex flex-col gap-3 bg-gray-100 rounded-2xl p-5">
+                        <AppLink<ConsumerRoute>
+                            route={ConsumerRoute::Commerce { commerce_id: commerce_id.clone() }}
+                            class="flex items-center gap-4"
+                            selected_class="">
+                            <img src={profile.logo_url.clone()} alt={profile.name.clone()}
+                                class="w-14 h-14 object-cover rounded-xl border-2 border-fuente" />
+                            <h2 class="text-2xl font-bold text-fuente">{&profile.name}</h2>
+                        </AppLink<ConsumerRoute>>
+                        <div class="grid sm:grid-cols-2 lg:grid-cols-3 gap-3">
+                            {products.iter().map(|product| {
+                                html! {
+                                    <AppLink<ConsumerRoute>
+                                        route={ConsumerRoute::Commerce { commerce_id: commerce_id.clone() }}
+                                        class="flex items-center gap-3 bg-white rounded-xl p-3"
+                                        selected_class="">
+                                        <img src={product.thumbnail_url()} alt={product.name()}
+                                            class="w-16 h-16 min-w-16 object-contain rounded-lg bg-gray-100" />
+                                        <div class="flex flex-col gap-1">
+                                            <p class="font-bold text-gray-600">{product.name()}</p>
+                                            <p class="text-fuente font-semibold">{language_ctx.format_price(product.price(), &rates)}</p>
+                                            <ProductTagBadges product={product.clone()} />
+                                        </div>
+                                    </AppLink<ConsumerRoute>>
+                                }
+                            }).collect::<Html>()}
+                        </div>
+                    </section>
+                }
+            }).collect::<Html>()}
+        </main>
+    }
+}
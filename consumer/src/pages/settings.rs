@@ -1,11 +1,11 @@
-use crate::contexts::{ConsumerDataAction, ConsumerDataStore};
+use crate::contexts::{CartAction, CartStore, ConsumerDataAction, ConsumerDataStore};
 use crate::router::ConsumerRoute;
 use fuente::contexts::LanguageConfigsStore;
 use fuente::mass::PopupProps;
 use fuente::mass::{
     templates::{KeyRecoverySection, SettingsPageTemplate},
-    AddressLookupDetails, AppLink, CardComponent, ImageUploadInput, LanguageToggle, NewAddressForm,
-    NewAddressProps, PopupSection, SimpleInput,
+    AddressLookupDetails, AppLink, CardComponent, CurrencyToggle, ImageUploadInput, LanguageToggle,
+    NewAddressForm, NewAddressProps, PopupSection, SimpleInput,
 };
 use fuente::models::{
     ConsumerAddress, ConsumerAddressIdb, ConsumerProfile, ConsumerProfileIdb, TEST_PUB_KEY,
@@ -221,6 +221,8 @@ pub fn settings_page() -> Html {
                     SettingsPage::Language => html! {
                         <div class="w-full">
                             <LanguageToggle />
+                            <CurrencyToggle />
+                            <CartSyncToggle />
                         </div>
                     },
             }}
@@ -229,6 +231,29 @@ pub fn settings_page() -> Html {
     }
 }
 
+/// Lets the consumer pick up their cart on other devices logged in with the same key.
+#[function_component(CartSyncToggle)]
+pub fn cart_sync_toggle() -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let cart_ctx = use_context::<CartStore>().expect("No cart context found");
+    let sync_devices = cart_ctx.sync_devices();
+    let onclick = Callback::from(move |_: MouseEvent| {
+        cart_ctx.dispatch(CartAction::SetDeviceSync(!sync_devices));
+    });
+    html! {
+        <div class="flex flex-col gap-1 mt-4">
+            <div class="flex items-center gap-2">
+                <input type="checkbox" id="cart_sync" checked={sync_devices} {onclick} />
+                <label for="cart_sync" class="font-bold text-fuente">
+                    {&translations["profile_settings_cart_sync"]}
+                </label>
+            </div>
+            <p class="text-sm text-neutral-500">{&translations["profile_settings_cart_sync_note"]}</p>
+        </div>
+    }
+}
+
 #[function_component(MyContactDetails)]
 pub fn my_contact_details() -> Html {
     let user_ctx = use_context::<ConsumerDataStore>().expect("No user context found");
@@ -1,4 +1,5 @@
 use crate::contexts::{CommerceDataStore, LiveOrderStore};
+use crate::pages::DeliveryEtaBanner;
 use crate::router::ConsumerRoute;
 use fuente::models::OrderPaymentStatus;
 use fuente::{
@@ -128,6 +129,9 @@ pub fn track_packages_page() -> Html {
                                     order={order.clone()}
                                     on_submit={Callback::from(move |_| {})}
                                 />
+                                <div class="mt-4">
+                                    <DeliveryEtaBanner order_id={order_id.clone()} />
+                                </div>
 
                                 // Map Section if driver is assigned
                                 {if has_courier {
@@ -9,15 +9,16 @@ use yew_router::prelude::*;
 
 use fuente::{
     contexts::LanguageConfigsStore,
-    mass::{AppLink, LoginPage},
+    mass::{AppLink, LoginPage, TagFilterPicker},
+    models::TagFilter,
 };
 
 use crate::{
     contexts::{CartStore, CommerceDataStore, ConsumerDataStore, RequireAuth},
     pages::{
-        AllCommercesPage, CartPage, CheckoutPage, CommercePage, FavoritesPage, HistoryPage,
-        HomePage, LiveOrderCheck, NewAddressPage, NewProfilePage, SettingsPageComponent,
-        TrackPackagesPage,
+        AllCommercesPage, CartPage, CheckoutPage, CommercePage, FavoritesPage, GroupOrderPage,
+        HistoryPage, HomePage, LiveOrderCheck, NewAddressPage, NewProfilePage, SearchPage,
+        SearchQuery, SettingsPageComponent, TrackPackagesPage,
     },
 };
 
@@ -39,8 +40,12 @@ pub enum ConsumerRoute {
     BrowseStores,
     #[at("/commerce/:commerce_id")]
     Commerce { commerce_id: String },
+    #[at("/search")]
+    Search,
     #[at("/order/:order_id")]
     Order { order_id: String },
+    #[at("/group/:code")]
+    GroupOrder { code: String },
     #[at("/track-packages")] // Add this new route
     TrackPackages,
     #[at("/login")]
@@ -87,6 +92,7 @@ pub fn consumer_pages() -> Html {
                         ConsumerRoute::Commerce { commerce_id } => html!{
                             <CommercePage {commerce_id} />
                         },
+                        ConsumerRoute::Search => html!{<SearchPage />},
 
                         // Protected routes - for now just render normally
                         ConsumerRoute::Cart => html!{
@@ -103,6 +109,11 @@ pub fn consumer_pages() -> Html {
                         },
                         ConsumerRoute::Favorites => html!{<FavoritesPage />},
                         ConsumerRoute::Order { order_id: _ } => html!{<LiveOrderCheck />},
+                        ConsumerRoute::GroupOrder { code } => html!{
+                            <RequireAuth>
+                                <GroupOrderPage {code} />
+                            </RequireAuth>
+                        },
                         ConsumerRoute::TrackPackages => html!{<TrackPackagesPage />},
                         ConsumerRoute::Login => {
                             if logged_in.is_some() {
@@ -170,7 +181,7 @@ fn login_button() -> Html {
 pub fn header() -> Html {
     let cart_ctx = use_context::<CartStore>().expect("CartContext not found");
     let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
-    let cart_len = cart_ctx.cart().len();
+    let cart_len = cart_ctx.item_count();
     let is_authenticated = key_ctx.get_identity().is_some();
 
     html! {
@@ -305,6 +316,7 @@ pub fn commerce_filters() -> Html {
     let is_open = use_state(|| false);
     let wrapper_ref = use_node_ref();
     let search_term = use_state(|| String::new());
+    let tag_filter = use_state(TagFilter::default);
 
     {
         let wrapper_clone = wrapper_ref.clone();
@@ -335,24 +347,41 @@ pub fn commerce_filters() -> Html {
     }
 
     let businesses_clone = businesses.clone();
-    use_effect_with(search_term.clone(), move |search_term| {
-        let search_term = search_term.clone();
-        businesses_clone.set(
-            commerce_ctx
-                .commerces()
-                .iter()
-                .filter(|profile| {
-                    profile
-                        .profile()
-                        .name
-                        .to_lowercase()
-                        .contains(&*search_term.to_lowercase())
-                })
-                .cloned()
-                .collect(),
-        );
-        || {}
-    });
+    use_effect_with(
+        (
+            search_term.clone(),
+            tag_filter.clone(),
+            commerce_ctx.clone(),
+        ),
+        move |(search_term, tag_filter, commerce_ctx)| {
+            let suggestions = if search_term.trim().is_empty() {
+                commerce_ctx
+                    .commerces()
+                    .iter()
+                    // Only commerces selling something that fits the dietary filter
+                    .filter(|profile| {
+                        tag_filter.is_empty()
+                            || commerce_ctx
+                                .find_product_list_by_id(profile.id())
+                                .is_some_and(|menu| {
+                                    menu.menu().categories().iter().any(|category| {
+                                        category
+                                            .products()
+                                            .iter()
+                                            .any(|product| tag_filter.matches(product))
+                                    })
+                                })
+                    })
+                    .map(|profile| (profile.clone(), vec![]))
+                    .collect()
+            } else {
+                let now = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+                commerce_ctx.search(search_term, tag_filter, now)
+            };
+            businesses_clone.set(suggestions);
+            || {}
+        },
+    );
 
     let set_search_term = {
         let search_term = search_term.clone();
@@ -371,13 +400,40 @@ pub fn commerce_filters() -> Html {
             is_open.set(false);
         })
     };
+    let navigator = use_navigator().expect("Navigator not found");
+    let show_results = {
+        let search_term = search_term.clone();
+        let is_open = is_open.clone();
+        Callback::from(move |_: ()| {
+            let query = SearchQuery {
+                q: (*search_term).clone(),
+            };
+            if let Err(e) = navigator.push_with_query(&ConsumerRoute::Search, &query) {
+                gloo::console::error!("Failed to open search", e.to_string());
+            }
+            is_open.set(false);
+        })
+    };
+    let onsubmit = {
+        let show_results = show_results.clone();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            show_results.emit(());
+        })
+    };
+    let see_all = Callback::from(move |_: MouseEvent| show_results.emit(()));
+    let open_search = {
+        let is_open = is_open.clone();
+        Callback::from(move |_: FocusEvent| is_open.set(true))
+    };
     html! {
     <div ref={wrapper_ref} class="relative w-full max-w-2xl mx-auto">
-        <div class="relative">
+        <form {onsubmit} class="relative">
             <input
-                type="text"
+                type="search"
                 value={(*search_term).clone()}
                 oninput={set_search_term}
+                onfocus={open_search}
                 placeholder={translations["nav_search"].clone()}
                 class="w-full pl-5 pr-10 py-3 border-2 border-fuente rounded-xl text-fuente placeholder:text-fuente"
             />
@@ -396,15 +452,24 @@ pub fn commerce_filters() -> Html {
                         <X class="w-5 h-5 text-gray-400 hover:text-gray-600" />
                     </button>
             } }}
-        </div>
-        {if *is_open && businesses.len() > 0 {
+        </form>
+        {if *is_open {
             html! {
                 <div class="absolute z-10 w-full mt-1 bg-white border-2 border-gray-300 rounded-md shadow-lg">
+                    <div class="p-3 border-b border-gray-200">
+                        <TagFilterPicker filter={tag_filter.clone()} />
+                    </div>
                     <ul class="py-1">
-                        {businesses.iter().map(|profile| {
+                        {businesses.iter().map(|(profile, products)| {
                             let commerce_data = profile.profile().clone();
                             let commerce_id = profile.id().to_string();
                             let is_open_clone = is_open.clone();
+                            let product_names = products
+                                .iter()
+                                .take(3)
+                                .map(|product| product.name())
+                                .collect::<Vec<_>>()
+                                .join(", ");
                             html! {
                                 <li class="px-4 py-2 cursor-pointer hover:bg-gray-100 text-fuente font-semibold"
                                     onclick={Callback::from(move |_| is_open_clone.set(false))}
@@ -414,11 +479,20 @@ pub fn commerce_filters() -> Html {
                                         selected_class=""
                                         route={ConsumerRoute::Commerce { commerce_id: commerce_id.clone() }}>
                                         {commerce_data.name}
+                                        if !product_names.is_empty() {
+                                            <p class="text-sm font-normal text-gray-500 truncate">{product_names}</p>
+                                        }
                                     </AppLink<ConsumerRoute>>
                                 </li>
                             }
                         }).collect::<Html>()}
                     </ul>
+                    if !search_term.trim().is_empty() {
+                        <button type="button" onclick={see_all}
+                            class="w-full text-left px-4 py-2 border-t border-gray-200 text-fuente-orange font-semibold">
+                            {&translations["search_see_all"]}
+                        </button>
+                    }
                 </div>
             }
         } else { html! {} }}
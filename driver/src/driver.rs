@@ -6,7 +6,9 @@ use driver::{
     router::DriverPages,
 };
 use fuente::{
-    contexts::{AdminConfigsProvider, AdminConfigsStore, LanguageConfigsProvider},
+    contexts::{
+        AdminConfigsProvider, AdminConfigsStore, LanguageConfigsProvider, OrderChatProvider,
+    },
     mass::{templates::LoginPageTemplate, LoadingScreen, LoginPage, SimpleInput, ToastProvider, PwaInstall},
     models::{
         init_commerce_db, init_consumer_db, DriverProfile, DriverProfileIdb, DRIVER_HUB_PUB_KEY,
@@ -92,7 +94,9 @@ fn app_context(props: &ChildrenProps) -> Html {
             <DriverDataProvider>
                 <CommerceDataProvider>
                     <OrderHubProvider>
-                        {props.children.clone()}
+                        <OrderChatProvider>
+                            {props.children.clone()}
+                        </OrderChatProvider>
                     </OrderHubProvider>
                 </CommerceDataProvider>
             </ DriverDataProvider>
@@ -224,8 +228,9 @@ pub fn new_profile_form() -> Html {
         let telephone = form_element
             .input_value("telephone")
             .expect("Failed to get telephone");
+        let ln_address = form_element.input_value("ln_address").unwrap_or_default();
         let sender = sender.clone();
-        let user_profile = DriverProfile::new(nickname, telephone);
+        let user_profile = DriverProfile::new(nickname, telephone, ln_address);
         let pubkey = key_ctx.get_pubkey().expect("No pubkey");
 
         yew::platform::spawn_local(async move {
@@ -269,6 +274,14 @@ pub fn new_profile_form() -> Html {
                     input_type="tel"
                     required={true}
                     />
+                <SimpleInput
+                    id="ln_address"
+                    name="ln_address"
+                    label="Lightning Address"
+                    value=""
+                    input_type="text"
+                    required={false}
+                    />
                 <button
                     type="submit"
                     class="bg-fuente-light p-3 rounded-3xl font-bold text-white hover:cursor-pointer w-2/4 mx-auto whitespace-normal text-nowrap">
@@ -65,6 +65,11 @@ pub fn history_page() -> Html {
         })
         .collect::<Vec<_>>();
     completed_orders.sort_by(|a, b| b.order_timestamp().cmp(&a.order_timestamp()));
+    // Tips the node still holds or failed to pay out aren't earned yet
+    let tips_earned: u64 = completed_orders
+        .iter()
+        .map(|order| order.courier_tip_paid_sats)
+        .sum();
     canceled_orders.sort_by(|a, b| b.order_timestamp().cmp(&a.order_timestamp()));
 
     if let Some(order_id) = (*selected_order).clone() {
@@ -97,6 +102,9 @@ pub fn history_page() -> Html {
                       {&translations["orders_heading"]}
                   </h1>
                   <div class="flex items-center gap-5">
+                      <p class="text-fuente-orange font-bold text-md text-nowrap">
+                          {format!("{}: {} sats", &translations["driver_history_tips"], tips_earned)}
+                      </p>
                       <label for="date" class="hidden lg:block text-fuente font-light text-md w-full text-right">{&translations["store_orders_history_date"]}</label>
                       <div class="relative w-fit">
                             <input {onchange} type="date" placeholder=""
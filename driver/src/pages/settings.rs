@@ -10,7 +10,7 @@ use fuente::{
     },
     models::{DriverProfile, DriverProfileIdb, DRIVER_HUB_PUB_KEY},
 };
-use lucide_yew::{Key, Phone, ScrollText, SquarePen, X};
+use lucide_yew::{Key, Phone, ScrollText, SquarePen, Zap, X};
 use nostr_minions::{browser_api::HtmlForm, key_manager::NostrIdStore, relay_pool::NostrProps};
 use yew::prelude::*;
 
@@ -155,6 +155,13 @@ pub fn my_contact_details() -> Html {
                                 <p class="text-gray-500">{&profile.telephone()}</p>
                             </div>
                         </div>
+                        <div class="flex items-center space-x-3">
+                            <Zap class="text-gray-500 w-5 h-5" />
+                            <div>
+                                <p class="text-gray-500 text-lg font-bold">{&translations["checkout_client_information_heading_ln_address"]}</p>
+                                <p class="text-gray-500">{&profile.ln_address_str()}</p>
+                            </div>
+                        </div>
                         <div class="flex items-center space-x-3">
                             <Key class="text-gray-500 w-5 h-5" />
                             <div>
@@ -187,8 +194,9 @@ pub fn new_profile_form(props: &PopupProps) -> Html {
         let telephone = form_element
             .input_value("telephone")
             .expect("Failed to get telephone");
+        let ln_address = form_element.input_value("ln_address").unwrap_or_default();
         let sender = sender.clone();
-        let user_profile = DriverProfile::new(nickname, telephone);
+        let user_profile = DriverProfile::new(nickname, telephone, ln_address);
         let pubkey = key_ctx.get_pubkey().expect("No pubkey");
         let popup_handle = popup_handle.clone();
         yew::platform::spawn_local(async move {
@@ -234,6 +242,14 @@ pub fn new_profile_form(props: &PopupProps) -> Html {
                     input_type="tel"
                     required={true}
                     />
+                <SimpleInput
+                    id="ln_address"
+                    name="ln_address"
+                    label="Lightning Address"
+                    value=""
+                    input_type="text"
+                    required={false}
+                    />
                 <button
                     type="submit"
                     class="bg-fuente-light text-white font-bold p-2 rounded-3xl px-4 w-fit shadow-xl">
@@ -3,7 +3,9 @@ use nostro2::notes::NostrTag;
 use std::rc::Rc;
 use yew::prelude::*;
 
-use crate::models::{AdminConfigurationType, NOSTR_KIND_SERVER_CONFIG};
+use crate::models::{
+    AdminConfigurationType, Currency, ExchangeRates, Promotion, NOSTR_KIND_SERVER_CONFIG,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct AdminConfigs {
@@ -11,6 +13,8 @@ pub struct AdminConfigs {
     commerce_whitelist: Vec<String>,
     courier_whitelist: Vec<String>,
     exchange_rate: String,
+    exchange_rates: ExchangeRates,
+    promotions: Vec<Promotion>,
 }
 impl AdminConfigs {
     pub fn is_loaded(&self) -> bool {
@@ -19,19 +23,32 @@ impl AdminConfigs {
     pub fn get_exchange_rate(&self) -> f64 {
         self.exchange_rate.parse::<f64>().unwrap_or(0.0)
     }
+    /// The published rate table, using the legacy SRD rate until one is published.
+    pub fn get_exchange_rates(&self) -> ExchangeRates {
+        let mut rates = self.exchange_rates.clone();
+        if rates.rate(Currency::SRD).is_none() {
+            rates.set_rate(Currency::SRD, self.get_exchange_rate());
+        }
+        rates
+    }
     pub fn get_commerce_whitelist(&self) -> Vec<String> {
         self.commerce_whitelist.clone()
     }
     pub fn get_courier_whitelist(&self) -> Vec<String> {
         self.courier_whitelist.clone()
     }
+    pub fn get_promotions(&self) -> Vec<Promotion> {
+        self.promotions.clone()
+    }
 }
 
 pub enum AdminConfigsAction {
     FinishLoading,
     UpdateExchangeRate(String),
+    UpdateExchangeRates(ExchangeRates),
     UpdateCommerceWhitelist(Vec<String>),
     UpdateCourierWhitelist(Vec<String>),
+    UpdatePromotions(Vec<Promotion>),
 }
 impl Reducible for AdminConfigs {
     type Action = AdminConfigsAction;
@@ -46,6 +63,10 @@ impl Reducible for AdminConfigs {
                 exchange_rate: rate,
                 ..(*self).clone()
             }),
+            AdminConfigsAction::UpdateExchangeRates(rates) => Rc::new(AdminConfigs {
+                exchange_rates: rates,
+                ..(*self).clone()
+            }),
             AdminConfigsAction::UpdateCommerceWhitelist(whitelist) => Rc::new(AdminConfigs {
                 commerce_whitelist: whitelist,
                 ..(*self).clone()
@@ -54,6 +75,10 @@ impl Reducible for AdminConfigs {
                 courier_whitelist: whitelist,
                 ..(*self).clone()
             }),
+            AdminConfigsAction::UpdatePromotions(promotions) => Rc::new(AdminConfigs {
+                promotions,
+                ..(*self).clone()
+            }),
         }
     }
 }
@@ -66,6 +91,8 @@ pub fn key_handler(props: &yew::html::ChildrenProps) -> Html {
         commerce_whitelist: vec![],
         courier_whitelist: vec![],
         exchange_rate: "0".to_string(),
+        exchange_rates: ExchangeRates::default(),
+        promotions: vec![],
     });
 
     html! {
@@ -110,6 +137,13 @@ fn admin_config_sync() -> Html {
                                 ));
                             }
                         }
+                        AdminConfigurationType::ExchangeRates => {
+                            if let Ok(rates) = serde_json::from_str::<ExchangeRates>(&note.content)
+                            {
+                                ctx_handler
+                                    .dispatch(AdminConfigsAction::UpdateExchangeRates(rates));
+                            }
+                        }
                         AdminConfigurationType::CommerceWhitelist => {
                             match serde_json::from_str::<Vec<String>>(&note.content) {
                                 Ok(whitelist) => {
@@ -156,6 +190,14 @@ fn admin_config_sync() -> Html {
                                 }
                             }
                         }
+                        AdminConfigurationType::Promotions => {
+                            if let Ok(promotions) =
+                                serde_json::from_str::<Vec<Promotion>>(&note.content)
+                            {
+                                ctx_handler
+                                    .dispatch(AdminConfigsAction::UpdatePromotions(promotions));
+                            }
+                        }
                         _ => {}
                     }
                 }
@@ -1,6 +1,8 @@
 use std::rc::Rc;
 use yew::prelude::*;
 
+use crate::models::{Currency, ExchangeRates, Money};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AppLocale {
     English,
@@ -11,6 +13,7 @@ pub enum AppLocale {
 pub struct LanguageConfigs {
     locale: AppLocale,
     translations: TranslationData,
+    display_currency: Option<Currency>,
 }
 impl LanguageConfigs {
     pub fn translations(&self) -> &std::collections::HashMap<String, String> {
@@ -19,10 +22,30 @@ impl LanguageConfigs {
      pub fn current_locale(&self) -> AppLocale {
         self.locale
     }
+    /// Formats an amount with the separators of the current locale.
+    pub fn format_money(&self, amount: Money) -> String {
+        match self.locale {
+            AppLocale::English => amount.localized("en"),
+            AppLocale::Dutch => amount.localized("nl"),
+        }
+    }
+    /// Currency prices are shown in, `None` keeps each commerce's own currency.
+    pub fn display_currency(&self) -> Option<Currency> {
+        self.display_currency
+    }
+    /// Formats a price in the display currency, falling back to its own when no rate is known.
+    pub fn format_price(&self, amount: Money, rates: &ExchangeRates) -> String {
+        let amount = self
+            .display_currency
+            .and_then(|currency| rates.convert(amount, currency).ok())
+            .unwrap_or(amount);
+        self.format_money(amount)
+    }
 }
 
 pub enum LanguageConfigsAction {
     ChangeLocale(AppLocale),
+    ChangeDisplayCurrency(Option<Currency>),
 }
 impl Reducible for LanguageConfigs {
     type Action = LanguageConfigsAction;
@@ -32,7 +55,14 @@ impl Reducible for LanguageConfigs {
             LanguageConfigsAction::ChangeLocale(locale) => Rc::new(LanguageConfigs {
                 locale,
                 translations: TranslationData::load_translation(locale),
+                display_currency: self.display_currency,
             }),
+            LanguageConfigsAction::ChangeDisplayCurrency(display_currency) => {
+                Rc::new(LanguageConfigs {
+                    display_currency,
+                    ..(*self).clone()
+                })
+            }
         }
     }
 }
@@ -43,6 +73,7 @@ pub fn key_handler(props: &yew::html::ChildrenProps) -> Html {
     let ctx = use_reducer(|| LanguageConfigs {
         locale: AppLocale::English,
         translations: TranslationData::default(),
+        display_currency: None,
     });
 
     html! {
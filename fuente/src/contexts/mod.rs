@@ -1,4 +1,6 @@
 mod admin_configs;
 mod language;
+mod order_chat;
 pub use admin_configs::*;
 pub use language::*;
+pub use order_chat::*;
@@ -0,0 +1,129 @@
+use nostr_minions::{key_manager::NostrIdStore, relay_pool::NostrProps};
+use nostro2::relays::NostrSubscription;
+use std::rc::Rc;
+use yew::{platform::spawn_local, prelude::*};
+
+use crate::models::{OrderChat, OrderChatMessage, OrderInvoiceState, NOSTR_KIND_ORDER_CHAT};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OrderChats {
+    chats: Vec<OrderChat>,
+    closed: Vec<String>,
+}
+impl OrderChats {
+    pub fn chat(&self, order_id: &str) -> Option<&OrderChat> {
+        self.chats.iter().find(|chat| chat.order_id == order_id)
+    }
+    pub fn unread(&self, order: &OrderInvoiceState, own_pubkey: &str) -> usize {
+        self.chat(&order.order_id())
+            .map(|chat| chat.unread(order, own_pubkey))
+            .unwrap_or_default()
+    }
+}
+
+pub enum OrderChatAction {
+    AddMessage(OrderChatMessage),
+    MarkRead(Box<OrderInvoiceState>),
+    /// Drops the messages of a finished order and ignores any sent after.
+    CloseChat(String),
+}
+impl Reducible for OrderChats {
+    type Action = OrderChatAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut chats = (*self).clone();
+        match action {
+            OrderChatAction::AddMessage(message) => {
+                if chats.closed.contains(&message.order_id) {
+                    return self;
+                }
+                match chats
+                    .chats
+                    .iter_mut()
+                    .find(|chat| chat.order_id == message.order_id)
+                {
+                    Some(chat) => chat.add_message(message),
+                    None => {
+                        let mut chat = OrderChat::new(message.order_id.clone());
+                        chat.add_message(message);
+                        chats.chats.push(chat);
+                    }
+                }
+            }
+            OrderChatAction::MarkRead(order) => {
+                let order_id = order.order_id();
+                let Some(chat) = chats
+                    .chats
+                    .iter_mut()
+                    .find(|chat| chat.order_id == order_id)
+                else {
+                    return self;
+                };
+                chat.mark_read(&order);
+            }
+            OrderChatAction::CloseChat(order_id) => {
+                if chats.closed.contains(&order_id) {
+                    return self;
+                }
+                chats.chats.retain(|chat| chat.order_id != order_id);
+                chats.closed.push(order_id);
+            }
+        }
+        Rc::new(chats)
+    }
+}
+pub type OrderChatStore = UseReducerHandle<OrderChats>;
+
+#[function_component(OrderChatProvider)]
+pub fn order_chat_provider(props: &yew::html::ChildrenProps) -> Html {
+    let ctx = use_reducer(OrderChats::default);
+
+    html! {
+        <ContextProvider<OrderChatStore> context={ctx}>
+            {props.children.clone()}
+            <OrderChatSync />
+        </ContextProvider<OrderChatStore>>
+    }
+}
+
+#[function_component(OrderChatSync)]
+fn order_chat_sync() -> Html {
+    let ctx = use_context::<OrderChatStore>().expect("OrderChatStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("NostrProps not found");
+    let keys_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+
+    let subscriber = relay_ctx.subscribe.clone();
+    use_effect_with(keys_ctx.get_pubkey(), move |pubkey| {
+        if let Some(pubkey) = pubkey {
+            let mut filter = NostrSubscription {
+                kinds: Some(vec![NOSTR_KIND_ORDER_CHAT]),
+                ..Default::default()
+            };
+            filter.add_tag("#p", pubkey.as_str());
+            subscriber.emit(filter.into());
+        }
+        || {}
+    });
+
+    use_effect_with(relay_ctx.unique_notes.clone(), move |notes| {
+        if let Some(note) = notes.last().cloned() {
+            if note.kind == NOSTR_KIND_ORDER_CHAT {
+                spawn_local(async move {
+                    let Ok(decrypted) = keys_ctx.decrypt_note(&note).await else {
+                        gloo::console::error!("Failed to decrypt chat message");
+                        return;
+                    };
+                    match OrderChatMessage::from_note(&note, &decrypted) {
+                        Ok(message) => ctx.dispatch(OrderChatAction::AddMessage(message)),
+                        Err(e) => {
+                            gloo::console::error!("Failed to parse chat message:", e.to_string())
+                        }
+                    }
+                });
+            }
+        }
+        || {}
+    });
+
+    html! {}
+}
@@ -1,5 +1,5 @@
 use html::ChildrenProps;
-use lucide_yew::{Download, Globe, Plus, X};
+use lucide_yew::{Banknote, Download, Globe, Plus, X};
 use nostr_minions::browser_api::BeforeInstallPromptEvent;
 use nostr_minions::key_manager::UserIdentity;
 use nostro2::notes::NostrNote;
@@ -193,8 +193,8 @@ use nostr_minions::{browser_api::HtmlDocument, relay_pool::NostrProps};
 use crate::{
     contexts::{AppLocale, LanguageConfigsAction, LanguageConfigsStore},
     models::{
-        NOSTR_KIND_PRESIGNED_URL_REQ, NOSTR_KIND_PRESIGNED_URL_RESP, NOSTR_KIND_SERVER_REQUEST,
-        TEST_PUB_KEY,
+        Currency, NOSTR_KIND_PRESIGNED_URL_REQ, NOSTR_KIND_PRESIGNED_URL_RESP,
+        NOSTR_KIND_SERVER_REQUEST, TEST_PUB_KEY,
     },
 };
 #[function_component(ImageUploadInput)]
@@ -507,6 +507,55 @@ pub fn language_toggle() -> Html {
     }
 }
 
+#[function_component(CurrencyToggle)]
+pub fn currency_toggle() -> Html {
+    let language_ctx =
+        use_context::<LanguageConfigsStore>().expect("LanguageConfigsStore not found");
+    let display_currency = language_ctx.display_currency();
+    let translations = language_ctx.translations();
+    let options = std::iter::once(None).chain(Currency::ALL.into_iter().map(Some));
+
+    html! {
+        <div class="p-6 space-y-6">
+            <div class="flex items-center space-x-3 border-b pb-2">
+                <Banknote class="text-fuente w-6 h-6" />
+                <h2 class="text-2xl font-bold text-fuente">
+                  {&translations["profile_settings_currency"]}
+                </h2>
+            </div>
+            <div class="flex flex-wrap gap-2">
+                {options.map(|currency| {
+                    let onclick = {
+                        let language_ctx = language_ctx.clone();
+                        Callback::from(move |_| {
+                            language_ctx.dispatch(LanguageConfigsAction::ChangeDisplayCurrency(currency));
+                        })
+                    };
+                    html! {
+                        <button {onclick}
+                            class={classes!(
+                                "px-4",
+                                "py-2",
+                                "rounded-lg",
+                                if display_currency == currency {
+                                    "bg-fuente text-white"
+                                } else {
+                                    "bg-gray-100 hover:bg-gray-200"
+                                }
+                            )}
+                        >
+                            {match currency {
+                                Some(currency) => currency.code().to_string(),
+                                None => translations["profile_settings_currency_store"].clone(),
+                            }}
+                        </button>
+                    }
+                }).collect::<Html>()}
+            </div>
+        </div>
+    }
+}
+
 #[function_component(PwaInstall)]
 pub fn pwa_install() -> Html {
     let is_installable = use_state(|| None);
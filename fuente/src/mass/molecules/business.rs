@@ -1,20 +1,25 @@
 use yew::prelude::*;
 
 use crate::contexts::LanguageConfigsStore;
-use crate::models::{CommerceProfile, ParticipantRating};
+use crate::models::{
+    CommerceDiscovery, CommerceProfile, CommerceSort, DeliveryEstimate, ParticipantRating,
+};
 
 #[derive(Clone, Properties, PartialEq)]
 pub struct CommerceProfileProps {
     pub commerce_data: CommerceProfile,
     #[prop_or_default]
     pub rating: Option<ParticipantRating>,
+    #[prop_or_default]
+    pub estimate: Option<DeliveryEstimate>,
 }
 
 #[function_component(CommerceProfileCard)]
 pub fn business_card(props: &CommerceProfileProps) -> Html {
     let CommerceProfileProps {
         commerce_data,
-        rating: _,
+        estimate,
+        ..
     } = props;
     let logo_url = if commerce_data.logo_url.is_empty() {
         "/public/assets/img/company.png".to_string()
@@ -22,7 +27,7 @@ pub fn business_card(props: &CommerceProfileProps) -> Html {
         commerce_data.logo_url.clone()
     };
     html! {
-        <div class="flex flex-col items-center">
+        <div class="relative flex flex-col items-center">
             <div class="w-full aspect-square overflow-hidden rounded-lg -m-2">
                 <img 
                     src={logo_url} 
@@ -30,6 +35,9 @@ pub fn business_card(props: &CommerceProfileProps) -> Html {
                     class="w-full h-full object-cover object-center"
                 />
             </div>
+            if let Some(estimate) = estimate {
+                <DeliveryEstimateBadge estimate={*estimate} class="absolute bottom-2 left-2" />
+            }
         </div>
     }
 }
@@ -38,10 +46,7 @@ pub fn business_card(props: &CommerceProfileProps) -> Html {
 pub fn business_details(props: &CommerceProfileProps) -> Html {
     let language_ctx = use_context::<LanguageConfigsStore>().expect("LanguageStore not found");
     let translations = language_ctx.translations();
-    let CommerceProfileProps {
-        commerce_data,
-        rating: _,
-    } = props;
+    let CommerceProfileProps { commerce_data, .. } = props;
     html! {
         <section class="lg:mt-5 space-y-3 border-t border-t-gray-400 md:border-t-0 py-3 w-full">
             <h3 class="text-gray-500 font-light text-lg">{&translations["stores_settings_option_information"]}</h3>
@@ -63,13 +68,90 @@ pub fn business_details(props: &CommerceProfileProps) -> Html {
 
 #[function_component(CommerceProfileAddressDetails)]
 pub fn business_address_details(props: &CommerceProfileProps) -> Html {
-    let CommerceProfileProps {
-        commerce_data,
-        rating: _,
-    } = props;
+    let CommerceProfileProps { commerce_data, .. } = props;
     html! {
         <section class="space-y-3 py-3 w-full md:col-start-1 md:col-end-3">
             <span class="text-neutral-400 line-clamp-3 text-center">{commerce_data.lookup.display_name()}</span>
         </section>
     }
 }
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct DeliveryEstimateBadgeProps {
+    pub estimate: DeliveryEstimate,
+    #[prop_or_default]
+    pub class: Classes,
+}
+
+#[function_component(DeliveryEstimateBadge)]
+pub fn delivery_estimate_badge(props: &DeliveryEstimateBadgeProps) -> Html {
+    let DeliveryEstimateBadgeProps { estimate, class } = props;
+    html! {
+        <span class={classes!("bg-white", "text-fuente", "font-semibold", "text-xs", "rounded-full", "px-2", "py-1", "shadow", "text-nowrap", class.clone())}>
+            {format!("{} · {}", estimate.minutes_display(), estimate.distance_display())}
+        </span>
+    }
+}
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct CommerceDiscoveryBarProps {
+    pub discovery: UseStateHandle<CommerceDiscovery>,
+    /// Distance limits need the consumer's address.
+    pub has_location: bool,
+}
+
+fn discovery_change(
+    discovery: &UseStateHandle<CommerceDiscovery>,
+    change: impl Fn(&mut CommerceDiscovery) + 'static,
+) -> Callback<MouseEvent> {
+    let discovery = discovery.clone();
+    Callback::from(move |_: MouseEvent| {
+        let mut new_discovery = (*discovery).clone();
+        change(&mut new_discovery);
+        discovery.set(new_discovery);
+    })
+}
+
+#[function_component(CommerceDiscoveryBar)]
+pub fn commerce_discovery_bar(props: &CommerceDiscoveryBarProps) -> Html {
+    let CommerceDiscoveryBarProps {
+        discovery,
+        has_location,
+    } = props;
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("LanguageStore not found");
+    let translations = language_ctx.translations();
+    let chip = |label: String, is_selected: bool, onclick: Callback<MouseEvent>| {
+        html! {
+            <button {onclick} type="button"
+                class={classes!(
+                    "border", "border-fuente", "rounded-full", "px-3", "py-1", "text-sm", "text-nowrap",
+                    if is_selected { "bg-fuente text-white" } else { "text-fuente" }
+                )}>
+                {label}
+            </button>
+        }
+    };
+    html! {
+        <div class="flex flex-wrap items-center gap-2">
+            <span class="text-gray-500 font-semibold">{&translations["discovery_sort_by"]}</span>
+            {CommerceSort::ALL.into_iter().map(|sort| {
+                let onclick = discovery_change(discovery, move |discovery| discovery.sort = sort);
+                chip(translations[sort.translation_key()].clone(), discovery.sort == sort, onclick)
+            }).collect::<Html>()}
+            <span class="w-px h-6 bg-gray-300 mx-1"></span>
+            {chip(
+                translations["discovery_open_now"].clone(),
+                discovery.open_now,
+                discovery_change(discovery, |discovery| discovery.open_now = !discovery.open_now),
+            )}
+            if *has_location {
+                {CommerceDiscovery::DISTANCE_OPTIONS.into_iter().map(|km| {
+                    let is_selected = discovery.max_distance_km == Some(km);
+                    let limit = (!is_selected).then_some(km);
+                    let onclick = discovery_change(discovery, move |discovery| discovery.max_distance_km = limit);
+                    chip(format!("{} {} km", translations["discovery_within"], km), is_selected, onclick)
+                }).collect::<Html>()}
+            }
+        </div>
+    }
+}
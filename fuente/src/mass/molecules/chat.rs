@@ -0,0 +1,169 @@
+use lucide_yew::{MessageCircle, SendHorizontal};
+use nostr_minions::{browser_api::HtmlForm, key_manager::NostrIdStore, relay_pool::NostrProps};
+use yew::{platform::spawn_local, prelude::*};
+
+use crate::{
+    contexts::{LanguageConfigsStore, OrderChatAction, OrderChatStore},
+    models::{OrderChat, OrderChatMessage, OrderInvoiceState, OrderParticipant},
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct OrderChatPanelProps {
+    pub order: OrderInvoiceState,
+}
+
+/// Messages between the consumer, commerce and courier of an order, folded
+/// away behind a button that counts the unread ones.
+#[function_component(OrderChatPanel)]
+pub fn order_chat_panel(props: &OrderChatPanelProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
+    let translations = language_ctx.translations();
+    let chat_ctx = use_context::<OrderChatStore>().expect("OrderChatStore not found");
+    let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
+    let relay_ctx = use_context::<NostrProps>().expect("Nostr context not found");
+    let expanded = use_state(|| false);
+    let order = &props.order;
+    let order_id = order.order_id();
+    let is_open = OrderChat::is_open(order);
+    let own_pubkey = key_ctx.get_pubkey().unwrap_or_default();
+    let chat = chat_ctx.chat(&order_id).cloned();
+    let unread = chat_ctx.unread(order, &own_pubkey);
+
+    {
+        let chat_ctx = chat_ctx.clone();
+        let order_id = order_id.clone();
+        use_effect_with(is_open, move |is_open| {
+            if !is_open {
+                chat_ctx.dispatch(OrderChatAction::CloseChat(order_id));
+            }
+            || {}
+        });
+    }
+    {
+        let chat_ctx = chat_ctx.clone();
+        let order = order.clone();
+        use_effect_with((*expanded, unread), move |(expanded, unread)| {
+            if *expanded && *unread > 0 {
+                chat_ctx.dispatch(OrderChatAction::MarkRead(Box::new(order)));
+            }
+            || {}
+        });
+    }
+
+    if !is_open {
+        return html! {
+            <p class="text-gray-500 font-light text-sm mt-5">{&translations["order_chat_closed"]}</p>
+        };
+    }
+
+    let toggle = {
+        let expanded = expanded.clone();
+        Callback::from(move |_| expanded.set(!*expanded))
+    };
+    let onsubmit = {
+        let order = order.clone();
+        let chat_ctx = chat_ctx.clone();
+        let send_note = relay_ctx.send_note.clone();
+        let own_pubkey = own_pubkey.clone();
+        let keys = key_ctx.get_identity().cloned();
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let Some(keys) = keys.clone() else {
+                return;
+            };
+            let form_element = e.target_unchecked_into::<web_sys::HtmlFormElement>();
+            let form = HtmlForm::new(e).expect("Could not get form");
+            let body = form.input_value("chat_message").unwrap_or_default();
+            let mut message = match OrderChatMessage::new(&order, &body) {
+                Ok(message) => message,
+                Err(e) => {
+                    gloo::console::error!("Could not send message:", e.to_string());
+                    return;
+                }
+            };
+            form_element.reset();
+            // Everyone gets a copy, the sender too so it survives a reload
+            let recipients = OrderChat::participants(&order)
+                .into_iter()
+                .map(|(_, pubkey)| pubkey)
+                .collect::<Vec<_>>();
+            message.sender = own_pubkey.clone();
+            message.sent_at = (web_sys::js_sys::Date::now() / 1000.0) as i64;
+            chat_ctx.dispatch(OrderChatAction::AddMessage(message.clone()));
+            let send_note = send_note.clone();
+            spawn_local(async move {
+                for recipient in recipients {
+                    match message.sign_message(&keys, recipient).await {
+                        Ok(note) => send_note.emit(note),
+                        Err(e) => gloo::console::error!("Could not sign message:", e.to_string()),
+                    }
+                }
+            });
+        })
+    };
+
+    let messages = chat
+        .as_ref()
+        .map(|chat| chat.messages(order))
+        .unwrap_or_default();
+    html! {
+        <div class="mt-5">
+            <button
+                type="button"
+                onclick={toggle}
+                class="flex items-center gap-2 border-2 border-fuente text-fuente rounded-full px-4 py-2 font-bold"
+            >
+                <MessageCircle class="w-5 h-5" />
+                <span>{&translations["order_chat_title"]}</span>
+                if unread > 0 {
+                    <span class="bg-red-500 text-white text-xs rounded-full px-2 py-0.5">{unread}</span>
+                }
+            </button>
+            if *expanded {
+                <div class="mt-3 border border-gray-200 rounded-2xl p-3 space-y-3">
+                    <div class="max-h-64 overflow-y-auto space-y-2">
+                        if messages.is_empty() {
+                            <p class="text-gray-400 font-light text-sm">{&translations["order_chat_empty"]}</p>
+                        }
+                        {messages.iter().map(|message| {
+                            let is_own = message.sender == own_pubkey;
+                            let sender = match OrderChat::participant(order, &message.sender) {
+                                Some(OrderParticipant::Consumer) => translations["order_chat_consumer"].as_str(),
+                                Some(OrderParticipant::Commerce) => translations["order_chat_commerce"].as_str(),
+                                Some(OrderParticipant::Courier) => translations["order_chat_courier"].as_str(),
+                                None => "",
+                            };
+                            html! {
+                                <div class={classes!("flex", "flex-col", if is_own { "items-end" } else { "items-start" })}>
+                                    <div class={classes!(
+                                        "rounded-2xl", "px-3", "py-2", "max-w-xs", "text-sm", "break-words",
+                                        if is_own { "bg-fuente text-white" } else { "bg-gray-100 text-gray-800" }
+                                    )}>
+                                        {&message.body}
+                                    </div>
+                                    <p class="text-gray-400 text-xs">
+                                        {format!("{} · {}", sender, message.locale_time())}
+                                    </p>
+                                </div>
+                            }
+                        }).collect::<Html>()}
+                    </div>
+                    <form {onsubmit} class="flex items-center gap-2">
+                        <input
+                            type="text"
+                            id="chat_message"
+                            name="chat_message"
+                            maxlength="500"
+                            autocomplete="off"
+                            placeholder={translations["order_chat_placeholder"].clone()}
+                            class="flex-1 border border-gray-300 rounded-full py-2 px-4 text-gray-700"
+                        />
+                        <button type="submit" class="bg-fuente text-white rounded-full p-2">
+                            <SendHorizontal class="w-5 h-5" />
+                        </button>
+                    </form>
+                </div>
+            }
+        </div>
+    }
+}
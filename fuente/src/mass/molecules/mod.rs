@@ -1,18 +1,22 @@
 mod address;
 mod business;
+mod chat;
 mod consumers;
 mod drivers;
 mod login;
 mod products;
+mod promotions;
 mod ratings;
 mod orders;
 mod toast;
 pub use address::*;
 pub use business::*;
+pub use chat::*;
 pub use consumers::*;
 pub use drivers::*;
 pub use login::*;
 pub use products::*;
+pub use promotions::*;
 pub use ratings::*;
 pub use orders::*;
 pub use toast::*;
@@ -1,8 +1,14 @@
 use crate::mass::{OrderDetailModal, OrderPickupModal, PopupSection};
+use gloo::timers::callback::Interval;
+use lucide_yew::{MessageCircle, Timer};
+use nostr_minions::key_manager::NostrIdStore;
 use nostro2::notes::NostrNote;
 use yew::prelude::*;
 
-use crate::models::{CommerceProfile, OrderInvoiceState};
+use crate::{
+    contexts::{LanguageConfigsStore, OrderChatStore},
+    models::{CommerceProfile, OrderInvoiceState, OrderStatus, PrepTime},
+};
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct OrderStateCardProps {
@@ -12,6 +18,8 @@ pub struct OrderStateCardProps {
 
 #[function_component(OrderStateCard)]
 pub fn order_state_card(props: &OrderStateCardProps) -> Html {
+    let chat_ctx = use_context::<OrderChatStore>();
+    let key_ctx = use_context::<NostrIdStore>();
     let order = &props.order;
     let order_req = order.get_order_request();
     let profile = order_req.profile;
@@ -26,11 +34,62 @@ pub fn order_state_card(props: &OrderStateCardProps) -> Html {
     );
     let locale_date = timestamp.to_locale_date_string("nl-SR", &locale_options);
     let locale_time = timestamp.to_locale_time_string("nl-SR");
+    let unread = match (chat_ctx, key_ctx.and_then(|keys| keys.get_pubkey())) {
+        (Some(chat_ctx), Some(pubkey)) => chat_ctx.unread(order, &pubkey),
+        _ => 0,
+    };
     html! {
         <div onclick={props.on_click.clone()} id={order_id} class="bg-white shadow py-2 px-5 rounded-2xl space-y-1 cursor-pointer mt-3">
-            <p class="pointer-events-none text-fuente font-bold text-md">{profile.nickname}</p>
+            <div class="pointer-events-none flex items-center justify-between">
+                <p class="text-fuente font-bold text-md">{profile.nickname}</p>
+                if unread > 0 {
+                    <span class="flex items-center gap-1 bg-red-500 text-white text-xs rounded-full px-2 py-0.5">
+                        <MessageCircle class="w-3 h-3" />
+                        {unread}
+                    </span>
+                }
+            </div>
             <p class="pointer-events-none font-bold text-sm">{format!("#{}", &order.order_id()[..8])}</p>
             <p class="pointer-events-none text-gray-500 text-xs">{format!("{} | {}", locale_date, locale_time)}</p>
+            if let (OrderStatus::Preparing, Some(preparation)) = (order.order_status, order.preparation) {
+                <PrepCountdown {preparation} />
+            }
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct PrepCountdownProps {
+    pub preparation: PrepTime,
+}
+
+/// Counts down the prep time the commerce gave for an order.
+#[function_component(PrepCountdown)]
+pub fn prep_countdown(props: &PrepCountdownProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
+    let translations = language_ctx.translations();
+    let now = use_state(|| (web_sys::js_sys::Date::now() / 1000.0) as i64);
+    {
+        let now = now.clone();
+        use_effect_with((), move |_| {
+            let interval = Interval::new(1_000, move || {
+                now.set((web_sys::js_sys::Date::now() / 1000.0) as i64);
+            });
+            move || {
+                interval.cancel();
+            }
+        });
+    }
+    let preparation = props.preparation;
+    html! {
+        <div class="pointer-events-none flex items-center gap-2 text-amber-500">
+            <Timer class="w-4 h-4" />
+            if preparation.seconds_left(*now) > 0 {
+                <span class="text-sm font-light">{&translations["order_prep_ready_in"]}</span>
+                <span class="text-sm font-bold tabular-nums">{preparation.countdown_display(*now)}</span>
+            } else {
+                <span class="text-sm font-bold">{&translations["order_prep_almost_ready"]}</span>
+            }
         </div>
     }
 }
@@ -40,6 +99,9 @@ pub struct OrderCardProps {
     pub order: OrderInvoiceState,
     pub order_note: NostrNote,
     pub on_click: Callback<SubmitEvent>,
+    /// Prep time filled in when the commerce accepts the order.
+    #[prop_or_default]
+    pub suggested_prep_minutes: Option<u32>,
 }
 
 #[function_component(OrderCard)]
@@ -54,7 +116,10 @@ pub fn order_card(props: &OrderCardProps) -> Html {
         <OrderStateCard order={props.order.clone()} on_click={open_popup} />
         <PopupSection close_handle={order_popup.clone()}>
             <div class="bg-white rounded-2xl p-4 m-4 flex-1 overflow-y-auto">
-                <OrderDetailModal order={props.order.clone()} on_submit={props.on_click.clone()} />
+                <OrderDetailModal
+                    order={props.order.clone()}
+                    on_submit={props.on_click.clone()}
+                    suggested_prep_minutes={props.suggested_prep_minutes} />
             </div>
         </PopupSection>
         </>
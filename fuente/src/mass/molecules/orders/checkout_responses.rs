@@ -1,4 +1,4 @@
-use lucide_yew::{Bitcoin, Frown};
+use lucide_yew::{Bitcoin, CalendarClock, Frown};
 use yew::prelude::*;
 
 use crate::{contexts::LanguageConfigsStore, models::{OrderInvoiceState, OrderStatus}};
@@ -77,6 +77,41 @@ pub fn settings_template() -> Html {
         </>
     }
 }
+#[derive(Clone, PartialEq, Properties)]
+pub struct OrderScheduledProps {
+    pub order: OrderInvoiceState,
+}
+#[function_component(OrderScheduledTemplate)]
+pub fn order_scheduled_template(props: &OrderScheduledProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
+    let translations = language_ctx.translations();
+    let slot = props.order.get_order_request().scheduled_for;
+    html! {
+        <>
+            <main class="mt-20">
+                <div class="w-52 mx-auto flex justify-center items-center">
+                    <CalendarClock class="text-fuente-orange" size=128 />
+                </div>
+
+                <div class="max-w-md mx-auto mt-5 space-y-3">
+                    <h1 class="text-3xl font-bold text-fuente text-center tracking-tighter">
+                        {&translations["order_scheduled_heading"]}
+                    </h1>
+                    <p class="font-light text-fuente text-center w-5/6 mx-auto">
+                        {&translations["order_scheduled_detail"]}
+                    </p>
+                    {if let Some(slot) = slot {
+                        html! {
+                            <p class="font-bold text-fuente-orange text-center text-xl">{slot.locale_display()}</p>
+                        }
+                    } else {
+                        html! {}
+                    }}
+                </div>
+            </main>
+        </>
+    }
+}
 #[function_component(OrderSuccessTemplate)]
 pub fn settings_template(props: &OrderConfirmationProps) -> Html {
     let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
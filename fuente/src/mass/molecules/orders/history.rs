@@ -2,7 +2,7 @@ use lucide_yew::ArrowLeft;
 use yew::prelude::*;
 use crate::mass::OrderStateCard;
 
-use crate::{contexts::LanguageConfigsStore, models::{OrderInvoiceState, OrderStatus}};
+use crate::{contexts::LanguageConfigsStore, models::{CourierTipStatus, OrderInvoiceState, OrderStatus}};
 
 
 #[derive(Properties, Clone, PartialEq)]
@@ -129,8 +129,9 @@ pub struct OrderDetailsProps {
 
 #[function_component(OrderDetails)]
 pub fn order_details(props: &OrderDetailsProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
     let order_req = props.order.get_order_request();
-    let products = order_req.products.counted_products();
+    let lines = order_req.products.lines();
 
     html! {
         <div class="flex flex-col w-full h-full p-4 max-w-4xl mx-auto">
@@ -176,25 +177,62 @@ pub fn order_details(props: &OrderDetailsProps) -> Html {
                 <div class="space-y-4">
                     <h3 class="font-medium text-fuente">{"Order Items"}</h3>
                     <div class="space-y-2">
-                        {products.iter().map(|(product, count)| {
-                            let subtotal = product.price().parse::<f64>().unwrap() * *count as f64;
+                        {lines.iter().map(|line| {
                             html! {
                                 <div class="flex justify-between py-2 border-b">
                                     <div>
-                                        <p class="font-medium">{product.name()}</p>
+                                        <p class="font-medium">{line.name()}</p>
+                                        if !line.sides().is_empty() {
+                                            <p class="text-sm text-gray-500">{line.sides_display()}</p>
+                                        }
+                                        if let Some(contributor) = line.contributor() {
+                                            <p class="text-sm text-gray-400">{contributor}</p>
+                                        }
+                                        if let Some(note) = line.note() {
+                                            <p class="text-sm text-fuente-orange italic">{note}</p>
+                                        }
                                         <p class="text-sm text-gray-500">
-                                            {format!("{} x {} SRD", count, product.price())}
+                                            {format!("{} x {}", line.quantity(), language_ctx.format_money(line.unit_price()))}
                                         </p>
                                     </div>
-                                    <p class="font-medium">{format!("{:.2} SRD", subtotal)}</p>
+                                    <p class="font-medium">{language_ctx.format_money(line.subtotal())}</p>
                                 </div>
                             }
                         }).collect::<Html>()}
 
                         <div class="flex justify-between pt-4 font-medium">
                             <p>{"Total"}</p>
-                            <p>{format!("{:.2} SRD", order_req.products.total())}</p>
+                            <p>{language_ctx.format_money(order_req.products.total())}</p>
                         </div>
+                        {if let Some(promotion) = &props.order.promotion {
+                            html! {
+                                <div class="flex justify-between pt-2 font-medium text-green-600">
+                                    <p>{format!("Promotion {}", promotion.code.clone().unwrap_or_default())}</p>
+                                    <p>{format!("-{}", language_ctx.format_money(promotion.discount))}</p>
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }}
+                        if let Some(fee) = props.order.small_order_fee {
+                            <div class="flex justify-between pt-2 font-medium">
+                                <p>{"Small Order Fee"}</p>
+                                <p>{language_ctx.format_money(fee)}</p>
+                            </div>
+                        }
+                        {if props.order.courier_tip_sats > 0 {
+                            html! {
+                                <div class="flex justify-between pt-2 font-medium text-fuente-orange">
+                                    <p>{"Courier Tip"}</p>
+                                    <p>{match props.order.tip_payout {
+                                        Some(CourierTipStatus::Failed) => format!("{} sats (payout failed)", props.order.courier_tip_sats),
+                                        _ => format!("{} sats", props.order.courier_tip_sats),
+                                    }}</p>
+                                </div>
+                            }
+                        } else {
+                            html! {}
+                        }}
                     </div>
                 </div>
           </div>
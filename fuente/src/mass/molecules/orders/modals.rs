@@ -1,16 +1,18 @@
-use lucide_yew::{Check, Clock, Hammer, MapPinCheck, Truck, X};
+use lucide_yew::{CalendarClock, Check, Clock, Hammer, MapPinCheck, Truck, X};
 use nostr_minions::key_manager::NostrIdStore;
 use yew::prelude::*;
 
 use crate::{
     contexts::LanguageConfigsStore,
-    mass::{CustomerDetails, ProductListItem},
+    mass::{CustomerDetails, OrderChatPanel, PrepCountdown, ProductListItem},
     models::{DriverProfile, OrderInvoiceState, OrderStatus},
 };
 #[derive(Clone, PartialEq, Properties)]
 pub struct OrderDetailModalProps {
     pub order: OrderInvoiceState,
     pub on_submit: Callback<SubmitEvent>,
+    #[prop_or_default]
+    pub suggested_prep_minutes: Option<u32>,
 }
 
 #[function_component(OrderDetailModal)]
@@ -18,13 +20,17 @@ pub fn order_detail_modal(props: &OrderDetailModalProps) -> Html {
     let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
     let translations = language_ctx.translations();
     let key_ctx = use_context::<NostrIdStore>().expect("NostrIdStore not found");
-    let OrderDetailModalProps { order, on_submit } = props;
+    let OrderDetailModalProps {
+        order,
+        on_submit,
+        suggested_prep_minutes,
+    } = props;
     let is_customer = {
         let keypair = key_ctx.get_pubkey().expect("Nostr key not found");
         order.order.pubkey == keypair
     };
     let request = order.get_order_request();
-    let products = request.products.counted_products();
+    let lines = request.products.lines();
     let order_total = request.products.total();
     let customer_profile = &request.profile;
     let order_status = order.order_status.clone();
@@ -42,6 +48,9 @@ pub fn order_detail_modal(props: &OrderDetailModalProps) -> Html {
                 <div>
                     <p class="text-fuente-dark font-bold text-2xl">{format!("#{}", &order.order_id()[..12])}</p>
                     <p class="text-gray-500 font-light text-lg">{&translations["store_order_modal_title"]}</p>
+                    if let (OrderStatus::Preparing, Some(preparation)) = (order_status, order.preparation) {
+                        <PrepCountdown {preparation} />
+                    }
                 </div>
                 <button
                     class={classes!(
@@ -57,6 +66,7 @@ pub fn order_detail_modal(props: &OrderDetailModalProps) -> Html {
                     )}>
                     {
                         match order_status {
+                            OrderStatus::Scheduled => html! {<CalendarClock class={icon_class} />},
                             OrderStatus::Pending => html! {<Clock class={icon_class} />},
                             OrderStatus::Preparing => html! {<Hammer class={icon_class} />},
                             OrderStatus::ReadyForDelivery => html! {<MapPinCheck class={icon_class} />},
@@ -69,18 +79,42 @@ pub fn order_detail_modal(props: &OrderDetailModalProps) -> Html {
             </div>
 
             <h3 class="text-gray-500 mt-5 font-light">{&translations["store_order_modal_products"]}</h3>
-            {products.iter().map(|(product, count)| {
+            {lines.iter().map(|line| {
                 html! {
-                    <ProductListItem product={product.clone()} count={*count} />
+                    <ProductListItem line={line.clone()} />
                 }
             }).collect::<Html>()}
 
             <div class="my-5 bg-gray-200 flex justify-end p-3">
                 <div class="space-y-2">
-                    <p class="text-fuente font-bold text-lg text-right">{format!("SRD {}", order_total)}</p>
+                    <p class="text-fuente font-bold text-lg text-right">{language_ctx.format_money(order_total)}</p>
+                    if let Some(fee) = order.small_order_fee {
+                        <p class="text-gray-500 font-light text-sm text-right">
+                            {format!("{} {}", translations["cart_small_order_fee"], language_ctx.format_money(fee))}
+                        </p>
+                    }
+                    {if let Some(promotion) = &order.promotion {
+                        html! {
+                            <p class="text-green-600 font-light text-sm text-right">
+                                {format!("{} {} -{}", translations["cart_discount"], promotion.code.clone().unwrap_or(promotion.description.clone()), language_ctx.format_money(promotion.discount))}
+                            </p>
+                        }
+                    } else {
+                        html! {<></>}
+                    }}
                 </div>
             </div>
 
+            {if let Some(slot) = &request.scheduled_for {
+                html! {
+                    <div class="mb-5">
+                        <h3 class="text-gray-500 font-light">{&translations["order_scheduled_for"]}</h3>
+                        <p class="text-purple-500 font-bold text-lg">{slot.locale_display()}</p>
+                    </div>
+                }
+            } else {
+                html! {<></>}
+            }}
             <CustomerDetails customer={customer_profile.clone()} />
             {if let Some(Ok(driver)) = driver_profile {
                 html! {
@@ -97,9 +131,13 @@ pub fn order_detail_modal(props: &OrderDetailModalProps) -> Html {
             } else {
                 html! {<></>}
             }}
+            <OrderChatPanel order={order.clone()} />
             {if !is_customer {
                 html! {
-                    <OrderModalForm current_status={order.order_status.clone()} on_order_click={on_submit.clone()} />
+                    <OrderModalForm
+                        current_status={order.order_status.clone()}
+                        on_order_click={on_submit.clone()}
+                        suggested_prep_minutes={*suggested_prep_minutes} />
                 }
             } else {
                 html! {<></>}
@@ -111,6 +149,8 @@ pub fn order_detail_modal(props: &OrderDetailModalProps) -> Html {
 pub struct OrderModalFormProps {
     pub current_status: OrderStatus,
     pub on_order_click: Callback<SubmitEvent>,
+    #[prop_or_default]
+    pub suggested_prep_minutes: Option<u32>,
 }
 #[function_component(OrderModalForm)]
 pub fn order_modal_form(props: &OrderModalFormProps) -> Html {
@@ -119,6 +159,7 @@ pub fn order_modal_form(props: &OrderModalFormProps) -> Html {
     let OrderModalFormProps {
         current_status,
         on_order_click,
+        suggested_prep_minutes,
     } = props;
 
     let cancel_button_text = match current_status {
@@ -163,6 +204,18 @@ pub fn order_modal_form(props: &OrderModalFormProps) -> Html {
                 <div class="mt-5 space-y-4">
                     <form onsubmit={on_order_click.clone()}>
                         <input type="hidden" name="order_status" value={OrderStatus::Preparing.to_string()} />
+                        <label for="prep_minutes" class="block text-gray-500 font-light">
+                            {&translations["store_order_prep_minutes"]}
+                        </label>
+                        <input
+                            type="number"
+                            id="prep_minutes"
+                            name="prep_minutes"
+                            min="1"
+                            max="240"
+                            value={suggested_prep_minutes.map(|minutes| minutes.to_string())}
+                            class="border border-gray-300 rounded-xl w-full py-2 px-3 mt-1 text-gray-700"
+                        />
                         <input type="submit" value={order_button_text.clone()}
                         class="bg-fuente-orange text-white text-center text-lg font-bold rounded-full w-full py-3 mt-5 cursor-pointer" />
                     </form>
@@ -1,4 +1,4 @@
-use lucide_yew::{Check, Clock, Hammer, MapPinCheck, Truck, X};
+use lucide_yew::{CalendarClock, Check, Clock, Hammer, MapPinCheck, Truck, X};
 use nostr_minions::{
     browser_api::GeolocationCoordinates,
     widgets::leaflet::{IconOptions, LeafletComponent, LeafletMap, LeafletMapOptions, Marker},
@@ -9,7 +9,7 @@ use crate::{
     contexts::LanguageConfigsStore,
     mass::{
         CommerceProfileAddressDetails, CommerceProfileDetails, CustomerAddressDetails,
-        CustomerDetails,
+        CustomerDetails, PrepCountdown,
     },
     models::{CommerceProfile, OrderInvoiceState, OrderStatus},
 };
@@ -109,6 +109,7 @@ pub fn order_detail_modal(props: &OrderPickupModalProps) -> Html {
                         )}
                     >
                         {match order.order_status {
+                            OrderStatus::Scheduled => html! {<CalendarClock class={order.order_status.text_color()} />},
                             OrderStatus::Pending => html! {<Clock class={order.order_status.text_color()} />},
                             OrderStatus::Preparing => html! {<Hammer class={order.order_status.text_color()} />},
                             OrderStatus::ReadyForDelivery => html! {<MapPinCheck class={order.order_status.text_color()} />},
@@ -136,6 +137,17 @@ pub fn order_detail_modal(props: &OrderPickupModalProps) -> Html {
                 />
 
                 {match order_state {
+                    OrderStatus::Preparing => html! {
+                        <div class="grid grid-cols-1 lg:grid-cols-2 lg:gap-5">
+                            if let Some(preparation) = order.preparation {
+                                <div class="lg:col-span-2 mt-3">
+                                    <PrepCountdown {preparation} />
+                                </div>
+                            }
+                            <CommerceProfileDetails commerce_data={commerce_profile.clone()} />
+                            <CommerceProfileAddressDetails commerce_data={commerce_profile.clone()} />
+                        </div>
+                    },
                     OrderStatus::ReadyForDelivery => html! {
                         <div class="grid grid-cols-1 lg:grid-cols-2 lg:gap-5">
                             <CommerceProfileDetails commerce_data={commerce_profile.clone()} />
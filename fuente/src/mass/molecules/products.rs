@@ -1,6 +1,9 @@
 use yew::prelude::*;
 
-use crate::models::{ProductItem, ProductMenu, ProductOrder};
+use crate::contexts::{AdminConfigsStore, LanguageConfigsStore};
+use crate::models::{
+    OrderLine, ProductItem, ProductMenu, ProductOrder, ProductTag, TagFilter, MAX_SPICE_LEVEL,
+};
 
 #[derive(Properties, Clone, PartialEq)]
 pub struct ProductCardProps {
@@ -35,13 +38,14 @@ pub fn product_card(props: &ProductCardProps) -> Html {
                     class="w-12 h-12 min-w-12 min-h-12 bg-neutral-300 rounded-full object-cover"
                 />
                 <div class="flex flex-col">
-                    <h3 class="text-lg font-bold">{format!("{} - SRD {}", product.name(), product.price())}</h3>
+                    <h3 class="text-lg font-bold">{format!("{} - {}", product.name(), product.price())}</h3>
                     if let Some(discount) = product.discount() {
                         <p class="text-sm text-green-500">
-                            {format!("Discount: SRD {}", discount)}
+                            {format!("Discount: {}", discount)}
                         </p>
                     }
                     <p class="text-sm text-gray-500">{format!("SKU: {}", product.sku())}</p>
+                    <ProductTagBadges product={product.clone()} />
                     <p class="text-neutral-400">{product.description()}</p>
                     <details class="mt-2">
                         <summary class="text-sm font-semibold">{"Product Details"}</summary>
@@ -104,15 +108,13 @@ pub struct ProductMenuListProps {
 #[function_component(OrderRequestDetailsComponent)]
 pub fn order_request_details(props: &ProductMenuListProps) -> Html {
     let ProductMenuListProps { order } = props;
-    let counted = order.counted_products();
     let total_srd = order.total();
     let products_html = html! {
-        {counted.iter().map(|(item, count)| {
-            let price = item.price().parse::<f64>().unwrap_or(0.0);
+        {order.lines().iter().map(|line| {
             html! {
                     <div class="flex flex-row gap-2">
-                        <p>{format!("{} x {}", count, item.name())}</p>
-                        <p>{format!("SRD {:.2}", price * (*count as f64))}</p>
+                        <p>{format!("{} x {}", line.quantity(), line.name())}</p>
+                        <p>{line.subtotal().to_string()}</p>
                     </div>
             }
         }).collect::<Html>()}
@@ -122,7 +124,7 @@ pub fn order_request_details(props: &ProductMenuListProps) -> Html {
             {products_html}
             <div class="flex flex-row justify-between">
                 <p class="text-lg font-bold">{"Total"}</p>
-                <p class="text-lg font-bold">{format!("SRD {:.2}", total_srd)}</p>
+                <p class="text-lg font-bold">{total_srd.to_string()}</p>
             </div>
         </div>
     }
@@ -130,27 +132,243 @@ pub fn order_request_details(props: &ProductMenuListProps) -> Html {
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct ProductListItemProps {
-    pub product: ProductItem,
-    pub count: u32,
+    pub line: OrderLine,
 }
 #[function_component(ProductListItem)]
 pub fn product_list_item(props: &ProductListItemProps) -> Html {
-    let ProductListItemProps { product, count } = props;
+    let ProductListItemProps { line } = props;
     html! {
         <div class="mt-5 space-y-3 flex items-center justify-between">
             <div class="flex items-center gap-5">
-                <img src={product.thumbnail_url()} alt={product.name()} class="w-20 block object-contain" />
                 <div>
-                    <p class="text-gray-500 font-bold text-md">{product.name()}</p>
-                    <p class="text-gray-500 font-light line-clamp-3">{product.details()}</p>
-                    <p class="text-gray-500 font-bold text-md uppercase">{product.sku()}</p>
+                    <p class="text-gray-500 font-bold text-md">{line.name()}</p>
+                    if !line.sides().is_empty() {
+                        <p class="text-fuente font-semibold text-sm">{line.sides_display()}</p>
+                    }
+                    if let Some(contributor) = line.contributor() {
+                        <p class="text-gray-400 text-sm">{contributor}</p>
+                    }
+                    if let Some(note) = line.note() {
+                        <p class="text-fuente-orange font-semibold text-sm italic">{note}</p>
+                    }
                 </div>
             </div>
 
             <div class="flex flex-col items-center gap-2">
-                <p class="text-gray-500 font-bold text-xl">{product.price()}</p>
-                <p class="text-gray-500 font-bold text-md">{format!("x{}", count)}</p>
+                <p class="text-gray-500 font-bold text-xl">{line.unit_price().to_string()}</p>
+                <p class="text-gray-500 font-bold text-md">{format!("x{}", line.quantity())}</p>
+            </div>
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ModifierGroupPickerProps {
+    pub product: ProductItem,
+    pub selected: UseStateHandle<Vec<String>>,
+}
+#[function_component(ModifierGroupPicker)]
+pub fn modifier_group_picker(props: &ModifierGroupPickerProps) -> Html {
+    let ModifierGroupPickerProps { product, selected } = props;
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let admin_ctx = use_context::<AdminConfigsStore>().expect("AdminConfigsStore not found");
+    let rates = admin_ctx.get_exchange_rates();
+    html! {
+        <div class="flex flex-col gap-4 mt-5">
+            {product.modifier_groups().iter().map(|group| {
+                let group_side_ids = group.options().iter().map(|side| side.id()).collect::<Vec<_>>();
+                html! {
+                    <div class="flex flex-col gap-2">
+                        <div class="flex items-center gap-2">
+                            <p class="text-gray-500 font-bold text-lg">{group.name()}</p>
+                            <p class="text-gray-400 text-sm">{group.rules_display()}</p>
+                        </div>
+                        <div class="flex flex-wrap gap-2">
+                            {group.options().iter().map(|side| {
+                                let is_selected = selected.contains(&side.id());
+                                let onclick = {
+                                    let selected = selected.clone();
+                                    let side_id = side.id();
+                                    let group_side_ids = group_side_ids.clone();
+                                    let max = group.max_selections() as usize;
+                                    Callback::from(move |_: MouseEvent| {
+                                        let mut new_selection = (*selected).clone();
+                                        if new_selection.contains(&side_id) {
+                                            new_selection.retain(|id| id != &side_id);
+                                        } else if max == 1 {
+                                            new_selection.retain(|id| !group_side_ids.contains(id));
+                                            new_selection.push(side_id.clone());
+                                        } else if new_selection.iter().filter(|id| group_side_ids.contains(id)).count() < max {
+                                            new_selection.push(side_id.clone());
+                                        }
+                                        selected.set(new_selection);
+                                    })
+                                };
+                                let price = side.price();
+                                html! {
+                                    <button {onclick} type="button"
+                                        class={classes!(
+                                            "border-2", "border-fuente", "rounded-full", "px-4", "py-2", "text-sm", "font-semibold",
+                                            if is_selected { "bg-fuente text-white" } else { "text-fuente" }
+                                        )}>
+                                        {side.name()}
+                                        if !price.is_zero() {
+                                            <span class="ml-1 font-light">{format!("+{}", language_ctx.format_price(price, &rates))}</span>
+                                        }
+                                    </button>
+                                }
+                            }).collect::<Html>()}
+                        </div>
+                    </div>
+                }
+            }).collect::<Html>()}
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ProductTagBadgesProps {
+    pub product: ProductItem,
+}
+#[function_component(ProductTagBadges)]
+pub fn product_tag_badges(props: &ProductTagBadgesProps) -> Html {
+    let ProductTagBadgesProps { product } = props;
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    if product.tags().is_empty() && product.spice_level() == 0 {
+        return html! {};
+    }
+    html! {
+        <div class="flex flex-wrap gap-1">
+            {product.tags().iter().map(|tag| {
+                html! {
+                    <span class={classes!(
+                        "rounded-full", "px-2", "py-0.5", "text-xs", "font-semibold",
+                        if tag.is_warning() { "bg-orange-100 text-fuente-orange" } else { "bg-green-100 text-green-700" }
+                    )}>
+                        {&translations[tag.translation_key()]}
+                    </span>
+                }
+            }).collect::<Html>()}
+            if product.spice_level() > 0 {
+                <span class="rounded-full px-2 py-0.5 text-xs font-semibold bg-red-100 text-red-600">
+                    {format!("{} {}", translations["product_tag_spicy"], "🌶".repeat(product.spice_level() as usize))}
+                </span>
+            }
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct ProductTagPickerProps {
+    pub tags: UseStateHandle<Vec<ProductTag>>,
+    pub spice_level: UseStateHandle<u8>,
+}
+#[function_component(ProductTagPicker)]
+pub fn product_tag_picker(props: &ProductTagPickerProps) -> Html {
+    let ProductTagPickerProps { tags, spice_level } = props;
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    html! {
+        <div class="flex flex-col gap-2">
+            <p class="text-gray-400 font-semibold">{&translations["product_tags_heading"]}</p>
+            <div class="flex flex-wrap gap-2">
+                {ProductTag::ALL.iter().map(|tag| {
+                    let is_selected = tags.contains(tag);
+                    let onclick = {
+                        let tags = tags.clone();
+                        let tag = *tag;
+                        Callback::from(move |_: MouseEvent| {
+                            let mut new_tags = (*tags).clone();
+                            match new_tags.iter().position(|selected| *selected == tag) {
+                                Some(i) => {
+                                    new_tags.remove(i);
+                                }
+                                None => new_tags.push(tag),
+                            }
+                            tags.set(new_tags);
+                        })
+                    };
+                    html! {
+                        <button {onclick} type="button"
+                            class={classes!(
+                                "border-2", "border-fuente", "rounded-full", "px-3", "py-1", "text-sm", "font-semibold",
+                                if is_selected { "bg-fuente text-white" } else { "text-fuente" }
+                            )}>
+                            {&translations[tag.translation_key()]}
+                        </button>
+                    }
+                }).collect::<Html>()}
             </div>
+            <p class="text-gray-400 font-semibold">{&translations["product_spice_level"]}</p>
+            <div class="flex flex-wrap gap-2">
+                {(0..=MAX_SPICE_LEVEL).map(|level| {
+                    let onclick = {
+                        let spice_level = spice_level.clone();
+                        Callback::from(move |_: MouseEvent| spice_level.set(level))
+                    };
+                    html! {
+                        <button {onclick} type="button"
+                            class={classes!(
+                                "border-2", "border-fuente", "rounded-full", "px-3", "py-1", "text-sm", "font-semibold",
+                                if **spice_level == level { "bg-fuente text-white" } else { "text-fuente" }
+                            )}>
+                            {if level == 0 { "-".to_string() } else { "🌶".repeat(level as usize) }}
+                        </button>
+                    }
+                }).collect::<Html>()}
+            </div>
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct TagFilterPickerProps {
+    pub filter: UseStateHandle<TagFilter>,
+}
+#[function_component(TagFilterPicker)]
+pub fn tag_filter_picker(props: &TagFilterPickerProps) -> Html {
+    let TagFilterPickerProps { filter } = props;
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("No language context found");
+    let translations = language_ctx.translations();
+    let chip = |label: String, is_selected: bool, onclick: Callback<MouseEvent>| {
+        html! {
+            <button {onclick} type="button"
+                class={classes!(
+                    "border", "border-fuente", "rounded-full", "px-3", "py-1", "text-sm", "text-nowrap",
+                    if is_selected { "bg-fuente text-white" } else { "text-fuente" }
+                )}>
+                {label}
+            </button>
+        }
+    };
+    let onclick_mild = {
+        let filter = filter.clone();
+        Callback::from(move |_: MouseEvent| {
+            let mut new_filter = (*filter).clone();
+            new_filter.mild_only = !new_filter.mild_only;
+            filter.set(new_filter);
+        })
+    };
+    html! {
+        <div class="flex flex-wrap gap-2">
+            {ProductTag::ALL.iter().map(|tag| {
+                let onclick = {
+                    let filter = filter.clone();
+                    let tag = *tag;
+                    Callback::from(move |_: MouseEvent| {
+                        let mut new_filter = (*filter).clone();
+                        new_filter.toggle(tag);
+                        filter.set(new_filter);
+                    })
+                };
+                chip(
+                    translations[tag.filter_translation_key()].clone(),
+                    filter.is_selected(*tag),
+                    onclick,
+                )
+            }).collect::<Html>()}
+            {chip(translations["product_filter_mild"].clone(), filter.mild_only, onclick_mild)}
         </div>
     }
 }
@@ -0,0 +1,229 @@
+use lucide_yew::Trash2;
+use nostr_minions::browser_api::HtmlForm;
+use yew::prelude::*;
+
+use crate::mass::{MoneyInput, SimpleInput, SimpleSelect};
+use crate::models::{Currency, Money, ProductCategory, Promotion, PromotionDiscount};
+
+fn parse_date(value: &str) -> Option<i64> {
+    let millis = web_sys::js_sys::Date::parse(value);
+    (!millis.is_nan()).then(|| (millis / 1000.0) as i64)
+}
+
+fn format_date(timestamp: i64) -> String {
+    let date = web_sys::js_sys::Date::new(&web_sys::wasm_bindgen::JsValue::from_f64(
+        timestamp as f64 * 1000.0,
+    ));
+    date.to_locale_date_string("nl-SR", &web_sys::js_sys::Object::new())
+        .into()
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct PromotionFormProps {
+    /// Categories the promotion can be limited to, empty for platform-wide promotions.
+    #[prop_or_default]
+    pub categories: Vec<ProductCategory>,
+    /// Currency for amounts typed without a code.
+    #[prop_or_default]
+    pub currency: Currency,
+    pub on_save: Callback<Promotion>,
+}
+
+#[function_component(PromotionForm)]
+pub fn promotion_form(props: &PromotionFormProps) -> Html {
+    let first_order_only = use_state(|| false);
+    let onsubmit = {
+        let first_order_only = first_order_only.clone();
+        let on_save = props.on_save.clone();
+        let currency = props.currency;
+        Callback::from(move |e: SubmitEvent| {
+            e.prevent_default();
+            let form = HtmlForm::new(e).expect("Failed to get form");
+            let value = |id: &str| {
+                form.input_value(id)
+                    .ok()
+                    .map(|value| value.trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+            let amount = value("discount_value").unwrap_or_default();
+            let discount = match form.select_value("discount_type").ok().as_deref() {
+                Some("fixed") => {
+                    PromotionDiscount::Fixed(Money::parse_in(&amount, currency).unwrap_or_default())
+                }
+                _ => PromotionDiscount::Percentage(amount.parse().unwrap_or_default()),
+            };
+            let mut promotion = Promotion::new(
+                value("promotion_code"),
+                value("promotion_description").unwrap_or_default(),
+                discount,
+            );
+            promotion.minimum_order =
+                value("minimum_order").and_then(|minimum| Money::parse_in(&minimum, currency).ok());
+            promotion.usage_limit = value("usage_limit").and_then(|limit| limit.parse().ok());
+            promotion.valid_from = value("valid_from").and_then(|date| parse_date(&date));
+            promotion.valid_until = value("valid_until")
+                .and_then(|date| parse_date(&date))
+                .map(|end| end + 24 * 60 * 60 - 1);
+            promotion.first_order_only = *first_order_only;
+            promotion.categories = form
+                .select_value("promotion_category")
+                .ok()
+                .filter(|category| !category.is_empty())
+                .into_iter()
+                .collect();
+            on_save.emit(promotion);
+        })
+    };
+    let toggle_first_order = {
+        let first_order_only = first_order_only.clone();
+        Callback::from(move |_| first_order_only.set(!*first_order_only))
+    };
+    html! {
+        <form {onsubmit}
+            class="w-full h-full flex flex-col gap-4 rounded-3xl p-4 bg-fuente-dark max-h-[80vh] overflow-y-auto">
+            <div class="flex flex-col px-4 gap-2">
+                <SimpleInput
+                    id="promotion_code"
+                    name="promotion_code"
+                    label="Code (leave empty to apply automatically)"
+                    value=""
+                    input_type="text"
+                    required={false}
+                />
+                <SimpleInput
+                    id="promotion_description"
+                    name="promotion_description"
+                    label="Description"
+                    value=""
+                    input_type="text"
+                    required={true}
+                />
+                <div class="grid grid-cols-2 gap-2 bg-white rounded-xl p-2">
+                    <SimpleSelect id="discount_type" name="discount_type" label="Discount">
+                        <option value="percentage">{"Percentage"}</option>
+                        <option value="fixed">{"Fixed amount (SRD)"}</option>
+                    </SimpleSelect>
+                    <MoneyInput
+                        id="discount_value"
+                        name="discount_value"
+                        label="Value"
+                        value=""
+                        input_type="number"
+                        required={true}
+                    />
+                    <MoneyInput
+                        id="minimum_order"
+                        name="minimum_order"
+                        label="Minimum order (SRD)"
+                        value=""
+                        input_type="number"
+                        required={false}
+                    />
+                    <MoneyInput
+                        id="usage_limit"
+                        name="usage_limit"
+                        label="Usage limit"
+                        value=""
+                        input_type="number"
+                        required={false}
+                    />
+                </div>
+                <div class="grid grid-cols-2 gap-2">
+                    <SimpleInput
+                        id="valid_from"
+                        name="valid_from"
+                        label="Valid from"
+                        value=""
+                        input_type="date"
+                        required={false}
+                    />
+                    <SimpleInput
+                        id="valid_until"
+                        name="valid_until"
+                        label="Valid until"
+                        value=""
+                        input_type="date"
+                        required={false}
+                    />
+                </div>
+                {if props.categories.is_empty() {
+                    html! {}
+                } else {
+                    html! {
+                        <div class="bg-white rounded-xl p-2">
+                            <SimpleSelect id="promotion_category" name="promotion_category" label="Applies to">
+                                <option value="">{"Whole order"}</option>
+                                {for props.categories.iter().map(|category| html! {
+                                    <option value={category.id()}>{category.name()}</option>
+                                })}
+                            </SimpleSelect>
+                        </div>
+                    }
+                }}
+                <div class="flex items-center gap-2 text-white">
+                    <input
+                        type="checkbox"
+                        id="first_order_only"
+                        checked={*first_order_only}
+                        onclick={toggle_first_order}
+                    />
+                    <label for="first_order_only">{"First order only"}</label>
+                </div>
+            </div>
+            <button
+                type="submit"
+                class="text-sm bg-fuente text-white font-bold p-2 px-4 rounded-3xl"
+                >{"Save"}</button>
+        </form>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct PromotionCardProps {
+    pub promotion: Promotion,
+    pub on_delete: Callback<String>,
+}
+
+#[function_component(PromotionCard)]
+pub fn promotion_card(props: &PromotionCardProps) -> Html {
+    let PromotionCardProps {
+        promotion,
+        on_delete,
+    } = props;
+    let onclick = {
+        let on_delete = on_delete.clone();
+        let id = promotion.id.clone();
+        Callback::from(move |_: MouseEvent| on_delete.emit(id.clone()))
+    };
+    let mut rules = vec![];
+    if let Some(minimum) = &promotion.minimum_order {
+        rules.push(format!("Min. {}", minimum));
+    }
+    if promotion.first_order_only {
+        rules.push("First order only".to_string());
+    }
+    if let Some(limit) = promotion.usage_limit {
+        rules.push(format!("{} uses", limit));
+    }
+    if let Some(from) = promotion.valid_from {
+        rules.push(format!("From {}", format_date(from)));
+    }
+    if let Some(until) = promotion.valid_until {
+        rules.push(format!("Until {}", format_date(until)));
+    }
+    html! {
+        <div class="flex justify-between items-center gap-4 border-2 border-fuente rounded-2xl p-4">
+            <div class="flex flex-col gap-1">
+                <p class="text-fuente font-bold text-lg">
+                    {promotion.code.clone().unwrap_or("Automatic".to_string())}
+                    <span class="ml-2 text-fuente-orange">{promotion.discount.display()}</span>
+                </p>
+                <p class="text-gray-500">{&promotion.description}</p>
+                <p class="text-gray-400 text-sm">{rules.join(" · ")}</p>
+            </div>
+            <button {onclick} type="button">
+                <Trash2 class="w-6 h-6 text-red-500" />
+            </button>
+        </div>
+    }
+}
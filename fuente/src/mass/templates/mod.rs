@@ -521,8 +521,9 @@ struct OrderDetailsProps {
 
 #[function_component(OrderDetails)]
 fn order_details(props: &OrderDetailsProps) -> Html {
+    let language_ctx = use_context::<LanguageConfigsStore>().expect("Language context not found");
     let order_req = props.order.get_order_request();
-    let products = order_req.products.counted_products();
+    let lines = order_req.products.lines();
     let profile = order_req.profile;
 
     html! {
@@ -567,24 +568,32 @@ fn order_details(props: &OrderDetailsProps) -> Html {
                 <div class="space-y-4">
                     <h3 class="font-medium text-gray-500">{"Order Items"}</h3>
                     <div class="space-y-2">
-                        {products.iter().map(|(product, count)| {
-                            let subtotal = product.price().parse::<f64>().unwrap() * *count as f64;
+                        {lines.iter().map(|line| {
                             html! {
                                 <div class="flex justify-between py-2 border-b">
                                     <div>
-                                        <p class="font-medium">{product.name()}</p>
+                                        <p class="font-medium">{line.name()}</p>
+                                        if !line.sides().is_empty() {
+                                            <p class="text-sm text-gray-500">{line.sides_display()}</p>
+                                        }
+                                        if let Some(contributor) = line.contributor() {
+                                            <p class="text-sm text-gray-400">{contributor}</p>
+                                        }
+                                        if let Some(note) = line.note() {
+                                            <p class="text-sm text-fuente-orange italic">{note}</p>
+                                        }
                                         <p class="text-sm text-gray-500">
-                                            {format!("{} x {} SRD", count, product.price())}
+                                            {format!("{} x {}", line.quantity(), language_ctx.format_money(line.unit_price()))}
                                         </p>
                                     </div>
-                                    <p class="font-medium">{format!("{:.2} SRD", subtotal)}</p>
+                                    <p class="font-medium">{language_ctx.format_money(line.subtotal())}</p>
                                 </div>
                             }
                         }).collect::<Html>()}
 
                         <div class="flex justify-between pt-4 font-medium">
                             <p>{"Total"}</p>
-                            <p>{format!("{:.2} SRD", order_req.products.total())}</p>
+                            <p>{language_ctx.format_money(order_req.products.total())}</p>
                         </div>
                     </div>
                 </div>
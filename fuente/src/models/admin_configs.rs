@@ -6,7 +6,7 @@ use nostro2::{keypair::NostrKeypair, notes::NostrNote};
 use serde::{Deserialize, Serialize};
 use web_sys::wasm_bindgen::JsValue;
 
-use super::TEST_PUB_KEY;
+use super::{Currency, ExchangeRates, Promotion, TEST_PUB_KEY};
 
 use super::{
     nostr_kinds::{NOSTR_KIND_ADMIN_REQUEST, NOSTR_KIND_SERVER_CONFIG},
@@ -21,6 +21,8 @@ pub enum AdminConfigurationType {
     UserRegistrations,
     ExchangeRate,
     CourierWhitelist,
+    Promotions,
+    ExchangeRates,
 }
 impl AdminConfigurationType {
     pub fn to_hash(&self) -> String {
@@ -40,6 +42,8 @@ impl TryFrom<&str> for AdminConfigurationType {
             3 => Ok(AdminConfigurationType::UserRegistrations),
             4 => Ok(AdminConfigurationType::ExchangeRate),
             5 => Ok(AdminConfigurationType::CourierWhitelist),
+            6 => Ok(AdminConfigurationType::Promotions),
+            7 => Ok(AdminConfigurationType::ExchangeRates),
             _ => Err(anyhow::anyhow!("Invalid AdminConfigurationType")),
         }
     }
@@ -55,6 +59,8 @@ impl TryFrom<String> for AdminConfigurationType {
             3 => Ok(AdminConfigurationType::UserRegistrations),
             4 => Ok(AdminConfigurationType::ExchangeRate),
             5 => Ok(AdminConfigurationType::CourierWhitelist),
+            6 => Ok(AdminConfigurationType::Promotions),
+            7 => Ok(AdminConfigurationType::ExchangeRates),
             _ => Err(anyhow::anyhow!("Invalid AdminConfigurationType")),
         }
     }
@@ -69,6 +75,8 @@ impl TryFrom<u32> for AdminConfigurationType {
             3 => Ok(AdminConfigurationType::UserRegistrations),
             4 => Ok(AdminConfigurationType::ExchangeRate),
             5 => Ok(AdminConfigurationType::CourierWhitelist),
+            6 => Ok(AdminConfigurationType::Promotions),
+            7 => Ok(AdminConfigurationType::ExchangeRates),
             _ => Err(anyhow::anyhow!("Invalid AdminConfigurationType")),
         }
     }
@@ -82,6 +90,8 @@ impl From<AdminConfigurationType> for u32 {
             AdminConfigurationType::UserRegistrations => 3,
             AdminConfigurationType::ExchangeRate => 4,
             AdminConfigurationType::CourierWhitelist => 5,
+            AdminConfigurationType::Promotions => 6,
+            AdminConfigurationType::ExchangeRates => 7,
         }
     }
 }
@@ -100,6 +110,10 @@ pub struct AdminConfiguration {
     consumer_blacklist: Vec<String>,
     user_registrations: Vec<String>,
     exchange_rate: f64,
+    #[serde(default)]
+    promotions: Vec<Promotion>,
+    #[serde(default)]
+    exchange_rates: ExchangeRates,
 }
 impl Default for AdminConfiguration {
     fn default() -> Self {
@@ -110,6 +124,8 @@ impl Default for AdminConfiguration {
             couriers_whitelist: Vec::new(),
             user_registrations: Vec::new(),
             exchange_rate: 1.0,
+            promotions: Vec::new(),
+            exchange_rates: ExchangeRates::default(),
         }
     }
 }
@@ -245,6 +261,44 @@ impl AdminConfiguration {
         priv_key.sign_nostr_event(&mut note);
         Ok(note)
     }
+    pub fn sign_promotions(&self, priv_key: &NostrKeypair) -> anyhow::Result<NostrNote> {
+        let serialized = serde_json::to_string(&self.promotions)?;
+
+        let mut note = NostrNote {
+            pubkey: priv_key.public_key(),
+            kind: NOSTR_KIND_SERVER_CONFIG,
+            content: serialized,
+            ..Default::default()
+        };
+
+        let config_str: String = AdminConfigurationType::Promotions.into();
+        let config_hash = AdminConfigurationType::Promotions.to_hash();
+        note.tags
+            .add_parameter_tag(&format!("{}-{}", &config_hash, &config_str));
+        note.tags.add_parameter_tag(&config_hash.to_string());
+        note.tags.add_parameter_tag(&config_str);
+        priv_key.sign_nostr_event(&mut note);
+        Ok(note)
+    }
+    pub fn sign_exchange_rates(&self, priv_key: &NostrKeypair) -> anyhow::Result<NostrNote> {
+        let serialized = serde_json::to_string(&self.exchange_rates)?;
+
+        let mut note = NostrNote {
+            pubkey: priv_key.public_key(),
+            kind: NOSTR_KIND_SERVER_CONFIG,
+            content: serialized,
+            ..Default::default()
+        };
+
+        let config_str: String = AdminConfigurationType::ExchangeRates.into();
+        let config_hash = AdminConfigurationType::ExchangeRates.to_hash();
+        note.tags
+            .add_parameter_tag(&format!("{}-{}", &config_hash, &config_str));
+        note.tags.add_parameter_tag(&config_hash.to_string());
+        note.tags.add_parameter_tag(&config_str);
+        priv_key.sign_nostr_event(&mut note);
+        Ok(note)
+    }
     pub fn update_commerce_whitelist(&mut self, new_commerce: String) {
         self.commerce_whitelist.push(new_commerce);
     }
@@ -265,6 +319,16 @@ impl AdminConfiguration {
     }
     pub fn set_exchange_rate(&mut self, exchange_rate: f64) {
         self.exchange_rate = exchange_rate;
+        self.exchange_rates.set_rate(Currency::SRD, exchange_rate);
+    }
+    pub fn set_exchange_rates(&mut self, exchange_rates: ExchangeRates) {
+        if let Some(srd_rate) = exchange_rates.rate(Currency::SRD) {
+            self.exchange_rate = srd_rate;
+        }
+        self.exchange_rates = exchange_rates;
+    }
+    pub fn set_promotions(&mut self, promotions: Vec<Promotion>) {
+        self.promotions = promotions;
     }
     pub fn check_admin_whitelist(&self, admin: &str) -> anyhow::Result<()> {
         if self.admin_whitelist.contains(&admin.to_string()) {
@@ -310,6 +374,17 @@ impl AdminConfiguration {
     pub fn get_exchange_rate(&self) -> f64 {
         self.exchange_rate
     }
+    /// The rate table, falling back to the legacy SRD rate when none was published.
+    pub fn get_exchange_rates(&self) -> ExchangeRates {
+        let mut exchange_rates = self.exchange_rates.clone();
+        if exchange_rates.rate(Currency::SRD).is_none() {
+            exchange_rates.set_rate(Currency::SRD, self.exchange_rate);
+        }
+        exchange_rates
+    }
+    pub fn get_promotions(&self) -> Vec<Promotion> {
+        self.promotions.clone()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -0,0 +1,249 @@
+use nostr_minions::{browser_api::IdbStoreManager, key_manager::UserIdentity};
+use nostro2::notes::NostrNote;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use web_sys::wasm_bindgen::JsValue;
+
+use super::{
+    apply_promotions, AppliedPromotion, ConsumerAddress, ConsumerProfile, DeliverySlot,
+    ExchangeRates, GroupShare, Money, OrderRequest, ProductOrder, Promotion, PromotionContext,
+    DB_NAME_FUENTE, DB_VERSION_FUENTE, NOSTR_KIND_CONSUMER_CART,
+    NOSTR_KIND_CONSUMER_REPLACEABLE_GIFTWRAP, NOSTR_KIND_SERVER_REQUEST, STORE_NAME_CONSUMER_CARTS,
+    TEST_PUB_KEY,
+};
+
+/// Items from one commerce, sent as an order of its own at checkout.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CartBasket {
+    commerce_id: String,
+    cart_items: ProductOrder,
+    #[serde(default)]
+    delivery_slot: Option<DeliverySlot>,
+    #[serde(default)]
+    courier_tip: Option<Money>,
+    #[serde(default)]
+    promo_code: Option<String>,
+    /// Participants of a group order paying for their own items.
+    #[serde(default)]
+    group_shares: Vec<GroupShare>,
+}
+
+impl CartBasket {
+    pub fn new(commerce_id: String) -> Self {
+        Self {
+            commerce_id,
+            cart_items: ProductOrder::new(vec![]),
+            delivery_slot: None,
+            courier_tip: None,
+            promo_code: None,
+            group_shares: vec![],
+        }
+    }
+    pub fn commerce_id(&self) -> String {
+        self.commerce_id.clone()
+    }
+    pub fn delivery_slot(&self) -> Option<DeliverySlot> {
+        self.delivery_slot.clone()
+    }
+    pub fn courier_tip(&self) -> Money {
+        self.courier_tip.unwrap_or_default()
+    }
+    pub fn promo_code(&self) -> Option<String> {
+        self.promo_code.clone()
+    }
+    /// Shares still holding items, a participant whose lines were removed pays nothing.
+    pub fn group_shares(&self) -> Vec<GroupShare> {
+        self.group_shares
+            .iter()
+            .filter(|share| !self.cart_items.contributed_by(&share.name).is_empty())
+            .cloned()
+            .collect()
+    }
+    pub fn product_order(&self) -> ProductOrder {
+        self.cart_items.clone()
+    }
+    pub fn items_mut(&mut self) -> &mut ProductOrder {
+        &mut self.cart_items
+    }
+    pub fn set_delivery_slot(&mut self, delivery_slot: Option<DeliverySlot>) {
+        self.delivery_slot = delivery_slot;
+    }
+    pub fn set_courier_tip(&mut self, tip: Money) {
+        self.courier_tip = tip.is_positive().then_some(tip);
+    }
+    pub fn set_promo_code(&mut self, promo_code: Option<String>) {
+        self.promo_code = promo_code;
+    }
+    pub fn set_group_shares(&mut self, group_shares: Vec<GroupShare>) {
+        self.group_shares = group_shares;
+    }
+    /// Previews the discount the invoicer will apply, the server has the final say.
+    pub fn promotion(
+        &self,
+        code: Option<&str>,
+        offered: &[(String, Promotion)],
+        is_first_order: bool,
+        rates: ExchangeRates,
+    ) -> Result<Option<AppliedPromotion>, String> {
+        let context = PromotionContext {
+            now: (web_sys::js_sys::Date::now() / 1000.0) as i64,
+            is_first_order,
+            rates,
+            ..Default::default()
+        };
+        let participants: Vec<String> = self
+            .group_shares()
+            .into_iter()
+            .map(|share| share.name)
+            .collect();
+        let host_items = self.cart_items.without_contributions(&participants);
+        apply_promotions(offered, code, &host_items, &context).map_err(|e| e.to_string())
+    }
+    /// Units of the product already in the basket, across all of its lines.
+    pub fn quantity_of(&self, product_id: &str) -> u32 {
+        self.cart_items
+            .quantities()
+            .get(product_id)
+            .copied()
+            .unwrap_or_default()
+    }
+    pub async fn sign_request(
+        &self,
+        keys: &UserIdentity,
+        profile: ConsumerProfile,
+        address: ConsumerAddress,
+    ) -> (String, NostrNote) {
+        let mut new_request = OrderRequest::new(
+            self.commerce_id.clone(),
+            profile,
+            address,
+            self.cart_items.clone(),
+        );
+        new_request.scheduled_for = self.delivery_slot.clone();
+        new_request.courier_tip = self.courier_tip;
+        new_request.promo_code = self.promo_code.clone();
+        new_request.group_shares = self.group_shares();
+        let note = new_request.sign_request(keys).await;
+        let content = note.to_string();
+        let giftwrap = NostrNote {
+            pubkey: keys.get_pubkey().await.unwrap(),
+            kind: NOSTR_KIND_SERVER_REQUEST,
+            content,
+            ..Default::default()
+        };
+        let giftwrap = keys
+            .sign_nip44(giftwrap, TEST_PUB_KEY.to_string())
+            .await
+            .unwrap();
+        (note.id.unwrap(), giftwrap)
+    }
+}
+
+/// A consumer's cart as kept on the device and shared between their devices.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedCart {
+    pub baskets: Vec<CartBasket>,
+    /// Unix time of the last change, the newest copy wins.
+    pub updated_at: i64,
+}
+impl ToString for SavedCart {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<NostrNote> for SavedCart {
+    type Error = anyhow::Error;
+    fn try_from(note: NostrNote) -> Result<Self, Self::Error> {
+        if note.kind != NOSTR_KIND_CONSUMER_CART {
+            return Err(anyhow::anyhow!("Wrong Kind"));
+        }
+        Ok(serde_json::from_str(&note.content)?)
+    }
+}
+impl SavedCart {
+    pub async fn signed_data(&self, keys: &UserIdentity) -> NostrNote {
+        let unsigned_note = NostrNote {
+            pubkey: keys.get_pubkey().await.expect("no pubkey"),
+            kind: NOSTR_KIND_CONSUMER_CART,
+            content: self.to_string(),
+            ..Default::default()
+        };
+        keys.sign_nostr_note(unsigned_note)
+            .await
+            .expect("could not sign")
+    }
+    /// Replaceable note only the consumer can read, so their other devices
+    /// pick the cart up.
+    pub async fn giftwrapped_data(&self, keys: &UserIdentity) -> Result<NostrNote, JsValue> {
+        let pubkey = keys
+            .get_pubkey()
+            .await
+            .ok_or(JsValue::from_str("no pubkey"))?;
+        let inner_note = self.signed_data(keys).await;
+        let mut giftwrap = NostrNote {
+            pubkey: pubkey.clone(),
+            kind: NOSTR_KIND_CONSUMER_REPLACEABLE_GIFTWRAP,
+            content: inner_note.to_string(),
+            ..Default::default()
+        };
+        giftwrap.tags.add_parameter_tag(&Self::d_tag());
+        keys.sign_nip44(giftwrap, pubkey).await
+    }
+    pub fn d_tag() -> String {
+        let mut hasher = Sha256::new();
+        hasher.update("cart".as_bytes());
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SavedCartIdb {
+    pubkey: String,
+    cart: SavedCart,
+    /// Whether this device publishes the cart for the consumer's other devices.
+    #[serde(default)]
+    sync_devices: bool,
+}
+impl SavedCartIdb {
+    pub fn new(pubkey: String, cart: SavedCart, sync_devices: bool) -> Self {
+        Self {
+            pubkey,
+            cart,
+            sync_devices,
+        }
+    }
+    pub fn cart(&self) -> SavedCart {
+        self.cart.clone()
+    }
+    pub fn sync_devices(&self) -> bool {
+        self.sync_devices
+    }
+}
+impl TryFrom<JsValue> for SavedCartIdb {
+    type Error = JsValue;
+    fn try_from(value: JsValue) -> Result<Self, Self::Error> {
+        Ok(serde_wasm_bindgen::from_value(value)?)
+    }
+}
+impl Into<JsValue> for SavedCartIdb {
+    fn into(self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self).unwrap()
+    }
+}
+impl IdbStoreManager for SavedCartIdb {
+    fn config() -> nostr_minions::browser_api::IdbStoreConfig {
+        nostr_minions::browser_api::IdbStoreConfig {
+            db_name: DB_NAME_FUENTE,
+            db_version: DB_VERSION_FUENTE,
+            store_name: STORE_NAME_CONSUMER_CARTS,
+            document_key: "pubkey",
+        }
+    }
+    fn key(&self) -> JsValue {
+        JsValue::from_str(&self.pubkey)
+    }
+}
@@ -0,0 +1,255 @@
+use nostr_minions::key_manager::UserIdentity;
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
+use serde::{Deserialize, Serialize};
+
+use super::{OrderInvoiceState, OrderParticipant, OrderStatus, NOSTR_KIND_ORDER_CHAT};
+
+/// Longest message a participant can send, in characters.
+pub const MAX_CHAT_MESSAGE_LENGTH: usize = 500;
+
+/// A message between the people handling an order, sent once to each of them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OrderChatMessage {
+    /// Shared by the copies sent to each participant.
+    pub message_id: String,
+    pub order_id: String,
+    pub body: String,
+    /// Taken from the note it arrived in, never from its content.
+    #[serde(skip)]
+    pub sender: String,
+    #[serde(skip)]
+    pub sent_at: i64,
+}
+impl OrderChatMessage {
+    pub fn new(order: &OrderInvoiceState, body: &str) -> anyhow::Result<Self> {
+        if !OrderChat::is_open(order) {
+            return Err(anyhow::anyhow!("The chat closed with the order"));
+        }
+        let body = body.trim();
+        if body.is_empty() {
+            return Err(anyhow::anyhow!("Message is empty"));
+        }
+        if body.chars().count() > MAX_CHAT_MESSAGE_LENGTH {
+            return Err(anyhow::anyhow!(
+                "Messages can't be longer than {} characters",
+                MAX_CHAT_MESSAGE_LENGTH
+            ));
+        }
+        Ok(Self {
+            message_id: NostrKeypair::generate(false).public_key()[..16].to_string(),
+            order_id: order.order_id(),
+            body: body.to_string(),
+            sender: String::new(),
+            sent_at: 0,
+        })
+    }
+    /// Reads a message out of its decrypted note content.
+    pub fn from_note(note: &NostrNote, content: &str) -> anyhow::Result<Self> {
+        let mut message: OrderChatMessage = serde_json::from_str(content)?;
+        message.sender = note.pubkey.clone();
+        message.sent_at = note.created_at;
+        Ok(message)
+    }
+    pub async fn sign_message(
+        &self,
+        keys: &UserIdentity,
+        recipient: String,
+    ) -> anyhow::Result<NostrNote> {
+        let pubkey = keys
+            .get_pubkey()
+            .await
+            .ok_or(anyhow::anyhow!("No pubkey"))?;
+        let mut note = NostrNote {
+            pubkey,
+            kind: NOSTR_KIND_ORDER_CHAT,
+            content: self.to_string(),
+            ..Default::default()
+        };
+        note.tags.add_parameter_tag(&self.order_id);
+        keys.sign_nip44(note, recipient).await.map_err(|e| {
+            anyhow::anyhow!(e.as_string().unwrap_or_else(|| "Unknown error".to_string()))
+        })
+    }
+    pub fn locale_time(&self) -> String {
+        let sent_at = web_sys::js_sys::Date::new(&web_sys::wasm_bindgen::JsValue::from_f64(
+            self.sent_at as f64 * 1000.0,
+        ));
+        sent_at.to_locale_time_string("nl-SR").into()
+    }
+}
+impl ToString for OrderChatMessage {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+/// The messages exchanged about one order and how far the user has read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OrderChat {
+    pub order_id: String,
+    messages: Vec<OrderChatMessage>,
+    read_until: i64,
+}
+impl OrderChat {
+    pub fn new(order_id: String) -> Self {
+        Self {
+            order_id,
+            ..Default::default()
+        }
+    }
+    /// Participants can only write while the order is on its way.
+    pub fn is_open(order: &OrderInvoiceState) -> bool {
+        !matches!(
+            order.order_status,
+            OrderStatus::Completed | OrderStatus::Canceled
+        )
+    }
+    /// Everyone taking part in the order with the key they chat with.
+    pub fn participants(order: &OrderInvoiceState) -> Vec<(OrderParticipant, String)> {
+        let mut participants = vec![
+            (OrderParticipant::Consumer, order.order.pubkey.clone()),
+            (OrderParticipant::Commerce, order.get_commerce_pubkey()),
+        ];
+        if let Some(courier) = &order.courier {
+            participants.push((OrderParticipant::Courier, courier.pubkey.clone()));
+        }
+        participants
+    }
+    pub fn participant(order: &OrderInvoiceState, pubkey: &str) -> Option<OrderParticipant> {
+        Self::participants(order)
+            .into_iter()
+            .find(|(_, key)| key == pubkey)
+            .map(|(participant, _)| participant)
+    }
+    /// Keeps the message in order of sending, ignoring copies already seen.
+    pub fn add_message(&mut self, message: OrderChatMessage) {
+        if message.order_id != self.order_id
+            || self
+                .messages
+                .iter()
+                .any(|seen| seen.message_id == message.message_id && seen.sender == message.sender)
+        {
+            return;
+        }
+        let index = self
+            .messages
+            .partition_point(|sent| sent.sent_at <= message.sent_at);
+        self.messages.insert(index, message);
+    }
+    /// Messages sent by participants of the order, others are dropped.
+    pub fn messages(&self, order: &OrderInvoiceState) -> Vec<&OrderChatMessage> {
+        self.messages
+            .iter()
+            .filter(|message| Self::participant(order, &message.sender).is_some())
+            .collect()
+    }
+    pub fn unread(&self, order: &OrderInvoiceState, own_pubkey: &str) -> usize {
+        self.messages(order)
+            .into_iter()
+            .filter(|message| message.sender != own_pubkey && message.sent_at > self.read_until)
+            .count()
+    }
+    pub fn mark_read(&mut self, order: &OrderInvoiceState) {
+        if let Some(last) = self.messages(order).last() {
+            self.read_until = self.read_until.max(last.sent_at);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(sender: &str, sent_at: i64, body: &str) -> OrderChatMessage {
+        OrderChatMessage {
+            message_id: format!("{sender}-{sent_at}"),
+            order_id: "order".to_string(),
+            body: body.to_string(),
+            sender: sender.to_string(),
+            sent_at,
+        }
+    }
+
+    fn order() -> OrderInvoiceState {
+        let request = crate::models::OrderRequest {
+            commerce: "commerce".to_string(),
+            ..Default::default()
+        };
+        let mut order = OrderInvoiceState::new(
+            NostrNote {
+                pubkey: "consumer".to_string(),
+                kind: crate::models::NOSTR_KIND_CONSUMER_ORDER_REQUEST,
+                content: request.to_string(),
+                id: Some("order".to_string()),
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        order.courier = Some(NostrNote {
+            pubkey: "courier".to_string(),
+            ..Default::default()
+        });
+        order
+    }
+
+    #[test]
+    fn chat_keeps_messages_in_order_and_counts_unread() {
+        let order = order();
+        let mut chat = OrderChat::new("order".to_string());
+        chat.add_message(message("courier", 30, "Outside"));
+        chat.add_message(message("consumer", 10, "Ring the bell please"));
+        chat.add_message(message("commerce", 20, "Almost ready"));
+        // The copy a sender keeps for themselves may be signed a second later
+        chat.add_message(OrderChatMessage {
+            sent_at: 11,
+            ..message("consumer", 10, "Ring the bell please")
+        });
+        chat.add_message(OrderChatMessage {
+            order_id: "other".to_string(),
+            ..message("commerce", 40, "Wrong order")
+        });
+        let senders = chat
+            .messages
+            .iter()
+            .map(|message| message.sender.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(senders, vec!["consumer", "commerce", "courier"]);
+
+        assert_eq!(chat.unread(&order, "consumer"), 2);
+        chat.mark_read(&order);
+        assert_eq!(chat.unread(&order, "consumer"), 0);
+        chat.add_message(message("commerce", 50, "On its way"));
+        assert_eq!(chat.unread(&order, "consumer"), 1);
+        assert_eq!(chat.unread(&order, "commerce"), 0);
+    }
+
+    #[test]
+    fn chat_ignores_strangers_when_counting_unread() {
+        let order = order();
+        let mut chat = OrderChat::new("order".to_string());
+        chat.add_message(message("stranger", 10, "Free sats"));
+        assert_eq!(chat.unread(&order, "consumer"), 0);
+
+        chat.add_message(message("commerce", 20, "Almost ready"));
+        // A stranger dating a message ahead can't mark later ones as read
+        chat.add_message(message("stranger", 1000, "Click here"));
+        chat.mark_read(&order);
+        chat.add_message(message("courier", 30, "Outside"));
+        assert_eq!(chat.unread(&order, "consumer"), 1);
+    }
+
+    #[test]
+    fn message_content_does_not_carry_the_sender() {
+        let sent = message("consumer", 10, "Hello");
+        let note = NostrNote {
+            pubkey: "courier".to_string(),
+            created_at: 99,
+            ..Default::default()
+        };
+        let received = OrderChatMessage::from_note(&note, &sent.to_string()).unwrap();
+        assert_eq!(received.sender, "courier");
+        assert_eq!(received.sent_at, 99);
+        assert_eq!(received.body, "Hello");
+    }
+}
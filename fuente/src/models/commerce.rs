@@ -6,8 +6,9 @@ use nostr_minions::{
 };
 
 use super::{
-    gps::CoordinateStrings, nostr_kinds::NOSTR_KIND_COMMERCE_PROFILE, DB_NAME_FUENTE,
-    DB_VERSION_FUENTE, STORE_NAME_COMMERCE_PROFILES,
+    add_geohash_tags, gps::CoordinateStrings, nostr_kinds::NOSTR_KIND_COMMERCE_PROFILE,
+    schedule::CommerceAvailability, Currency, OrderMinimum, DB_NAME_FUENTE, DB_VERSION_FUENTE,
+    STORE_NAME_COMMERCE_PROFILES,
 };
 use nostro2::notes::NostrNote;
 use serde::{Deserialize, Serialize};
@@ -24,6 +25,13 @@ pub struct CommerceProfile {
     pub ln_address: String,
     pub logo_url: String,
     pub banner_url: String,
+    #[serde(default)]
+    pub availability: CommerceAvailability,
+    /// Currency the menu is priced in.
+    #[serde(default)]
+    pub currency: Currency,
+    #[serde(default)]
+    pub order_minimum: OrderMinimum,
 }
 impl Default for CommerceProfile {
     fn default() -> Self {
@@ -37,6 +45,9 @@ impl Default for CommerceProfile {
             ln_address: "".to_string(),
             logo_url: "".to_string(),
             banner_url: "".to_string(),
+            availability: CommerceAvailability::default(),
+            currency: Currency::default(),
+            order_minimum: OrderMinimum::default(),
         }
     }
 }
@@ -62,17 +73,21 @@ impl CommerceProfile {
             ln_address,
             logo_url,
             banner_url,
+            availability: CommerceAvailability::default(),
+            currency: Currency::default(),
+            order_minimum: OrderMinimum::default(),
         }
     }
     pub async fn signed_data(&self, user_keys: &UserIdentity) -> NostrNote {
         let pubkey = user_keys.get_pubkey().await.unwrap();
         let data = serde_json::to_string(self).unwrap();
-        let new_note = NostrNote {
+        let mut new_note = NostrNote {
             pubkey,
             kind: NOSTR_KIND_COMMERCE_PROFILE,
             content: data,
             ..Default::default()
         };
+        add_geohash_tags(&mut new_note, &self.geolocation);
         user_keys
             .sign_nostr_note(new_note)
             .await
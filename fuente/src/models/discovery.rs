@@ -0,0 +1,222 @@
+use super::{CommerceProfile, CoordinateStrings, Money, ParticipantRating};
+
+/// Average courier speed through town traffic.
+pub(super) const COURIER_SPEED_KMH: f64 = 25.0;
+/// Time for a courier to reach the commerce and pick the order up.
+pub(super) const PICKUP_MINUTES: u32 = 10;
+const BASE_DELIVERY_FEE: Money = Money::srd(1500);
+const DELIVERY_FEE_PER_KM: Money = Money::srd(400);
+
+/// Expected delivery from a commerce to the consumer's address.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeliveryEstimate {
+    pub distance_km: f64,
+    /// Preparation, pickup and travel time.
+    pub minutes: u32,
+    pub fee: Money,
+}
+impl DeliveryEstimate {
+    /// `None` when the commerce or the address has no coordinates.
+    pub fn new(commerce: &CommerceProfile, destination: &CoordinateStrings) -> Option<Self> {
+        let distance_km = commerce.geolocation.distance_km(destination)?;
+        let travel_minutes = (distance_km / COURIER_SPEED_KMH * 60.0).ceil() as u32;
+        let fee_cents = BASE_DELIVERY_FEE.cents()
+            + (DELIVERY_FEE_PER_KM.cents() as f64 * distance_km).round() as i64;
+        Some(Self {
+            distance_km,
+            minutes: commerce.availability.lead_minutes + PICKUP_MINUTES + travel_minutes,
+            fee: Money::srd(fee_cents),
+        })
+    }
+    pub fn distance_display(&self) -> String {
+        if self.distance_km < 1.0 {
+            format!("{} m", (self.distance_km * 1000.0).round() as u32)
+        } else {
+            format!("{:.1} km", self.distance_km)
+        }
+    }
+    /// Range shown to consumers, since the estimate is only a guess.
+    pub fn minutes_display(&self) -> String {
+        let low = self.minutes / 5 * 5;
+        format!("{}-{} min", low, low + 10)
+    }
+}
+
+/// A commerce as listed on the home and browse pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommerceListing {
+    pub commerce_id: String,
+    pub profile: CommerceProfile,
+    /// Satisfaction score out of 5.
+    pub rating: Option<f32>,
+    pub estimate: Option<DeliveryEstimate>,
+    pub open: bool,
+}
+impl CommerceListing {
+    pub fn new(
+        commerce_id: String,
+        profile: CommerceProfile,
+        rating: Option<&ParticipantRating>,
+        destination: Option<&CoordinateStrings>,
+        now: i64,
+    ) -> Self {
+        let estimate = destination.and_then(|address| DeliveryEstimate::new(&profile, address));
+        // Commerces without opening hours take orders whenever they're online
+        let open = !profile.availability.accepts_scheduled_orders()
+            || profile.availability.is_open_at(now);
+        Self {
+            commerce_id,
+            profile,
+            rating: rating.and_then(|rating| rating.satisfaction_score.parse().ok()),
+            estimate,
+            open,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CommerceSort {
+    #[default]
+    Distance,
+    Rating,
+    DeliveryFee,
+}
+impl CommerceSort {
+    pub const ALL: [CommerceSort; 3] = [
+        CommerceSort::Distance,
+        CommerceSort::Rating,
+        CommerceSort::DeliveryFee,
+    ];
+    pub fn translation_key(&self) -> &'static str {
+        match self {
+            CommerceSort::Distance => "discovery_sort_distance",
+            CommerceSort::Rating => "discovery_sort_rating",
+            CommerceSort::DeliveryFee => "discovery_sort_fee",
+        }
+    }
+}
+
+/// Sorting and filters picked by the consumer when browsing commerces.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommerceDiscovery {
+    pub sort: CommerceSort,
+    pub open_now: bool,
+    pub max_distance_km: Option<u32>,
+}
+impl CommerceDiscovery {
+    pub const DISTANCE_OPTIONS: [u32; 4] = [2, 5, 10, 20];
+
+    /// Filters and sorts `listings`. Commerces without an estimate are kept
+    /// unless a distance limit is set, and always go last.
+    pub fn apply(&self, mut listings: Vec<CommerceListing>) -> Vec<CommerceListing> {
+        listings.retain(|listing| {
+            let within_reach = match self.max_distance_km {
+                Some(max) => listing
+                    .estimate
+                    .is_some_and(|estimate| estimate.distance_km <= max as f64),
+                None => true,
+            };
+            within_reach && (!self.open_now || listing.open)
+        });
+        let distance = |listing: &CommerceListing| {
+            listing
+                .estimate
+                .map_or(f64::INFINITY, |estimate| estimate.distance_km)
+        };
+        match self.sort {
+            CommerceSort::Distance => {
+                listings.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+            }
+            CommerceSort::Rating => listings.sort_by(|a, b| {
+                let rating = |listing: &CommerceListing| listing.rating.unwrap_or(-1.0);
+                rating(b)
+                    .total_cmp(&rating(a))
+                    .then(distance(a).total_cmp(&distance(b)))
+            }),
+            CommerceSort::DeliveryFee => listings.sort_by_key(|listing| {
+                listing
+                    .estimate
+                    .map_or(i64::MAX, |estimate| estimate.fee.cents())
+            }),
+        }
+        // Closed commerces can still take scheduled orders, but open ones come first
+        listings.sort_by_key(|listing| !listing.open);
+        listings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{OpeningHours, Weekday};
+
+    fn coordinates(latitude: &str, longitude: &str) -> CoordinateStrings {
+        CoordinateStrings {
+            latitude: latitude.to_string(),
+            longitude: longitude.to_string(),
+        }
+    }
+
+    fn listing(id: &str, profile: CommerceProfile, rating: f32) -> CommerceListing {
+        let home = coordinates("5.8520", "-55.2038");
+        let mut listing = CommerceListing::new(id.to_string(), profile, None, Some(&home), 0);
+        listing.rating = Some(rating);
+        listing
+    }
+
+    fn located(latitude: &str) -> CommerceProfile {
+        CommerceProfile {
+            geolocation: coordinates(latitude, "-55.2038"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn estimates_grow_with_distance() {
+        let paramaribo = coordinates("5.8520", "-55.2038");
+        let nickerie = coordinates("5.9267", "-56.9731");
+        let distance = paramaribo.distance_km(&nickerie).unwrap();
+        assert!((distance - 196.0).abs() < 2.0, "{distance}");
+        assert!(paramaribo
+            .distance_km(&CoordinateStrings::default())
+            .is_none());
+
+        let near = listing("near", located("5.8600"), 4.0).estimate.unwrap();
+        let far = listing("far", located("5.9000"), 5.0).estimate.unwrap();
+        assert_eq!(near.distance_display(), "890 m");
+        assert_eq!(near.minutes, 45 + PICKUP_MINUTES + 3);
+        assert!(near.minutes < far.minutes);
+        assert!(near.fee.cents() < far.fee.cents());
+    }
+
+    #[test]
+    fn listings_are_sorted_and_filtered() {
+        // Epoch 0 is a Wednesday evening in Suriname, outside these hours
+        let mut closed = CommerceProfile::default();
+        closed.availability.hours = vec![OpeningHours::new(Weekday::Monday, 0, 60)];
+        let listings = vec![
+            listing("nowhere", closed, 3.0),
+            listing("far", located("5.9000"), 5.0),
+            listing("near", located("5.8600"), 4.0),
+        ];
+        assert!(!listings[0].open && listings[0].estimate.is_none());
+        let ids = |discovery: &CommerceDiscovery| {
+            discovery
+                .apply(listings.clone())
+                .into_iter()
+                .map(|listing| listing.commerce_id)
+                .collect::<Vec<_>>()
+        };
+
+        let mut discovery = CommerceDiscovery::default();
+        assert_eq!(ids(&discovery), vec!["near", "far", "nowhere"]);
+        discovery.sort = CommerceSort::Rating;
+        assert_eq!(ids(&discovery), vec!["far", "near", "nowhere"]);
+        discovery.sort = CommerceSort::DeliveryFee;
+        assert_eq!(ids(&discovery), vec!["near", "far", "nowhere"]);
+        discovery.open_now = true;
+        assert_eq!(ids(&discovery), vec!["near", "far"]);
+        discovery.max_distance_km = Some(2);
+        assert_eq!(ids(&discovery), vec!["near"]);
+    }
+}
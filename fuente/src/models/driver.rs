@@ -1,3 +1,4 @@
+use bright_lightning::LightningAddress;
 use nostro2::notes::NostrNote;
 use serde::{Deserialize, Serialize};
 use web_sys::wasm_bindgen::JsValue;
@@ -11,12 +12,15 @@ use nostr_minions::{browser_api::{GeolocationCoordinates, GeolocationPosition, I
 pub struct DriverProfile {
     nickname: String,
     telephone: String,
+    #[serde(default)]
+    ln_address: String,
 }
 impl Default for DriverProfile {
     fn default() -> Self {
         Self {
             nickname: "John Doe".to_string(),
             telephone: "11111111".to_string(),
+            ln_address: "".to_string(),
         }
     }
 }
@@ -68,10 +72,11 @@ impl TryFrom<NostrNote> for DriverProfile {
     }
 }
 impl DriverProfile {
-    pub fn new(nickname: String, telephone: String) -> Self {
+    pub fn new(nickname: String, telephone: String, ln_address: String) -> Self {
         Self {
             nickname,
             telephone,
+            ln_address,
         }
     }
     pub async fn signed_data(&self, keys: &UserIdentity) -> NostrNote {
@@ -123,6 +128,16 @@ impl DriverProfile {
     pub fn telephone(&self) -> String {
         self.telephone.clone()
     }
+    pub fn ln_address(&self) -> Option<LightningAddress> {
+        if self.ln_address.is_empty() {
+            return None;
+        }
+        let address = Box::leak(self.ln_address.clone().into_boxed_str());
+        Some(LightningAddress(address))
+    }
+    pub fn ln_address_str(&self) -> String {
+        self.ln_address.clone()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,310 @@
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    CommerceProfile, CoordinateStrings, OrderStatus, COURIER_SPEED_KMH, NOSTR_KIND_DELIVERY_ETA,
+    PICKUP_MINUTES,
+};
+
+/// Positions kept to work out how fast a courier is moving.
+const TRACK_LENGTH: usize = 5;
+/// Positions closer together in time say more about GPS noise than speed.
+const MIN_TRACK_SECONDS: i64 = 30;
+/// Slower than walking pace the courier is waiting, not riding.
+const MIN_COURIER_SPEED_KMH: f64 = 5.0;
+const MAX_COURIER_SPEED_KMH: f64 = 60.0;
+
+/// Finds the distance a courier travels between two points.
+pub trait RoutingEngine {
+    /// Route length in kilometers, `None` when either point has no coordinates.
+    fn route_km(&self, from: &CoordinateStrings, to: &CoordinateStrings) -> Option<f64>;
+}
+
+/// Great-circle distance stretched by how much the streets wind, for when
+/// no road router is available.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StraightLineRouter {
+    pub detour_factor: f64,
+}
+impl Default for StraightLineRouter {
+    fn default() -> Self {
+        Self { detour_factor: 1.3 }
+    }
+}
+impl RoutingEngine for StraightLineRouter {
+    fn route_km(&self, from: &CoordinateStrings, to: &CoordinateStrings) -> Option<f64> {
+        Some(from.distance_km(to)? * self.detour_factor)
+    }
+}
+
+/// The last few positions a courier reported, oldest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CourierTrack {
+    positions: Vec<(i64, CoordinateStrings)>,
+}
+impl CourierTrack {
+    pub fn record(&mut self, timestamp: i64, position: CoordinateStrings) {
+        if self
+            .positions
+            .last()
+            .is_some_and(|(last, _)| *last >= timestamp)
+        {
+            return;
+        }
+        self.positions.push((timestamp, position));
+        if self.positions.len() > TRACK_LENGTH {
+            self.positions.remove(0);
+        }
+    }
+    pub fn position(&self) -> Option<&CoordinateStrings> {
+        self.positions.last().map(|(_, position)| position)
+    }
+    /// Average speed along the kept positions, `None` until they span long enough.
+    pub fn speed_kmh(&self) -> Option<f64> {
+        let (first, _) = self.positions.first()?;
+        let (last, _) = self.positions.last()?;
+        let seconds = last - first;
+        if seconds < MIN_TRACK_SECONDS {
+            return None;
+        }
+        let km = self
+            .positions
+            .windows(2)
+            .filter_map(|pair| pair[0].1.distance_km(&pair[1].1))
+            .sum::<f64>();
+        Some(km / (seconds as f64 / 3600.0))
+    }
+}
+
+/// What is known about an order on its way to the consumer.
+#[derive(Debug, Clone, Copy)]
+pub struct DeliveryProgress<'a> {
+    pub status: OrderStatus,
+    pub commerce: &'a CommerceProfile,
+    pub destination: &'a CoordinateStrings,
+    /// When the commerce started preparing the order.
+    pub preparing_since: Option<i64>,
+    /// Prep time the commerce gave, its usual lead time when it gave none.
+    pub prep_minutes: Option<u32>,
+    /// Where the assigned courier has been, if one took the order.
+    pub courier: Option<&'a CourierTrack>,
+}
+
+/// Works out when an order reaches the consumer, routing with `R`.
+#[derive(Debug, Clone, Default)]
+pub struct EtaEstimator<R: RoutingEngine> {
+    router: R,
+}
+impl<R: RoutingEngine> EtaEstimator<R> {
+    pub fn new(router: R) -> Self {
+        Self { router }
+    }
+    /// Minutes until the order is delivered, `None` once it's done or when
+    /// the commerce or the address has no coordinates.
+    pub fn minutes_left(&self, progress: &DeliveryProgress, now: i64) -> Option<u32> {
+        let speed_kmh = match progress.courier.and_then(CourierTrack::speed_kmh) {
+            Some(speed) if speed >= MIN_COURIER_SPEED_KMH => speed.min(MAX_COURIER_SPEED_KMH),
+            _ => COURIER_SPEED_KMH,
+        };
+        let travel_minutes = |from: &CoordinateStrings, to: &CoordinateStrings| {
+            self.router
+                .route_km(from, to)
+                .map(|km| km / speed_kmh * 60.0)
+        };
+        let pickup = &progress.commerce.geolocation;
+        let courier_position = progress.courier.and_then(CourierTrack::position);
+        // Until a courier reports in, assume one is around the corner
+        let to_pickup = courier_position
+            .and_then(|position| travel_minutes(position, pickup))
+            .unwrap_or(PICKUP_MINUTES as f64);
+        let to_door = travel_minutes(pickup, progress.destination)?;
+        let lead_minutes = progress
+            .prep_minutes
+            .unwrap_or(progress.commerce.availability.lead_minutes)
+            as f64;
+        let minutes = match progress.status {
+            OrderStatus::Pending => lead_minutes + to_pickup + to_door,
+            OrderStatus::Preparing => {
+                let elapsed = progress
+                    .preparing_since
+                    .map(|since| (now - since) as f64 / 60.0)
+                    .unwrap_or_default();
+                (lead_minutes - elapsed).max(0.0).max(to_pickup) + to_door
+            }
+            OrderStatus::ReadyForDelivery => to_pickup + to_door,
+            OrderStatus::InDelivery => match courier_position {
+                Some(position) => travel_minutes(position, progress.destination)?,
+                None => to_door,
+            },
+            // Scheduled orders arrive in the slot the consumer picked
+            OrderStatus::Scheduled | OrderStatus::Completed | OrderStatus::Canceled => return None,
+        };
+        Some(minutes.ceil() as u32)
+    }
+    pub fn estimate(
+        &self,
+        order_id: String,
+        progress: &DeliveryProgress,
+        now: i64,
+    ) -> Option<DeliveryEta> {
+        let minutes = self.minutes_left(progress, now)?;
+        Some(DeliveryEta {
+            order_id,
+            status: progress.status,
+            arrives_at: now + minutes as i64 * 60,
+            updated_at: now,
+        })
+    }
+}
+
+/// When the order is expected at the consumer's door, as last sent by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeliveryEta {
+    pub order_id: String,
+    pub status: OrderStatus,
+    pub arrives_at: i64,
+    pub updated_at: i64,
+}
+impl DeliveryEta {
+    pub fn minutes_left(&self, now: i64) -> u32 {
+        ((self.arrives_at - now).max(0) as f64 / 60.0).ceil() as u32
+    }
+    /// Whether the consumer should hear about this estimate after `previous`.
+    pub fn differs_from(&self, previous: &DeliveryEta) -> bool {
+        self.status != previous.status || (self.arrives_at - previous.arrives_at).abs() >= 60
+    }
+}
+impl ToString for DeliveryEta {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<String> for DeliveryEta {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+impl DeliveryEta {
+    pub fn giftwrapped_eta(
+        &self,
+        keypair: &NostrKeypair,
+        consumer: String,
+    ) -> anyhow::Result<NostrNote> {
+        let mut new_note = NostrNote {
+            kind: NOSTR_KIND_DELIVERY_ETA,
+            content: self.to_string(),
+            pubkey: keypair.public_key(),
+            ..Default::default()
+        };
+        new_note.tags.add_parameter_tag(&self.order_id);
+        keypair.sign_nip_44_encrypted(&mut new_note, consumer)?;
+        Ok(new_note)
+    }
+    pub fn locale_arrival(&self) -> String {
+        let arrival = web_sys::js_sys::Date::new(&web_sys::wasm_bindgen::JsValue::from_f64(
+            self.arrives_at as f64 * 1000.0,
+        ));
+        arrival.to_locale_time_string("nl-SR").into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommerceAvailability;
+
+    /// Stand-in for a road router that puts every leg at the same length.
+    struct FixedRouter(f64);
+    impl RoutingEngine for FixedRouter {
+        fn route_km(&self, _from: &CoordinateStrings, _to: &CoordinateStrings) -> Option<f64> {
+            Some(self.0)
+        }
+    }
+
+    fn coordinates(latitude: f64, longitude: f64) -> CoordinateStrings {
+        CoordinateStrings {
+            latitude: latitude.to_string(),
+            longitude: longitude.to_string(),
+        }
+    }
+
+    #[test]
+    fn courier_speed_comes_from_recent_positions() {
+        let mut track = CourierTrack::default();
+        track.record(0, coordinates(5.85, -55.2));
+        assert_eq!(track.speed_kmh(), None);
+        // About 1.1 km north every two minutes
+        for step in 1..=6 {
+            track.record(step * 120, coordinates(5.85 + step as f64 * 0.01, -55.2));
+        }
+        track.record(600, coordinates(0.0, 0.0));
+        let speed = track.speed_kmh().unwrap();
+        assert!((speed - 33.4).abs() < 0.5, "{speed}");
+        let (latitude, _) = track.position().unwrap().degrees().unwrap();
+        assert!((latitude - 5.91).abs() < 1e-9);
+    }
+
+    #[test]
+    fn estimate_follows_the_order_through_delivery() {
+        let commerce = CommerceProfile {
+            geolocation: coordinates(5.85, -55.2),
+            availability: CommerceAvailability {
+                lead_minutes: 45,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let destination = coordinates(5.8, -55.15);
+        // 5 km legs take 12 minutes at the default speed
+        let estimator = EtaEstimator::new(FixedRouter(5.0));
+        let mut progress = DeliveryProgress {
+            status: OrderStatus::Pending,
+            commerce: &commerce,
+            destination: &destination,
+            preparing_since: None,
+            prep_minutes: None,
+            courier: None,
+        };
+        assert_eq!(estimator.minutes_left(&progress, 0), Some(45 + 10 + 12));
+
+        progress.status = OrderStatus::Preparing;
+        progress.preparing_since = Some(0);
+        assert_eq!(estimator.minutes_left(&progress, 10 * 60), Some(35 + 12));
+        assert_eq!(estimator.minutes_left(&progress, 60 * 60), Some(10 + 12));
+        // The commerce's own prep time wins over its usual lead time
+        progress.prep_minutes = Some(20);
+        assert_eq!(estimator.minutes_left(&progress, 5 * 60), Some(15 + 12));
+
+        let mut track = CourierTrack::default();
+        track.record(0, coordinates(5.85, -55.2));
+        track.record(600, coordinates(5.95, -55.2));
+        progress.courier = Some(&track);
+        progress.status = OrderStatus::ReadyForDelivery;
+        // 11 km in ten minutes is capped at the top speed
+        assert_eq!(estimator.minutes_left(&progress, 0), Some(5 + 5));
+
+        progress.status = OrderStatus::InDelivery;
+        let eta = estimator
+            .estimate("order".to_string(), &progress, 1000)
+            .unwrap();
+        assert_eq!(eta.arrives_at, 1000 + 5 * 60);
+        assert_eq!(eta.minutes_left(1000 + 90), 4);
+
+        progress.status = OrderStatus::Completed;
+        assert_eq!(estimator.minutes_left(&progress, 0), None);
+    }
+
+    #[test]
+    fn straight_line_router_needs_coordinates() {
+        let router = StraightLineRouter::default();
+        let route = router
+            .route_km(&coordinates(5.85, -55.2), &coordinates(5.86, -55.2))
+            .unwrap();
+        assert!((route - 1.45).abs() < 0.01, "{route}");
+        assert_eq!(
+            router.route_km(&CoordinateStrings::default(), &coordinates(5.86, -55.2)),
+            None
+        );
+    }
+}
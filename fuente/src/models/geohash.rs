@@ -0,0 +1,153 @@
+use nostro2::notes::{NostrNote, NostrTag};
+
+use super::CoordinateStrings;
+
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Precisions commerce profiles and menus are tagged with, from about
+/// 156 km down to 5 km cells, so clients can pick how wide they look.
+pub const GEOHASH_TAG_PRECISIONS: [usize; 3] = [3, 4, 5];
+/// Precision consumers subscribe with, cells of roughly 39 by 20 km.
+pub const GEOHASH_AREA_PRECISION: usize = 4;
+
+pub fn geohash_encode(latitude: f64, longitude: f64, precision: usize) -> String {
+    let mut latitude_range = (-90.0, 90.0);
+    let mut longitude_range = (-180.0, 180.0);
+    let mut hash = String::with_capacity(precision);
+    let mut bits = 0;
+    let mut bit_count = 0;
+    let mut even = true;
+    while hash.len() < precision {
+        let (range, value) = if even {
+            (&mut longitude_range, longitude)
+        } else {
+            (&mut latitude_range, latitude)
+        };
+        let middle = (range.0 + range.1) / 2.0;
+        bits <<= 1;
+        if value >= middle {
+            bits |= 1;
+            range.0 = middle;
+        } else {
+            range.1 = middle;
+        }
+        even = !even;
+        bit_count += 1;
+        if bit_count == 5 {
+            hash.push(BASE32[bits] as char);
+            bits = 0;
+            bit_count = 0;
+        }
+    }
+    hash
+}
+
+/// Latitude and longitude ranges covered by a geohash cell.
+fn geohash_bounds(hash: &str) -> Option<((f64, f64), (f64, f64))> {
+    let mut latitude_range = (-90.0, 90.0);
+    let mut longitude_range = (-180.0, 180.0);
+    let mut even = true;
+    for c in hash.bytes() {
+        let value = BASE32.iter().position(|b| *b == c)?;
+        for shift in (0..5).rev() {
+            let range = if even {
+                &mut longitude_range
+            } else {
+                &mut latitude_range
+            };
+            let middle = (range.0 + range.1) / 2.0;
+            if (value >> shift) & 1 == 1 {
+                range.0 = middle;
+            } else {
+                range.1 = middle;
+            }
+            even = !even;
+        }
+    }
+    Some((latitude_range, longitude_range))
+}
+
+/// The cell itself and the ones around it, so places just across a cell
+/// border are not missed.
+pub fn geohash_area(hash: &str) -> Vec<String> {
+    let Some((latitude_range, longitude_range)) = geohash_bounds(hash) else {
+        return vec![];
+    };
+    let height = latitude_range.1 - latitude_range.0;
+    let width = longitude_range.1 - longitude_range.0;
+    let latitude = (latitude_range.0 + latitude_range.1) / 2.0;
+    let longitude = (longitude_range.0 + longitude_range.1) / 2.0;
+    let mut cells = vec![hash.to_string()];
+    for d_lat in [-1.0, 0.0, 1.0] {
+        for d_lon in [-1.0, 0.0, 1.0] {
+            let neighbor_latitude = latitude + d_lat * height;
+            if neighbor_latitude.abs() > 90.0 {
+                continue;
+            }
+            let neighbor_longitude = (longitude + d_lon * width + 540.0).rem_euclid(360.0) - 180.0;
+            let cell = geohash_encode(neighbor_latitude, neighbor_longitude, hash.len());
+            if !cells.contains(&cell) {
+                cells.push(cell);
+            }
+        }
+    }
+    cells
+}
+
+impl CoordinateStrings {
+    pub fn geohash(&self, precision: usize) -> Option<String> {
+        let (latitude, longitude) = self.degrees()?;
+        Some(geohash_encode(latitude, longitude, precision))
+    }
+    /// Cells around these coordinates to subscribe to, empty without coordinates.
+    pub fn geohash_area(&self) -> Vec<String> {
+        self.geohash(GEOHASH_AREA_PRECISION)
+            .map(|hash| geohash_area(&hash))
+            .unwrap_or_default()
+    }
+}
+
+/// Adds a `g` tag per precision in [`GEOHASH_TAG_PRECISIONS`].
+pub fn add_geohash_tags(note: &mut NostrNote, location: &CoordinateStrings) {
+    for precision in GEOHASH_TAG_PRECISIONS {
+        if let Some(hash) = location.geohash(precision) {
+            note.tags.add_custom_tag(NostrTag::Custom("g"), &hash);
+        }
+    }
+}
+
+/// Notes published before geohash tags were added need to be signed again.
+pub fn has_geohash_tags(note: &NostrNote) -> bool {
+    !note.tags.find_tags(NostrTag::Custom("g")).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_encoded_and_surrounded() {
+        assert_eq!(geohash_encode(57.64911, 10.40744, 11), "u4pruydqqvj");
+        let paramaribo = CoordinateStrings {
+            latitude: "5.8520".to_string(),
+            longitude: "-55.2038".to_string(),
+        };
+        let hash = paramaribo.geohash(5).unwrap();
+        assert_eq!(hash.len(), 5);
+        assert!(hash.starts_with(&paramaribo.geohash(3).unwrap()));
+        assert!(CoordinateStrings::default().geohash_area().is_empty());
+
+        let area = paramaribo.geohash_area();
+        assert_eq!(area.len(), 9);
+        assert_eq!(area[0], paramaribo.geohash(GEOHASH_AREA_PRECISION).unwrap());
+        // A place 20 km east lands in the area even across a cell border
+        let east = CoordinateStrings {
+            latitude: "5.8520".to_string(),
+            longitude: "-55.0".to_string(),
+        };
+        assert!(area.contains(&east.geohash(GEOHASH_AREA_PRECISION).unwrap()));
+
+        // Cells at the date line wrap around instead of falling off
+        assert_eq!(geohash_area(&geohash_encode(0.0, 179.99, 4)).len(), 9);
+    }
+}
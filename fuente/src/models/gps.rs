@@ -35,3 +35,21 @@ impl Into<GeolocationCoordinates> for CoordinateStrings {
         }
     }
 }
+impl CoordinateStrings {
+    pub(super) fn degrees(&self) -> Option<(f64, f64)> {
+        let latitude = self.latitude.trim().parse::<f64>().ok()?;
+        let longitude = self.longitude.trim().parse::<f64>().ok()?;
+        Some((latitude, longitude))
+    }
+    /// Great-circle distance in kilometers, `None` when either side has no coordinates.
+    pub fn distance_km(&self, other: &CoordinateStrings) -> Option<f64> {
+        const EARTH_RADIUS_KM: f64 = 6371.0;
+        let (lat_a, lon_a) = self.degrees()?;
+        let (lat_b, lon_b) = other.degrees()?;
+        let d_lat = (lat_b - lat_a).to_radians();
+        let d_lon = (lon_b - lon_a).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2)
+            + lat_a.to_radians().cos() * lat_b.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+        Some(2.0 * EARTH_RADIUS_KM * a.sqrt().asin())
+    }
+}
@@ -0,0 +1,283 @@
+use nostr_minions::{browser_api::IdbStoreManager, key_manager::UserIdentity};
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
+use serde::{Deserialize, Serialize};
+use web_sys::wasm_bindgen::JsValue;
+
+use super::{
+    GroupShare, ProductOrder, DB_NAME_FUENTE, DB_VERSION_FUENTE, NOSTR_KIND_GROUP_CONTRIBUTION,
+    NOSTR_KIND_GROUP_ORDER, STORE_NAME_CONSUMER_GROUPS,
+};
+
+/// Link to a group order. Holds the secret of a key made for the group, notes
+/// of the group are encrypted to it so only people with the link can read them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupInvite {
+    /// Consumer who opened the group and places the order.
+    pub host: String,
+    secret: String,
+}
+impl GroupInvite {
+    pub fn new(host: String) -> Self {
+        let secret = NostrKeypair::generate(false)
+            .get_secret_key()
+            .iter()
+            .map(|x| format!("{:02x}", x))
+            .collect::<String>();
+        Self { host, secret }
+    }
+    /// Code shared with participants, the host's key followed by the group secret.
+    pub fn code(&self) -> String {
+        format!("{}{}", self.host, self.secret)
+    }
+    pub fn parse(code: &str) -> anyhow::Result<Self> {
+        let code = code.trim();
+        if code.len() != 128 || !code.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow::anyhow!("Invalid invite code"));
+        }
+        let (host, secret) = code.split_at(64);
+        Ok(Self {
+            host: host.to_lowercase(),
+            secret: secret.to_lowercase(),
+        })
+    }
+    pub fn keys(&self) -> anyhow::Result<NostrKeypair> {
+        Ok(NostrKeypair::try_from(self.secret.as_str())?)
+    }
+    /// Public key of the group, notes are encrypted to it and tagged with it.
+    pub fn group_id(&self) -> String {
+        self.keys()
+            .map(|keys| keys.public_key())
+            .unwrap_or_default()
+    }
+    async fn giftwrap(
+        &self,
+        keys: &UserIdentity,
+        kind: u32,
+        content: String,
+    ) -> Result<NostrNote, JsValue> {
+        let group_id = self.group_id();
+        let mut note = NostrNote {
+            pubkey: keys
+                .get_pubkey()
+                .await
+                .ok_or(JsValue::from_str("no pubkey"))?,
+            kind,
+            content,
+            ..Default::default()
+        };
+        note.tags.add_parameter_tag(&group_id);
+        keys.sign_nip44(note, group_id).await
+    }
+    fn unwrap(&self, note: &NostrNote, kind: u32) -> anyhow::Result<String> {
+        if note.kind != kind {
+            return Err(anyhow::anyhow!("Wrong Kind"));
+        }
+        Ok(self.keys()?.decrypt_nip_44_content(note)?)
+    }
+}
+
+/// Group order as described by its host.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupOrder {
+    pub commerce_id: String,
+    pub host_name: String,
+    /// Whether participants pay for their own items before the order is released.
+    pub split_payment: bool,
+    /// Set by the host at checkout, after which items can't change.
+    #[serde(default)]
+    pub closed: bool,
+    pub updated_at: i64,
+}
+impl ToString for GroupOrder {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl GroupOrder {
+    pub async fn giftwrapped_data(
+        &self,
+        keys: &UserIdentity,
+        invite: &GroupInvite,
+    ) -> Result<NostrNote, JsValue> {
+        invite
+            .giftwrap(keys, NOSTR_KIND_GROUP_ORDER, self.to_string())
+            .await
+    }
+    /// Only the host described in the invite can describe the group.
+    pub fn from_giftwrap(note: &NostrNote, invite: &GroupInvite) -> anyhow::Result<Self> {
+        if note.pubkey != invite.host {
+            return Err(anyhow::anyhow!("Not the group host"));
+        }
+        let content = invite.unwrap(note, NOSTR_KIND_GROUP_ORDER)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Items a participant picked for a group order, replaced on every change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupContribution {
+    pub name: String,
+    pub items: ProductOrder,
+    pub updated_at: i64,
+}
+impl ToString for GroupContribution {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl GroupContribution {
+    pub async fn giftwrapped_data(
+        &self,
+        keys: &UserIdentity,
+        invite: &GroupInvite,
+    ) -> Result<NostrNote, JsValue> {
+        invite
+            .giftwrap(keys, NOSTR_KIND_GROUP_CONTRIBUTION, self.to_string())
+            .await
+    }
+    pub fn from_giftwrap(note: &NostrNote, invite: &GroupInvite) -> anyhow::Result<Self> {
+        let content = invite.unwrap(note, NOSTR_KIND_GROUP_CONTRIBUTION)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+}
+
+/// Builds the host's order from everyone's contributions, keyed by pubkey.
+/// Lines are tagged with the contributor's name, made unique so shares
+/// can't be mixed up, and participants get a share when they pay their own.
+pub fn merge_group_contributions(
+    host: &str,
+    split_payment: bool,
+    contributions: &[(String, GroupContribution)],
+) -> (ProductOrder, Vec<GroupShare>) {
+    let mut order = ProductOrder::default();
+    let mut shares = vec![];
+    let mut names: Vec<String> = vec![];
+    for (pubkey, contribution) in contributions {
+        if contribution.items.is_empty() {
+            continue;
+        }
+        let mut name = contribution.name.trim().to_string();
+        if name.is_empty() || names.contains(&name) {
+            name = format!("{} ({})", name, &pubkey[..pubkey.len().min(6)])
+                .trim()
+                .to_string();
+        }
+        order.add_contribution(&name, &contribution.items);
+        if split_payment && pubkey != host {
+            shares.push(GroupShare {
+                pubkey: pubkey.clone(),
+                name: name.clone(),
+            });
+        }
+        names.push(name);
+    }
+    (order, shares)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupOrderIdb {
+    group_id: String,
+    invite: GroupInvite,
+    joined_at: i64,
+}
+impl GroupOrderIdb {
+    pub fn new(invite: GroupInvite) -> Self {
+        Self {
+            group_id: invite.group_id(),
+            invite,
+            joined_at: (web_sys::js_sys::Date::now() / 1000.0) as i64,
+        }
+    }
+    pub fn group_id(&self) -> String {
+        self.group_id.clone()
+    }
+    pub fn invite(&self) -> GroupInvite {
+        self.invite.clone()
+    }
+    pub fn joined_at(&self) -> i64 {
+        self.joined_at
+    }
+}
+impl TryFrom<JsValue> for GroupOrderIdb {
+    type Error = JsValue;
+    fn try_from(value: JsValue) -> Result<Self, Self::Error> {
+        Ok(serde_wasm_bindgen::from_value(value)?)
+    }
+}
+impl Into<JsValue> for GroupOrderIdb {
+    fn into(self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self).unwrap()
+    }
+}
+impl IdbStoreManager for GroupOrderIdb {
+    fn config() -> nostr_minions::browser_api::IdbStoreConfig {
+        nostr_minions::browser_api::IdbStoreConfig {
+            db_name: DB_NAME_FUENTE,
+            db_version: DB_VERSION_FUENTE,
+            store_name: STORE_NAME_CONSUMER_GROUPS,
+            document_key: "group_id",
+        }
+    }
+    fn key(&self) -> JsValue {
+        JsValue::from_str(&self.group_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Money, ProductItem};
+
+    fn contribution(name: &str, quantity: u32) -> GroupContribution {
+        let dawet = ProductItem::new(
+            1,
+            "Dawet".into(),
+            Money::srd(10),
+            "".into(),
+            "drinks".into(),
+        );
+        let mut items = ProductOrder::default();
+        (0..quantity).for_each(|_| items.add(&dawet));
+        GroupContribution {
+            name: name.to_string(),
+            items,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn contributions_are_merged_into_shares() {
+        let host = "a".repeat(64);
+        let guest = "b".repeat(64);
+        let namesake = "c".repeat(64);
+        let contributions = vec![
+            (host.clone(), contribution("Ana", 1)),
+            (guest.clone(), contribution("Ravi", 2)),
+            (namesake.clone(), contribution("Ana", 3)),
+            ("d".repeat(64), contribution("Noor", 0)),
+        ];
+        let (order, shares) = merge_group_contributions(&host, true, &contributions);
+        assert_eq!(order.item_count(), 6);
+        assert_eq!(order.lines().len(), 3);
+        let names: Vec<_> = shares.iter().map(|share| share.name.clone()).collect();
+        assert_eq!(names, vec!["Ravi", "Ana (cccccc)"]);
+        assert_eq!(order.contributed_by("Ana").item_count(), 1);
+        assert_eq!(
+            order.contributed_by(&shares[1].name).total(),
+            Money::srd(30)
+        );
+
+        let (_, shares) = merge_group_contributions(&host, false, &contributions);
+        assert!(shares.is_empty());
+    }
+
+    #[test]
+    fn invite_codes_round_trip() {
+        let code = format!("{}{}", "a".repeat(64), "0f".repeat(32));
+        let invite = GroupInvite::parse(&format!(" {} ", code.to_uppercase())).unwrap();
+        assert_eq!(invite.host, "a".repeat(64));
+        assert_eq!(invite.code(), code);
+        assert!(GroupInvite::parse("not a code").is_err());
+        assert!(GroupInvite::parse(&"g".repeat(128)).is_err());
+    }
+}
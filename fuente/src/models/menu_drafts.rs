@@ -0,0 +1,116 @@
+use nostr_minions::browser_api::IdbStoreManager;
+use serde::{Deserialize, Serialize};
+use web_sys::wasm_bindgen::JsValue;
+
+use super::{
+    ProductMenu, ProductMenuIdb, DB_NAME_FUENTE, DB_VERSION_FUENTE, STORE_NAME_MENU_DRAFTS,
+    STORE_NAME_MENU_HISTORY,
+};
+
+/// Published versions kept per commerce, older ones are dropped.
+pub const MENU_HISTORY_LIMIT: usize = 20;
+
+/// Menu edits kept on the device until the commerce publishes them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MenuDraftIdb {
+    pubkey: String,
+    menu: ProductMenu,
+    updated_at: u64,
+}
+impl MenuDraftIdb {
+    pub fn new(pubkey: &str, menu: ProductMenu) -> Self {
+        Self {
+            pubkey: pubkey.to_string(),
+            menu,
+            updated_at: web_sys::js_sys::Date::now() as u64 / 1000,
+        }
+    }
+    pub fn menu(&self) -> ProductMenu {
+        self.menu.clone()
+    }
+    pub fn updated_at(&self) -> u64 {
+        self.updated_at
+    }
+}
+impl TryFrom<JsValue> for MenuDraftIdb {
+    type Error = JsValue;
+    fn try_from(js_value: JsValue) -> Result<Self, Self::Error> {
+        Ok(serde_wasm_bindgen::from_value(js_value)?)
+    }
+}
+impl Into<JsValue> for MenuDraftIdb {
+    fn into(self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self).unwrap()
+    }
+}
+impl IdbStoreManager for MenuDraftIdb {
+    fn config() -> nostr_minions::browser_api::IdbStoreConfig {
+        nostr_minions::browser_api::IdbStoreConfig {
+            db_name: DB_NAME_FUENTE,
+            db_version: DB_VERSION_FUENTE,
+            store_name: STORE_NAME_MENU_DRAFTS,
+            document_key: "pubkey",
+        }
+    }
+    fn key(&self) -> JsValue {
+        JsValue::from_str(&self.pubkey)
+    }
+}
+
+/// A menu as it was published, kept so the commerce can go back to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MenuVersionIdb {
+    id: String,
+    pubkey: String,
+    menu: ProductMenu,
+    published_at: i64,
+}
+impl From<&ProductMenuIdb> for MenuVersionIdb {
+    fn from(entry: &ProductMenuIdb) -> Self {
+        let note = entry.note();
+        Self {
+            id: note.id.unwrap_or_default(),
+            pubkey: entry.id(),
+            menu: entry.menu(),
+            published_at: note.created_at,
+        }
+    }
+}
+impl MenuVersionIdb {
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+    pub fn pubkey(&self) -> String {
+        self.pubkey.clone()
+    }
+    pub fn menu(&self) -> ProductMenu {
+        self.menu.clone()
+    }
+    pub fn published_at(&self) -> i64 {
+        self.published_at
+    }
+}
+impl TryFrom<JsValue> for MenuVersionIdb {
+    type Error = JsValue;
+    fn try_from(js_value: JsValue) -> Result<Self, Self::Error> {
+        Ok(serde_wasm_bindgen::from_value(js_value)?)
+    }
+}
+impl Into<JsValue> for MenuVersionIdb {
+    fn into(self) -> JsValue {
+        serde_wasm_bindgen::to_value(&self).unwrap()
+    }
+}
+impl IdbStoreManager for MenuVersionIdb {
+    fn config() -> nostr_minions::browser_api::IdbStoreConfig {
+        nostr_minions::browser_api::IdbStoreConfig {
+            db_name: DB_NAME_FUENTE,
+            db_version: DB_VERSION_FUENTE,
+            store_name: STORE_NAME_MENU_HISTORY,
+            document_key: "id",
+        }
+    }
+    fn key(&self) -> JsValue {
+        JsValue::from_str(&self.id)
+    }
+}
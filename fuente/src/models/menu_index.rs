@@ -0,0 +1,80 @@
+use nostro2::notes::{NostrNote, NostrTag};
+use serde::{Deserialize, Serialize};
+
+use super::NOSTR_KIND_COMMERCE_MENU_CATEGORY;
+
+/// Points at the addressable event holding one category of a chunked menu.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MenuChunkRef {
+    pub category_id: String,
+    pub note_id: String,
+}
+
+/// Content of the menu event once the catalog is split per category,
+/// listing the category events that make up the current menu.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct MenuIndex {
+    pub chunks: Vec<MenuChunkRef>,
+}
+impl ToString for MenuIndex {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<String> for MenuIndex {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+impl MenuIndex {
+    pub fn chunk_for(&self, category_id: &str) -> Option<&MenuChunkRef> {
+        self.chunks
+            .iter()
+            .find(|chunk| chunk.category_id == category_id)
+    }
+    /// Event ids the index references that are not among the ones held.
+    pub fn missing(&self, held: &[String]) -> Vec<String> {
+        self.chunks
+            .iter()
+            .filter(|chunk| !held.contains(&chunk.note_id))
+            .map(|chunk| chunk.note_id.clone())
+            .collect()
+    }
+}
+
+/// Category id of a menu chunk, taken from its d-tag.
+pub fn menu_chunk_category(note: &NostrNote) -> Option<String> {
+    if note.kind != NOSTR_KIND_COMMERCE_MENU_CATEGORY {
+        return None;
+    }
+    note.tags
+        .find_tags(NostrTag::Parameterized)
+        .first()
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ProductCategory, ProductMenu};
+
+    #[test]
+    fn index_is_told_apart_from_legacy_menus() {
+        let mut menu = ProductMenu::new();
+        menu.add_category(ProductCategory::new(0, "Drinks".into()));
+        assert!(MenuIndex::try_from(menu.to_string()).is_err());
+
+        let index = MenuIndex {
+            chunks: vec![MenuChunkRef {
+                category_id: "drinks".into(),
+                note_id: "a1".into(),
+            }],
+        };
+        assert!(ProductMenu::try_from(index.to_string()).is_err());
+        let parsed = MenuIndex::try_from(index.to_string()).unwrap();
+        assert_eq!(parsed, index);
+        assert!(parsed.missing(&["a1".to_string()]).is_empty());
+        assert_eq!(parsed.missing(&[]), vec!["a1".to_string()]);
+    }
+}
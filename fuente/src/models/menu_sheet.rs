@@ -0,0 +1,580 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashSet;
+
+use super::{
+    parse_price, Currency, MenuChange, ModifierGroup, Money, ProductCategory, ProductItem,
+    ProductMenu, ProductSide,
+};
+
+const SHEET_COLUMNS: [&str; 8] = [
+    "category",
+    "name",
+    "sku",
+    "price",
+    "description",
+    "details",
+    "image_url",
+    "sides",
+];
+const PLACEHOLDER_IMAGE: &str = "/public/assets/img/logo.png";
+
+/// One product of a menu spreadsheet, as exported to and imported from CSV or JSON.
+///
+/// Optional columns missing from the sheet are `None` and leave the product as it
+/// is, so a sheet with only categories, names and prices just updates prices.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MenuSheetRow {
+    pub category: String,
+    pub name: String,
+    #[serde(deserialize_with = "amount_or_number")]
+    pub price: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    /// Left empty to keep the uploaded image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// Modifier groups written as `Size: Small=0.00, Large=2.50; Extras: Cheese=1.00`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sides: Option<String>,
+}
+fn amount_or_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(amount) => amount,
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    })
+}
+impl MenuSheetRow {
+    fn from_product(category: &ProductCategory, product: &ProductItem) -> Self {
+        let image_url = product.image_url();
+        Self {
+            category: category.name(),
+            name: product.name(),
+            price: product.original_price().amount(),
+            sku: Some(product.sku()),
+            description: Some(product.description()),
+            details: Some(product.details()),
+            image_url: Some(if image_url == PLACEHOLDER_IMAGE {
+                String::new()
+            } else {
+                image_url
+            }),
+            sides: Some(sides_cell(&product.modifier_groups())),
+        }
+    }
+    fn cells(&self) -> [String; 8] {
+        let optional = |cell: &Option<String>| cell.clone().unwrap_or_default();
+        [
+            self.category.clone(),
+            self.name.clone(),
+            optional(&self.sku),
+            self.price.clone(),
+            optional(&self.description),
+            optional(&self.details),
+            optional(&self.image_url),
+            optional(&self.sides),
+        ]
+    }
+}
+
+/// A row that can't be imported, `row` counts data rows from 1 and skips the header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuSheetError {
+    pub row: usize,
+    pub message: String,
+}
+impl std::fmt::Display for MenuSheetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}: {}", self.row, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuImport {
+    pub menu: ProductMenu,
+    pub changes: Vec<MenuChange>,
+}
+
+fn sides_cell(groups: &[ModifierGroup]) -> String {
+    groups
+        .iter()
+        .map(|group| {
+            let options = group
+                .options()
+                .iter()
+                .map(|side| format!("{}={}", side.name(), side.price().amount()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{}: {}", group.name(), options)
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+type SheetGroup = (String, Vec<(String, Money)>);
+
+fn parse_sides(cell: &str, currency: Currency) -> anyhow::Result<Vec<SheetGroup>> {
+    cell.split(';')
+        .map(str::trim)
+        .filter(|group| !group.is_empty())
+        .map(|group| {
+            let (name, options) = group
+                .split_once(':')
+                .ok_or(anyhow::anyhow!("Sides group \"{}\" needs a name", group))?;
+            let options = options
+                .split(',')
+                .map(str::trim)
+                .filter(|option| !option.is_empty())
+                .map(|option| match option.split_once('=') {
+                    Some((side, price)) => {
+                        Ok((side.trim().to_string(), parse_price(price, currency, true)?))
+                    }
+                    None => Ok((option.to_string(), Money::zero(currency))),
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            if options.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Sides group {} has no options",
+                    name.trim()
+                ));
+            }
+            Ok((name.trim().to_string(), options))
+        })
+        .collect()
+}
+
+/// Keeps the existing groups when the sheet lists the same sides, so their
+/// required and min/max rules survive a round trip through the spreadsheet.
+fn sheet_groups(
+    sides: Vec<SheetGroup>,
+    existing: &[ModifierGroup],
+    currency: Currency,
+) -> Vec<ModifierGroup> {
+    let current = parse_sides(&sides_cell(existing), currency).unwrap_or_default();
+    if current == sides {
+        return existing.to_vec();
+    }
+    sides
+        .into_iter()
+        .map(|(name, options)| {
+            let options = options
+                .into_iter()
+                .enumerate()
+                .map(|(i, (side, price))| ProductSide::new(i, side, price))
+                .collect::<Vec<_>>();
+            match existing.iter().find(|group| group.name() == name) {
+                Some(group) => ModifierGroup::new(
+                    name,
+                    group.required(),
+                    group.min_selections(),
+                    group.max_selections(),
+                    options,
+                )
+                .replacing(group),
+                None => {
+                    let max = options.len() as u32;
+                    ModifierGroup::new(name, false, 0, max, options)
+                }
+            }
+        })
+        .collect()
+}
+
+fn split_records(text: &str, delimiter: char) -> anyhow::Result<Vec<Vec<String>>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, '\r') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            (false, c) if c == delimiter => record.push(std::mem::take(&mut field)),
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow::anyhow!("A quoted field is never closed"));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|record| record.iter().any(|field| !field.trim().is_empty()));
+    Ok(records)
+}
+
+fn escape_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Reads a CSV sheet by its header, spreadsheets saved with a `;` separator work too.
+pub fn parse_menu_csv(text: &str) -> anyhow::Result<Vec<MenuSheetRow>> {
+    let header_line = text.lines().next().unwrap_or_default();
+    let delimiter = if !header_line.contains(',') && header_line.contains(';') {
+        ';'
+    } else {
+        ','
+    };
+    let mut records = split_records(text, delimiter)?.into_iter();
+    let header = records
+        .next()
+        .ok_or(anyhow::anyhow!("The sheet is empty"))?
+        .iter()
+        .map(|column| column.trim().to_lowercase().replace(' ', "_"))
+        .collect::<Vec<_>>();
+    let column = |name: &str| header.iter().position(|column| column == name);
+    for required in ["category", "name", "price"] {
+        if column(required).is_none() {
+            return Err(anyhow::anyhow!("Missing column {}", required));
+        }
+    }
+    let columns = SHEET_COLUMNS.map(column);
+    Ok(records
+        .map(|record| {
+            let cell = |i: usize| {
+                columns[i].map(|i| {
+                    record
+                        .get(i)
+                        .map(|cell| cell.trim())
+                        .unwrap_or_default()
+                        .to_string()
+                })
+            };
+            MenuSheetRow {
+                category: cell(0).unwrap_or_default(),
+                name: cell(1).unwrap_or_default(),
+                sku: cell(2),
+                price: cell(3).unwrap_or_default(),
+                description: cell(4),
+                details: cell(5),
+                image_url: cell(6),
+                sides: cell(7),
+            }
+        })
+        .collect())
+}
+
+pub fn parse_menu_json(text: &str) -> anyhow::Result<Vec<MenuSheetRow>> {
+    Ok(serde_json::from_str(text)?)
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+impl ProductMenu {
+    pub fn sheet_rows(&self) -> Vec<MenuSheetRow> {
+        self.categories()
+            .iter()
+            .flat_map(|category| {
+                category
+                    .products()
+                    .iter()
+                    .map(|product| MenuSheetRow::from_product(category, product))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+    pub fn to_csv(&self) -> String {
+        let mut csv = SHEET_COLUMNS.join(",");
+        csv.push_str("\r\n");
+        for row in self.sheet_rows() {
+            let cells = row.cells().map(|cell| escape_cell(&cell));
+            csv.push_str(&cells.join(","));
+            csv.push_str("\r\n");
+        }
+        csv
+    }
+    pub fn to_sheet_json(&self) -> String {
+        serde_json::to_string_pretty(&self.sheet_rows()).unwrap()
+    }
+    /// Builds the menu a sheet describes, replacing this one.
+    ///
+    /// Rows are matched to existing products by SKU, then by category and name,
+    /// then by name alone, so ids held by carts and orders survive the import.
+    /// Products and categories the sheet doesn't list are removed.
+    pub fn import_sheet(
+        &self,
+        rows: &[MenuSheetRow],
+        currency: Currency,
+    ) -> Result<MenuImport, Vec<MenuSheetError>> {
+        let existing = self
+            .categories()
+            .into_iter()
+            .flat_map(|category| {
+                category
+                    .products()
+                    .into_iter()
+                    .map(move |product| (category.clone(), product))
+            })
+            .collect::<Vec<_>>();
+        let mut errors = vec![];
+        let mut skus = HashSet::new();
+        let mut names = HashSet::new();
+        let mut matched = HashSet::new();
+        let mut categories: Vec<ProductCategory> = vec![];
+
+        for (i, row) in rows.iter().enumerate() {
+            let mut error = |message: String| {
+                errors.push(MenuSheetError {
+                    row: i + 1,
+                    message,
+                })
+            };
+            let (category_name, name) = (row.category.trim(), row.name.trim());
+            if category_name.is_empty() {
+                error("Missing category".to_string());
+                continue;
+            }
+            if name.is_empty() {
+                error("Missing name".to_string());
+                continue;
+            }
+            let price = match parse_price(&row.price, currency, false) {
+                Ok(price) => price,
+                Err(e) => {
+                    error(e.to_string());
+                    continue;
+                }
+            };
+            let sides = match row.sides.as_deref().map(|cell| parse_sides(cell, currency)) {
+                Some(Err(e)) => {
+                    error(e.to_string());
+                    continue;
+                }
+                Some(Ok(sides)) => Some(sides),
+                None => None,
+            };
+            let sku = row
+                .sku
+                .as_deref()
+                .map(str::trim)
+                .filter(|sku| !sku.is_empty());
+            if let Some(sku) = sku {
+                if !skus.insert(sku.to_string()) {
+                    error(format!("SKU {} is used twice", sku));
+                    continue;
+                }
+            }
+            if !names.insert((category_name.to_lowercase(), name.to_lowercase())) {
+                error(format!("{} is listed twice in {}", name, category_name));
+                continue;
+            }
+
+            let category_index = match categories
+                .iter()
+                .position(|category| same_name(&category.name(), category_name))
+            {
+                Some(index) => index,
+                None => {
+                    let category = match self
+                        .categories()
+                        .into_iter()
+                        .find(|category| same_name(&category.name(), category_name))
+                    {
+                        Some(mut category) => {
+                            category
+                                .products()
+                                .into_iter()
+                                .for_each(|product| category.remove_product(product.id()));
+                            category
+                        }
+                        None => ProductCategory::new(categories.len(), category_name.to_string()),
+                    };
+                    categories.push(category);
+                    categories.len() - 1
+                }
+            };
+            let category = &mut categories[category_index];
+
+            let unmatched =
+                |(_, product): &&(ProductCategory, ProductItem)| !matched.contains(&product.id());
+            let previous = sku
+                .and_then(|sku| {
+                    existing
+                        .iter()
+                        .filter(unmatched)
+                        .find(|(_, product)| product.sku() == sku)
+                })
+                .or_else(|| {
+                    existing.iter().filter(unmatched).find(|(old, product)| {
+                        same_name(&old.name(), category_name) && same_name(&product.name(), name)
+                    })
+                })
+                .or_else(|| {
+                    existing
+                        .iter()
+                        .filter(unmatched)
+                        .find(|(_, product)| same_name(&product.name(), name))
+                });
+
+            let mut product = match previous {
+                Some((_, product)) => {
+                    matched.insert(product.id());
+                    product.clone()
+                }
+                None => ProductItem::new(
+                    category.products().len(),
+                    name.to_string(),
+                    price,
+                    String::new(),
+                    category.id(),
+                ),
+            };
+            product.set_name(name.to_string());
+            product.set_price(price);
+            if let Some(sku) = sku {
+                product.set_sku(sku.to_string());
+            }
+            if let Some(description) = &row.description {
+                product.set_description(description.trim().to_string());
+            }
+            if let Some(details) = &row.details {
+                product.set_details(details.trim().to_string());
+            }
+            if let Some(image_url) = row.image_url.as_deref().map(str::trim) {
+                if !image_url.is_empty() {
+                    product.set_image_url(image_url.to_string());
+                }
+            }
+            if let Some(sides) = sides {
+                let groups = sheet_groups(sides, &product.modifier_groups(), currency);
+                product.set_modifier_groups(groups);
+            }
+
+            category.add_product(product);
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let mut menu = ProductMenu::new();
+        categories
+            .into_iter()
+            .for_each(|category| menu.add_category(category));
+        // Products that moved to another category still point at the old one
+        menu.migrate_ids();
+        let changes = menu.changes_from(self);
+        Ok(MenuImport { menu, changes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_menu() -> ProductMenu {
+        let mut menu = ProductMenu::new();
+        let drinks = ProductCategory::new(0, "Drinks".into());
+        let mut cola = ProductItem::new(0, "Cola".into(), Money::srd(250), "".into(), drinks.id());
+        cola.set_sku("COLA".into());
+        cola.set_modifier_groups(vec![ModifierGroup::new(
+            "Size".into(),
+            true,
+            1,
+            1,
+            vec![
+                ProductSide::new(0, "Small".into(), Money::srd(0)),
+                ProductSide::new(1, "Large".into(), Money::srd(100)),
+            ],
+        )]);
+        let water = ProductItem::new(1, "Water".into(), Money::srd(150), "".into(), drinks.id());
+        menu.add_category(drinks.clone());
+        menu.add_product(drinks.id(), cola);
+        menu.add_product(drinks.id(), water);
+        menu
+    }
+
+    #[test]
+    fn csv_export_round_trips() {
+        let menu = sample_menu();
+        let csv = menu.to_csv();
+        assert!(csv.contains("Size: Small=0.00, Large=1.00"));
+        let rows = parse_menu_csv(&csv).unwrap();
+        assert_eq!(rows, menu.sheet_rows());
+        let import = menu.import_sheet(&rows, Currency::SRD).unwrap();
+        assert!(import.changes.is_empty());
+        assert_eq!(import.menu.categories(), menu.categories());
+
+        let json = parse_menu_json(&menu.to_sheet_json()).unwrap();
+        assert_eq!(json, rows);
+    }
+
+    #[test]
+    fn import_keeps_ids_and_reports_changes() {
+        let menu = sample_menu();
+        let cola = menu.categories()[0].products()[0].clone();
+        let csv = "Category;Name;Price;SKU\n\
+                   Drinks;Cola Zero;3,00;COLA\n\
+                   Snacks;Chips;2.00;\n";
+        let rows = parse_menu_csv(csv).unwrap();
+        assert_eq!(rows[0].description, None);
+        let import = menu.import_sheet(&rows, Currency::SRD).unwrap();
+
+        let renamed = import.menu.find_product(&cola.id()).unwrap();
+        assert_eq!(renamed.name(), "Cola Zero");
+        assert_eq!(renamed.original_price(), Money::srd(300));
+        assert_eq!(renamed.modifier_groups(), cola.modifier_groups());
+        assert_eq!(import.menu.categories().len(), 2);
+        assert_eq!(
+            import.changes,
+            vec![
+                MenuChange::Updated {
+                    category: "Drinks".into(),
+                    name: "Cola Zero".into(),
+                    fields: vec!["name", "price"],
+                },
+                MenuChange::Added {
+                    category: "Snacks".into(),
+                    name: "Chips".into(),
+                },
+                MenuChange::Removed {
+                    category: "Drinks".into(),
+                    name: "Water".into(),
+                },
+            ]
+        );
+        let chips = &import.menu.categories()[1].products()[0];
+        assert_eq!(chips.category_id(), import.menu.categories()[1].id());
+    }
+
+    #[test]
+    fn invalid_rows_are_reported() {
+        let csv = "category,name,price,sides\n\
+                   Drinks,,1.00,\n\
+                   Drinks,Tea,abc,\n\
+                   Drinks,Coffee,2.00 USD,\n\
+                   Drinks,Juice,2.00,\"Size Small=1\"\n\
+                   Drinks,Soda,1.00,\n\
+                   drinks,soda,1.50,\n";
+        let rows = parse_menu_csv(csv).unwrap();
+        let errors = ProductMenu::new()
+            .import_sheet(&rows, Currency::SRD)
+            .unwrap_err();
+        let rows_with_errors = errors.iter().map(|e| e.row).collect::<Vec<_>>();
+        assert_eq!(rows_with_errors, vec![1, 2, 3, 4, 6]);
+        assert!(parse_menu_csv("name,price\nTea,1").is_err());
+        assert!(parse_menu_csv("category,name,price\n\"Tea,1").is_err());
+    }
+}
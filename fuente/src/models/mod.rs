@@ -3,26 +3,58 @@ use web_sys::wasm_bindgen::{closure::Closure, JsCast, JsValue};
 
 mod address;
 mod admin_configs;
+mod cart;
+mod chat;
 mod commerce;
 mod consumer_profile;
+mod discovery;
 mod driver;
+mod eta;
 mod favorites;
+mod geohash;
 mod gps;
+mod group_order;
+mod menu_drafts;
+mod menu_index;
+mod menu_sheet;
+mod money;
 mod nostr_kinds;
+mod order_minimum;
 mod orders;
+mod product_tags;
 mod products;
+mod promotions;
 mod ratings;
+mod schedule;
+mod search;
+mod stock;
 pub use address::*;
 pub use admin_configs::*;
+pub use cart::*;
+pub use chat::*;
 pub use commerce::*;
 pub use consumer_profile::*;
+pub use discovery::*;
 pub use driver::*;
+pub use eta::*;
 pub use favorites::*;
+pub use geohash::*;
 pub use gps::*;
+pub use group_order::*;
+pub use menu_drafts::*;
+pub use menu_index::*;
+pub use menu_sheet::*;
+pub use money::*;
 pub use nostr_kinds::*;
+pub use order_minimum::*;
 pub use orders::*;
+pub use product_tags::*;
 pub use products::*;
+pub use promotions::*;
 pub use ratings::*;
+pub use schedule::*;
+pub use search::*;
+pub use stock::*;
 
 pub const TEST_PUB_KEY: &str = "9fe3053c0c11b93261929ca6c167b1d955b56025f9025c40ecb1ef5ea0876d84";
 pub const DRIVER_HUB_PRIV_KEY: &str =
@@ -31,7 +63,7 @@ pub const DRIVER_HUB_PUB_KEY: &str =
     "9fe3053c0c11b93261929ca6c167b1d955b56025f9025c40ecb1ef5ea0876d84";
 
 pub const DB_NAME_FUENTE: &str = "fuente_db";
-pub const DB_VERSION_FUENTE: u32 = 7;
+pub const DB_VERSION_FUENTE: u32 = 10;
 
 pub const DB_NAME_COMMERCE: &str = "commerce_db";
 pub const DB_VERSION_COMMERCE: u32 = 6;
@@ -47,8 +79,12 @@ pub const STORE_NAME_COMMERCE_PROFILES: &str = "commerce_profiles";
 pub const STORE_NAME_CONSUMER_PROFILES: &str = "user_profiles";
 pub const STORE_NAME_CONSUMER_ADDRESSES: &str = "consumer_address";
 pub const STORE_NAME_PRODUCT_LISTS: &str = "product_lists";
+pub const STORE_NAME_MENU_DRAFTS: &str = "menu_drafts";
+pub const STORE_NAME_MENU_HISTORY: &str = "menu_history";
 pub const STORE_NAME_ORDER_HISTORY: &str = "order_history";
 pub const STORE_NAME_CONSUMER_FAVORITES: &str = "consumer_favorites";
+pub const STORE_NAME_CONSUMER_CARTS: &str = "consumer_carts";
+pub const STORE_NAME_CONSUMER_GROUPS: &str = "consumer_groups";
 pub const STORE_NAME_COURIER_PROFILES: &str = "courier_profiles";
 
 pub fn init_consumer_db() -> Result<(), JsValue> {
@@ -102,6 +138,14 @@ fn upgrade_fuente_db(db: web_sys::IdbDatabase) -> Result<(), JsValue> {
         products::ProductMenuIdb::create_data_store(&db)?;
         gloo::console::log!("Product list store created");
     }
+    if !db.object_store_names().contains(STORE_NAME_MENU_DRAFTS) {
+        menu_drafts::MenuDraftIdb::create_data_store(&db)?;
+        gloo::console::log!("Menu draft store created");
+    }
+    if !db.object_store_names().contains(STORE_NAME_MENU_HISTORY) {
+        menu_drafts::MenuVersionIdb::create_data_store(&db)?;
+        gloo::console::log!("Menu history store created");
+    }
     if !db.object_store_names().contains(STORE_NAME_ORDER_HISTORY) {
         orders::OrderStateIdb::create_data_store(&db)?;
         gloo::console::log!("Order history store created");
@@ -113,6 +157,14 @@ fn upgrade_fuente_db(db: web_sys::IdbDatabase) -> Result<(), JsValue> {
         FavoriteStore::create_data_store(&db)?;
         gloo::console::log!("Favorites store created");
     }
+    if !db.object_store_names().contains(STORE_NAME_CONSUMER_CARTS) {
+        cart::SavedCartIdb::create_data_store(&db)?;
+        gloo::console::log!("Consumer cart store created");
+    }
+    if !db.object_store_names().contains(STORE_NAME_CONSUMER_GROUPS) {
+        group_order::GroupOrderIdb::create_data_store(&db)?;
+        gloo::console::log!("Consumer group order store created");
+    }
     if !db.object_store_names().contains("stats") {
         PlatformStatIdb::create_data_store(&db)?;
         gloo::console::log!("Favorites store created");
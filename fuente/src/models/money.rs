@@ -0,0 +1,572 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+const SATOSHIS_IN_ONE_BTC: i128 = 100_000_000;
+/// Exchange rates are fixed to six decimals before any conversion.
+const RATE_SCALE: f64 = 1_000_000.0;
+/// Longer fractions can't be scaled to cents without overflowing.
+const MAX_FRACTION_DIGITS: usize = 18;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize,
+)]
+pub enum Currency {
+    #[default]
+    SRD,
+    USD,
+    EUR,
+}
+impl Currency {
+    pub const ALL: [Currency; 3] = [Currency::SRD, Currency::USD, Currency::EUR];
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::SRD => "SRD",
+            Currency::USD => "USD",
+            Currency::EUR => "EUR",
+        }
+    }
+}
+impl TryFrom<&str> for Currency {
+    type Error = anyhow::Error;
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        match code.trim().to_uppercase().as_str() {
+            "SRD" => Ok(Currency::SRD),
+            "USD" | "$" => Ok(Currency::USD),
+            "EUR" | "€" => Ok(Currency::EUR),
+            _ => Err(anyhow::anyhow!("Unknown currency")),
+        }
+    }
+}
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Divides rounding half away from zero.
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder.abs() * 2 >= denominator.abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
+    }
+}
+
+/// Fixed-point amount in cents of its currency.
+///
+/// Amounts are exact, only exchange rates are floats. Conversions between
+/// currencies round half away from zero to the cent, conversions to satoshis
+/// round up so the payee never receives less than the fiat amount.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Money {
+    cents: i64,
+    currency: Currency,
+}
+impl Money {
+    pub const fn new(cents: i64, currency: Currency) -> Self {
+        Self { cents, currency }
+    }
+    pub const fn zero(currency: Currency) -> Self {
+        Self::new(0, currency)
+    }
+    pub const fn srd(cents: i64) -> Self {
+        Self::new(cents, Currency::SRD)
+    }
+    /// Only meant for values coming from floats, like legacy JSON numbers.
+    pub fn from_f64(amount: f64, currency: Currency) -> Self {
+        Self::new((amount * 100.0).round() as i64, currency)
+    }
+    /// Parses "12.5", "12,50", "SRD 12.50" or "12.50 USD", defaulting to SRD.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        Self::parse_in(value, Currency::SRD)
+    }
+    /// Parses like `parse`, amounts without a code are taken in `currency`.
+    pub fn parse_in(value: &str, currency: Currency) -> anyhow::Result<Self> {
+        let value = value.trim();
+        let (amount, currency) = match value.split_once(' ') {
+            Some((left, right)) => match Currency::try_from(left) {
+                Ok(currency) => (right.trim(), currency),
+                Err(_) => (left.trim(), Currency::try_from(right)?),
+            },
+            None => (value, currency),
+        };
+        let (negative, amount) = match amount.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, amount),
+        };
+        let amount = amount.replace(',', ".");
+        let (whole, fraction) = amount.split_once('.').unwrap_or((&amount, ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(anyhow::anyhow!("Empty amount"));
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(anyhow::anyhow!("Invalid amount {}", value));
+        }
+        if fraction.len() > MAX_FRACTION_DIGITS {
+            return Err(anyhow::anyhow!("Too many decimals in {}", value));
+        }
+        let whole: i128 = if whole.is_empty() { 0 } else { whole.parse()? };
+        let digits = fraction.len() as u32;
+        let fraction: i128 = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse()?
+        };
+        let scale = 10_i128.pow(digits);
+        let cents = whole
+            .checked_mul(100)
+            .and_then(|whole| whole.checked_add(div_round(fraction * 100, scale)))
+            .ok_or(anyhow::anyhow!("Amount too large: {}", value))?;
+        let cents = i64::try_from(if negative { -cents } else { cents })?;
+        Ok(Self::new(cents, currency))
+    }
+    pub fn cents(&self) -> i64 {
+        self.cents
+    }
+    pub fn currency(&self) -> Currency {
+        self.currency
+    }
+    pub fn with_currency(&self, currency: Currency) -> Self {
+        Self::new(self.cents, currency)
+    }
+    /// For charts and sorting only, never for arithmetic.
+    pub fn as_f64(&self) -> f64 {
+        self.cents as f64 / 100.0
+    }
+    pub fn is_zero(&self) -> bool {
+        self.cents == 0
+    }
+    pub fn is_positive(&self) -> bool {
+        self.cents > 0
+    }
+    /// The amount without currency, as stored in product prices.
+    pub fn amount(&self) -> String {
+        let sign = if self.cents < 0 { "-" } else { "" };
+        let cents = self.cents.unsigned_abs();
+        format!("{}{}.{:02}", sign, cents / 100, cents % 100)
+    }
+    pub fn percent(&self, percent: u32) -> Self {
+        let cents = div_round(self.cents as i128 * percent as i128, 100);
+        Self::new(cents as i64, self.currency)
+    }
+    pub fn min(self, other: Self) -> Self {
+        if other < self {
+            other
+        } else {
+            self
+        }
+    }
+    /// Converts using `rate` units of this currency per unit of `currency`.
+    pub fn convert(&self, currency: Currency, rate: f64) -> anyhow::Result<Self> {
+        if currency == self.currency {
+            return Ok(*self);
+        }
+        let rate = (rate * RATE_SCALE).round() as i128;
+        if rate <= 0 {
+            return Err(anyhow::anyhow!("Invalid exchange rate"));
+        }
+        let cents = div_round(self.cents as i128 * RATE_SCALE as i128, rate);
+        Ok(Self::new(i64::try_from(cents)?, currency))
+    }
+    /// Converts to satoshis given the price of one bitcoin in this currency.
+    pub fn to_sats(&self, bitcoin_price: f64) -> anyhow::Result<u64> {
+        let price_cents = (bitcoin_price * 100.0).round() as i128;
+        if price_cents <= 0 {
+            return Err(anyhow::anyhow!("Invalid bitcoin price"));
+        }
+        if self.cents <= 0 {
+            return Ok(0);
+        }
+        let numerator = self.cents as i128 * SATOSHIS_IN_ONE_BTC;
+        let sats = (numerator + price_cents - 1) / price_cents;
+        Ok(u64::try_from(sats)?)
+    }
+    pub fn from_sats(sats: u64, bitcoin_price: f64, currency: Currency) -> Self {
+        let price_cents = (bitcoin_price * 100.0).round() as i128;
+        let cents = div_round(sats as i128 * price_cents, SATOSHIS_IN_ONE_BTC);
+        Self::new(cents as i64, currency)
+    }
+    /// Formats with the separators of the locale, "nl" locales use a decimal comma.
+    pub fn localized(&self, locale: &str) -> String {
+        let (thousands, decimal) = if locale.starts_with("nl") {
+            ('.', ',')
+        } else {
+            (',', '.')
+        };
+        let cents = self.cents.unsigned_abs();
+        let whole = (cents / 100).to_string();
+        let mut grouped = String::new();
+        for (i, digit) in whole.chars().enumerate() {
+            if i > 0 && (whole.len() - i).is_multiple_of(3) {
+                grouped.push(thousands);
+            }
+            grouped.push(digit);
+        }
+        let sign = if self.cents < 0 { "-" } else { "" };
+        format!(
+            "{} {}{}{}{:02}",
+            self.currency.code(),
+            sign,
+            grouped,
+            decimal,
+            cents % 100
+        )
+    }
+    /// Like `+`, but fails on mixed currencies or overflow instead of panicking.
+    /// Use it on amounts that come from someone else's notes.
+    pub fn checked_add(self, other: Money) -> anyhow::Result<Money> {
+        let currency = self.common_currency(&other)?;
+        let cents = self
+            .cents
+            .checked_add(other.cents)
+            .ok_or(anyhow::anyhow!("Amount too large"))?;
+        Ok(Money::new(cents, currency))
+    }
+    pub fn checked_sub(self, other: Money) -> anyhow::Result<Money> {
+        let currency = self.common_currency(&other)?;
+        let cents = self
+            .cents
+            .checked_sub(other.cents)
+            .ok_or(anyhow::anyhow!("Amount too large"))?;
+        Ok(Money::new(cents, currency))
+    }
+    pub fn checked_mul(self, quantity: u32) -> anyhow::Result<Money> {
+        let cents = self
+            .cents
+            .checked_mul(quantity as i64)
+            .ok_or(anyhow::anyhow!("Amount too large"))?;
+        Ok(Money::new(cents, self.currency))
+    }
+    fn common_currency(&self, other: &Self) -> anyhow::Result<Currency> {
+        // Zero amounts, like the start of a sum, take the currency of the other side
+        match (self.is_zero(), other.is_zero()) {
+            (true, _) => Ok(other.currency),
+            (_, true) => Ok(self.currency),
+            _ if self.currency == other.currency => Ok(self.currency),
+            _ => Err(anyhow::anyhow!(
+                "Mixed currencies: {} and {}",
+                self.currency.code(),
+                other.currency.code()
+            )),
+        }
+    }
+    fn same_currency(&self, other: &Self) -> Currency {
+        self.common_currency(other).expect("Mixed currencies")
+    }
+}
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.currency.code(), self.amount())
+    }
+}
+impl FromStr for Money {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+impl TryFrom<String> for Money {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+impl Add for Money {
+    type Output = Money;
+    fn add(self, other: Money) -> Money {
+        Money::new(self.cents + other.cents, self.same_currency(&other))
+    }
+}
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, other: Money) -> Money {
+        Money::new(self.cents - other.cents, self.same_currency(&other))
+    }
+}
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        *self = *self - other;
+    }
+}
+impl Mul<u32> for Money {
+    type Output = Money;
+    fn mul(self, quantity: u32) -> Money {
+        Money::new(self.cents * quantity as i64, self.currency)
+    }
+}
+impl Neg for Money {
+    type Output = Money;
+    fn neg(self) -> Money {
+        Money::new(-self.cents, self.currency)
+    }
+}
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::default(), |total, money| total + money)
+    }
+}
+/// SRD amounts serialize as the plain strings prices always used, other
+/// currencies carry their code, e.g. "12.50 USD".
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.currency {
+            Currency::SRD => serializer.serialize_str(&self.amount()),
+            currency => serializer.serialize_str(&format!("{} {}", self.amount(), currency)),
+        }
+    }
+}
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+        impl<'de> de::Visitor<'de> for MoneyVisitor {
+            type Value = Money;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an amount as string or number")
+            }
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+                // Old menus can hold empty prices
+                if value.trim().is_empty() {
+                    return Ok(Money::default());
+                }
+                Money::parse(value).map_err(E::custom)
+            }
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+                Ok(Money::from_f64(value, Currency::SRD))
+            }
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+                value
+                    .checked_mul(100)
+                    .map(Money::srd)
+                    .ok_or(E::custom("Amount too large"))
+            }
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+                i64::try_from(value)
+                    .ok()
+                    .and_then(|value| value.checked_mul(100))
+                    .map(Money::srd)
+                    .ok_or(E::custom("Amount too large"))
+            }
+        }
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+/// Parses a price typed by the commerce, refusing other currencies than its
+/// own. Only side price deltas are `signed`.
+pub fn parse_price(value: &str, currency: Currency, signed: bool) -> anyhow::Result<Money> {
+    let price = Money::parse_in(value, currency)
+        .map_err(|_| anyhow::anyhow!("Invalid price \"{}\"", value.trim()))?;
+    if price.currency() != currency {
+        return Err(anyhow::anyhow!("Prices must be in {}", currency));
+    }
+    if !signed && price.cents() < 0 {
+        return Err(anyhow::anyhow!("Price can't be negative"));
+    }
+    Ok(price)
+}
+
+/// Parses a discount on `price`, which has to take something off without
+/// making the product free.
+pub fn parse_discount(value: &str, price: Money) -> anyhow::Result<Money> {
+    let discount = parse_price(value, price.currency(), false)?;
+    if !discount.is_positive() || discount >= price {
+        return Err(anyhow::anyhow!(
+            "Discount must be between 0 and {}",
+            price.amount()
+        ));
+    }
+    Ok(discount)
+}
+
+/// Units of each currency worth one US dollar, the currency bitcoin is priced in.
+///
+/// Rates are kept in millionths so the table stays exact and comparable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    rates: BTreeMap<Currency, u64>,
+}
+impl Default for ExchangeRates {
+    fn default() -> Self {
+        let mut rates = BTreeMap::new();
+        rates.insert(Currency::USD, RATE_SCALE as u64);
+        Self { rates }
+    }
+}
+impl ExchangeRates {
+    pub fn rate(&self, currency: Currency) -> Option<f64> {
+        if currency == Currency::USD {
+            return Some(1.0);
+        }
+        self.rates
+            .get(&currency)
+            .map(|rate| *rate as f64 / RATE_SCALE)
+    }
+    pub fn set_rate(&mut self, currency: Currency, rate: f64) {
+        if currency == Currency::USD || rate <= 0.0 {
+            return;
+        }
+        self.rates
+            .insert(currency, (rate * RATE_SCALE).round() as u64);
+    }
+    pub fn rates(&self) -> Vec<(Currency, f64)> {
+        self.rates
+            .keys()
+            .filter_map(|currency| Some((*currency, self.rate(*currency)?)))
+            .collect()
+    }
+    pub fn convert(&self, amount: Money, currency: Currency) -> anyhow::Result<Money> {
+        if amount.currency == currency {
+            return Ok(amount);
+        }
+        let from = self
+            .rate(amount.currency)
+            .ok_or(anyhow::anyhow!("No exchange rate for {}", amount.currency))?;
+        let to = self
+            .rate(currency)
+            .ok_or(anyhow::anyhow!("No exchange rate for {}", currency))?;
+        amount.convert(currency, from / to)
+    }
+    /// Converts to satoshis given the dollar price of one bitcoin.
+    pub fn to_sats(&self, amount: Money, bitcoin_usd_price: f64) -> anyhow::Result<u64> {
+        let rate = self
+            .rate(amount.currency)
+            .ok_or(anyhow::anyhow!("No exchange rate for {}", amount.currency))?;
+        amount.to_sats(bitcoin_usd_price * rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_exactly() {
+        assert_eq!(Money::parse("12.5").unwrap(), Money::srd(1250));
+        assert_eq!(Money::parse("0.1").unwrap(), Money::srd(10));
+        assert_eq!(Money::parse("12,345").unwrap(), Money::srd(1235));
+        assert_eq!(Money::parse("-3").unwrap(), Money::srd(-300));
+        assert_eq!(
+            Money::parse("4.99 USD").unwrap(),
+            Money::new(499, Currency::USD)
+        );
+        assert_eq!(
+            Money::parse("EUR 7").unwrap(),
+            Money::new(700, Currency::EUR)
+        );
+        assert!(Money::parse("abc").is_err());
+        assert!(Money::parse("").is_err());
+        assert!(Money::parse(&format!("1.{}", "9".repeat(40))).is_err());
+        assert!(Money::parse(&"9".repeat(38)).is_err());
+        assert!(serde_json::from_str::<Money>(&i64::MAX.to_string()).is_err());
+        assert!(serde_json::from_str::<Money>(&u64::MAX.to_string()).is_err());
+    }
+
+    #[test]
+    fn arithmetic_is_exact() {
+        let total: Money = (0..10).map(|_| Money::parse("0.10").unwrap()).sum();
+        assert_eq!(total, Money::srd(100));
+        assert_eq!(Money::srd(999) * 3, Money::srd(2997));
+        assert_eq!(Money::srd(1999).percent(15), Money::srd(300));
+        assert_eq!(Money::srd(500) - Money::srd(750), Money::srd(-250));
+        assert_eq!(Money::srd(-250).amount(), "-2.50");
+    }
+
+    #[test]
+    fn checked_arithmetic_refuses_mixed_currencies() {
+        let usd = Money::new(500, Currency::USD);
+        assert_eq!(
+            Money::default().checked_add(usd).unwrap(),
+            Money::new(500, Currency::USD)
+        );
+        assert_eq!(usd.checked_sub(Money::new(0, Currency::EUR)).unwrap(), usd);
+        assert!(usd.checked_add(Money::srd(100)).is_err());
+        assert!(usd.checked_sub(Money::srd(100)).is_err());
+        assert!(Money::srd(i64::MAX).checked_add(Money::srd(1)).is_err());
+        assert!(Money::srd(i64::MAX).checked_mul(2).is_err());
+    }
+
+    #[test]
+    fn converts_with_rounding_rules() {
+        let srd = Money::srd(10_000);
+        let usd = srd.convert(Currency::USD, 36.5).unwrap();
+        assert_eq!(usd, Money::new(274, Currency::USD));
+        // 2.74 USD at 60000 USD/BTC is 4566.67 sats, rounded up
+        assert_eq!(usd.to_sats(60_000.0).unwrap(), 4567);
+        assert_eq!(
+            Money::from_sats(4567, 60_000.0, Currency::USD),
+            Money::new(274, Currency::USD)
+        );
+        assert!(srd.convert(Currency::USD, 0.0).is_err());
+    }
+
+    #[test]
+    fn rate_table_converts_between_currencies() {
+        let mut rates = ExchangeRates::default();
+        rates.set_rate(Currency::SRD, 36.5);
+        rates.set_rate(Currency::EUR, 0.9);
+        let usd = Money::new(1000, Currency::USD);
+        assert_eq!(
+            rates.convert(usd, Currency::SRD).unwrap(),
+            Money::srd(36_500)
+        );
+        assert_eq!(
+            rates.convert(Money::srd(36_500), Currency::EUR).unwrap(),
+            Money::new(900, Currency::EUR)
+        );
+        // 10 USD at 50000 USD/BTC, priced in SRD
+        assert_eq!(rates.to_sats(Money::srd(36_500), 50_000.0).unwrap(), 20_000);
+        let empty = ExchangeRates::default();
+        assert!(empty.convert(usd, Currency::SRD).is_err());
+        let json = serde_json::to_string(&rates).unwrap();
+        assert_eq!(serde_json::from_str::<ExchangeRates>(&json).unwrap(), rates);
+        assert_eq!(
+            Money::parse_in("5", Currency::EUR).unwrap(),
+            Money::new(500, Currency::EUR)
+        );
+    }
+
+    #[test]
+    fn serde_keeps_string_prices() {
+        let price: Money = serde_json::from_str("\"25.00\"").unwrap();
+        assert_eq!(price, Money::srd(2500));
+        assert_eq!(serde_json::to_string(&price).unwrap(), "\"25.00\"");
+        let usd = Money::new(125, Currency::USD);
+        let json = serde_json::to_string(&usd).unwrap();
+        assert_eq!(json, "\"1.25 USD\"");
+        assert_eq!(serde_json::from_str::<Money>(&json).unwrap(), usd);
+        assert_eq!(
+            serde_json::from_str::<Money>("7.5").unwrap(),
+            Money::srd(750)
+        );
+        assert_eq!(
+            serde_json::from_str::<Money>("\"\"").unwrap(),
+            Money::default()
+        );
+    }
+
+    #[test]
+    fn formats_for_locale() {
+        let money = Money::srd(123_456_789);
+        assert_eq!(money.to_string(), "SRD 1234567.89");
+        assert_eq!(money.localized("en-US"), "SRD 1,234,567.89");
+        assert_eq!(money.localized("nl-SR"), "SRD 1.234.567,89");
+    }
+}
@@ -3,28 +3,39 @@ pub const NOSTR_KIND_CONSUMER_PROFILE: u32 = 8991;
 pub const NOSTR_KIND_CONSUMER_PROFILE_ADDRESS: u32 = 8992;
 pub const NOSTR_KIND_CONSUMER_ORDER_REQUEST: u32 = 8993;
 pub const NOSTR_KIND_COMMERCE_ORDER_CONFIRMATION: u32 = 8994;
+pub const NOSTR_KIND_CONSUMER_TIP_REQUEST: u32 = 8995;
+pub const NOSTR_KIND_CONSUMER_CART: u32 = 8996;
 pub const NOSTR_KIND_PRESIGNED_URL_REQ: u32 = 9995;
 
 // PUBLIC REPLEACEABLE - ONLY ONE CAN EXIST
 pub const NOSTR_KIND_COMMERCE_PROFILE: u32 = 18990;
 pub const NOSTR_KIND_COMMERCE_PRODUCTS: u32 = 18991;
+pub const NOSTR_KIND_COMMERCE_PROMOTIONS: u32 = 18992;
 
 // ENCRYPTED STATIC KINDS - MEANT FOR STORING ON RELAYS
 pub const NOSTR_KIND_CONSUMER_GIFTWRAP: u32 = 8992;
+pub const NOSTR_KIND_ORDER_CHAT: u32 = 8997;
 // PARAMETERIZED KINDS - Only one per d-tag
 pub const NOSTR_KIND_ORDER_STATE: u32 = 38996;
 pub const NOSTR_KIND_CONSUMER_REPLACEABLE_GIFTWRAP: u32 = 38992;
+pub const NOSTR_KIND_COMMERCE_MENU_CATEGORY: u32 = 38993;
 pub const NOSTR_KIND_SERVER_CONFIG: u32 = 38997;
 pub const NOSTR_KIND_CONSUMER_REGISTRY: u32 = 38994;
 pub const NOSTR_KIND_COURIER_PROFILE: u32 = 38992;
 pub const NOSTR_KIND_SATISFACTION_EVENT: u32 = 38995;
 pub const NOSTR_KIND_PARTICIPANT_RATING: u32 = 38999;
+pub const NOSTR_KIND_COMMERCE_STOCK: u32 = 38989;
+pub const NOSTR_KIND_GROUP_ORDER: u32 = 38990;
+pub const NOSTR_KIND_GROUP_CONTRIBUTION: u32 = 38991;
+pub const NOSTR_KIND_GROUP_SHARE: u32 = 38998;
 
 // Ephemeral kinds - ARE NOT STORED AND MUST BE LIVE TO RECEIVE
 pub const NOSTR_KIND_SERVER_REQUEST: u32 = 28190;
 pub const NOSTR_KIND_DRIVER_STATE: u32 = 28991;
 pub const NOSTR_KIND_ADMIN_REQUEST: u32 = 28992;
+pub const NOSTR_KIND_DELIVERY_ETA: u32 = 28993;
 pub const NOSTR_KIND_PRESIGNED_URL_RESP: u32 = 29996;
+pub const NOSTR_KIND_COURIER_TIP: u32 = 29997;
 pub const NOSTR_KIND_COMMERCE_UPDATE: u32 = 29998;
 pub const NOSTR_KIND_COURIER_UPDATE: u32 = 29999;
 pub const NOSTR_KIND_CONSUMER_CANCEL: u32 = 29599;
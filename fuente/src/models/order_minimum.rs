@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use super::Money;
+
+/// Smallest order a commerce delivers, in the currency of its menu.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OrderMinimum {
+    /// Item subtotal an order has to reach, none accepts any amount.
+    #[serde(default)]
+    pub minimum: Option<Money>,
+    /// Charged on orders under the minimum instead of refusing them.
+    #[serde(default)]
+    pub small_order_fee: Option<Money>,
+}
+impl OrderMinimum {
+    pub fn new(minimum: Option<Money>, small_order_fee: Option<Money>) -> Self {
+        // A fee only means something below a minimum
+        let minimum = minimum.filter(Money::is_positive);
+        Self {
+            minimum,
+            small_order_fee: minimum.and(small_order_fee.filter(Money::is_positive)),
+        }
+    }
+    /// What's left to add before the subtotal reaches the minimum.
+    pub fn remaining(&self, subtotal: Money) -> Money {
+        match self.minimum {
+            Some(minimum) if subtotal.cents() < minimum.cents() => minimum - subtotal,
+            _ => Money::zero(subtotal.currency()),
+        }
+    }
+    /// The surcharge the subtotal takes, fails when the commerce refuses it.
+    pub fn surcharge(&self, subtotal: Money) -> anyhow::Result<Money> {
+        if self
+            .minimum
+            .is_some_and(|minimum| minimum.checked_sub(subtotal).is_err())
+        {
+            return Err(anyhow::anyhow!("Order minimum is in another currency"));
+        }
+        if self.remaining(subtotal).is_zero() {
+            return Ok(Money::zero(subtotal.currency()));
+        }
+        match self.small_order_fee {
+            Some(fee) => Ok(fee),
+            None => Err(anyhow::anyhow!(
+                "Orders need at least {} of items",
+                self.minimum.unwrap_or_default()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_orders_are_refused_or_surcharged() {
+        let refused = OrderMinimum::new(Some(Money::srd(5000)), None);
+        assert_eq!(refused.remaining(Money::srd(3500)), Money::srd(1500));
+        assert!(refused.surcharge(Money::srd(3500)).is_err());
+        assert_eq!(refused.surcharge(Money::srd(5000)).unwrap(), Money::srd(0));
+
+        let surcharged = OrderMinimum::new(Some(Money::srd(5000)), Some(Money::srd(750)));
+        assert_eq!(
+            surcharged.surcharge(Money::srd(3500)).unwrap(),
+            Money::srd(750)
+        );
+        assert_eq!(
+            surcharged.surcharge(Money::srd(6000)).unwrap(),
+            Money::srd(0)
+        );
+
+        let open = OrderMinimum::new(Some(Money::srd(0)), Some(Money::srd(750)));
+        assert_eq!(open, OrderMinimum::default());
+        assert_eq!(open.remaining(Money::srd(100)), Money::srd(0));
+        assert_eq!(open.surcharge(Money::srd(100)).unwrap(), Money::srd(0));
+    }
+}
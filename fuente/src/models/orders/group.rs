@@ -0,0 +1,59 @@
+use bright_lightning::{LnAddressPaymentRequest, LndHodlInvoice};
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Money, NOSTR_KIND_GROUP_SHARE};
+
+/// Participant of a group order paying for their own items.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GroupShare {
+    pub pubkey: String,
+    /// Contributor their lines are tagged with in the order.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GroupShareStatus {
+    Pending,
+    Paid,
+    Failed,
+}
+
+/// Invoice for one participant's share of a split group order, sent to the
+/// participant and kept with the order so the host can follow the payments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct GroupShareState {
+    pub order_id: String,
+    pub share: GroupShare,
+    pub amount: Money,
+    pub sat_amount: u64,
+    pub consumer_invoice: Option<LndHodlInvoice>,
+    pub commerce_invoice: Option<LnAddressPaymentRequest>,
+    pub status: GroupShareStatus,
+}
+impl ToString for GroupShareState {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<String> for GroupShareState {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+impl GroupShareState {
+    pub fn giftwrapped_state(&self, keypair: &NostrKeypair) -> anyhow::Result<NostrNote> {
+        let mut new_note = NostrNote {
+            kind: NOSTR_KIND_GROUP_SHARE,
+            content: self.to_string(),
+            pubkey: keypair.public_key(),
+            ..Default::default()
+        };
+        new_note
+            .tags
+            .add_parameter_tag(&format!("{}-{}", self.order_id, self.share.pubkey));
+        keypair.sign_nip_44_encrypted(&mut new_note, self.share.pubkey.clone())?;
+        Ok(new_note)
+    }
+}
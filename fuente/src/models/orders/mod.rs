@@ -1,10 +1,16 @@
 mod db;
+mod group;
+mod prep;
 mod request;
 mod state;
+mod tips;
 mod update;
 pub use db::*;
+pub use group::*;
+pub use prep::*;
 pub use request::*;
 pub use state::*;
+pub use tips::*;
 pub use update::*;
 
 #[cfg(test)]
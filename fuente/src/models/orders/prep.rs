@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// Added to the suggested prep time for each order already being prepared.
+const MINUTES_PER_OPEN_ORDER: u32 = 5;
+const MAX_PREP_MINUTES: u32 = 240;
+
+/// How long the commerce said an order takes, counted from when it accepted it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PrepTime {
+    pub minutes: u32,
+    pub started_at: i64,
+}
+impl PrepTime {
+    pub fn new(minutes: u32, started_at: i64) -> anyhow::Result<Self> {
+        if minutes == 0 || minutes > MAX_PREP_MINUTES {
+            return Err(anyhow::anyhow!(
+                "Prep time has to be between 1 and {} minutes",
+                MAX_PREP_MINUTES
+            ));
+        }
+        Ok(Self {
+            minutes,
+            started_at,
+        })
+    }
+    /// Prep time offered to the commerce when it accepts an order: its usual
+    /// lead time, plus some for every order it's already working on.
+    pub fn suggested_minutes(lead_minutes: u32, open_orders: usize) -> u32 {
+        (lead_minutes + MINUTES_PER_OPEN_ORDER * open_orders as u32).clamp(1, MAX_PREP_MINUTES)
+    }
+    pub fn ready_at(&self) -> i64 {
+        self.started_at + self.minutes as i64 * 60
+    }
+    pub fn seconds_left(&self, now: i64) -> i64 {
+        (self.ready_at() - now).max(0)
+    }
+    /// Time left as `mm:ss`, stopping at zero when the commerce runs late.
+    pub fn countdown_display(&self, now: i64) -> String {
+        let seconds = self.seconds_left(now);
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prep_time_counts_down_from_acceptance() {
+        assert!(PrepTime::new(0, 1_000).is_err());
+        assert!(PrepTime::new(MAX_PREP_MINUTES + 1, 1_000).is_err());
+
+        let prep = PrepTime::new(20, 1_000).unwrap();
+        assert_eq!(prep.ready_at(), 1_000 + 20 * 60);
+        assert_eq!(prep.countdown_display(1_000), "20:00");
+        assert_eq!(prep.countdown_display(1_000 + 75), "18:45");
+        assert_eq!(prep.countdown_display(1_000 + 30 * 60), "00:00");
+
+        assert_eq!(PrepTime::suggested_minutes(30, 0), 30);
+        assert_eq!(PrepTime::suggested_minutes(30, 3), 45);
+        assert_eq!(PrepTime::suggested_minutes(200, 20), MAX_PREP_MINUTES);
+    }
+}
@@ -3,7 +3,8 @@ use nostro2::notes::NostrNote;
 
 use crate::models::NOSTR_KIND_SERVER_REQUEST;
 use crate::models::{
-    ConsumerAddress, ConsumerProfile, ProductOrder, NOSTR_KIND_CONSUMER_ORDER_REQUEST,
+    ConsumerAddress, ConsumerProfile, Currency, DeliverySlot, GroupShare, Money, ProductOrder,
+    NOSTR_KIND_CONSUMER_ORDER_REQUEST,
 };
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq, serde::Serialize, serde::Deserialize)]
@@ -12,6 +13,15 @@ pub struct OrderRequest {
     pub profile: ConsumerProfile,
     pub address: ConsumerAddress,
     pub products: ProductOrder,
+    #[serde(default)]
+    pub scheduled_for: Option<DeliverySlot>,
+    #[serde(default)]
+    pub courier_tip: Option<Money>,
+    #[serde(default)]
+    pub promo_code: Option<String>,
+    /// Participants of a group order paying for their own lines.
+    #[serde(default)]
+    pub group_shares: Vec<GroupShare>,
 }
 impl Default for OrderRequest {
     fn default() -> Self {
@@ -20,6 +30,10 @@ impl Default for OrderRequest {
             profile: ConsumerProfile::default(),
             address: ConsumerAddress::default(),
             products: ProductOrder::default(),
+            scheduled_for: None,
+            courier_tip: None,
+            promo_code: None,
+            group_shares: vec![],
         }
     }
 }
@@ -66,8 +80,47 @@ impl OrderRequest {
             profile,
             address,
             products,
+            scheduled_for: None,
+            courier_tip: None,
+            promo_code: None,
+            group_shares: vec![],
         }
     }
+    /// Refuses orders that can't be added up in the currency of the commerce,
+    /// before any of their totals are taken.
+    pub fn check_currency(&self, currency: Currency) -> anyhow::Result<()> {
+        let total = self
+            .products
+            .checked_total()?
+            .checked_add(self.tip_amount())?;
+        if !total.is_zero() && total.currency() != currency {
+            return Err(anyhow::anyhow!(
+                "Order has to be priced in {}",
+                currency.code()
+            ));
+        }
+        Ok(())
+    }
+    pub fn tip_amount(&self) -> Money {
+        self.courier_tip.unwrap_or_default()
+    }
+    /// What a participant of a split group order pays for their lines.
+    pub fn share_total(&self, share: &GroupShare) -> Money {
+        self.products.contributed_by(&share.name).total()
+    }
+    /// Products the consumer who placed the order pays for, everything but
+    /// the shares of the group participants. Promotions are priced on these.
+    pub fn host_products(&self) -> ProductOrder {
+        let participants: Vec<String> = self
+            .group_shares
+            .iter()
+            .map(|share| share.name.clone())
+            .collect();
+        self.products.without_contributions(&participants)
+    }
+    pub fn host_total(&self) -> Money {
+        self.host_products().total()
+    }
     pub async fn sign_request(&self, keys: &UserIdentity) -> NostrNote {
         let content = self.to_string();
         let note = NostrNote {
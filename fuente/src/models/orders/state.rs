@@ -2,12 +2,16 @@ use bright_lightning::{LnAddressPaymentRequest, LndHodlInvoice};
 use nostro2::{keypair::NostrKeypair, notes::NostrNote};
 use serde::{Deserialize, Serialize};
 
-use crate::models::{DRIVER_HUB_PUB_KEY, NOSTR_KIND_ORDER_STATE};
+use crate::models::{
+    AppliedPromotion, CourierTipStatus, GroupShareState, GroupShareStatus, Money,
+    DRIVER_HUB_PUB_KEY, NOSTR_KIND_ORDER_STATE,
+};
 
-use super::request::OrderRequest;
+use super::{prep::PrepTime, request::OrderRequest};
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize, Deserialize, Copy)]
 pub enum OrderStatus {
+    Scheduled,
     Pending,
     Preparing,
     ReadyForDelivery,
@@ -35,6 +39,7 @@ impl TryFrom<&String> for OrderStatus {
 impl OrderStatus {
     pub fn display(&self) -> &'static str {
         match self {
+            Self::Scheduled => "Scheduled",
             Self::Pending => "Pending",
             Self::Preparing => "Preparing",
             Self::ReadyForDelivery => "Ready for Delivery",
@@ -45,6 +50,7 @@ impl OrderStatus {
     }
     pub fn theme_color(&self) -> &'static str {
         match self {
+            Self::Scheduled => "bg-purple-100",
             Self::Pending => "bg-gray-100",
             Self::Preparing => "bg-orange-100",
             Self::ReadyForDelivery => "bg-sky-100",
@@ -55,6 +61,7 @@ impl OrderStatus {
     }
     pub fn text_color(&self) -> &'static str {
         match self {
+            Self::Scheduled => "text-purple-500",
             Self::Pending => "text-gray-500",
             Self::Preparing => "text-orange-500",
             Self::ReadyForDelivery => "text-sky-500",
@@ -65,6 +72,7 @@ impl OrderStatus {
     }
     pub fn border_color(&self) -> &'static str {
         match self {
+            Self::Scheduled => "border-purple-500",
             Self::Pending => "border-gray-500",
             Self::Preparing => "border-orange-500",
             Self::ReadyForDelivery => "border-sky-500",
@@ -140,6 +148,28 @@ pub struct OrderInvoiceState {
     pub payment_status: OrderPaymentStatus,
     pub order_status: OrderStatus,
     pub courier: Option<NostrNote>,
+    #[serde(default)]
+    pub courier_tip_sats: u64,
+    /// Sats of `courier_tip_sats` that actually reached the courier.
+    #[serde(default)]
+    pub courier_tip_paid_sats: u64,
+    /// Payout of the tip held since checkout, `None` until the order is delivered.
+    #[serde(default)]
+    pub tip_payout: Option<CourierTipStatus>,
+    #[serde(default)]
+    pub promotion: Option<AppliedPromotion>,
+    /// Invoices of the participants paying their own part of a group order.
+    #[serde(default)]
+    pub group_shares: Vec<GroupShareState>,
+    /// Charged by the commerce because the order is under its minimum.
+    #[serde(default)]
+    pub small_order_fee: Option<Money>,
+    /// Prep time the commerce gave when it accepted the order.
+    #[serde(default)]
+    pub preparation: Option<PrepTime>,
+    /// Why the invoicer refused the order instead of sending an invoice.
+    #[serde(default)]
+    pub refusal: Option<String>,
 }
 impl OrderInvoiceState {
     pub fn new(
@@ -154,8 +184,36 @@ impl OrderInvoiceState {
             payment_status: OrderPaymentStatus::PaymentPending,
             order_status: OrderStatus::Pending,
             courier: None,
+            courier_tip_sats: 0,
+            courier_tip_paid_sats: 0,
+            tip_payout: None,
+            promotion: None,
+            group_shares: vec![],
+            small_order_fee: None,
+            preparation: None,
+            refusal: None,
         }
     }
+    /// Whether the consumer and every participant sharing the bill have
+    /// paid, so the order can go to the commerce.
+    pub fn group_paid(&self) -> bool {
+        self.payment_status == OrderPaymentStatus::PaymentReceived
+            && self
+                .group_shares
+                .iter()
+                .all(|share| share.status == GroupShareStatus::Paid)
+    }
+    /// Marks the payment of the consumer, or of the participant with
+    /// `pubkey`, as held.
+    pub fn record_group_payment(&mut self, pubkey: &str) {
+        if self.order.pubkey == pubkey {
+            self.payment_status = OrderPaymentStatus::PaymentReceived;
+        }
+        self.group_shares
+            .iter_mut()
+            .filter(|share| share.share.pubkey == pubkey)
+            .for_each(|share| share.status = GroupShareStatus::Paid);
+    }
     pub fn signed_order_state(&self, keypair: &NostrKeypair) -> NostrNote {
         let mut new_note = NostrNote {
             kind: NOSTR_KIND_ORDER_STATE,
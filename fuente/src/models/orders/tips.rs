@@ -0,0 +1,107 @@
+use bright_lightning::{LnAddressPaymentRequest, LndHodlInvoice};
+use nostr_minions::key_manager::UserIdentity;
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{
+    Money, NOSTR_KIND_CONSUMER_TIP_REQUEST, NOSTR_KIND_COURIER_TIP, NOSTR_KIND_SERVER_REQUEST,
+    TEST_PUB_KEY,
+};
+
+/// Tip sent by the consumer to the courier after the order was delivered.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CourierTipRequest {
+    pub order_id: String,
+    pub amount: Money,
+}
+impl ToString for CourierTipRequest {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<&NostrNote> for CourierTipRequest {
+    type Error = anyhow::Error;
+    fn try_from(note: &NostrNote) -> Result<Self, Self::Error> {
+        if note.kind != NOSTR_KIND_CONSUMER_TIP_REQUEST {
+            return Err(anyhow::anyhow!("Wrong Kind"));
+        }
+        Ok(serde_json::from_str(&note.content)?)
+    }
+}
+impl CourierTipRequest {
+    pub fn new(order_id: String, amount: Money) -> Self {
+        Self { order_id, amount }
+    }
+    pub fn amount(&self) -> Money {
+        self.amount
+    }
+    pub async fn sign_request(&self, keys: &UserIdentity) -> anyhow::Result<NostrNote> {
+        let pubkey = keys
+            .get_pubkey()
+            .await
+            .ok_or(anyhow::anyhow!("No pubkey"))?;
+        let note = NostrNote {
+            kind: NOSTR_KIND_CONSUMER_TIP_REQUEST,
+            content: self.to_string(),
+            pubkey: pubkey.clone(),
+            ..Default::default()
+        };
+        let note = keys
+            .sign_nostr_note(note)
+            .await
+            .map_err(|_e| anyhow::anyhow!("Could not sign note"))?;
+        let giftwrap = NostrNote {
+            kind: NOSTR_KIND_SERVER_REQUEST,
+            content: note.to_string(),
+            pubkey,
+            ..Default::default()
+        };
+        keys.sign_nip44(giftwrap, TEST_PUB_KEY.to_string())
+            .await
+            .map_err(|_e| anyhow::anyhow!("Could not sign giftwrap"))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CourierTipStatus {
+    Pending,
+    Paid,
+    Failed,
+}
+
+/// Invoice state for a post-delivery tip, sent back to the consumer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CourierTipState {
+    pub request: CourierTipRequest,
+    pub consumer_invoice: Option<LndHodlInvoice>,
+    pub courier_invoice: Option<LnAddressPaymentRequest>,
+    pub sat_amount: u64,
+    pub status: CourierTipStatus,
+}
+impl ToString for CourierTipState {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<String> for CourierTipState {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+impl CourierTipState {
+    pub fn giftwrapped_state(
+        &self,
+        keypair: &NostrKeypair,
+        receiver: String,
+    ) -> anyhow::Result<NostrNote> {
+        let mut new_note = NostrNote {
+            kind: NOSTR_KIND_COURIER_TIP,
+            content: self.to_string(),
+            pubkey: keypair.public_key(),
+            ..Default::default()
+        };
+        keypair.sign_nip_44_encrypted(&mut new_note, receiver)?;
+        Ok(new_note)
+    }
+}
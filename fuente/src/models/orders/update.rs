@@ -10,6 +10,9 @@ use super::{state::OrderStatus, OrderInvoiceState};
 pub struct OrderUpdateRequest {
     pub order: NostrNote,
     pub status_update: OrderStatus,
+    /// Minutes the commerce needs, sent along when it accepts the order.
+    #[serde(default)]
+    pub prep_minutes: Option<u32>,
 }
 impl TryFrom<NostrNote> for OrderUpdateRequest {
     type Error = anyhow::Error;
@@ -24,6 +27,7 @@ impl OrderUpdateRequest {
         Self {
             order,
             status_update,
+            prep_minutes: None,
         }
     }
     pub fn invoice_state(&self) -> anyhow::Result<OrderInvoiceState> {
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use super::ProductItem;
+
+/// Highest spice level a product can be marked with.
+pub const MAX_SPICE_LEVEL: u8 = 3;
+
+/// Dietary and allergen facts a commerce can mark a product with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProductTag {
+    Vegetarian,
+    Vegan,
+    Halal,
+    GlutenFree,
+    ContainsNuts,
+    ContainsDairy,
+    ContainsShellfish,
+    AgeRestricted,
+}
+impl ProductTag {
+    pub const ALL: [ProductTag; 8] = [
+        ProductTag::Vegetarian,
+        ProductTag::Vegan,
+        ProductTag::Halal,
+        ProductTag::GlutenFree,
+        ProductTag::ContainsNuts,
+        ProductTag::ContainsDairy,
+        ProductTag::ContainsShellfish,
+        ProductTag::AgeRestricted,
+    ];
+    /// Consumers filter these out instead of asking for them.
+    pub fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            ProductTag::ContainsNuts
+                | ProductTag::ContainsDairy
+                | ProductTag::ContainsShellfish
+                | ProductTag::AgeRestricted
+        )
+    }
+    /// Language key of the badge shown on products.
+    pub fn translation_key(&self) -> &'static str {
+        match self {
+            ProductTag::Vegetarian => "product_tag_vegetarian",
+            ProductTag::Vegan => "product_tag_vegan",
+            ProductTag::Halal => "product_tag_halal",
+            ProductTag::GlutenFree => "product_tag_gluten_free",
+            ProductTag::ContainsNuts => "product_tag_contains_nuts",
+            ProductTag::ContainsDairy => "product_tag_contains_dairy",
+            ProductTag::ContainsShellfish => "product_tag_contains_shellfish",
+            ProductTag::AgeRestricted => "product_tag_age_restricted",
+        }
+    }
+    /// Language key of the consumer filter, "Nut free" rather than "Contains nuts".
+    pub fn filter_translation_key(&self) -> &'static str {
+        match self {
+            ProductTag::ContainsNuts => "product_filter_no_nuts",
+            ProductTag::ContainsDairy => "product_filter_no_dairy",
+            ProductTag::ContainsShellfish => "product_filter_no_shellfish",
+            ProductTag::AgeRestricted => "product_filter_no_age_restricted",
+            _ => self.translation_key(),
+        }
+    }
+}
+
+/// Dietary filter picked by the consumer. Products need every requested tag,
+/// none of the excluded warnings and, when `mild_only` is set, no spice.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagFilter {
+    pub required: Vec<ProductTag>,
+    pub excluded: Vec<ProductTag>,
+    pub mild_only: bool,
+}
+impl TagFilter {
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.excluded.is_empty() && !self.mild_only
+    }
+    pub fn is_selected(&self, tag: ProductTag) -> bool {
+        self.required.contains(&tag) || self.excluded.contains(&tag)
+    }
+    /// Turns the filter for `tag` on or off, warnings are excluded and the rest required.
+    pub fn toggle(&mut self, tag: ProductTag) {
+        let list = if tag.is_warning() {
+            &mut self.excluded
+        } else {
+            &mut self.required
+        };
+        match list.iter().position(|selected| *selected == tag) {
+            Some(i) => {
+                list.remove(i);
+            }
+            None => list.push(tag),
+        }
+    }
+    pub fn matches(&self, product: &ProductItem) -> bool {
+        let tags = product.tags();
+        self.required.iter().all(|tag| tags.contains(tag))
+            && !self.excluded.iter().any(|tag| tags.contains(tag))
+            && (!self.mild_only || product.spice_level() == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Money;
+
+    #[test]
+    fn filter_requires_diets_and_excludes_warnings() {
+        let mut curry = ProductItem::new(
+            0,
+            "Curry".into(),
+            Money::srd(6000),
+            "".into(),
+            "mains".into(),
+        );
+        curry.set_tags(vec![ProductTag::Vegan, ProductTag::ContainsNuts]);
+        curry.set_spice_level(5);
+        assert_eq!(curry.spice_level(), MAX_SPICE_LEVEL);
+
+        let mut filter = TagFilter::default();
+        assert!(filter.matches(&curry));
+        filter.toggle(ProductTag::Vegan);
+        assert!(filter.matches(&curry));
+        filter.toggle(ProductTag::ContainsNuts);
+        assert_eq!(filter.excluded, vec![ProductTag::ContainsNuts]);
+        assert!(!filter.matches(&curry));
+        filter.toggle(ProductTag::ContainsNuts);
+        filter.mild_only = true;
+        assert!(!filter.matches(&curry));
+        filter.mild_only = false;
+        filter.toggle(ProductTag::Halal);
+        assert!(!filter.matches(&curry));
+        filter.toggle(ProductTag::Halal);
+        filter.toggle(ProductTag::Vegan);
+        assert!(filter.is_empty());
+    }
+}
@@ -1,26 +1,30 @@
-use nostro2::notes::NostrNote;
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashMap,
-    f64,
-    hash::{DefaultHasher, Hash, Hasher},
-};
+use std::collections::{HashMap, HashSet};
 use web_sys::wasm_bindgen::JsValue;
 
 use nostr_minions::browser_api::IdbStoreManager;
 use nostr_minions::key_manager::UserIdentity;
 
 use super::{
-    nostr_kinds::NOSTR_KIND_COMMERCE_PRODUCTS, DB_NAME_FUENTE, DB_VERSION_FUENTE,
-    STORE_NAME_PRODUCT_LISTS,
+    add_geohash_tags, menu_chunk_category,
+    nostr_kinds::{NOSTR_KIND_COMMERCE_MENU_CATEGORY, NOSTR_KIND_COMMERCE_PRODUCTS},
+    within_hours, CoordinateStrings, MenuChunkRef, MenuIndex, Money, OpeningHours, ProductTag,
+    DB_NAME_FUENTE, DB_VERSION_FUENTE, MAX_SPICE_LEVEL, STORE_NAME_PRODUCT_LISTS,
 };
 
+/// Random id for products, categories and their options, so renaming or
+/// moving them keeps the references carts, favorites and order history hold.
+fn new_entry_id() -> String {
+    NostrKeypair::generate(false).public_key()[..16].to_string()
+}
+
 #[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub struct ProductSide {
     id: String,
     order: usize,
     name: String,
-    price: String,
+    price: Money,
 }
 impl ToString for ProductSide {
     fn to_string(&self) -> String {
@@ -33,6 +37,133 @@ impl TryFrom<String> for ProductSide {
         Ok(serde_json::from_str(&s)?)
     }
 }
+impl ProductSide {
+    pub fn new(order: usize, name: String, price: Money) -> Self {
+        Self {
+            id: new_entry_id(),
+            order,
+            name,
+            price,
+        }
+    }
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+    /// Price delta added to the product price, can be zero or negative.
+    pub fn price(&self) -> Money {
+        self.price
+    }
+    pub fn order(&self) -> usize {
+        self.order
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize, Deserialize)]
+pub struct ModifierGroup {
+    id: String,
+    name: String,
+    required: bool,
+    min_selections: u32,
+    max_selections: u32,
+    options: Vec<ProductSide>,
+}
+impl ToString for ModifierGroup {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<String> for ModifierGroup {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+impl ModifierGroup {
+    pub fn new(
+        name: String,
+        required: bool,
+        min_selections: u32,
+        max_selections: u32,
+        options: Vec<ProductSide>,
+    ) -> Self {
+        let min_selections = if required {
+            min_selections.max(1)
+        } else {
+            min_selections
+        };
+        Self {
+            id: new_entry_id(),
+            name,
+            required,
+            min_selections,
+            max_selections: max_selections.max(min_selections).max(1),
+            options,
+        }
+    }
+    /// Takes over the ids of the group being edited, and of its options by
+    /// name, so carts and past orders still find them.
+    pub fn replacing(mut self, previous: &ModifierGroup) -> Self {
+        self.id = previous.id.clone();
+        for side in self.options.iter_mut() {
+            if let Some(kept) = previous.options.iter().find(|kept| kept.name == side.name) {
+                side.id = kept.id.clone();
+            }
+        }
+        self
+    }
+    pub fn id(&self) -> String {
+        self.id.clone()
+    }
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+    pub fn required(&self) -> bool {
+        self.required
+    }
+    pub fn min_selections(&self) -> u32 {
+        self.min_selections
+    }
+    pub fn max_selections(&self) -> u32 {
+        self.max_selections
+    }
+    pub fn options(&self) -> Vec<ProductSide> {
+        let mut options = self.options.clone();
+        options.sort_by_key(|side| side.order);
+        options
+    }
+    pub fn rules_display(&self) -> String {
+        match (self.min_selections, self.max_selections) {
+            (0, max) => format!("Optional, up to {}", max),
+            (min, max) if min == max => format!("Choose {}", min),
+            (min, max) => format!("Choose {} to {}", min, max),
+        }
+    }
+    /// Checks that the sides picked from this group respect its min/max selections.
+    pub fn check_selection(&self, selected: &[ProductSide]) -> anyhow::Result<()> {
+        let count = selected
+            .iter()
+            .filter(|side| self.options.iter().any(|option| option.id == side.id))
+            .count() as u32;
+        if count < self.min_selections {
+            return Err(anyhow::anyhow!(
+                "Choose at least {} for {}",
+                self.min_selections,
+                self.name
+            ));
+        }
+        if count > self.max_selections {
+            return Err(anyhow::anyhow!(
+                "Choose at most {} for {}",
+                self.max_selections,
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq, Serialize, Deserialize)]
 pub struct ProductItem {
@@ -40,9 +171,12 @@ pub struct ProductItem {
     name: String,
     #[serde(default)]
     sku: Option<String>,
-    price: String,
+    price: Money,
     #[serde(default)]
-    discount: Option<String>,
+    discount: Option<Money>,
+    /// Weekly windows the discount applies in, empty when it always applies.
+    #[serde(default)]
+    discount_hours: Vec<OpeningHours>,
     order: usize,
     category: String,
     #[serde(default)]
@@ -53,18 +187,35 @@ pub struct ProductItem {
     #[serde(default)]
     thumbnail_url: Option<String>,
     sides: Vec<ProductSide>,
+    #[serde(default)]
+    modifier_groups: Vec<ModifierGroup>,
+    /// Sides picked by the consumer, only set on items inside a `ProductOrder`.
+    #[serde(default)]
+    selected_sides: Vec<ProductSide>,
+    /// Switched off by the commerce to take the product off sale without deleting it.
+    #[serde(default = "available_by_default")]
+    available: bool,
+    /// Units left when the menu was published, `None` when stock isn't tracked.
+    #[serde(default)]
+    stock: Option<u32>,
+    #[serde(default)]
+    tags: Vec<ProductTag>,
+    /// From 0, not spicy, up to `MAX_SPICE_LEVEL`.
+    #[serde(default)]
+    spice_level: u8,
+}
+fn available_by_default() -> bool {
+    true
 }
 impl ProductItem {
     pub fn new(
         order: usize,
         name: String,
-        price: String,
+        price: Money,
         description: String,
         category: String,
     ) -> Self {
-        let mut hasher = DefaultHasher::new();
-        format!("{}{}", name, category).hash(&mut hasher);
-        let id = hasher.finish().to_string();
+        let id = new_entry_id();
         let sku = Some(format!("SKU-{}", id[..6].to_uppercase()));
         Self {
             id,
@@ -73,12 +224,19 @@ impl ProductItem {
             price,
             order,
             discount: None,
+            discount_hours: vec![],
             category,
             description,
             details: String::new(),
             image_url: None,
             thumbnail_url: None,
             sides: vec![],
+            modifier_groups: vec![],
+            selected_sides: vec![],
+            available: true,
+            stock: None,
+            tags: vec![],
+            spice_level: 0,
         }
     }
     // Add new getter methods
@@ -108,19 +266,66 @@ impl ProductItem {
     pub fn name(&self) -> String {
         self.name.clone()
     }
-    pub fn price(&self) -> String {
-        let base = self.price.parse::<f64>().unwrap_or(0.0);
-
-        if let Some(discount) = &self.discount {
-            let disc = discount.parse::<f64>().unwrap_or(0.0);
-            return format!("{:.2}", base - disc);
+    /// Price with the discount taken off. Scheduled discounts only count once
+    /// the item is priced for a moment with `priced_at`, a discount in another
+    /// currency than the price is left out.
+    pub fn price(&self) -> Money {
+        self.checked_price().unwrap_or(self.price)
+    }
+    /// Price with the discount taken off, failing on a discount in another currency.
+    pub fn checked_price(&self) -> anyhow::Result<Money> {
+        match self.discount {
+            Some(discount) if self.discount_hours.is_empty() => self.price.checked_sub(discount),
+            _ => Ok(self.price),
         }
-
-        // Return original base price if no discount
-        format!("{:.2}", base)
     }
-    pub fn discount(&self) -> Option<String> {
-        self.discount.clone()
+    /// Fails unless the price, discount and every side share one currency.
+    pub fn check_currency(&self) -> anyhow::Result<()> {
+        self.sides
+            .iter()
+            .chain(self.selected_sides.iter())
+            .chain(
+                self.modifier_groups
+                    .iter()
+                    .flat_map(|group| group.options.iter()),
+            )
+            .map(|side| side.price)
+            .chain(self.discount)
+            .find(|amount| !amount.is_zero() && amount.currency() != self.price.currency())
+            .map_or(Ok(()), |amount| {
+                Err(anyhow::anyhow!(
+                    "{} is priced in {} but has amounts in {}",
+                    self.name,
+                    self.price.currency(),
+                    amount.currency()
+                ))
+            })
+    }
+    pub fn original_price(&self) -> Money {
+        self.price
+    }
+    pub fn discount(&self) -> Option<Money> {
+        self.discount
+    }
+    pub fn discount_hours(&self) -> Vec<OpeningHours> {
+        self.discount_hours.clone()
+    }
+    pub fn set_discount_hours(&mut self, hours: Vec<OpeningHours>) {
+        self.discount_hours = hours;
+    }
+    pub fn has_scheduled_discount(&self) -> bool {
+        self.discount.is_some() && !self.discount_hours.is_empty()
+    }
+    /// The item as sold at `timestamp`, with a scheduled discount kept only inside its window.
+    pub fn priced_at(&self, timestamp: i64, utc_offset_minutes: i32) -> ProductItem {
+        let mut item = self.clone();
+        if !item.discount_hours.is_empty()
+            && !within_hours(&item.discount_hours, timestamp, utc_offset_minutes)
+        {
+            item.discount = None;
+        }
+        item.discount_hours.clear();
+        item
     }
     pub fn id(&self) -> String {
         self.id.clone()
@@ -134,6 +339,44 @@ impl ProductItem {
     pub fn add_side(&mut self, side: ProductSide) {
         self.sides.push(side);
     }
+    pub fn modifier_groups(&self) -> Vec<ModifierGroup> {
+        self.modifier_groups.clone()
+    }
+    pub fn has_modifiers(&self) -> bool {
+        !self.modifier_groups.is_empty()
+    }
+    pub fn set_modifier_groups(&mut self, groups: Vec<ModifierGroup>) {
+        self.modifier_groups = groups;
+    }
+    pub fn selected_sides(&self) -> Vec<ProductSide> {
+        self.selected_sides.clone()
+    }
+    /// Returns the item as an order line with the given sides, checking every modifier group.
+    pub fn with_sides(&self, side_ids: &[String]) -> anyhow::Result<ProductItem> {
+        let selected: Vec<ProductSide> = self
+            .modifier_groups
+            .iter()
+            .flat_map(|group| group.options.iter())
+            .filter(|side| side_ids.contains(&side.id))
+            .cloned()
+            .collect();
+        if selected.len() != side_ids.len() {
+            return Err(anyhow::anyhow!("Unknown side selected"));
+        }
+        for group in self.modifier_groups.iter() {
+            group.check_selection(&selected)?;
+        }
+        let mut item = self.clone();
+        item.selected_sides = selected;
+        Ok(item)
+    }
+    /// Unit price including the price deltas of the selected sides, sides in
+    /// another currency than the price are left out.
+    pub fn price_with_sides(&self) -> Money {
+        self.selected_sides.iter().fold(self.price(), |unit, side| {
+            unit.checked_add(side.price).unwrap_or(unit)
+        })
+    }
     pub fn set_image_url(&mut self, url: String) {
         self.image_url = Some(url);
     }
@@ -146,10 +389,10 @@ impl ProductItem {
     pub fn set_details(&mut self, details: String) {
         self.details = details;
     }
-    pub fn set_discount(&mut self, discount: Option<String>) {
+    pub fn set_discount(&mut self, discount: Option<Money>) {
         self.discount = discount;
     }
-    pub fn set_price(&mut self, price: String) {
+    pub fn set_price(&mut self, price: Money) {
         self.price = price;
     }
     pub fn set_description(&mut self, description: String) {
@@ -158,6 +401,40 @@ impl ProductItem {
     pub fn set_name(&mut self, name: String) {
         self.name = name;
     }
+    pub fn is_available(&self) -> bool {
+        self.available
+    }
+    pub fn set_available(&mut self, available: bool) {
+        self.available = available;
+    }
+    pub fn stock(&self) -> Option<u32> {
+        self.stock
+    }
+    pub fn set_stock(&mut self, stock: Option<u32>) {
+        self.stock = stock;
+    }
+    pub fn tags(&self) -> Vec<ProductTag> {
+        self.tags.clone()
+    }
+    pub fn set_tags(&mut self, tags: Vec<ProductTag>) {
+        self.tags = tags;
+    }
+    pub fn spice_level(&self) -> u8 {
+        self.spice_level
+    }
+    pub fn set_spice_level(&mut self, level: u8) {
+        self.spice_level = level.min(MAX_SPICE_LEVEL);
+    }
+    pub fn is_sold_out(&self) -> bool {
+        !self.available || self.stock == Some(0)
+    }
+    /// Whether `quantity` units can be sold once `sold` units are already taken.
+    pub fn can_supply(&self, quantity: u32, sold: u32) -> bool {
+        self.available
+            && self
+                .stock
+                .is_none_or(|stock| quantity <= stock.saturating_sub(sold))
+    }
 }
 impl TryFrom<String> for ProductItem {
     type Error = anyhow::Error;
@@ -176,21 +453,29 @@ pub struct ProductCategory {
     name: String,
     order: usize,
     products: Vec<ProductItem>,
+    /// Weekly windows the category can be ordered in, empty when always available.
+    #[serde(default)]
+    hours: Vec<OpeningHours>,
 }
 impl ToString for ProductCategory {
     fn to_string(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
 }
+impl TryFrom<String> for ProductCategory {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
 impl ProductCategory {
     pub fn new(order: usize, name: String) -> Self {
-        let mut hasher = DefaultHasher::new();
-        format!("{}", name).hash(&mut hasher);
         ProductCategory {
-            id: hasher.finish().to_string(),
+            id: new_entry_id(),
             name,
             order,
             products: vec![],
+            hours: vec![],
         }
     }
     pub fn add_product(&mut self, product: ProductItem) {
@@ -220,14 +505,127 @@ impl ProductCategory {
         products.sort_by(|a, b| a.order.cmp(&b.order));
         products
     }
+    pub fn hours(&self) -> Vec<OpeningHours> {
+        self.hours.clone()
+    }
+    pub fn set_hours(&mut self, hours: Vec<OpeningHours>) {
+        self.hours = hours;
+    }
+    pub fn is_available_at(&self, timestamp: i64, utc_offset_minutes: i32) -> bool {
+        self.hours.is_empty() || within_hours(&self.hours, timestamp, utc_offset_minutes)
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OrderLine {
+    product_id: String,
+    name: String,
+    category: String,
+    /// Unit price when the order was placed, sides excluded.
+    price: Money,
+    quantity: u32,
+    #[serde(default)]
+    sides: Vec<ProductSide>,
+    #[serde(default)]
+    note: Option<String>,
+    /// Who picked the line in a group order.
+    #[serde(default)]
+    contributor: Option<String>,
+}
+impl From<&ProductItem> for OrderLine {
+    fn from(product: &ProductItem) -> Self {
+        Self {
+            product_id: product.id.clone(),
+            name: product.name.clone(),
+            category: product.category.clone(),
+            price: product.price(),
+            quantity: 1,
+            sides: product.selected_sides.clone(),
+            note: None,
+            contributor: None,
+        }
+    }
 }
+impl OrderLine {
+    pub fn product_id(&self) -> String {
+        self.product_id.clone()
+    }
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+    pub fn category_id(&self) -> String {
+        self.category.clone()
+    }
+    pub fn quantity(&self) -> u32 {
+        self.quantity
+    }
+    pub fn sides(&self) -> Vec<ProductSide> {
+        self.sides.clone()
+    }
+    pub fn sides_display(&self) -> String {
+        self.sides
+            .iter()
+            .map(|side| side.name.clone())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+    pub fn note(&self) -> Option<String> {
+        self.note.clone()
+    }
+    pub fn contributor(&self) -> Option<String> {
+        self.contributor.clone()
+    }
+    /// Unit price including the price deltas of the selected sides.
+    pub fn unit_price(&self) -> Money {
+        self.price + self.sides.iter().map(|side| side.price).sum()
+    }
+    pub fn subtotal(&self) -> Money {
+        self.unit_price() * self.quantity
+    }
+    /// Subtotal that fails instead of panicking on mixed currencies.
+    pub fn checked_subtotal(&self) -> anyhow::Result<Money> {
+        self.sides
+            .iter()
+            .try_fold(self.price, |unit, side| unit.checked_add(side.price))?
+            .checked_mul(self.quantity)
+    }
+    /// Lines are the same item when product, sides, note and contributor
+    /// match, regardless of quantity.
+    pub fn is_same_item(&self, other: &OrderLine) -> bool {
+        self.product_id == other.product_id
+            && self.sides == other.sides
+            && self.note == other.note
+            && self.contributor == other.contributor
+    }
+}
+
+/// Orders used to carry one full `ProductItem` per unit.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProductOrderRepr {
+    Lines { lines: Vec<OrderLine> },
+    Legacy { products: Vec<ProductItem> },
+}
+impl From<ProductOrderRepr> for ProductOrder {
+    fn from(repr: ProductOrderRepr) -> Self {
+        match repr {
+            ProductOrderRepr::Lines { lines } => Self { lines },
+            ProductOrderRepr::Legacy { products } => {
+                let mut order = Self::default();
+                products.iter().for_each(|product| order.add(product));
+                order
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(from = "ProductOrderRepr")]
 pub struct ProductOrder {
-    products: Vec<ProductItem>,
+    lines: Vec<OrderLine>,
 }
 impl Default for ProductOrder {
     fn default() -> Self {
-        Self { products: vec![] }
+        Self { lines: vec![] }
     }
 }
 impl ToString for ProductOrder {
@@ -242,43 +640,148 @@ impl TryFrom<String> for ProductOrder {
     }
 }
 impl ProductOrder {
-    pub fn new(products: Vec<ProductItem>) -> Self {
-        Self { products }
+    pub fn new(lines: Vec<OrderLine>) -> Self {
+        Self { lines }
     }
-    pub fn products(&self) -> Vec<ProductItem> {
-        self.products.clone()
-    }
-    pub fn counted_products(&self) -> Vec<(ProductItem, u32)> {
-        let mut counted_products = HashMap::new();
-        self.products.iter().for_each(|product| {
-            let count = counted_products.entry(product.clone()).or_insert(0);
-            *count += 1;
-        });
-        let mut products: Vec<(ProductItem, u32)> = counted_products.into_iter().collect();
-        products.sort_by(|a, b| a.0.order.cmp(&b.0.order));
-        products
+    pub fn lines(&self) -> Vec<OrderLine> {
+        self.lines.clone()
     }
-    pub fn total(&self) -> f64 {
-        self.products
-            .iter()
-            .map(|p| p.price().parse::<f64>().unwrap_or(0.0))
-            .sum()
+    pub fn item_count(&self) -> u32 {
+        self.lines.iter().map(|line| line.quantity).sum()
+    }
+    /// Units per product id, summed over lines with different sides.
+    pub fn quantities(&self) -> HashMap<String, u32> {
+        let mut quantities = HashMap::new();
+        for line in self.lines.iter() {
+            *quantities.entry(line.product_id()).or_insert(0) += line.quantity;
+        }
+        quantities
+    }
+    pub fn total(&self) -> Money {
+        self.lines.iter().map(|line| line.subtotal()).sum()
+    }
+    /// Total of an order received from someone else, failing on mixed
+    /// currencies where `total` would panic.
+    pub fn checked_total(&self) -> anyhow::Result<Money> {
+        self.lines.iter().try_fold(Money::default(), |total, line| {
+            total.checked_add(line.checked_subtotal()?)
+        })
     }
     pub fn is_empty(&self) -> bool {
-        self.products.is_empty()
+        self.lines.is_empty()
     }
-    pub fn add(&mut self, product: ProductItem) {
-        self.products.push(product);
+    /// Adds one unit of the product, with the sides selected on it.
+    pub fn add(&mut self, product: &ProductItem) {
+        self.add_line(OrderLine::from(product));
     }
-    pub fn remove_one(&mut self, product_id: String) {
-        if let Some(i) = self.products.iter().position(|p| p.id == product_id) {
-            self.products.remove(i);
+    pub fn add_line(&mut self, line: OrderLine) {
+        match self.lines.iter_mut().find(|l| l.is_same_item(&line)) {
+            Some(existing) => existing.quantity += line.quantity,
+            None => self.lines.push(line),
         }
     }
-    pub fn remove_all(&mut self, product_id: String) {
-        self.products.retain(|p| p.id != product_id);
+    pub fn add_one(&mut self, line: &OrderLine) {
+        if let Some(existing) = self.lines.iter_mut().find(|l| l.is_same_item(line)) {
+            existing.quantity += 1;
+        }
+    }
+    pub fn remove_one(&mut self, line: &OrderLine) {
+        if let Some(i) = self.lines.iter().position(|l| l.is_same_item(line)) {
+            if self.lines[i].quantity > 1 {
+                self.lines[i].quantity -= 1;
+            } else {
+                self.lines.remove(i);
+            }
+        }
+    }
+    pub fn remove_line(&mut self, line: &OrderLine) {
+        self.lines.retain(|l| !l.is_same_item(line));
+    }
+    /// Adds every line of `other`, tagged with the person who picked it.
+    pub fn add_contribution(&mut self, contributor: &str, other: &ProductOrder) {
+        for line in other.lines.iter() {
+            self.add_line(OrderLine {
+                contributor: Some(contributor.to_string()),
+                ..line.clone()
+            });
+        }
+    }
+    /// The lines picked by `contributor` in a group order.
+    pub fn contributed_by(&self, contributor: &str) -> ProductOrder {
+        let lines = self
+            .lines
+            .iter()
+            .filter(|line| line.contributor.as_deref() == Some(contributor))
+            .cloned()
+            .collect();
+        Self { lines }
+    }
+    /// The lines left once those picked by any of `contributors` are taken out.
+    pub fn without_contributions(&self, contributors: &[String]) -> ProductOrder {
+        let lines = self
+            .lines
+            .iter()
+            .filter(|line| {
+                line.contributor
+                    .as_ref()
+                    .is_none_or(|contributor| !contributors.contains(contributor))
+            })
+            .cloned()
+            .collect();
+        Self { lines }
     }
+    pub fn set_note(&mut self, line: &OrderLine, note: Option<String>) {
+        let note = note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+        if let Some(existing) = self.lines.iter_mut().find(|l| l.is_same_item(line)) {
+            existing.note = note;
+        }
+    }
+}
+/// What changed for a line of a past order when it's ordered again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReorderChange {
+    /// Off the menu, sold out or not served at the moment.
+    Removed { name: String },
+    /// The options changed so much the product has to be picked again.
+    OptionsChanged { name: String },
+    /// Options picked last time that are no longer offered.
+    SidesDropped { name: String, sides: Vec<String> },
+    PriceChanged {
+        name: String,
+        was: Money,
+        now: Money,
+    },
+}
+
+/// A past order rebuilt against the current menu.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Reorder {
+    pub order: ProductOrder,
+    pub changes: Vec<ReorderChange>,
 }
+/// Difference between two versions of a menu, shown before publishing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuChange {
+    Added {
+        category: String,
+        name: String,
+    },
+    Updated {
+        category: String,
+        name: String,
+        fields: Vec<&'static str>,
+    },
+    Removed {
+        category: String,
+        name: String,
+    },
+    Hours {
+        category: String,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProductMenu {
     categories: Vec<ProductCategory>,
@@ -316,6 +819,286 @@ impl ProductMenu {
             category.remove_product(product_id.to_string());
         }
     }
+    pub fn set_category_hours(&mut self, category_id: &str, hours: Vec<OpeningHours>) {
+        if let Some(category) = self.categories.iter_mut().find(|c| c.id == category_id) {
+            category.hours = hours;
+        }
+    }
+    /// The menu as it can be ordered at `timestamp`: categories outside their
+    /// hours are left out and scheduled discounts are applied or dropped.
+    pub fn at(&self, timestamp: i64, utc_offset_minutes: i32) -> ProductMenu {
+        let categories = self
+            .categories
+            .iter()
+            .filter(|category| category.is_available_at(timestamp, utc_offset_minutes))
+            .map(|category| {
+                let mut category = category.clone();
+                category.products = category
+                    .products
+                    .iter()
+                    .map(|product| product.priced_at(timestamp, utc_offset_minutes))
+                    .collect();
+                category
+            })
+            .collect();
+        ProductMenu { categories }
+    }
+    /// The order with every line rebuilt from the menu as it stands at
+    /// `timestamp`. Fails when a product can't be ordered at that time or its
+    /// options don't follow the menu's rules.
+    pub fn price_order(
+        &self,
+        order: &ProductOrder,
+        timestamp: i64,
+        utc_offset_minutes: i32,
+    ) -> anyhow::Result<ProductOrder> {
+        let menu = self.at(timestamp, utc_offset_minutes);
+        let mut priced = order.clone();
+        for line in priced.lines.iter_mut() {
+            let product = menu.find_product(&line.product_id).ok_or(anyhow::anyhow!(
+                "{} can't be ordered at this time",
+                line.name
+            ))?;
+            let side_ids: Vec<String> = line.sides.iter().map(|side| side.id()).collect();
+            let item = product
+                .with_sides(&side_ids)
+                .map_err(|e| anyhow::anyhow!("{}: {}", line.name, e))?;
+            item.check_currency()?;
+            *line = OrderLine {
+                price: item.checked_price()?,
+                quantity: line.quantity,
+                note: line.note.clone(),
+                contributor: line.contributor.clone(),
+                ..OrderLine::from(&item)
+            };
+            line.checked_subtotal()?;
+        }
+        Ok(priced)
+    }
+    /// Rebuilds `past` with the products, sides and prices of this menu,
+    /// listing every line that couldn't be ordered as it was.
+    pub fn reorder(&self, past: &ProductOrder) -> Reorder {
+        let mut reorder = Reorder::default();
+        for line in past.lines.iter() {
+            let name = line.name.clone();
+            let Some(product) = self
+                .find_product(&line.product_id)
+                .filter(|product| !product.is_sold_out())
+            else {
+                reorder.changes.push(ReorderChange::Removed { name });
+                continue;
+            };
+            let offered: Vec<ProductSide> = product
+                .modifier_groups
+                .iter()
+                .flat_map(|group| group.options.iter().cloned())
+                .collect();
+            let (kept, dropped): (Vec<ProductSide>, Vec<ProductSide>) = line
+                .sides
+                .iter()
+                .cloned()
+                .partition(|side| offered.iter().any(|option| option.id == side.id));
+            let kept_ids: Vec<String> = kept.iter().map(|side| side.id()).collect();
+            let Ok(item) = product.with_sides(&kept_ids) else {
+                reorder.changes.push(ReorderChange::OptionsChanged { name });
+                continue;
+            };
+            if !dropped.is_empty() {
+                reorder.changes.push(ReorderChange::SidesDropped {
+                    name: name.clone(),
+                    sides: dropped.iter().map(|side| side.name()).collect(),
+                });
+            }
+            let mut new_line = OrderLine::from(&item);
+            new_line.quantity = line.quantity;
+            new_line.note = line.note.clone();
+            if new_line.unit_price() != line.unit_price() {
+                reorder.changes.push(ReorderChange::PriceChanged {
+                    name,
+                    was: line.unit_price(),
+                    now: new_line.unit_price(),
+                });
+            }
+            reorder.order.add_line(new_line);
+        }
+        reorder
+    }
+    pub fn find_product(&self, product_id: &str) -> Option<ProductItem> {
+        self.categories
+            .iter()
+            .flat_map(|category| category.products.iter())
+            .find(|product| product.id == product_id)
+            .cloned()
+    }
+    /// Fails unless every amount on the menu is in one currency.
+    pub fn check_currency(&self) -> anyhow::Result<()> {
+        let mut currency = None;
+        for product in self.categories.iter().flat_map(|c| c.products.iter()) {
+            product.check_currency()?;
+            if product.price.is_zero() {
+                continue;
+            }
+            match currency {
+                Some(currency) if currency != product.price.currency() => {
+                    return Err(anyhow::anyhow!(
+                        "Menu has prices in {} and {}",
+                        currency,
+                        product.price.currency()
+                    ));
+                }
+                _ => currency = Some(product.price.currency()),
+            }
+        }
+        Ok(())
+    }
+    /// Checks the order against availability and stock, `sold` holds the units
+    /// already taken per product id since the menu was published.
+    pub fn check_stock(
+        &self,
+        order: &ProductOrder,
+        sold: &HashMap<String, u32>,
+    ) -> anyhow::Result<()> {
+        for (product_id, quantity) in order.quantities() {
+            let product = self
+                .find_product(&product_id)
+                .ok_or(anyhow::anyhow!("Product no longer on the menu"))?;
+            let sold = sold.get(&product_id).copied().unwrap_or_default();
+            if !product.can_supply(quantity, sold) {
+                return Err(anyhow::anyhow!("{} is sold out", product.name));
+            }
+        }
+        Ok(())
+    }
+    /// Units left of every product with a stock count once `sold` are taken.
+    pub fn remaining_stock(&self, sold: &HashMap<String, u32>) -> HashMap<String, u32> {
+        self.categories
+            .iter()
+            .flat_map(|category| category.products.iter())
+            .filter_map(|product| {
+                let sold = sold.get(&product.id).copied().unwrap_or_default();
+                Some((product.id.clone(), product.stock?.saturating_sub(sold)))
+            })
+            .collect()
+    }
+    /// The menu with its stock counts lowered to the units the server says are left.
+    pub fn with_remaining_stock(&self, remaining: &HashMap<String, u32>) -> ProductMenu {
+        let mut menu = self.clone();
+        for product in menu
+            .categories
+            .iter_mut()
+            .flat_map(|category| category.products.iter_mut())
+        {
+            if let (Some(stock), Some(left)) = (product.stock, remaining.get(&product.id)) {
+                product.stock = Some(stock.min(*left));
+            }
+        }
+        menu
+    }
+    /// Menus from before ids were random derived them from names, so equally named
+    /// categories or products could share one. Gives the duplicates a fresh id and
+    /// returns whether the menu changed and has to be republished.
+    pub fn migrate_ids(&mut self) -> bool {
+        let mut changed = false;
+        let mut category_ids = HashSet::new();
+        let mut product_ids = HashSet::new();
+        for category in self.categories.iter_mut() {
+            if !category_ids.insert(category.id.clone()) {
+                category.id = new_entry_id();
+                category_ids.insert(category.id.clone());
+                changed = true;
+            }
+            for product in category.products.iter_mut() {
+                if product.category != category.id {
+                    product.category = category.id.clone();
+                    changed = true;
+                }
+                if !product_ids.insert(product.id.clone()) {
+                    product.id = new_entry_id();
+                    product_ids.insert(product.id.clone());
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+    /// Products added, edited or removed since `previous`, matched by id.
+    pub fn changes_from(&self, previous: &ProductMenu) -> Vec<MenuChange> {
+        let mut changes = vec![];
+        for category in self.categories() {
+            let hours_changed = previous
+                .categories
+                .iter()
+                .find(|old| old.id == category.id)
+                .is_some_and(|old| old.hours != category.hours);
+            if hours_changed {
+                changes.push(MenuChange::Hours {
+                    category: category.name(),
+                });
+            }
+            for product in category.products() {
+                let Some(old) = previous.find_product(&product.id) else {
+                    changes.push(MenuChange::Added {
+                        category: category.name(),
+                        name: product.name(),
+                    });
+                    continue;
+                };
+                let mut fields = vec![];
+                if old.category != product.category {
+                    fields.push("category");
+                }
+                if old.name != product.name {
+                    fields.push("name");
+                }
+                if old.sku != product.sku {
+                    fields.push("sku");
+                }
+                if old.price != product.price
+                    || old.discount != product.discount
+                    || old.discount_hours != product.discount_hours
+                {
+                    fields.push("price");
+                }
+                if old.description != product.description {
+                    fields.push("description");
+                }
+                if old.details != product.details {
+                    fields.push("details");
+                }
+                if old.image_url != product.image_url || old.thumbnail_url != product.thumbnail_url
+                {
+                    fields.push("image_url");
+                }
+                if old.modifier_groups != product.modifier_groups {
+                    fields.push("sides");
+                }
+                if old.available != product.available || old.stock != product.stock {
+                    fields.push("stock");
+                }
+                if old.tags != product.tags || old.spice_level != product.spice_level {
+                    fields.push("tags");
+                }
+                if !fields.is_empty() {
+                    changes.push(MenuChange::Updated {
+                        category: category.name(),
+                        name: product.name(),
+                        fields,
+                    });
+                }
+            }
+        }
+        for category in previous.categories() {
+            for product in category.products() {
+                if self.find_product(&product.id).is_none() {
+                    changes.push(MenuChange::Removed {
+                        category: category.name(),
+                        name: product.name(),
+                    });
+                }
+            }
+        }
+        changes
+    }
     pub fn new() -> Self {
         Self { categories: vec![] }
     }
@@ -331,6 +1114,7 @@ impl ToString for ProductMenu {
         serde_json::to_string(self).unwrap()
     }
 }
+/// Menus with amounts in more than one currency are refused.
 impl TryFrom<NostrNote> for ProductMenu {
     type Error = anyhow::Error;
     fn try_from(note: NostrNote) -> Result<Self, Self::Error> {
@@ -338,6 +1122,7 @@ impl TryFrom<NostrNote> for ProductMenu {
             return Err(anyhow::anyhow!("Wrong Kind"));
         }
         let product_menu: ProductMenu = note.content.try_into()?;
+        product_menu.check_currency()?;
         Ok(product_menu)
     }
 }
@@ -347,17 +1132,78 @@ pub struct ProductMenuIdb {
     pubkey: String,
     menu: ProductMenu,
     note: NostrNote,
+    /// Category events of a chunked menu, empty for menus published as a single event.
+    #[serde(default)]
+    chunks: Vec<NostrNote>,
 }
 impl ProductMenuIdb {
-    pub async fn new(menu: ProductMenu, user_keys: &UserIdentity) -> Self {
+    /// Signs the menu, tagging it with the geohashes of the commerce `location`.
+    pub async fn new(
+        menu: ProductMenu,
+        location: &CoordinateStrings,
+        user_keys: &UserIdentity,
+    ) -> Self {
+        Self::signed(menu, &[], location, user_keys).await
+    }
+    /// Signs the edited menu, reusing the events of the categories that did not change.
+    pub async fn update(
+        &self,
+        menu: ProductMenu,
+        location: &CoordinateStrings,
+        user_keys: &UserIdentity,
+    ) -> Self {
+        Self::signed(menu, &self.chunks, location, user_keys).await
+    }
+    async fn signed(
+        menu: ProductMenu,
+        previous: &[NostrNote],
+        location: &CoordinateStrings,
+        user_keys: &UserIdentity,
+    ) -> Self {
         let pubkey = user_keys.get_pubkey().await.expect("no pubkey");
-        let content = menu.to_string();
-        let new_note = NostrNote {
+        let mut chunks = vec![];
+        for category in menu.categories() {
+            let content = category.to_string();
+            let unchanged = previous.iter().find(|chunk| {
+                menu_chunk_category(chunk).as_deref() == Some(category.id.as_str())
+                    && chunk.content == content
+            });
+            let chunk = match unchanged {
+                Some(chunk) => chunk.clone(),
+                None => {
+                    let mut new_chunk = NostrNote {
+                        pubkey: pubkey.clone(),
+                        kind: NOSTR_KIND_COMMERCE_MENU_CATEGORY,
+                        content,
+                        ..Default::default()
+                    };
+                    new_chunk.tags.add_parameter_tag(&category.id);
+                    user_keys
+                        .sign_nostr_note(new_chunk)
+                        .await
+                        .expect("Could not sign note")
+                }
+            };
+            chunks.push(chunk);
+        }
+        let index = MenuIndex {
+            chunks: chunks
+                .iter()
+                .filter_map(|chunk| {
+                    Some(MenuChunkRef {
+                        category_id: menu_chunk_category(chunk)?,
+                        note_id: chunk.id.clone()?,
+                    })
+                })
+                .collect(),
+        };
+        let mut new_note = NostrNote {
             pubkey: pubkey.clone(),
             kind: NOSTR_KIND_COMMERCE_PRODUCTS,
-            content,
+            content: index.to_string(),
             ..Default::default()
         };
+        add_geohash_tags(&mut new_note, location);
         let new_note = user_keys
             .sign_nostr_note(new_note)
             .await
@@ -366,30 +1212,167 @@ impl ProductMenuIdb {
             pubkey: new_note.pubkey.clone(),
             menu,
             note: new_note,
+            chunks,
         }
     }
     pub fn menu(&self) -> ProductMenu {
         self.menu.clone()
     }
+    /// A copy showing the stock the server published, the signed notes stay as they are.
+    pub fn with_remaining_stock(&self, remaining: &HashMap<String, u32>) -> Self {
+        Self {
+            menu: self.menu.with_remaining_stock(remaining),
+            ..self.clone()
+        }
+    }
     pub fn note(&self) -> NostrNote {
         self.note.clone()
     }
     pub fn id(&self) -> String {
         self.pubkey.clone()
     }
+    /// Every event making up the menu, with the index last so it never
+    /// points at categories that were not published yet.
+    pub fn notes(&self) -> Vec<NostrNote> {
+        let mut notes = self.chunks.clone();
+        notes.push(self.note.clone());
+        notes
+    }
+    /// The events that differ from a previously published version of the menu.
+    pub fn changed_notes(&self, previous: Option<&ProductMenuIdb>) -> Vec<NostrNote> {
+        let Some(previous) = previous else {
+            return self.notes();
+        };
+        let mut notes: Vec<NostrNote> = self
+            .chunks
+            .iter()
+            .filter(|chunk| !previous.chunks.iter().any(|old| old.id == chunk.id))
+            .cloned()
+            .collect();
+        notes.push(self.note.clone());
+        notes
+    }
+    /// Ids of the category events the index references but have not arrived yet.
+    pub fn missing_chunks(&self) -> Vec<String> {
+        match MenuIndex::try_from(self.note.content.clone()) {
+            Ok(index) => {
+                let held: Vec<String> = self.chunks.iter().filter_map(|c| c.id.clone()).collect();
+                index.missing(&held)
+            }
+            Err(_) => vec![],
+        }
+    }
+    pub fn is_complete(&self) -> bool {
+        self.missing_chunks().is_empty()
+    }
+    /// Folds a menu event into the entry, be it the index, a category or a
+    /// legacy menu published as a single event. Events that would leave the
+    /// menu with amounts in more than one currency are refused.
+    pub fn apply_note(&mut self, note: NostrNote) -> anyhow::Result<()> {
+        let mut applied = self.clone();
+        applied.fold_note(note)?;
+        applied.menu.check_currency()?;
+        *self = applied;
+        Ok(())
+    }
+    fn fold_note(&mut self, note: NostrNote) -> anyhow::Result<()> {
+        if note.pubkey != self.pubkey {
+            return Err(anyhow::anyhow!("Menu event from another commerce"));
+        }
+        match note.kind {
+            NOSTR_KIND_COMMERCE_PRODUCTS => {
+                if note.created_at < self.note.created_at {
+                    return Ok(());
+                }
+                if MenuIndex::try_from(note.content.clone()).is_err() {
+                    self.menu = ProductMenu::try_from(note.content.clone())?;
+                    self.chunks.clear();
+                }
+                self.note = note;
+            }
+            NOSTR_KIND_COMMERCE_MENU_CATEGORY => {
+                let category_id =
+                    menu_chunk_category(&note).ok_or(anyhow::anyhow!("Category without d-tag"))?;
+                let category = ProductCategory::try_from(note.content.clone())?;
+                ProductMenu {
+                    categories: vec![category],
+                }
+                .check_currency()?;
+                if self.chunks.iter().any(|chunk| {
+                    menu_chunk_category(chunk).as_ref() == Some(&category_id)
+                        && chunk.created_at > note.created_at
+                }) {
+                    return Ok(());
+                }
+                self.chunks
+                    .retain(|chunk| menu_chunk_category(chunk).as_ref() != Some(&category_id));
+                self.chunks.push(note);
+            }
+            _ => return Err(anyhow::anyhow!("Wrong Kind")),
+        }
+        self.assemble();
+        Ok(())
+    }
+    /// Rebuilds the menu from the categories the index lists, in the order they were published.
+    fn assemble(&mut self) {
+        let Ok(index) = MenuIndex::try_from(self.note.content.clone()) else {
+            return;
+        };
+        let categories = index
+            .chunks
+            .iter()
+            .filter_map(|chunk_ref| {
+                let chunk = self
+                    .chunks
+                    .iter()
+                    .find(|chunk| chunk.id.as_ref() == Some(&chunk_ref.note_id))
+                    .or_else(|| {
+                        self.chunks.iter().find(|chunk| {
+                            menu_chunk_category(chunk).as_ref() == Some(&chunk_ref.category_id)
+                        })
+                    })?;
+                ProductCategory::try_from(chunk.content.clone()).ok()
+            })
+            .collect();
+        self.menu = ProductMenu { categories };
+        // Categories the index dropped are gone, newer ones wait for their index
+        let dropped: Vec<String> = self
+            .chunks
+            .iter()
+            .filter(|chunk| {
+                menu_chunk_category(chunk)
+                    .is_some_and(|category_id| index.chunk_for(&category_id).is_none())
+                    && chunk.created_at <= self.note.created_at
+            })
+            .filter_map(|chunk| chunk.id.clone())
+            .collect();
+        self.chunks
+            .retain(|chunk| !chunk.id.as_ref().is_some_and(|id| dropped.contains(id)));
+    }
 }
 impl TryFrom<NostrNote> for ProductMenuIdb {
     type Error = anyhow::Error;
     fn try_from(note: NostrNote) -> Result<Self, Self::Error> {
-        if note.kind != NOSTR_KIND_COMMERCE_PRODUCTS {
+        if note.kind != NOSTR_KIND_COMMERCE_PRODUCTS
+            && note.kind != NOSTR_KIND_COMMERCE_MENU_CATEGORY
+        {
             return Err(anyhow::anyhow!("Wrong Kind"));
         }
-        let menu: ProductMenu = serde_json::from_str(&note.content)?;
-        Ok(Self {
+        // A category can arrive before its index, it waits for it in an empty entry
+        let mut entry = Self {
             pubkey: note.pubkey.clone(),
-            menu,
-            note,
-        })
+            menu: ProductMenu::new(),
+            note: NostrNote {
+                pubkey: note.pubkey.clone(),
+                kind: NOSTR_KIND_COMMERCE_PRODUCTS,
+                content: MenuIndex::default().to_string(),
+                created_at: 0,
+                ..Default::default()
+            },
+            chunks: vec![],
+        };
+        entry.apply_note(note)?;
+        Ok(entry)
     }
 }
 impl TryFrom<JsValue> for ProductMenuIdb {
@@ -416,3 +1399,600 @@ impl IdbStoreManager for ProductMenuIdb {
         JsValue::from_str(&self.pubkey)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Currency;
+
+    fn burger() -> ProductItem {
+        let mut burger = ProductItem::new(
+            0,
+            "Burger".into(),
+            Money::srd(5000),
+            "".into(),
+            "mains".into(),
+        );
+        burger.set_modifier_groups(vec![
+            ModifierGroup::new(
+                "Side".into(),
+                true,
+                1,
+                1,
+                vec![
+                    ProductSide::new(0, "Fries".into(), Money::default()),
+                    ProductSide::new(1, "Salad".into(), Money::srd(500)),
+                ],
+            ),
+            ModifierGroup::new(
+                "Extras".into(),
+                false,
+                0,
+                2,
+                vec![
+                    ProductSide::new(0, "Cheese".into(), Money::srd(750)),
+                    ProductSide::new(1, "Bacon".into(), Money::srd(1000)),
+                    ProductSide::new(2, "Egg".into(), Money::srd(400)),
+                ],
+            ),
+        ]);
+        burger
+    }
+    fn side_id(product: &ProductItem, name: &str) -> String {
+        product
+            .modifier_groups()
+            .iter()
+            .flat_map(|group| group.options())
+            .find(|side| side.name() == name)
+            .map(|side| side.id())
+            .unwrap()
+    }
+
+    #[test]
+    fn selections_respect_group_rules() {
+        let burger = burger();
+        assert!(burger.with_sides(&[]).is_err());
+        let too_many = ["Fries", "Cheese", "Bacon", "Egg"].map(|name| side_id(&burger, name));
+        assert!(burger.with_sides(&too_many).is_err());
+        assert!(burger.with_sides(&["unknown".to_string()]).is_err());
+        let line = burger
+            .with_sides(&[side_id(&burger, "Salad"), side_id(&burger, "Cheese")])
+            .unwrap();
+        assert_eq!(line.price_with_sides(), Money::srd(6250));
+    }
+
+    #[test]
+    fn order_total_includes_sides() {
+        let burger = burger();
+        let plain = burger.with_sides(&[side_id(&burger, "Fries")]).unwrap();
+        let loaded = burger
+            .with_sides(&[side_id(&burger, "Fries"), side_id(&burger, "Bacon")])
+            .unwrap();
+        let mut order = ProductOrder::default();
+        order.add(&plain);
+        order.add(&plain);
+        order.add(&loaded);
+        assert_eq!(order.total(), Money::srd(16000));
+        assert_eq!(order.lines().len(), 2);
+        order.remove_line(&OrderLine::from(&plain));
+        assert_eq!(order.total(), Money::srd(6000));
+    }
+
+    #[test]
+    fn edited_modifier_groups_keep_their_ids() {
+        let burger = burger();
+        let side = burger.modifier_groups()[0].clone();
+        let edited = ModifierGroup::new(
+            "Side".into(),
+            true,
+            1,
+            1,
+            vec![
+                ProductSide::new(0, "Salad".into(), Money::srd(600)),
+                ProductSide::new(1, "Rice".into(), Money::srd(300)),
+            ],
+        )
+        .replacing(&side);
+        assert_eq!(edited.id(), side.id());
+        assert_eq!(edited.options()[0].id(), side_id(&burger, "Salad"));
+        let rice = edited.options()[1].id();
+        assert!(side.options().iter().all(|kept| kept.id() != rice));
+    }
+
+    #[test]
+    fn checked_total_refuses_mixed_currencies() {
+        let burger = burger();
+        let fries = burger.with_sides(&[side_id(&burger, "Fries")]).unwrap();
+        let mut order = ProductOrder::default();
+        order.add(&fries);
+        assert_eq!(order.checked_total().unwrap(), order.total());
+
+        let shake = ProductItem::new(
+            1,
+            "Shake".into(),
+            Money::new(450, Currency::USD),
+            "".into(),
+            "drinks".into(),
+        );
+        order.add(&shake);
+        assert!(order.checked_total().is_err());
+    }
+
+    #[test]
+    fn lines_track_quantity_and_notes() {
+        let dawet = ProductItem::new(
+            1,
+            "Dawet".into(),
+            Money::srd(10),
+            "".into(),
+            "drinks".into(),
+        );
+        let mut order = ProductOrder::default();
+        (0..3).for_each(|_| order.add(&dawet));
+        assert_eq!(order.lines().len(), 1);
+        assert_eq!(order.item_count(), 3);
+        assert_eq!(order.total(), Money::srd(30));
+
+        let line = order.lines()[0].clone();
+        order.set_note(&line, Some(" no ice ".into()));
+        order.add(&dawet);
+        let lines = order.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].note(), Some("no ice".to_string()));
+        order.remove_one(&lines[1]);
+        assert_eq!(order.item_count(), 3);
+    }
+
+    #[test]
+    fn group_lines_keep_their_contributor() {
+        let dawet = ProductItem::new(
+            1,
+            "Dawet".into(),
+            Money::srd(10),
+            "".into(),
+            "drinks".into(),
+        );
+        let mut mine = ProductOrder::default();
+        mine.add(&dawet);
+        let mut theirs = ProductOrder::default();
+        (0..2).for_each(|_| theirs.add(&dawet));
+
+        let mut group = ProductOrder::default();
+        group.add_contribution("Ana", &mine);
+        group.add_contribution("Ravi", &theirs);
+        group.add_contribution("Ana", &mine);
+        assert_eq!(group.lines().len(), 2);
+        assert_eq!(group.item_count(), 4);
+        assert_eq!(group.contributed_by("Ana").total(), Money::srd(20));
+        assert_eq!(group.contributed_by("Ravi").lines()[0].quantity(), 2);
+        assert_eq!(group.lines()[1].contributor(), Some("Ravi".to_string()));
+        assert!(group.contributed_by("Noor").is_empty());
+        let host = group.without_contributions(&["Ravi".to_string()]);
+        assert_eq!(host.total(), Money::srd(20));
+        assert_eq!(group.without_contributions(&[]), group);
+    }
+
+    #[test]
+    fn legacy_orders_are_grouped_into_lines() {
+        let roti = ProductItem::new(
+            0,
+            "Roti".into(),
+            Money::srd(8000),
+            "".into(),
+            "mains".into(),
+        );
+        let legacy = serde_json::json!({ "products": [roti, roti] }).to_string();
+        let order = ProductOrder::try_from(legacy).unwrap();
+        assert_eq!(order.lines().len(), 1);
+        assert_eq!(order.lines()[0].quantity(), 2);
+        assert_eq!(order.total(), Money::srd(16000));
+        let roundtrip = ProductOrder::try_from(order.to_string()).unwrap();
+        assert_eq!(roundtrip, order);
+    }
+
+    #[test]
+    fn menus_keep_to_one_currency() {
+        let mut shake = ProductItem::new(
+            0,
+            "Shake".into(),
+            Money::new(450, Currency::USD),
+            "".into(),
+            "drinks".into(),
+        );
+        shake.set_discount(Some(Money::srd(100)));
+        assert_eq!(shake.price(), Money::new(450, Currency::USD));
+        assert!(shake.check_currency().is_err());
+
+        let mut drinks = ProductCategory::new(0, "Drinks".into());
+        drinks.add_product(shake.clone());
+        assert!(ProductMenuIdb::try_from(category_note(&drinks, "d1", 1)).is_err());
+
+        // Every category is fine on its own, together they mix currencies
+        shake.set_discount(None);
+        let mut drinks = ProductCategory::new(0, "Drinks".into());
+        drinks.add_product(shake);
+        let mut mains = ProductCategory::new(1, "Mains".into());
+        mains.add_product(burger());
+        let mut entry = ProductMenuIdb::try_from(category_note(&drinks, "d1", 1)).unwrap();
+        entry.apply_note(category_note(&mains, "m1", 1)).unwrap();
+        assert!(entry
+            .apply_note(index_note(&[(&drinks, "d1"), (&mains, "m1")], "i1", 1))
+            .is_err());
+        assert!(entry.menu().categories().is_empty());
+    }
+
+    #[test]
+    fn stock_limits_orders() {
+        let burger = burger();
+        let mut menu = ProductMenu::new();
+        let category = ProductCategory::new(0, "Mains".into());
+        menu.add_category(category.clone());
+        let mut limited = burger.clone();
+        limited.set_stock(Some(3));
+        menu.add_product(category.id(), limited);
+
+        let mut order = ProductOrder::default();
+        order.add(&burger.with_sides(&[side_id(&burger, "Fries")]).unwrap());
+        order.add(&burger.with_sides(&[side_id(&burger, "Salad")]).unwrap());
+        let mut sold = HashMap::new();
+        assert!(menu.check_stock(&order, &sold).is_ok());
+        sold.insert(burger.id(), 2);
+        assert!(menu.check_stock(&order, &sold).is_err());
+
+        let mut off_sale = burger.clone();
+        off_sale.set_available(false);
+        menu.remove_product(&category.id(), &burger.id());
+        menu.add_product(category.id(), off_sale);
+        assert!(menu.check_stock(&order, &HashMap::new()).is_err());
+
+        // Menus published before stock tracking keep their products on sale
+        let legacy: ProductItem = serde_json::from_value(serde_json::json!({
+            "id": "1", "name": "Roti", "price": "80.00", "order": 0,
+            "category": "mains", "description": "", "sides": []
+        }))
+        .unwrap();
+        assert!(legacy.is_available() && !legacy.is_sold_out());
+    }
+
+    #[test]
+    fn remaining_stock_lowers_the_menu() {
+        let burger = burger();
+        let dawet = ProductItem::new(
+            1,
+            "Dawet".into(),
+            Money::srd(1000),
+            "".into(),
+            "mains".into(),
+        );
+        let mut menu = ProductMenu::new();
+        let category = ProductCategory::new(0, "Mains".into());
+        menu.add_category(category.clone());
+        let mut limited = burger.clone();
+        limited.set_stock(Some(3));
+        menu.add_product(category.id(), limited);
+        menu.add_product(category.id(), dawet.clone());
+
+        let sold = HashMap::from([(burger.id(), 5), (dawet.id(), 2)]);
+        let remaining = menu.remaining_stock(&sold);
+        assert_eq!(remaining, HashMap::from([(burger.id(), 0)]));
+
+        let shown = menu.with_remaining_stock(&remaining);
+        let products = shown.categories()[0].products();
+        assert!(products
+            .iter()
+            .any(|p| p.id() == burger.id() && p.is_sold_out()));
+        assert!(products
+            .iter()
+            .any(|p| p.id() == dawet.id() && !p.is_sold_out()));
+    }
+
+    #[test]
+    fn past_orders_are_rebuilt_from_the_menu() {
+        let burger = burger();
+        let dawet = ProductItem::new(
+            1,
+            "Dawet".into(),
+            Money::srd(1000),
+            "".into(),
+            "mains".into(),
+        );
+        let roti = ProductItem::new(
+            2,
+            "Roti".into(),
+            Money::srd(8000),
+            "".into(),
+            "mains".into(),
+        );
+        let mut past = ProductOrder::default();
+        let loaded = burger
+            .with_sides(&[side_id(&burger, "Salad"), side_id(&burger, "Egg")])
+            .unwrap();
+        past.add(&loaded);
+        past.add(&loaded);
+        past.add(&burger.with_sides(&[side_id(&burger, "Fries")]).unwrap());
+        past.add(&dawet);
+        past.add(&roti);
+        let lines = past.lines();
+        past.set_note(&lines[0], Some("well done".into()));
+
+        let category = ProductCategory::new(0, "Mains".into());
+        let mut menu = ProductMenu::new();
+        menu.add_category(category.clone());
+        // Eggs are gone, salad became the only side and dawet got cheaper
+        let mut current = burger.clone();
+        let groups = current.modifier_groups();
+        let salad = groups[0]
+            .options()
+            .into_iter()
+            .filter(|side| side.name() == "Salad")
+            .collect();
+        let mut side_group = groups[0].clone();
+        side_group.options = salad;
+        let mut extras = groups[1].clone();
+        extras.options.retain(|side| side.name() != "Egg");
+        current.set_modifier_groups(vec![side_group, extras]);
+        let mut cheaper = dawet.clone();
+        cheaper.set_price(Money::srd(800));
+        menu.add_product(category.id(), current);
+        menu.add_product(category.id(), cheaper);
+
+        let reorder = menu.reorder(&past);
+        assert_eq!(
+            reorder.changes,
+            vec![
+                ReorderChange::SidesDropped {
+                    name: "Burger".into(),
+                    sides: vec!["Egg".into()],
+                },
+                ReorderChange::PriceChanged {
+                    name: "Burger".into(),
+                    was: Money::srd(5900),
+                    now: Money::srd(5500),
+                },
+                ReorderChange::OptionsChanged {
+                    name: "Burger".into(),
+                },
+                ReorderChange::PriceChanged {
+                    name: "Dawet".into(),
+                    was: Money::srd(1000),
+                    now: Money::srd(800),
+                },
+                ReorderChange::Removed {
+                    name: "Roti".into(),
+                },
+            ]
+        );
+        let lines = reorder.order.lines();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].quantity(), 2);
+        assert_eq!(lines[0].note(), Some("well done".to_string()));
+        assert_eq!(reorder.order.total(), Money::srd(11800));
+        assert_eq!(menu.reorder(&reorder.order).changes, vec![]);
+    }
+
+    #[test]
+    fn ids_do_not_depend_on_names() {
+        let mut category = ProductCategory::new(0, "Mains".into());
+        assert_ne!(category.id(), ProductCategory::new(0, "Mains".into()).id());
+        let roti = ProductItem::new(0, "Roti".into(), Money::srd(8000), "".into(), category.id());
+        let other = ProductItem::new(0, "Roti".into(), Money::srd(8000), "".into(), category.id());
+        assert_ne!(roti.id(), other.id());
+
+        // Menus from before could hold the same category twice
+        category.add_product(roti.clone());
+        let mut menu = ProductMenu::new();
+        menu.add_category(category.clone());
+        menu.add_category(category.clone());
+        assert!(menu.migrate_ids());
+        let categories = menu.categories();
+        assert_eq!(categories[0].id(), category.id());
+        assert_ne!(categories[1].id(), category.id());
+        let moved = categories[1].products()[0].clone();
+        assert_ne!(moved.id(), roti.id());
+        assert_eq!(moved.category_id(), categories[1].id());
+        assert_eq!(
+            menu.find_product(&roti.id()).unwrap().category_id(),
+            category.id()
+        );
+        assert!(!menu.migrate_ids());
+    }
+
+    #[test]
+    fn draft_changes_are_matched_by_id() {
+        let mut published = ProductMenu::new();
+        let category = ProductCategory::new(0, "Mains".into());
+        let burger = burger();
+        let fries = ProductItem::new(1, "Fries".into(), Money::srd(1500), "".into(), "".into());
+        published.add_category(category.clone());
+        published.add_product(category.id(), burger.clone());
+        published.add_product(category.id(), fries.clone());
+        assert!(published.changes_from(&published).is_empty());
+
+        let mut draft = published.clone();
+        let mut renamed = burger.clone();
+        renamed.set_name("Cheeseburger".into());
+        renamed.set_available(false);
+        draft.remove_product(&category.id(), &burger.id());
+        draft.remove_product(&category.id(), &fries.id());
+        draft.add_product(category.id(), renamed);
+        assert_eq!(
+            draft.changes_from(&published),
+            vec![
+                MenuChange::Updated {
+                    category: "Mains".into(),
+                    name: "Cheeseburger".into(),
+                    fields: vec!["name", "stock"],
+                },
+                MenuChange::Removed {
+                    category: "Mains".into(),
+                    name: "Fries".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn scheduled_menus_follow_the_clock() {
+        use crate::models::Weekday;
+        // Monday 2025-03-03 09:00 in Suriname (UTC-3)
+        let monday_morning = 1_741_003_200;
+        let evening = monday_morning + 8 * 60 * 60;
+
+        let mut breakfast = ProductCategory::new(0, "Breakfast".into());
+        breakfast.set_hours(vec![OpeningHours::new(Weekday::Monday, 7 * 60, 11 * 60)]);
+        let pom = ProductItem::new(0, "Pom".into(), Money::srd(3000), "".into(), breakfast.id());
+        let mains = ProductCategory::new(1, "Mains".into());
+        let mut burger = burger();
+        burger.set_discount(Some(Money::srd(1000)));
+        burger.set_discount_hours(vec![OpeningHours::new(Weekday::Monday, 16 * 60, 18 * 60)]);
+        assert_eq!(burger.price(), Money::srd(5000));
+        let mut menu = ProductMenu::new();
+        menu.add_category(breakfast.clone());
+        menu.add_category(mains.clone());
+        menu.add_product(breakfast.id(), pom.clone());
+        menu.add_product(mains.id(), burger.clone());
+
+        let morning_menu = menu.at(monday_morning, -180);
+        assert_eq!(morning_menu.categories().len(), 2);
+        assert_eq!(
+            morning_menu.find_product(&burger.id()).unwrap().price(),
+            Money::srd(5000)
+        );
+        let evening_menu = menu.at(evening, -180);
+        assert_eq!(evening_menu.categories().len(), 1);
+        assert_eq!(
+            evening_menu.find_product(&burger.id()).unwrap().price(),
+            Money::srd(4000)
+        );
+
+        // Lines are repriced with the window active when the order is placed
+        let mut order = ProductOrder::default();
+        let morning_burger = morning_menu.find_product(&burger.id()).unwrap();
+        order.add(
+            &morning_burger
+                .with_sides(&[side_id(&burger, "Fries")])
+                .unwrap(),
+        );
+        let priced = menu.price_order(&order, evening, -180).unwrap();
+        assert_eq!(order.total(), Money::srd(5000));
+        assert_eq!(priced.total(), Money::srd(4000));
+        order.add(&pom);
+        assert!(menu.price_order(&order, monday_morning, -180).is_ok());
+        assert!(menu.price_order(&order, evening, -180).is_err());
+    }
+
+    #[test]
+    fn orders_are_checked_against_the_menu_options() {
+        let burger = burger();
+        let mains = ProductCategory::new(0, "Mains".into());
+        let mut menu = ProductMenu::new();
+        menu.add_category(mains.clone());
+        menu.add_product(mains.id(), burger.clone());
+
+        let mut order = ProductOrder::default();
+        order.add(&burger.with_sides(&[side_id(&burger, "Salad")]).unwrap());
+        assert_eq!(menu.price_order(&order, 0, 0).unwrap(), order);
+
+        // Sides are priced by the menu, not by what the consumer sent
+        let mut cheap = order.clone();
+        cheap.lines[0].sides[0].price = Money::default();
+        assert_eq!(menu.price_order(&cheap, 0, 0).unwrap(), order);
+
+        let mut missing_side = ProductOrder::default();
+        missing_side.add(&burger);
+        assert!(menu.price_order(&missing_side, 0, 0).is_err());
+
+        let mut unknown_side = order.clone();
+        unknown_side.lines[0].sides[0].id = "unknown".into();
+        assert!(menu.price_order(&unknown_side, 0, 0).is_err());
+    }
+
+    fn menu_note(kind: u32, content: String, id: &str, created_at: i64) -> NostrNote {
+        NostrNote {
+            pubkey: "commerce".into(),
+            kind,
+            content,
+            created_at,
+            id: Some(id.into()),
+            ..Default::default()
+        }
+    }
+    fn category_note(category: &ProductCategory, id: &str, created_at: i64) -> NostrNote {
+        let mut note = menu_note(
+            NOSTR_KIND_COMMERCE_MENU_CATEGORY,
+            category.to_string(),
+            id,
+            created_at,
+        );
+        note.tags.add_parameter_tag(&category.id());
+        note
+    }
+    fn index_note(chunks: &[(&ProductCategory, &str)], id: &str, created_at: i64) -> NostrNote {
+        let index = MenuIndex {
+            chunks: chunks
+                .iter()
+                .map(|(category, note_id)| MenuChunkRef {
+                    category_id: category.id(),
+                    note_id: note_id.to_string(),
+                })
+                .collect(),
+        };
+        menu_note(
+            NOSTR_KIND_COMMERCE_PRODUCTS,
+            index.to_string(),
+            id,
+            created_at,
+        )
+    }
+
+    #[test]
+    fn chunked_menus_are_assembled() {
+        let mut drinks = ProductCategory::new(0, "Drinks".into());
+        drinks.add_product(ProductItem::new(
+            0,
+            "Dawet".into(),
+            Money::srd(2000),
+            "".into(),
+            drinks.id(),
+        ));
+        let mains = ProductCategory::new(1, "Mains".into());
+
+        // A category can show up before the index pointing at it
+        let mut entry = ProductMenuIdb::try_from(category_note(&drinks, "d1", 1)).unwrap();
+        assert!(entry.menu().categories().is_empty());
+        entry
+            .apply_note(index_note(&[(&drinks, "d1"), (&mains, "m1")], "i1", 1))
+            .unwrap();
+        assert_eq!(entry.missing_chunks(), vec!["m1".to_string()]);
+        entry.apply_note(category_note(&mains, "m1", 1)).unwrap();
+        assert!(entry.is_complete());
+        assert_eq!(entry.menu().categories().len(), 2);
+
+        // Partial update: only the changed category and the index are republished
+        drinks.remove_product(drinks.products()[0].id());
+        entry.apply_note(category_note(&drinks, "d2", 2)).unwrap();
+        entry
+            .apply_note(index_note(&[(&drinks, "d2")], "i2", 2))
+            .unwrap();
+        assert_eq!(entry.menu().categories(), vec![drinks.clone()]);
+        assert_eq!(entry.notes().len(), 2);
+        // Stale events are ignored
+        entry
+            .apply_note(index_note(&[(&drinks, "d1"), (&mains, "m1")], "i1", 1))
+            .unwrap();
+        assert_eq!(entry.menu().categories().len(), 1);
+
+        let mut legacy_menu = ProductMenu::new();
+        legacy_menu.add_category(mains.clone());
+        entry
+            .apply_note(menu_note(
+                NOSTR_KIND_COMMERCE_PRODUCTS,
+                legacy_menu.to_string(),
+                "l1",
+                3,
+            ))
+            .unwrap();
+        assert_eq!(entry.menu(), legacy_menu);
+        assert!(entry.is_complete());
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use nostr_minions::key_manager::UserIdentity;
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
+use serde::{Deserialize, Serialize};
+
+use super::{ExchangeRates, Money, ProductOrder, NOSTR_KIND_COMMERCE_PROMOTIONS};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PromotionDiscount {
+    /// Whole percent taken off the eligible subtotal.
+    Percentage(u32),
+    /// Fixed amount taken off the eligible subtotal.
+    Fixed(Money),
+}
+impl PromotionDiscount {
+    pub fn display(&self) -> String {
+        match self {
+            PromotionDiscount::Percentage(percent) => format!("{}%", percent),
+            PromotionDiscount::Fixed(amount) => amount.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Promotion {
+    pub id: String,
+    /// Promotions without a code are applied automatically.
+    pub code: Option<String>,
+    pub description: String,
+    pub discount: PromotionDiscount,
+    pub minimum_order: Option<Money>,
+    pub first_order_only: bool,
+    pub usage_limit: Option<u32>,
+    pub valid_from: Option<i64>,
+    pub valid_until: Option<i64>,
+    /// Category ids the discount applies to, empty means the whole order.
+    pub categories: Vec<String>,
+}
+impl Default for Promotion {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            code: None,
+            description: String::new(),
+            discount: PromotionDiscount::Percentage(0),
+            minimum_order: None,
+            first_order_only: false,
+            usage_limit: None,
+            valid_from: None,
+            valid_until: None,
+            categories: vec![],
+        }
+    }
+}
+impl ToString for Promotion {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<String> for Promotion {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+impl Promotion {
+    pub fn new(code: Option<String>, description: String, discount: PromotionDiscount) -> Self {
+        let id = NostrKeypair::generate(false).public_key()[..16].to_string();
+        Self {
+            id,
+            code: code
+                .map(|code| code.trim().to_uppercase())
+                .filter(|code| !code.is_empty()),
+            description,
+            discount,
+            ..Default::default()
+        }
+    }
+    pub fn is_automatic(&self) -> bool {
+        self.code.is_none()
+    }
+    pub fn matches_code(&self, code: &str) -> bool {
+        self.code
+            .as_ref()
+            .map(|own| own.eq_ignore_ascii_case(code.trim()))
+            .unwrap_or(false)
+    }
+    fn eligible_subtotal(&self, order: &ProductOrder) -> Money {
+        if self.categories.is_empty() {
+            return order.total();
+        }
+        order
+            .lines()
+            .iter()
+            .filter(|line| self.categories.contains(&line.category_id()))
+            .map(|line| line.subtotal())
+            .sum()
+    }
+    /// Checks the promotion rules against the order and returns the amount to take off.
+    pub fn discount_for(
+        &self,
+        order: &ProductOrder,
+        context: &PromotionContext,
+    ) -> anyhow::Result<Money> {
+        if self.valid_from.is_some_and(|from| context.now < from)
+            || self.valid_until.is_some_and(|until| context.now > until)
+        {
+            return Err(anyhow::anyhow!("Promotion is not active"));
+        }
+        if let Some(limit) = self.usage_limit {
+            if context.redemptions.get(&self.id).copied().unwrap_or(0) >= limit {
+                return Err(anyhow::anyhow!("Promotion usage limit reached"));
+            }
+        }
+        if self.first_order_only && !context.is_first_order {
+            return Err(anyhow::anyhow!("Promotion only valid on a first order"));
+        }
+        let total = order.total();
+        if let Some(minimum) = self.minimum_order {
+            if total < context.rates.convert(minimum, total.currency())? {
+                return Err(anyhow::anyhow!("Order below promotion minimum"));
+            }
+        }
+        let eligible = self.eligible_subtotal(order);
+        if !eligible.is_positive() {
+            return Err(anyhow::anyhow!("No products eligible for promotion"));
+        }
+        let discount = match self.discount {
+            PromotionDiscount::Percentage(percent) => eligible.percent(percent.min(100)),
+            PromotionDiscount::Fixed(amount) => context
+                .rates
+                .convert(amount, eligible.currency())?
+                .min(eligible),
+        };
+        Ok(discount)
+    }
+}
+
+/// What the promotion rules are checked against when an order is placed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PromotionContext {
+    pub now: i64,
+    pub is_first_order: bool,
+    /// Orders holding each promotion, by promotion id.
+    pub redemptions: HashMap<String, u32>,
+    /// Converts fixed amounts set in another currency than the order's.
+    pub rates: ExchangeRates,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AppliedPromotion {
+    pub promotion_id: String,
+    pub code: Option<String>,
+    pub description: String,
+    /// Pubkey of the commerce that issued the promotion, or of the platform for admin promotions.
+    pub issuer: String,
+    pub discount: Money,
+}
+impl AppliedPromotion {
+    pub fn amount(&self) -> Money {
+        self.discount
+    }
+    pub fn is_issued_by(&self, pubkey: &str) -> bool {
+        self.issuer == pubkey
+    }
+}
+
+/// Finds the best promotion for an order among the ones offered by each issuer.
+/// Automatic promotions are always considered, coded ones only if the consumer entered the code.
+pub fn apply_promotions(
+    offered: &[(String, Promotion)],
+    code: Option<&str>,
+    order: &ProductOrder,
+    context: &PromotionContext,
+) -> anyhow::Result<Option<AppliedPromotion>> {
+    let code = code.map(str::trim).filter(|code| !code.is_empty());
+    let mut code_error = anyhow::anyhow!("Invalid promotion code");
+    let mut code_accepted = false;
+    let mut best: Option<(Money, &String, &Promotion)> = None;
+    for (issuer, promotion) in offered {
+        let entered = code.is_some_and(|code| promotion.matches_code(code));
+        if !promotion.is_automatic() && !entered {
+            continue;
+        }
+        match promotion.discount_for(order, context) {
+            Ok(discount) => {
+                code_accepted |= entered;
+                if best.is_none_or(|(amount, _, _)| discount > amount) {
+                    best = Some((discount, issuer, promotion));
+                }
+            }
+            Err(e) if entered => code_error = e,
+            Err(_) => {}
+        }
+    }
+    if code.is_some() && !code_accepted {
+        return Err(code_error);
+    }
+    Ok(best.map(|(discount, issuer, promotion)| AppliedPromotion {
+        promotion_id: promotion.id.clone(),
+        code: promotion.code.clone(),
+        description: promotion.description.clone(),
+        issuer: issuer.clone(),
+        discount,
+    }))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct PromotionList {
+    pub promotions: Vec<Promotion>,
+}
+impl ToString for PromotionList {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<String> for PromotionList {
+    type Error = anyhow::Error;
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Ok(serde_json::from_str(&s)?)
+    }
+}
+impl TryFrom<NostrNote> for PromotionList {
+    type Error = anyhow::Error;
+    fn try_from(note: NostrNote) -> Result<Self, Self::Error> {
+        if note.kind != NOSTR_KIND_COMMERCE_PROMOTIONS {
+            return Err(anyhow::anyhow!("Wrong Kind"));
+        }
+        let list: PromotionList = note.content.try_into()?;
+        Ok(list)
+    }
+}
+impl TryFrom<&NostrNote> for PromotionList {
+    type Error = anyhow::Error;
+    fn try_from(note: &NostrNote) -> Result<Self, Self::Error> {
+        if note.kind != NOSTR_KIND_COMMERCE_PROMOTIONS {
+            return Err(anyhow::anyhow!("Wrong Kind"));
+        }
+        let list: PromotionList = note.content.clone().try_into()?;
+        Ok(list)
+    }
+}
+impl PromotionList {
+    pub fn add(&mut self, promotion: Promotion) {
+        self.promotions.retain(|p| p.id != promotion.id);
+        self.promotions.push(promotion);
+    }
+    pub fn remove(&mut self, promotion_id: &str) {
+        self.promotions.retain(|p| p.id != promotion_id);
+    }
+    pub async fn signed_note(&self, keys: &UserIdentity) -> anyhow::Result<NostrNote> {
+        let note = NostrNote {
+            pubkey: keys
+                .get_pubkey()
+                .await
+                .ok_or(anyhow::anyhow!("No pubkey"))?,
+            kind: NOSTR_KIND_COMMERCE_PROMOTIONS,
+            content: self.to_string(),
+            ..Default::default()
+        };
+        keys.sign_nostr_note(note)
+            .await
+            .map_err(|_e| anyhow::anyhow!("Could not sign promotions"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Currency, ProductItem};
+
+    fn order() -> ProductOrder {
+        let mut order = ProductOrder::default();
+        order.add(&ProductItem::new(
+            0,
+            "Roti".into(),
+            Money::srd(8000),
+            "".into(),
+            "mains".into(),
+        ));
+        order.add(&ProductItem::new(
+            1,
+            "Dawet".into(),
+            Money::srd(2000),
+            "".into(),
+            "drinks".into(),
+        ));
+        order
+    }
+
+    #[test]
+    fn percentage_on_category() {
+        let mut promotion = Promotion::new(
+            Some("drinks10".into()),
+            "".into(),
+            PromotionDiscount::Percentage(50),
+        );
+        promotion.categories = vec!["drinks".into()];
+        let context = PromotionContext::default();
+        assert_eq!(
+            promotion.discount_for(&order(), &context).unwrap(),
+            Money::srd(1000)
+        );
+        assert!(promotion.matches_code("DRINKS10 "));
+    }
+
+    #[test]
+    fn rules_are_enforced() {
+        let mut promotion =
+            Promotion::new(None, "".into(), PromotionDiscount::Fixed(Money::srd(2500)));
+        promotion.minimum_order = Some(Money::srd(15000));
+        let mut context = PromotionContext::default();
+        assert!(promotion.discount_for(&order(), &context).is_err());
+
+        promotion.minimum_order = None;
+        promotion.usage_limit = Some(1);
+        context.redemptions.insert(promotion.id.clone(), 1);
+        assert!(promotion.discount_for(&order(), &context).is_err());
+
+        promotion.usage_limit = None;
+        promotion.first_order_only = true;
+        assert!(promotion.discount_for(&order(), &context).is_err());
+        context.is_first_order = true;
+        assert_eq!(
+            promotion.discount_for(&order(), &context).unwrap(),
+            Money::srd(2500)
+        );
+
+        promotion.valid_until = Some(10);
+        context.now = 11;
+        assert!(promotion.discount_for(&order(), &context).is_err());
+    }
+
+    #[test]
+    fn fixed_amounts_use_the_rate_table() {
+        let promotion = Promotion::new(
+            None,
+            "".into(),
+            PromotionDiscount::Fixed(Money::new(100, Currency::USD)),
+        );
+        let mut context = PromotionContext::default();
+        assert!(promotion.discount_for(&order(), &context).is_err());
+        context.rates.set_rate(Currency::SRD, 36.5);
+        assert_eq!(
+            promotion.discount_for(&order(), &context).unwrap(),
+            Money::srd(3650)
+        );
+    }
+
+    #[test]
+    fn best_promotion_is_applied() {
+        let automatic = Promotion::new(None, "".into(), PromotionDiscount::Fixed(Money::srd(500)));
+        let coded = Promotion::new(
+            Some("WELCOME".into()),
+            "".into(),
+            PromotionDiscount::Percentage(10),
+        );
+        let offered = vec![
+            ("commerce".to_string(), automatic),
+            ("platform".to_string(), coded),
+        ];
+        let context = PromotionContext::default();
+        let applied = apply_promotions(&offered, None, &order(), &context)
+            .unwrap()
+            .unwrap();
+        assert_eq!(applied.discount, Money::srd(500));
+        let applied = apply_promotions(&offered, Some("welcome"), &order(), &context)
+            .unwrap()
+            .unwrap();
+        assert_eq!(applied.discount, Money::srd(1000));
+        assert!(applied.is_issued_by("platform"));
+        assert!(apply_promotions(&offered, Some("NOPE"), &order(), &context).is_err());
+    }
+}
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+/// Orders can only be scheduled this far ahead. The consumer's HODL invoice
+/// has to stay open until the order is released, and the HTLC expires after that.
+pub const MAX_SCHEDULE_AHEAD_SECONDS: i64 = 12 * 60 * 60;
+const SECONDS_IN_A_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+    /// The epoch (1970-01-01) was a Thursday.
+    pub fn from_epoch_day(day: i64) -> Self {
+        Self::ALL[(day + 3).rem_euclid(7) as usize]
+    }
+    pub fn display(&self) -> String {
+        match self {
+            Weekday::Monday => "Monday".to_string(),
+            Weekday::Tuesday => "Tuesday".to_string(),
+            Weekday::Wednesday => "Wednesday".to_string(),
+            Weekday::Thursday => "Thursday".to_string(),
+            Weekday::Friday => "Friday".to_string(),
+            Weekday::Saturday => "Saturday".to_string(),
+            Weekday::Sunday => "Sunday".to_string(),
+        }
+    }
+}
+impl TryFrom<&str> for Weekday {
+    type Error = anyhow::Error;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|day| day.display() == value)
+            .ok_or(anyhow::anyhow!("Invalid weekday"))
+    }
+}
+
+/// Opening window for one day of the week, in minutes since local midnight.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OpeningHours {
+    pub day: Weekday,
+    pub opens: u32,
+    pub closes: u32,
+}
+impl OpeningHours {
+    pub fn new(day: Weekday, opens: u32, closes: u32) -> Self {
+        Self { day, opens, closes }
+    }
+    pub fn parse_time(value: &str) -> Option<u32> {
+        let (hours, minutes) = value.split_once(':')?;
+        let hours = hours.parse::<u32>().ok()?;
+        let minutes = minutes.parse::<u32>().ok()?;
+        if hours > 23 || minutes > 59 {
+            return None;
+        }
+        Some(hours * 60 + minutes)
+    }
+    pub fn format_time(minutes: u32) -> String {
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+    pub fn display(&self) -> String {
+        format!(
+            "{} {} - {}",
+            self.day.display(),
+            Self::format_time(self.opens),
+            Self::format_time(self.closes)
+        )
+    }
+}
+
+/// A delivery window chosen by the consumer, as unix timestamps in seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeliverySlot {
+    pub start: i64,
+    pub end: i64,
+}
+impl DeliverySlot {
+    pub fn display(&self, utc_offset_minutes: i32) -> String {
+        let offset = utc_offset_minutes as i64 * 60;
+        let start = self.start + offset;
+        let end = self.end + offset;
+        let day = Weekday::from_epoch_day(start.div_euclid(SECONDS_IN_A_DAY));
+        let minutes = |ts: i64| (ts.rem_euclid(SECONDS_IN_A_DAY) / 60) as u32;
+        format!(
+            "{} {} - {}",
+            day.display(),
+            OpeningHours::format_time(minutes(start)),
+            OpeningHours::format_time(minutes(end))
+        )
+    }
+    pub fn locale_display(&self) -> String {
+        let date = |timestamp: i64| {
+            web_sys::js_sys::Date::new(&web_sys::wasm_bindgen::JsValue::from_f64(
+                timestamp as f64 * 1000.0,
+            ))
+        };
+        let locale_options = web_sys::js_sys::Object::new();
+        let start = date(self.start);
+        let day: String = start.to_locale_date_string("nl-SR", &locale_options).into();
+        let start_time: String = start.to_locale_time_string("nl-SR").into();
+        let end_time: String = date(self.end).to_locale_time_string("nl-SR").into();
+        format!("{} {} - {}", day, start_time, end_time)
+    }
+}
+
+/// Whether the local time at `timestamp` falls inside any of the weekly windows.
+pub fn within_hours(hours: &[OpeningHours], timestamp: i64, utc_offset_minutes: i32) -> bool {
+    let local = timestamp + utc_offset_minutes as i64 * 60;
+    let day = Weekday::from_epoch_day(local.div_euclid(SECONDS_IN_A_DAY));
+    let minute = (local.rem_euclid(SECONDS_IN_A_DAY) / 60) as u32;
+    hours
+        .iter()
+        .any(|hours| hours.day == day && hours.opens <= minute && minute < hours.closes)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommerceAvailability {
+    pub hours: Vec<OpeningHours>,
+    pub slot_minutes: u32,
+    pub lead_minutes: u32,
+    pub utc_offset_minutes: i32,
+}
+impl Default for CommerceAvailability {
+    fn default() -> Self {
+        Self {
+            hours: vec![],
+            slot_minutes: 30,
+            lead_minutes: 45,
+            // Suriname Time (UTC-3)
+            utc_offset_minutes: -180,
+        }
+    }
+}
+impl CommerceAvailability {
+    pub fn accepts_scheduled_orders(&self) -> bool {
+        !self.hours.is_empty()
+    }
+    fn local_time(&self, timestamp: i64) -> i64 {
+        timestamp + self.utc_offset_minutes as i64 * 60
+    }
+    pub fn is_open_at(&self, timestamp: i64) -> bool {
+        within_hours(&self.hours, timestamp, self.utc_offset_minutes)
+    }
+    /// When the order should be handed to the commerce so it's ready for the slot.
+    pub fn release_time(&self, slot: &DeliverySlot) -> i64 {
+        slot.start - self.lead_minutes as i64 * 60
+    }
+    pub fn upcoming_slots(&self, now: i64) -> Vec<DeliverySlot> {
+        let slot_seconds = self.slot_minutes.max(5) as i64 * 60;
+        let earliest = now + self.lead_minutes as i64 * 60;
+        let latest = now + MAX_SCHEDULE_AHEAD_SECONDS;
+        let offset = self.utc_offset_minutes as i64 * 60;
+        let first_day = self.local_time(earliest).div_euclid(SECONDS_IN_A_DAY);
+        let last_day = self.local_time(latest).div_euclid(SECONDS_IN_A_DAY);
+        let mut slots = vec![];
+        for day in first_day..=last_day {
+            let weekday = Weekday::from_epoch_day(day);
+            for hours in self.hours.iter().filter(|hours| hours.day == weekday) {
+                let opens = day * SECONDS_IN_A_DAY + hours.opens as i64 * 60 - offset;
+                let closes = day * SECONDS_IN_A_DAY + hours.closes as i64 * 60 - offset;
+                let mut start = opens;
+                while start + slot_seconds <= closes {
+                    if start >= earliest && start <= latest {
+                        slots.push(DeliverySlot {
+                            start,
+                            end: start + slot_seconds,
+                        });
+                    }
+                    start += slot_seconds;
+                }
+            }
+        }
+        slots.sort_by_key(|slot| slot.start);
+        slots.dedup();
+        slots
+    }
+    pub fn is_valid_slot(&self, slot: &DeliverySlot, now: i64) -> bool {
+        self.upcoming_slots(now).contains(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Monday 2025-03-03 12:00 UTC, 09:00 in Suriname
+    const MONDAY_NOON_UTC: i64 = 1_741_003_200;
+
+    fn weekday_hours() -> CommerceAvailability {
+        CommerceAvailability {
+            hours: vec![OpeningHours::new(Weekday::Monday, 9 * 60, 12 * 60)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn weekday_from_epoch() {
+        assert_eq!(Weekday::from_epoch_day(0), Weekday::Thursday);
+        assert_eq!(
+            Weekday::from_epoch_day(MONDAY_NOON_UTC / SECONDS_IN_A_DAY),
+            Weekday::Monday
+        );
+    }
+
+    #[test]
+    fn slots_respect_lead_time_and_closing() {
+        let availability = weekday_hours();
+        assert!(availability.is_open_at(MONDAY_NOON_UTC));
+        let slots = availability.upcoming_slots(MONDAY_NOON_UTC);
+        // 09:45 is the earliest start, so the first slot is 10:00 and the last 11:30
+        assert_eq!(slots.len(), 4);
+        assert_eq!(slots[0].start, MONDAY_NOON_UTC + 60 * 60);
+        assert_eq!(slots[3].end, MONDAY_NOON_UTC + 3 * 60 * 60);
+        assert_eq!(slots[0].display(-180), "Monday 10:00 - 10:30");
+        assert!(availability.is_valid_slot(&slots[1], MONDAY_NOON_UTC));
+        assert_eq!(
+            availability.release_time(&slots[0]),
+            MONDAY_NOON_UTC + 15 * 60
+        );
+    }
+
+    #[test]
+    fn no_hours_means_no_slots() {
+        let availability = CommerceAvailability::default();
+        assert!(!availability.accepts_scheduled_orders());
+        assert!(availability.upcoming_slots(MONDAY_NOON_UTC).is_empty());
+    }
+}
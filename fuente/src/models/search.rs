@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+
+use super::{ProductMenu, ProductTag};
+
+const WEIGHT_NAME: u32 = 3;
+const WEIGHT_CATEGORY: u32 = 2;
+const WEIGHT_TAG: u32 = 2;
+const WEIGHT_TEXT: u32 = 1;
+
+/// Words a tag is found by, in English and Dutch.
+fn tag_keywords(tag: ProductTag) -> &'static [&'static str] {
+    match tag {
+        ProductTag::Vegetarian => &["vegetarian", "veggie", "vegetarisch"],
+        ProductTag::Vegan => &["vegan", "veganistisch", "plantaardig"],
+        ProductTag::Halal => &["halal"],
+        ProductTag::GlutenFree => &["gluten free", "glutenvrij"],
+        ProductTag::ContainsNuts => &["nuts", "noten", "pinda"],
+        ProductTag::ContainsDairy => &["dairy", "milk", "zuivel", "melk"],
+        ProductTag::ContainsShellfish => &["shellfish", "schaaldieren", "garnalen", "shrimp"],
+        ProductTag::AgeRestricted => &["alcohol", "18+"],
+    }
+}
+
+fn fold_accent(c: char) -> &'static str {
+    match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => "a",
+        'ç' => "c",
+        'è' | 'é' | 'ê' | 'ë' => "e",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'ñ' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => "o",
+        'ù' | 'ú' | 'û' | 'ü' => "u",
+        'ý' | 'ÿ' => "y",
+        'ĳ' => "ij",
+        'æ' => "ae",
+        'œ' => "oe",
+        'ß' => "ss",
+        _ => "",
+    }
+}
+
+/// Drops plural endings so "pizzas", "broodjes" and "bananen" match their singular.
+fn stem(word: &str) -> String {
+    for suffix in ["'s", "es", "en", "s"] {
+        if let Some(stem) = word.strip_suffix(suffix) {
+            if stem.chars().count() >= 3 {
+                return stem.to_string();
+            }
+        }
+    }
+    word.to_string()
+}
+
+/// Lowercased, accent free and stemmed words of `text`.
+pub fn search_tokens(text: &str) -> Vec<String> {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.to_lowercase().chars() {
+        match fold_accent(c) {
+            "" if c.is_alphanumeric() || c == '\'' || c == '+' => folded.push(c),
+            "" => folded.push(' '),
+            replacement => folded.push_str(replacement),
+        }
+    }
+    folded
+        .split_whitespace()
+        .map(|word| word.trim_matches('\''))
+        .filter(|word| !word.is_empty())
+        .map(stem)
+        .collect()
+}
+
+/// Edit distance between `a` and `b`, giving up once it's over `max`.
+fn within_distance(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().min().is_some_and(|min| *min > max) {
+            return false;
+        }
+        previous = current;
+    }
+    previous[b.len()] <= max
+}
+
+/// How well one query word matches one indexed word, 0 when it doesn't.
+fn match_score(query: &str, word: &str) -> u32 {
+    if query == word {
+        return 3;
+    }
+    let length = query.chars().count();
+    if length >= 2 && word.starts_with(query) {
+        return 2;
+    }
+    let typos = match length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    if typos > 0 && within_distance(query, word, typos) {
+        return 1;
+    }
+    0
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SearchTarget {
+    Commerce,
+    Product(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SearchDocument {
+    commerce_id: String,
+    target: SearchTarget,
+    fields: Vec<(u32, Vec<String>)>,
+}
+impl SearchDocument {
+    /// Sum of the best weighted match of every query word, `None` unless all of them match.
+    fn score(&self, query: &[String]) -> Option<u32> {
+        query.iter().try_fold(0, |total, query_word| {
+            let best = self
+                .fields
+                .iter()
+                .flat_map(|(weight, words)| {
+                    words
+                        .iter()
+                        .map(move |word| weight * match_score(query_word, word))
+                })
+                .max()
+                .unwrap_or_default();
+            (best > 0).then_some(total + best)
+        })
+    }
+}
+
+/// Commerce matching a search, with the products that matched in order of relevance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommerceSearchResult {
+    pub commerce_id: String,
+    pub score: u32,
+    /// Whether the commerce itself matched, rather than only some of its products.
+    pub commerce_matched: bool,
+    pub product_ids: Vec<String>,
+}
+impl CommerceSearchResult {
+    fn new(commerce_id: &str) -> Self {
+        Self {
+            commerce_id: commerce_id.to_string(),
+            score: 0,
+            commerce_matched: false,
+            product_ids: vec![],
+        }
+    }
+}
+
+/// A matched product id with its score.
+type ScoredProduct = (u32, String);
+
+/// In-memory index over commerce profiles and menus, updated per commerce as notes arrive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchIndex {
+    documents: Vec<SearchDocument>,
+}
+impl SearchIndex {
+    pub fn set_commerce(&mut self, commerce_id: &str, name: &str, description: &str) {
+        self.documents.retain(|document| {
+            document.commerce_id != commerce_id || document.target != SearchTarget::Commerce
+        });
+        self.documents.push(SearchDocument {
+            commerce_id: commerce_id.to_string(),
+            target: SearchTarget::Commerce,
+            fields: vec![
+                (WEIGHT_NAME, search_tokens(name)),
+                (WEIGHT_TEXT, search_tokens(description)),
+            ],
+        });
+    }
+    /// Replaces the products indexed for the commerce with the ones on `menu`.
+    pub fn set_menu(&mut self, commerce_id: &str, menu: &ProductMenu) {
+        self.documents.retain(|document| {
+            document.commerce_id != commerce_id || document.target == SearchTarget::Commerce
+        });
+        for category in menu.categories() {
+            let category_words = search_tokens(&category.name());
+            for product in category.products() {
+                let tag_words = product
+                    .tags()
+                    .into_iter()
+                    .flat_map(|tag| {
+                        tag_keywords(tag)
+                            .iter()
+                            .flat_map(|word| search_tokens(word))
+                    })
+                    .collect();
+                let text = format!("{} {}", product.description(), product.details());
+                self.documents.push(SearchDocument {
+                    commerce_id: commerce_id.to_string(),
+                    target: SearchTarget::Product(product.id()),
+                    fields: vec![
+                        (WEIGHT_NAME, search_tokens(&product.name())),
+                        (WEIGHT_CATEGORY, category_words.clone()),
+                        (WEIGHT_TAG, tag_words),
+                        (WEIGHT_TEXT, search_tokens(&text)),
+                    ],
+                });
+            }
+        }
+    }
+    pub fn retain_commerces(&mut self, commerce_ids: &[String]) {
+        self.documents
+            .retain(|document| commerce_ids.contains(&document.commerce_id));
+    }
+    /// Matches grouped per commerce, best first.
+    pub fn search(&self, query: &str) -> Vec<CommerceSearchResult> {
+        let query = search_tokens(query);
+        if query.is_empty() {
+            return vec![];
+        }
+        let mut groups: HashMap<&String, (CommerceSearchResult, Vec<ScoredProduct>)> =
+            HashMap::new();
+        for document in self.documents.iter() {
+            let Some(score) = document.score(&query) else {
+                continue;
+            };
+            let (group, products) = groups
+                .entry(&document.commerce_id)
+                .or_insert_with(|| (CommerceSearchResult::new(&document.commerce_id), vec![]));
+            group.score = group.score.max(score);
+            match &document.target {
+                SearchTarget::Commerce => group.commerce_matched = true,
+                SearchTarget::Product(product_id) => products.push((score, product_id.clone())),
+            }
+        }
+        let mut results: Vec<CommerceSearchResult> = groups
+            .into_values()
+            .map(|(mut group, mut products)| {
+                products.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+                group.product_ids = products.into_iter().map(|(_, id)| id).collect();
+                group
+            })
+            .collect();
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(b.product_ids.len().cmp(&a.product_ids.len()))
+                .then(a.commerce_id.cmp(&b.commerce_id))
+        });
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Money, ProductCategory, ProductItem};
+
+    fn index() -> (SearchIndex, ProductItem, ProductItem) {
+        let mut menu = ProductMenu::new();
+        let category = ProductCategory::new(0, "Pizza's".into());
+        let margherita = ProductItem::new(
+            0,
+            "Margherita".into(),
+            Money::srd(9000),
+            "Tomaat en mozzarella".into(),
+            category.id(),
+        );
+        let mut crepe = ProductItem::new(
+            1,
+            "Crêpe".into(),
+            Money::srd(4000),
+            "".into(),
+            category.id(),
+        );
+        crepe.set_tags(vec![ProductTag::Vegan]);
+        menu.add_category(category.clone());
+        menu.add_product(category.id(), margherita.clone());
+        menu.add_product(category.id(), crepe.clone());
+
+        let mut index = SearchIndex::default();
+        index.set_commerce("napoli", "Napoli", "Italiaans restaurant");
+        index.set_menu("napoli", &menu);
+        index.set_commerce("roti", "Roti Shop", "Surinaamse keuken");
+        (index, margherita, crepe)
+    }
+
+    #[test]
+    fn words_are_folded_and_stemmed() {
+        assert_eq!(search_tokens("Crêpes & Pizza's"), vec!["crep", "pizza"]);
+        assert_eq!(search_tokens("BROODJES"), vec!["broodj"]);
+        assert_eq!(search_tokens("broodje"), vec!["broodje"]);
+    }
+
+    #[test]
+    fn products_are_found_across_commerces() {
+        let (index, margherita, crepe) = index();
+        let results = index.search("pizza");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].commerce_id, "napoli");
+        assert!(!results[0].commerce_matched);
+        assert_eq!(results[0].product_ids.len(), 2);
+
+        // Accents, typos, prefixes and Dutch tag names
+        assert_eq!(index.search("crepe")[0].product_ids, vec![crepe.id()]);
+        assert_eq!(
+            index.search("margarita")[0].product_ids,
+            vec![margherita.id()]
+        );
+        assert_eq!(index.search("mozz")[0].product_ids, vec![margherita.id()]);
+        assert_eq!(
+            index.search("veganistisch")[0].product_ids,
+            vec![crepe.id()]
+        );
+        assert!(index.search("sushi").is_empty());
+
+        let results = index.search("roti");
+        assert_eq!(results[0].commerce_id, "roti");
+        assert!(results[0].commerce_matched);
+    }
+
+    #[test]
+    fn menus_are_replaced_per_commerce() {
+        let (mut index, ..) = index();
+        index.set_menu("napoli", &ProductMenu::new());
+        assert!(index.search("margherita").is_empty());
+        assert_eq!(index.search("napoli").len(), 1);
+        index.retain_commerces(&["roti".to_string()]);
+        assert!(index.search("napoli").is_empty());
+    }
+}
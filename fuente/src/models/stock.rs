@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use nostro2::{keypair::NostrKeypair, notes::NostrNote};
+use serde::{Deserialize, Serialize};
+
+use super::{ProductMenu, NOSTR_KIND_COMMERCE_STOCK, TEST_PUB_KEY};
+
+/// Units left of each product the commerce keeps a stock count for, published
+/// by the server as paid orders take them and canceled orders give them back.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommerceStock {
+    pub commerce_id: String,
+    pub remaining: HashMap<String, u32>,
+}
+impl CommerceStock {
+    pub fn new(commerce_id: String, menu: &ProductMenu, sold: &HashMap<String, u32>) -> Self {
+        Self {
+            commerce_id,
+            remaining: menu.remaining_stock(sold),
+        }
+    }
+    pub fn signed_note(&self, keypair: &NostrKeypair) -> NostrNote {
+        let mut new_note = NostrNote {
+            kind: NOSTR_KIND_COMMERCE_STOCK,
+            content: self.to_string(),
+            pubkey: keypair.public_key(),
+            ..Default::default()
+        };
+        new_note.tags.add_parameter_tag(&self.commerce_id);
+        keypair.sign_nostr_event(&mut new_note);
+        new_note
+    }
+}
+impl ToString for CommerceStock {
+    fn to_string(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+impl TryFrom<&NostrNote> for CommerceStock {
+    type Error = anyhow::Error;
+    fn try_from(note: &NostrNote) -> Result<Self, Self::Error> {
+        if note.kind != NOSTR_KIND_COMMERCE_STOCK {
+            return Err(anyhow::anyhow!("Wrong Kind"));
+        }
+        if note.pubkey != TEST_PUB_KEY {
+            return Err(anyhow::anyhow!("Stock is only published by the server"));
+        }
+        Ok(serde_json::from_str(&note.content)?)
+    }
+}
@@ -4,17 +4,27 @@ use bright_lightning::{
     LndPaymentRequest, LndPaymentResponse, LndWebsocketMessage,
 };
 use fuente::models::{
-    CommerceProfile, OrderInvoiceState, OrderParticipant, OrderPaymentStatus, OrderRequest,
-    OrderStatus,
+    AppliedPromotion, CommerceProfile, CourierTipRequest, CourierTipState, CourierTipStatus,
+    DriverProfile, ExchangeRates, GroupShareState, GroupShareStatus, Money, OrderInvoiceState,
+    OrderParticipant, OrderPaymentStatus, OrderRequest, OrderStatus,
 };
 use nostro2::{keypair::NostrKeypair, notes::NostrNote};
 use tokio::sync::broadcast::Sender;
 
 use crate::state::InvoicerStateLock;
-pub const SATOSHIS_IN_ONE_BTC: f64 = 100_000_000.0;
 pub const MILISATOSHIS_IN_ONE_SATOSHI: u64 = 1000;
 pub const ILLUMINODES_FEES: u64 = 20;
 pub const FUENTE_FEES: u64 = 0;
+/// Tries at paying out a courier tip before it's recorded as failed.
+const TIP_PAYOUT_ATTEMPTS: u64 = 5;
+const TIP_PAYOUT_RETRY_SECONDS: u64 = 30;
+
+/// Why a courier tip wasn't paid out, payments that may still be in flight
+/// are not retried so the courier is never paid twice.
+enum TipPayoutError {
+    NotSent(anyhow::Error),
+    Unsettled(anyhow::Error),
+}
 
 #[derive(Clone)]
 pub struct Invoicer {
@@ -43,34 +53,95 @@ impl Invoicer {
             lightning_wallet,
         })
     }
+    /// Rounds up to the next satoshi so the payee never receives less than the priced amount.
+    pub async fn money_to_sats(
+        &self,
+        amount: Money,
+        exchange_rates: &ExchangeRates,
+    ) -> anyhow::Result<u64> {
+        let dollar_rate = Rates::find_usd_rate(&self.rest_client).await?;
+        exchange_rates.to_sats(amount, dollar_rate)
+    }
+    /// Priced once per order so all of its invoices use the same rate.
     pub async fn create_order_invoice(
         &self,
         order: &OrderRequest,
         commerce_profile: &CommerceProfile,
-        srd_dollar_exchange_rate: f64,
-    ) -> anyhow::Result<(LnAddressPaymentRequest, LndHodlInvoice)> {
-        let invoice_total_srd = order.products.total();
-        let dollar_amount = invoice_total_srd / srd_dollar_exchange_rate;
-        let dollar_rate = Rates::find_usd_rate(&self.rest_client).await?;
-        let invoice_satoshi_amount = dollar_amount / dollar_rate * SATOSHIS_IN_ONE_BTC;
+        exchange_rates: &ExchangeRates,
+        dollar_rate: f64,
+        promotion: Option<&AppliedPromotion>,
+        small_order_fee: Money,
+    ) -> anyhow::Result<(LnAddressPaymentRequest, LndHodlInvoice, u64)> {
+        let discount = promotion.map(|p| p.amount()).unwrap_or_default();
+        // Commerce promotions come out of the commerce's share, platform
+        // promotions are covered by the node when the HTLC is settled.
+        let commerce_discount = match promotion {
+            Some(p) if p.is_issued_by(&order.commerce) => discount,
+            _ => Money::default(),
+        };
+        // Participants of a split group order pay their lines on invoices of their own
+        // The small order fee goes to the commerce with the items
+        let items_total = order.host_total().checked_add(small_order_fee)?;
+        let invoice_satoshi_amount =
+            exchange_rates.to_sats(items_total.checked_sub(commerce_discount)?, dollar_rate)?;
+        let consumer_satoshi_amount =
+            exchange_rates.to_sats(items_total.checked_sub(discount)?, dollar_rate)?;
+        let tip_satoshi_amount = exchange_rates.to_sats(order.tip_amount(), dollar_rate)?;
         let invoice = commerce_profile
             .ln_address()
             .get_invoice(
                 &self.rest_client,
-                invoice_satoshi_amount as u64 * MILISATOSHIS_IN_ONE_SATOSHI,
+                invoice_satoshi_amount * MILISATOSHIS_IN_ONE_SATOSHI,
             )
             .await?;
-        let hodl_amount = invoice_satoshi_amount as u64 + ILLUMINODES_FEES + FUENTE_FEES;
+        // The tip is held with the order and paid out to the courier on delivery
+        let hodl_amount =
+            consumer_satoshi_amount + tip_satoshi_amount + ILLUMINODES_FEES + FUENTE_FEES;
         let hodl_invoice = self
             .lightning_wallet
             .get_hodl_invoice(invoice.r_hash()?, hodl_amount)
             .await?;
-        Ok((invoice, hodl_invoice))
+        Ok((invoice, hodl_invoice, tip_satoshi_amount))
+    }
+    /// One commerce invoice and hodl invoice per participant paying their
+    /// own lines, settled together with the order's.
+    pub async fn create_share_invoices(
+        &self,
+        order: &OrderRequest,
+        order_id: &str,
+        commerce_profile: &CommerceProfile,
+        exchange_rates: &ExchangeRates,
+        dollar_rate: f64,
+    ) -> anyhow::Result<Vec<GroupShareState>> {
+        let mut shares = vec![];
+        for share in order.group_shares.iter() {
+            let amount = order.share_total(share);
+            let sat_amount = exchange_rates.to_sats(amount, dollar_rate)?;
+            let invoice = commerce_profile
+                .ln_address()
+                .get_invoice(&self.rest_client, sat_amount * MILISATOSHIS_IN_ONE_SATOSHI)
+                .await?;
+            let hodl_invoice = self
+                .lightning_wallet
+                .get_hodl_invoice(invoice.r_hash()?, sat_amount + ILLUMINODES_FEES)
+                .await?;
+            shares.push(GroupShareState {
+                order_id: order_id.to_string(),
+                share: share.clone(),
+                amount,
+                sat_amount,
+                consumer_invoice: Some(hodl_invoice),
+                commerce_invoice: Some(invoice),
+                status: GroupShareStatus::Pending,
+            });
+        }
+        Ok(shares)
     }
     pub async fn order_payment_notifier(
         self,
         order_invoice: OrderInvoiceState,
         keys: NostrKeypair,
+        release_at: Option<i64>,
         state_clone: InvoicerStateLock,
         broadcaster: Sender<nostro2::relays::WebSocketMessage>,
     ) -> anyhow::Result<()> {
@@ -84,6 +155,7 @@ impl Invoicer {
             .await?;
         let iter = subscriber.receiver;
         let mut ping_counter = 0;
+        let mut accepted = false;
         while let Some(payment_response) = iter.read::<LndHodlInvoiceState>().await {
             match payment_response {
                 LndWebsocketMessage::Response(invoice_state) => match invoice_state.state() {
@@ -94,31 +166,74 @@ impl Invoicer {
                         broadcaster.send(giftwrapped.into())?;
                     }
                     HodlState::ACCEPTED => {
-                        let mut new_order = order_invoice.clone();
-                        new_order.order_status = OrderStatus::Pending;
-                        new_order.payment_status = OrderPaymentStatus::PaymentReceived;
-                        let (signed_update, giftwrapped) =
-                            new_order.giftwrapped_order(OrderParticipant::Consumer, &keys)?;
-                        let (_, giftwrapped_commerce) =
-                            new_order.giftwrapped_order(OrderParticipant::Commerce, &keys)?;
-                        state_clone.update_live_order(signed_update).await?;
-                        broadcaster.send(giftwrapped.into())?;
-                        broadcaster.send(giftwrapped_commerce.into())?;
+                        accepted = true;
+                        if order_invoice.group_shares.is_empty() {
+                            self.release_paid_order(
+                                order_invoice.clone(),
+                                release_at,
+                                &keys,
+                                &state_clone,
+                                &broadcaster,
+                            )
+                            .await?;
+                            continue;
+                        }
+                        let consumer = order_invoice.order.pubkey.clone();
+                        match state_clone
+                            .record_group_payment(&order_invoice.order_id(), &consumer)
+                            .await?
+                        {
+                            Some(paid) => {
+                                self.release_paid_order(
+                                    paid,
+                                    release_at,
+                                    &keys,
+                                    &state_clone,
+                                    &broadcaster,
+                                )
+                                .await?
+                            }
+                            None => {
+                                self.send_group_progress(
+                                    &order_invoice,
+                                    &keys,
+                                    &state_clone,
+                                    &broadcaster,
+                                )
+                                .await?
+                            }
+                        }
                     }
                     HodlState::SETTLED => {
-                        let mut new_order = order_invoice.clone();
+                        // Group orders carry the payments of their participants
+                        let mut new_order = state_clone
+                            .find_live_order(&order_invoice.order_id())
+                            .await
+                            .unwrap_or(order_invoice.clone());
                         new_order.order_status = OrderStatus::Preparing;
                         new_order.payment_status = OrderPaymentStatus::PaymentSuccess;
                         let (signed_update, giftwrapped) =
                             new_order.giftwrapped_order(OrderParticipant::Consumer, &keys)?;
                         let (_, giftwrapped_commerce) =
                             new_order.giftwrapped_order(OrderParticipant::Commerce, &keys)?;
+                        // Couriers see the order coming up while it's being prepared
+                        let (_, giftwrapped_courier) =
+                            new_order.giftwrapped_order(OrderParticipant::Courier, &keys)?;
                         state_clone.update_live_order(signed_update).await?;
                         broadcaster.send(giftwrapped.into())?;
                         broadcaster.send(giftwrapped_commerce.into())?;
+                        broadcaster.send(giftwrapped_courier.into())?;
+                        Self::send_delivery_eta(
+                            &new_order.order_id(),
+                            &keys,
+                            &state_clone,
+                            &broadcaster,
+                        )
+                        .await;
                         break;
                     }
                     HodlState::CANCELED => {
+                        self.cancel_share_htlcs(&order_invoice).await;
                         let mut new_order = order_invoice.clone();
                         new_order.order_status = OrderStatus::Canceled;
                         new_order.payment_status = OrderPaymentStatus::PaymentFailed;
@@ -126,16 +241,23 @@ impl Invoicer {
                             new_order.giftwrapped_order(OrderParticipant::Consumer, &keys)?;
                         let (_, giftwrapped_commerce) =
                             new_order.giftwrapped_order(OrderParticipant::Commerce, &keys)?;
+                        state_clone.release_stock(&new_order.order_id()).await;
                         state_clone
                             .remove_live_order(new_order.order_id().as_str())
                             .await?;
                         broadcaster.send(giftwrapped.into())?;
                         broadcaster.send(giftwrapped_commerce.into())?;
+                        if accepted && release_at.is_some() {
+                            // Keeps the order from being released again after a restart
+                            let (_, giftwrapped_hub) =
+                                new_order.giftwrapped_order(OrderParticipant::Courier, &keys)?;
+                            broadcaster.send(giftwrapped_hub.into())?;
+                        }
                         break;
                     }
                 },
                 LndWebsocketMessage::Error(_e) => {
-                    self.cancel_htlc(invoice.clone()).await?;
+                    self.cancel_order_htlcs(&order_invoice).await?;
                     let mut new_order = order_invoice.clone();
                     new_order.order_status = OrderStatus::Canceled;
                     new_order.payment_status = OrderPaymentStatus::PaymentFailed;
@@ -143,18 +265,31 @@ impl Invoicer {
                         new_order.giftwrapped_order(OrderParticipant::Consumer, &keys)?;
                     let (_, giftwrapped_commerce) =
                         new_order.giftwrapped_order(OrderParticipant::Commerce, &keys)?;
+                    state_clone.release_stock(&new_order.order_id()).await;
                     state_clone
                         .remove_live_order(new_order.order_id().as_str())
                         .await?;
                     broadcaster.send(giftwrapped.into())?;
                     broadcaster.send(giftwrapped_commerce.into())?;
+                    if accepted && release_at.is_some() {
+                        let (_, giftwrapped_hub) =
+                            new_order.giftwrapped_order(OrderParticipant::Courier, &keys)?;
+                        broadcaster.send(giftwrapped_hub.into())?;
+                    }
                     break;
                 }
                 _ => {
+                    // Once paid, scheduled orders are held until their release time
+                    // and group orders wait on their participants, so keepalive pings
+                    // don't mean the order went stale.
+                    if accepted && (release_at.is_some() || !order_invoice.group_shares.is_empty())
+                    {
+                        continue;
+                    }
                     ping_counter += 1;
                     if ping_counter > 5 {
                         tracing::warn!("Canceling HTLC due to inactivity");
-                        self.cancel_htlc(invoice.clone()).await?;
+                        self.cancel_order_htlcs(&order_invoice).await?;
                         let mut new_order = order_invoice.clone();
                         new_order.order_status = OrderStatus::Canceled;
                         new_order.payment_status = OrderPaymentStatus::PaymentFailed;
@@ -162,6 +297,7 @@ impl Invoicer {
                             new_order.giftwrapped_order(OrderParticipant::Consumer, &keys)?;
                         let (_, giftwrapped_commerce) =
                             new_order.giftwrapped_order(OrderParticipant::Commerce, &keys)?;
+                        state_clone.release_stock(&new_order.order_id()).await;
                         state_clone
                             .remove_live_order(new_order.order_id().as_str())
                             .await?;
@@ -172,6 +308,242 @@ impl Invoicer {
                 }
             }
         }
+        Self::send_stock_updates(&keys, &state_clone, &broadcaster).await;
+        Ok(())
+    }
+    /// Sends a paid order to the commerce, holding the stock it takes.
+    async fn release_paid_order(
+        &self,
+        mut new_order: OrderInvoiceState,
+        release_at: Option<i64>,
+        keys: &NostrKeypair,
+        state_clone: &InvoicerStateLock,
+        broadcaster: &Sender<nostro2::relays::WebSocketMessage>,
+    ) -> anyhow::Result<()> {
+        if let Err(e) = state_clone.reserve_stock(&new_order).await {
+            // The consumer gets refunded and the CANCELED update cleans up
+            tracing::warn!("Could not reserve stock: {}", e);
+            self.cancel_order_htlcs(&new_order).await?;
+            return Ok(());
+        }
+        new_order.order_status = match release_at {
+            Some(_) => OrderStatus::Scheduled,
+            None => OrderStatus::Pending,
+        };
+        new_order.payment_status = OrderPaymentStatus::PaymentReceived;
+        let (signed_update, giftwrapped) =
+            new_order.giftwrapped_order(OrderParticipant::Consumer, keys)?;
+        let (_, giftwrapped_commerce) =
+            new_order.giftwrapped_order(OrderParticipant::Commerce, keys)?;
+        state_clone.update_live_order(signed_update).await?;
+        broadcaster.send(giftwrapped.into())?;
+        broadcaster.send(giftwrapped_commerce.into())?;
+        if release_at.is_some() {
+            // The hub copy is what brings the order back after a restart
+            let (_, giftwrapped_hub) =
+                new_order.giftwrapped_order(OrderParticipant::Courier, keys)?;
+            broadcaster.send(giftwrapped_hub.into())?;
+        }
+        Self::send_delivery_eta(&new_order.order_id(), keys, state_clone, broadcaster).await;
+        Self::send_stock_updates(keys, state_clone, broadcaster).await;
+        if let Some(release_at) = release_at {
+            Self::schedule_release(
+                new_order.order_id(),
+                release_at,
+                keys,
+                state_clone,
+                broadcaster,
+            )
+            .await;
+        }
+        Ok(())
+    }
+    /// Holds a scheduled order back until its release time, once per order.
+    pub async fn schedule_release(
+        order_id: String,
+        release_at: i64,
+        keys: &NostrKeypair,
+        state_clone: &InvoicerStateLock,
+        broadcaster: &Sender<nostro2::relays::WebSocketMessage>,
+    ) {
+        if !state_clone.arm_scheduled_release(&order_id).await {
+            return;
+        }
+        let keys = keys.clone();
+        let state_clone = state_clone.clone();
+        let broadcaster = broadcaster.clone();
+        tokio::task::spawn(async move {
+            if let Err(e) = Self::release_scheduled_order(
+                order_id.clone(),
+                release_at,
+                keys,
+                state_clone,
+                broadcaster,
+            )
+            .await
+            {
+                tracing::error!("Could not release scheduled order {}: {:?}", order_id, e);
+            }
+        });
+    }
+    /// Lets the consumer know when their order should arrive, if that changed.
+    pub async fn send_delivery_eta(
+        order_id: &str,
+        keys: &NostrKeypair,
+        state_clone: &InvoicerStateLock,
+        broadcaster: &Sender<nostro2::relays::WebSocketMessage>,
+    ) {
+        match state_clone.delivery_eta_update(order_id, keys).await {
+            Ok(Some(eta)) => {
+                if let Err(e) = broadcaster.send(eta.into()) {
+                    tracing::error!("{:?}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Could not estimate delivery of {}: {}", order_id, e),
+        }
+    }
+    /// Publishes the stock left at the commerces whose orders took or gave back units.
+    pub async fn send_stock_updates(
+        keys: &NostrKeypair,
+        state_clone: &InvoicerStateLock,
+        broadcaster: &Sender<nostro2::relays::WebSocketMessage>,
+    ) {
+        for note in state_clone.stock_updates(keys).await {
+            if let Err(e) = broadcaster.send(note.into()) {
+                tracing::error!("{:?}", e);
+            }
+        }
+    }
+    /// Lets the consumer follow the payments of a group order still waiting
+    /// on some of its participants.
+    async fn send_group_progress(
+        &self,
+        order_invoice: &OrderInvoiceState,
+        keys: &NostrKeypair,
+        state_clone: &InvoicerStateLock,
+        broadcaster: &Sender<nostro2::relays::WebSocketMessage>,
+    ) -> anyhow::Result<()> {
+        let order = state_clone
+            .find_live_order(&order_invoice.order_id())
+            .await
+            .unwrap_or(order_invoice.clone());
+        let (_, giftwrapped) = order.giftwrapped_order(OrderParticipant::Consumer, keys)?;
+        broadcaster.send(giftwrapped.into())?;
+        Ok(())
+    }
+    async fn share_payment_notifier(
+        self,
+        mut share: GroupShareState,
+        order_invoice: OrderInvoiceState,
+        keys: NostrKeypair,
+        release_at: Option<i64>,
+        state_clone: InvoicerStateLock,
+        broadcaster: Sender<nostro2::relays::WebSocketMessage>,
+    ) -> anyhow::Result<()> {
+        let invoice = share
+            .commerce_invoice
+            .clone()
+            .ok_or(anyhow!("No invoice"))?;
+        let subscriber = self
+            .lightning_wallet
+            .subscribe_to_invoice(invoice.r_hash_url_safe()?)
+            .await?;
+        let iter = subscriber.receiver;
+        let mut ping_counter = 0;
+        while let Some(payment_response) = iter.read::<LndHodlInvoiceState>().await {
+            match payment_response {
+                LndWebsocketMessage::Response(invoice_state) => match invoice_state.state() {
+                    HodlState::OPEN => {
+                        broadcaster.send(share.giftwrapped_state(&keys)?.into())?;
+                    }
+                    HodlState::ACCEPTED => {
+                        share.status = GroupShareStatus::Paid;
+                        broadcaster.send(share.giftwrapped_state(&keys)?.into())?;
+                        match state_clone
+                            .record_group_payment(&share.order_id, &share.share.pubkey)
+                            .await?
+                        {
+                            Some(paid) => {
+                                self.release_paid_order(
+                                    paid,
+                                    release_at,
+                                    &keys,
+                                    &state_clone,
+                                    &broadcaster,
+                                )
+                                .await?
+                            }
+                            None => {
+                                self.send_group_progress(
+                                    &order_invoice,
+                                    &keys,
+                                    &state_clone,
+                                    &broadcaster,
+                                )
+                                .await?
+                            }
+                        }
+                    }
+                    HodlState::SETTLED => break,
+                    HodlState::CANCELED => {
+                        // One share falling through cancels the whole group
+                        share.status = GroupShareStatus::Failed;
+                        broadcaster.send(share.giftwrapped_state(&keys)?.into())?;
+                        if let Err(e) = self.cancel_order_htlcs(&order_invoice).await {
+                            tracing::warn!("Group order already canceled: {:?}", e);
+                        }
+                        break;
+                    }
+                },
+                LndWebsocketMessage::Error(_e) => {
+                    share.status = GroupShareStatus::Failed;
+                    broadcaster.send(share.giftwrapped_state(&keys)?.into())?;
+                    self.cancel_order_htlcs(&order_invoice).await?;
+                    break;
+                }
+                _ => {
+                    if share.status == GroupShareStatus::Paid {
+                        continue;
+                    }
+                    ping_counter += 1;
+                    if ping_counter > 5 {
+                        tracing::warn!("Canceling group share HTLC due to inactivity");
+                        share.status = GroupShareStatus::Failed;
+                        broadcaster.send(share.giftwrapped_state(&keys)?.into())?;
+                        self.cancel_order_htlcs(&order_invoice).await?;
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    async fn release_scheduled_order(
+        order_id: String,
+        release_at: i64,
+        keys: NostrKeypair,
+        state_clone: InvoicerStateLock,
+        broadcaster: Sender<nostro2::relays::WebSocketMessage>,
+    ) -> anyhow::Result<()> {
+        let wait = (release_at - unix_timestamp()).max(0) as u64;
+        tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+        let mut live_order = state_clone
+            .find_live_order(&order_id)
+            .await
+            .ok_or(anyhow!("Scheduled order not found"))?;
+        if live_order.order_status != OrderStatus::Scheduled {
+            return Ok(());
+        }
+        live_order.order_status = OrderStatus::Pending;
+        let (signed_update, giftwrapped) =
+            live_order.giftwrapped_order(OrderParticipant::Consumer, &keys)?;
+        let (_, giftwrapped_commerce) =
+            live_order.giftwrapped_order(OrderParticipant::Commerce, &keys)?;
+        state_clone.update_live_order(signed_update).await?;
+        broadcaster.send(giftwrapped.into())?;
+        broadcaster.send(giftwrapped_commerce.into())?;
+        tracing::info!("Released scheduled order {}", order_id);
         Ok(())
     }
     pub async fn new_order_invoice(
@@ -179,28 +551,357 @@ impl Invoicer {
         order: OrderRequest,
         signed_note: NostrNote,
         commerce: CommerceProfile,
-        exchange_rate: f64,
+        exchange_rates: ExchangeRates,
         keys: NostrKeypair,
         state_clone: InvoicerStateLock,
         broadcaster: Sender<nostro2::relays::WebSocketMessage>,
     ) -> anyhow::Result<OrderInvoiceState> {
-        let invoice = self
-            .create_order_invoice(&order, &commerce, exchange_rate)
+        let release_at = match &order.scheduled_for {
+            Some(slot) => {
+                if !commerce.availability.is_valid_slot(slot, unix_timestamp()) {
+                    return Err(anyhow!("Invalid delivery slot"));
+                }
+                Some(commerce.availability.release_time(slot))
+            }
+            None => None,
+        };
+        // Everything below adds the order up, which panics on mixed currencies
+        order.check_currency(commerce.currency)?;
+        state_clone.check_stock(&order).await?;
+        state_clone
+            .check_prices(
+                &order,
+                unix_timestamp(),
+                commerce.availability.utc_offset_minutes,
+            )
             .await?;
-        let state_update = OrderInvoiceState::new(
-            signed_note.clone(),
-            Some(invoice.1),
-            Some(invoice.0.clone()),
-        );
+        if !order.group_shares.is_empty() {
+            if order.promo_code.is_some() {
+                return Err(anyhow!("Promo codes can't be used when splitting the bill"));
+            }
+            if order.group_shares.iter().any(|share| {
+                share.pubkey == signed_note.pubkey || !order.share_total(share).is_positive()
+            }) {
+                return Err(anyhow!("Invalid group shares"));
+            }
+        }
+        let small_order_fee = commerce.order_minimum.surcharge(order.products.total())?;
+        let dollar_rate = Rates::find_usd_rate(&self.rest_client).await?;
+        let order_id = signed_note.id.clone().ok_or(anyhow!("Order has no id"))?;
+        let promotion = state_clone
+            .claim_promotion(&order, &order_id, &signed_note.pubkey, unix_timestamp())
+            .await?;
+        let state_update = async {
+            let invoice = self
+                .create_order_invoice(
+                    &order,
+                    &commerce,
+                    &exchange_rates,
+                    dollar_rate,
+                    promotion.as_ref(),
+                    small_order_fee,
+                )
+                .await?;
+            let mut state_update = OrderInvoiceState::new(
+                signed_note.clone(),
+                Some(invoice.1),
+                Some(invoice.0.clone()),
+            );
+            state_update.courier_tip_sats = invoice.2;
+            state_update.promotion = promotion.clone();
+            state_update.small_order_fee = small_order_fee.is_positive().then_some(small_order_fee);
+            state_update.group_shares = self
+                .create_share_invoices(&order, &order_id, &commerce, &exchange_rates, dollar_rate)
+                .await?;
+            anyhow::Ok(state_update)
+        }
+        .await;
+        let state_update = match state_update {
+            Ok(state_update) => state_update,
+            Err(e) => {
+                state_clone.release_promotion(&order_id).await;
+                return Err(e);
+            }
+        };
+        for share in state_update.group_shares.iter() {
+            tokio::task::spawn(self.clone().share_payment_notifier(
+                share.clone(),
+                state_update.clone(),
+                keys.clone(),
+                release_at,
+                state_clone.clone(),
+                broadcaster.clone(),
+            ));
+        }
         let task = self.clone().order_payment_notifier(
             state_update.clone(),
             keys,
+            release_at,
             state_clone,
             broadcaster,
         );
         tokio::task::spawn(task);
         Ok(state_update)
     }
+    pub async fn new_tip_invoice(
+        &self,
+        tip_request: CourierTipRequest,
+        live_order: OrderInvoiceState,
+        exchange_rates: ExchangeRates,
+        keys: NostrKeypair,
+        state_clone: InvoicerStateLock,
+        broadcaster: Sender<nostro2::relays::WebSocketMessage>,
+    ) -> anyhow::Result<()> {
+        let courier_note = live_order
+            .courier
+            .as_ref()
+            .ok_or(anyhow!("Order has no courier"))?;
+        let courier = DriverProfile::try_from(courier_note)?;
+        let sat_amount = self
+            .money_to_sats(tip_request.amount(), &exchange_rates)
+            .await?;
+        let courier_invoice = courier
+            .ln_address()
+            .ok_or(anyhow!("Courier has no lightning address"))?
+            .get_invoice(&self.rest_client, sat_amount * MILISATOSHIS_IN_ONE_SATOSHI)
+            .await?;
+        let consumer_invoice = self
+            .lightning_wallet
+            .get_hodl_invoice(courier_invoice.r_hash()?, sat_amount + ILLUMINODES_FEES)
+            .await?;
+        let tip_state = CourierTipState {
+            request: tip_request,
+            consumer_invoice: Some(consumer_invoice),
+            courier_invoice: Some(courier_invoice),
+            sat_amount,
+            status: CourierTipStatus::Pending,
+        };
+        let task = self.clone().tip_payment_notifier(
+            tip_state,
+            live_order,
+            keys,
+            state_clone,
+            broadcaster,
+        );
+        tokio::task::spawn(task);
+        Ok(())
+    }
+    async fn tip_payment_notifier(
+        self,
+        mut tip_state: CourierTipState,
+        live_order: OrderInvoiceState,
+        keys: NostrKeypair,
+        state_clone: InvoicerStateLock,
+        broadcaster: Sender<nostro2::relays::WebSocketMessage>,
+    ) -> anyhow::Result<()> {
+        let consumer = live_order.order.pubkey.clone();
+        let invoice = tip_state
+            .courier_invoice
+            .clone()
+            .ok_or(anyhow!("No invoice"))?;
+        let subscriber = self
+            .lightning_wallet
+            .subscribe_to_invoice(invoice.r_hash_url_safe()?)
+            .await?;
+        let iter = subscriber.receiver;
+        let mut ping_counter = 0;
+        while let Some(payment_response) = iter.read::<LndHodlInvoiceState>().await {
+            match payment_response {
+                LndWebsocketMessage::Response(invoice_state) => match invoice_state.state() {
+                    HodlState::OPEN => {
+                        broadcaster
+                            .send(tip_state.giftwrapped_state(&keys, consumer.clone())?.into())?;
+                    }
+                    HodlState::ACCEPTED => {
+                        self.settle_htlc(invoice.clone()).await?;
+                    }
+                    HodlState::SETTLED => {
+                        tip_state.status = CourierTipStatus::Paid;
+                        broadcaster
+                            .send(tip_state.giftwrapped_state(&keys, consumer.clone())?.into())?;
+                        let mut order = state_clone
+                            .find_live_order(&live_order.order_id())
+                            .await
+                            .unwrap_or(live_order.clone());
+                        // Settling the HTLC paid the courier's own invoice
+                        order.courier_tip_sats += tip_state.sat_amount;
+                        order.courier_tip_paid_sats += tip_state.sat_amount;
+                        let (signed_update, giftwrapped) =
+                            order.giftwrapped_order(OrderParticipant::Courier, &keys)?;
+                        let (_, giftwrapped_consumer) =
+                            order.giftwrapped_order(OrderParticipant::Consumer, &keys)?;
+                        state_clone.update_live_order(signed_update).await?;
+                        broadcaster.send(giftwrapped.into())?;
+                        broadcaster.send(giftwrapped_consumer.into())?;
+                        break;
+                    }
+                    HodlState::CANCELED => {
+                        tip_state.status = CourierTipStatus::Failed;
+                        broadcaster
+                            .send(tip_state.giftwrapped_state(&keys, consumer.clone())?.into())?;
+                        break;
+                    }
+                },
+                LndWebsocketMessage::Error(_e) => {
+                    self.cancel_htlc(invoice.clone()).await?;
+                    tip_state.status = CourierTipStatus::Failed;
+                    broadcaster
+                        .send(tip_state.giftwrapped_state(&keys, consumer.clone())?.into())?;
+                    break;
+                }
+                _ => {
+                    ping_counter += 1;
+                    if ping_counter > 5 {
+                        tracing::warn!("Canceling tip HTLC due to inactivity");
+                        self.cancel_htlc(invoice.clone()).await?;
+                        tip_state.status = CourierTipStatus::Failed;
+                        broadcaster
+                            .send(tip_state.giftwrapped_state(&keys, consumer.clone())?.into())?;
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Pays out the tip held with the order once the courier completes the
+    /// delivery, retrying with a growing delay before recording the payout as
+    /// failed so it can be settled by hand.
+    pub async fn pay_courier_tip(
+        &self,
+        order: OrderInvoiceState,
+        keys: NostrKeypair,
+        state_clone: InvoicerStateLock,
+        broadcaster: Sender<nostro2::relays::WebSocketMessage>,
+    ) -> anyhow::Result<()> {
+        let tip_sats = order.courier_tip_sats;
+        let mut attempt = 1;
+        let payout = loop {
+            match self.send_courier_tip(&order, tip_sats).await {
+                Ok(()) => break Ok(()),
+                // A payment that may still be in flight is never sent twice
+                Err(TipPayoutError::Unsettled(e)) => break Err(e),
+                Err(TipPayoutError::NotSent(e)) if attempt >= TIP_PAYOUT_ATTEMPTS => break Err(e),
+                Err(TipPayoutError::NotSent(e)) => {
+                    tracing::warn!(
+                        "Attempt {} at paying the courier tip of order {} failed: {}",
+                        attempt,
+                        order.order_id(),
+                        e
+                    );
+                }
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(
+                TIP_PAYOUT_RETRY_SECONDS * attempt,
+            ))
+            .await;
+            attempt += 1;
+        };
+        let mut order = state_clone
+            .find_live_order(&order.order_id())
+            .await
+            .unwrap_or(order);
+        match payout {
+            Ok(()) => {
+                tracing::info!("Paid courier tip for order {}", order.order_id());
+                order.tip_payout = Some(CourierTipStatus::Paid);
+                order.courier_tip_paid_sats += tip_sats;
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Gave up paying the courier tip of order {}: {}",
+                    order.order_id(),
+                    e
+                );
+                order.tip_payout = Some(CourierTipStatus::Failed);
+            }
+        }
+        let (signed_update, giftwrapped) =
+            order.giftwrapped_order(OrderParticipant::Courier, &keys)?;
+        state_clone.update_live_order(signed_update).await?;
+        broadcaster.send(giftwrapped.into())?;
+        Ok(())
+    }
+    async fn send_courier_tip(
+        &self,
+        order: &OrderInvoiceState,
+        tip_sats: u64,
+    ) -> Result<(), TipPayoutError> {
+        let invoice = async {
+            let courier_note = order.courier.as_ref().ok_or(anyhow!("No courier"))?;
+            let courier = DriverProfile::try_from(courier_note)?;
+            courier
+                .ln_address()
+                .ok_or(anyhow!("Courier has no lightning address"))?
+                .get_invoice(&self.rest_client, tip_sats * MILISATOSHIS_IN_ONE_SATOSHI)
+                .await
+        }
+        .await
+        .map_err(TipPayoutError::NotSent)?;
+        let payment_req = LndPaymentRequest::new(invoice.pr, 1000, 150.to_string(), false);
+        let lnd_ws = self
+            .lightning_wallet
+            .invoice_channel()
+            .await
+            .map_err(TipPayoutError::NotSent)?;
+        lnd_ws
+            .sender
+            .send(payment_req)
+            .await
+            .map_err(|e| TipPayoutError::NotSent(anyhow!("{:?}", e)))?;
+        let event_stream = lnd_ws.receiver;
+        let mut ping_counter = 0;
+        while let Some(ws_msg) = event_stream.read::<LndPaymentResponse>().await {
+            match ws_msg {
+                LndWebsocketMessage::Response(payment_status) => match payment_status.status() {
+                    InvoicePaymentState::Succeeded => return Ok(()),
+                    InvoicePaymentState::Failed => {
+                        return Err(TipPayoutError::NotSent(anyhow!("Payment failed")))
+                    }
+                    _ => {}
+                },
+                LndWebsocketMessage::Ping => {
+                    ping_counter += 1;
+                    if ping_counter > 5 {
+                        return Err(TipPayoutError::Unsettled(anyhow!("Payment timed out")));
+                    }
+                }
+                LndWebsocketMessage::Error(e) => {
+                    return Err(TipPayoutError::Unsettled(anyhow!("{:?}", e)))
+                }
+            }
+        }
+        Err(TipPayoutError::Unsettled(anyhow!("Payment stream closed")))
+    }
+    /// Cancels the order's HTLC and those of its group shares.
+    pub async fn cancel_order_htlcs(&self, order: &OrderInvoiceState) -> anyhow::Result<()> {
+        self.cancel_share_htlcs(order).await;
+        let invoice = order
+            .commerce_invoice
+            .as_ref()
+            .cloned()
+            .ok_or(anyhow!("No invoice"))?;
+        self.cancel_htlc(invoice).await
+    }
+    pub async fn cancel_share_htlcs(&self, order: &OrderInvoiceState) {
+        for share in order.group_shares.iter() {
+            let Some(invoice) = share.commerce_invoice.clone() else {
+                continue;
+            };
+            if let Err(e) = self.cancel_htlc(invoice).await {
+                tracing::warn!("Could not cancel group share HTLC: {:?}", e);
+            }
+        }
+    }
+    /// Settles the HTLCs of the participants who paid their own share.
+    pub async fn settle_share_htlcs(&self, order: &OrderInvoiceState) -> anyhow::Result<()> {
+        for share in order.group_shares.iter() {
+            if let Some(invoice) = share.commerce_invoice.clone() {
+                self.settle_htlc(invoice).await?;
+            }
+        }
+        Ok(())
+    }
     pub async fn cancel_htlc(&self, invoice: LnAddressPaymentRequest) -> anyhow::Result<()> {
         self.lightning_wallet
             .cancel_htlc(invoice.r_hash_url_safe()?)
@@ -239,6 +940,13 @@ impl Invoicer {
     }
 }
 
+pub fn unix_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct Currency {
     name: String,
@@ -299,5 +1007,4 @@ mod tests {
         let dollar_rate = Rates::find_usd_rate(&client).await.unwrap();
         assert!(dollar_rate > 0.0);
     }
-
 }
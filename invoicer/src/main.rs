@@ -5,15 +5,18 @@ mod uploads;
 
 use anyhow::anyhow;
 use fuente::models::{
-    CommerceProfile, DriverProfile, OrderInvoiceState, OrderParticipant, OrderPaymentStatus,
-    OrderRequest, OrderStatus, OrderUpdateRequest, ProductMenu, DRIVER_HUB_PUB_KEY,
-    NOSTR_KIND_ADMIN_REQUEST, NOSTR_KIND_COMMERCE_PRODUCTS, NOSTR_KIND_COMMERCE_PROFILE,
-    NOSTR_KIND_COMMERCE_UPDATE, NOSTR_KIND_CONSUMER_CANCEL, NOSTR_KIND_CONSUMER_ORDER_REQUEST,
-    NOSTR_KIND_CONSUMER_REGISTRY, NOSTR_KIND_COURIER_PROFILE, NOSTR_KIND_COURIER_UPDATE,
-    NOSTR_KIND_ORDER_STATE, NOSTR_KIND_PRESIGNED_URL_REQ, NOSTR_KIND_PRESIGNED_URL_RESP,
-    NOSTR_KIND_SERVER_CONFIG, NOSTR_KIND_SERVER_REQUEST, TEST_PUB_KEY,
+    CommerceProfile, CourierTipRequest, CourierTipStatus, DriverProfile, DriverStateUpdate,
+    OrderInvoiceState, OrderParticipant, OrderPaymentStatus, OrderRequest, OrderStatus,
+    OrderUpdateRequest, PrepTime, PromotionList, DRIVER_HUB_PUB_KEY, NOSTR_KIND_ADMIN_REQUEST,
+    NOSTR_KIND_COMMERCE_MENU_CATEGORY, NOSTR_KIND_COMMERCE_PRODUCTS, NOSTR_KIND_COMMERCE_PROFILE,
+    NOSTR_KIND_COMMERCE_PROMOTIONS, NOSTR_KIND_COMMERCE_UPDATE, NOSTR_KIND_CONSUMER_CANCEL,
+    NOSTR_KIND_CONSUMER_ORDER_REQUEST, NOSTR_KIND_CONSUMER_REGISTRY,
+    NOSTR_KIND_CONSUMER_TIP_REQUEST, NOSTR_KIND_COURIER_PROFILE, NOSTR_KIND_COURIER_UPDATE,
+    NOSTR_KIND_DRIVER_STATE, NOSTR_KIND_ORDER_STATE, NOSTR_KIND_PRESIGNED_URL_REQ,
+    NOSTR_KIND_PRESIGNED_URL_RESP, NOSTR_KIND_SERVER_CONFIG, NOSTR_KIND_SERVER_REQUEST,
+    TEST_PUB_KEY,
 };
-use invoicer::Invoicer;
+use invoicer::{unix_timestamp, Invoicer};
 use nostro2::{
     keypair::NostrKeypair,
     notes::NostrNote,
@@ -88,6 +91,7 @@ impl InvoicerBot {
                 NOSTR_KIND_ADMIN_REQUEST,
                 NOSTR_KIND_PRESIGNED_URL_REQ,
                 NOSTR_KIND_COURIER_PROFILE,
+                NOSTR_KIND_DRIVER_STATE,
             ]),
             ..Default::default()
         };
@@ -96,6 +100,8 @@ impl InvoicerBot {
             kinds: Some(vec![
                 NOSTR_KIND_COMMERCE_PROFILE,
                 NOSTR_KIND_COMMERCE_PRODUCTS,
+                NOSTR_KIND_COMMERCE_MENU_CATEGORY,
+                NOSTR_KIND_COMMERCE_PROMOTIONS,
                 NOSTR_KIND_CONSUMER_REGISTRY,
             ]),
             ..Default::default()
@@ -147,9 +153,42 @@ impl InvoicerBot {
             NOSTR_KIND_ORDER_STATE => {
                 let decrypted = self.server_keys.decrypt_nip_44_content(&signed_note)?;
                 let inner_note = NostrNote::try_from(decrypted)?;
-                let _ = OrderInvoiceState::try_from(&inner_note)?;
+                let order_id = OrderInvoiceState::try_from(&inner_note)?.order_id();
                 self.bot_state.update_live_order(inner_note).await?;
                 tracing::info!("Order state updated");
+                // Scheduled orders read back after a restart still need releasing
+                let scheduled = self
+                    .bot_state
+                    .find_live_order(&order_id)
+                    .await
+                    .filter(|order| order.order_status == OrderStatus::Scheduled);
+                if let Some(order) = scheduled {
+                    if let Some(release_at) = self.bot_state.scheduled_release_time(&order).await {
+                        Invoicer::schedule_release(
+                            order.order_id(),
+                            release_at,
+                            &self.server_keys,
+                            &self.bot_state,
+                            &self.broadcaster,
+                        )
+                        .await;
+                    }
+                }
+            }
+            NOSTR_KIND_DRIVER_STATE => {
+                let decrypted = self.server_keys.decrypt_nip_44_content(&signed_note)?;
+                let state_update = DriverStateUpdate::try_from(decrypted)?;
+                if let Some(order_id) = self
+                    .bot_state
+                    .record_courier_position(
+                        &signed_note.pubkey,
+                        signed_note.created_at,
+                        state_update.get_location().into(),
+                    )
+                    .await
+                {
+                    self.send_delivery_eta(&order_id).await;
+                }
             }
             _ => {
                 if let Err(e) = self
@@ -160,6 +199,8 @@ impl InvoicerBot {
                 }
             }
         }
+        // Order updates and new stock counts both move the stock left
+        Invoicer::send_stock_updates(&self.server_keys, &self.bot_state, &self.broadcaster).await;
         Ok(())
     }
     async fn handle_public_notes(
@@ -173,11 +214,15 @@ impl InvoicerBot {
                 self.bot_state.add_commerce_profile(signed_note).await?;
                 tracing::info!("Added commerce profile");
             }
-            NOSTR_KIND_COMMERCE_PRODUCTS => {
-                ProductMenu::try_from(signed_note.clone())?;
+            NOSTR_KIND_COMMERCE_PRODUCTS | NOSTR_KIND_COMMERCE_MENU_CATEGORY => {
                 self.bot_state.add_commerce_menu(signed_note).await?;
                 tracing::info!("Added menu");
             }
+            NOSTR_KIND_COMMERCE_PROMOTIONS => {
+                PromotionList::try_from(&signed_note)?;
+                self.bot_state.add_commerce_promotions(signed_note).await?;
+                tracing::info!("Added promotions");
+            }
             NOSTR_KIND_COURIER_PROFILE => {
                 let inner_note = self.server_keys.decrypt_nip_44_content(&signed_note)?;
                 let driver_note = NostrNote::try_from(inner_note)?;
@@ -206,21 +251,35 @@ impl InvoicerBot {
         match inner_note.kind {
             NOSTR_KIND_CONSUMER_ORDER_REQUEST => {
                 let order_req = OrderRequest::try_from(&inner_note)?;
-                let registered_commerce = self
-                    .bot_state
-                    .find_commerce(order_req.commerce.as_str())
-                    .await?;
-                self.invoicer
-                    .new_order_invoice(
-                        order_req,
-                        inner_note,
-                        registered_commerce.0,
-                        self.bot_state.exchange_rate().await,
-                        self.server_keys.clone(),
-                        self.bot_state.clone(),
-                        self.broadcaster.clone(),
-                    )
-                    .await?;
+                let created = async {
+                    let registered_commerce = self
+                        .bot_state
+                        .find_commerce(order_req.commerce.as_str())
+                        .await?;
+                    self.invoicer
+                        .new_order_invoice(
+                            order_req,
+                            inner_note.clone(),
+                            registered_commerce.0,
+                            self.bot_state.exchange_rates().await,
+                            self.server_keys.clone(),
+                            self.bot_state.clone(),
+                            self.broadcaster.clone(),
+                        )
+                        .await
+                }
+                .await;
+                if let Err(e) = created {
+                    // Lets the consumer stop waiting for an invoice that won't come
+                    let mut refused = OrderInvoiceState::new(inner_note, None, None);
+                    refused.order_status = OrderStatus::Canceled;
+                    refused.payment_status = OrderPaymentStatus::PaymentFailed;
+                    refused.refusal = Some(e.to_string());
+                    let (_, giftwrapped) =
+                        refused.giftwrapped_order(OrderParticipant::Consumer, &self.server_keys)?;
+                    self.broadcaster.send(giftwrapped.into())?;
+                    return Err(e);
+                }
             }
             NOSTR_KIND_CONSUMER_CANCEL => {
                 let update_req = OrderUpdateRequest::try_from(inner_note)?;
@@ -246,6 +305,33 @@ impl InvoicerBot {
                 self.broadcaster.send(commerce_giftwrap.into())?;
                 tracing::info!("Order canceled");
             }
+            NOSTR_KIND_CONSUMER_TIP_REQUEST => {
+                let tip_request = CourierTipRequest::try_from(&inner_note)?;
+                if !tip_request.amount().is_positive() {
+                    return Err(anyhow!("Tip has to be positive"));
+                }
+                let live_order = self
+                    .bot_state
+                    .find_live_order(tip_request.order_id.as_str())
+                    .await
+                    .ok_or(anyhow!("Order not found"))?;
+                if live_order.order.pubkey != outer_note.pubkey {
+                    return Err(anyhow!("Unauthorized"));
+                }
+                if live_order.order_status != OrderStatus::Completed {
+                    return Err(anyhow!("Order not delivered yet"));
+                }
+                self.invoicer
+                    .new_tip_invoice(
+                        tip_request,
+                        live_order,
+                        self.bot_state.exchange_rates().await,
+                        self.server_keys.clone(),
+                        self.bot_state.clone(),
+                        self.broadcaster.clone(),
+                    )
+                    .await?;
+            }
             NOSTR_KIND_COMMERCE_UPDATE => {
                 self.handle_commerce_updates(inner_note, outer_note).await?;
             }
@@ -302,14 +388,39 @@ impl InvoicerBot {
         }
         match commerce_update.status_update {
             OrderStatus::Preparing => {
+                if invoice_state.order_status == OrderStatus::Scheduled {
+                    return Err(anyhow!("Order is still scheduled"));
+                }
+                if let Some(minutes) = commerce_update.prep_minutes {
+                    let preparation = PrepTime::new(minutes, unix_timestamp())?;
+                    self.bot_state
+                        .set_preparation(&invoice_state.order_id(), preparation)
+                        .await?;
+                }
                 let invoice = invoice_state
                     .commerce_invoice
                     .as_ref()
                     .cloned()
                     .ok_or(anyhow!("No invoice"))?;
                 self.invoicer.settle_htlc(invoice).await?;
+                if let Some(live_order) = self
+                    .bot_state
+                    .find_live_order(&invoice_state.order_id())
+                    .await
+                {
+                    self.invoicer.settle_share_htlcs(&live_order).await?;
+                }
             }
             OrderStatus::ReadyForDelivery => {
+                // A courier may have taken the order while it was being prepared
+                if let Some(live_order) = self
+                    .bot_state
+                    .find_live_order(&invoice_state.order_id())
+                    .await
+                {
+                    invoice_state.courier = live_order.courier;
+                    invoice_state.preparation = live_order.preparation;
+                }
                 invoice_state.order_status = OrderStatus::ReadyForDelivery;
                 let (update, giftwrap) = invoice_state
                     .giftwrapped_order(OrderParticipant::Commerce, &self.server_keys)?;
@@ -321,6 +432,7 @@ impl InvoicerBot {
                 self.broadcaster.send(giftwrap.into())?;
                 self.broadcaster.send(consumer_giftwrap.into())?;
                 self.broadcaster.send(courier_giftwrap.into())?;
+                self.send_delivery_eta(&invoice_state.order_id()).await;
             }
             OrderStatus::Canceled => {
                 invoice_state.order_status = OrderStatus::Canceled;
@@ -371,14 +483,36 @@ impl InvoicerBot {
             let (_, commerce_giftwrap) =
                 live_order.giftwrapped_order(OrderParticipant::Commerce, &self.server_keys)?;
             self.broadcaster.send(commerce_giftwrap.into())?;
+            self.send_delivery_eta(&live_order.order_id()).await;
             return Ok(());
         }
         match (&live_order.payment_status, &live_order.order_status) {
             (_, OrderStatus::Completed) => {}
             (_, OrderStatus::Canceled) => {}
             (OrderPaymentStatus::PaymentFailed, _) => {}
+            (_, OrderStatus::Preparing) => {
+                return Err(anyhow!("Order is still being prepared"));
+            }
             _ => {
                 live_order.order_status = order_state.status_update;
+                if live_order.order_status == OrderStatus::Completed
+                    && live_order.courier_tip_sats > 0
+                {
+                    live_order.tip_payout = Some(CourierTipStatus::Pending);
+                    let invoicer = self.invoicer.clone();
+                    let order = live_order.clone();
+                    let keys = self.server_keys.clone();
+                    let state = self.bot_state.clone();
+                    let broadcaster = self.broadcaster.clone();
+                    tokio::task::spawn(async move {
+                        if let Err(e) = invoicer
+                            .pay_courier_tip(order, keys, state, broadcaster)
+                            .await
+                        {
+                            tracing::error!("{:?}", e);
+                        }
+                    });
+                }
                 let (update, giftwrap) =
                     live_order.giftwrapped_order(OrderParticipant::Courier, &self.server_keys)?;
                 self.bot_state.update_live_order(update).await?;
@@ -389,9 +523,19 @@ impl InvoicerBot {
                 let (_, commerce_giftwrap) =
                     live_order.giftwrapped_order(OrderParticipant::Commerce, &self.server_keys)?;
                 self.broadcaster.send(commerce_giftwrap.into())?;
+                self.send_delivery_eta(&live_order.order_id()).await;
                 return Ok(());
             }
         }
         Err(anyhow!("Order state channel closed"))
     }
+    async fn send_delivery_eta(&self, order_id: &str) {
+        Invoicer::send_delivery_eta(
+            order_id,
+            &self.server_keys,
+            &self.bot_state,
+            &self.broadcaster,
+        )
+        .await
+    }
 }
@@ -1,3 +1,4 @@
+use fuente::models::ProductMenuIdb;
 use nostro2::notes::NostrNote;
 use std::collections::HashMap;
 
@@ -21,25 +22,36 @@ impl CommerceRegistry {
             if let Some(profile) = new_entry.profile {
                 old_entry.profile = Some(profile);
             }
-            if let Some(menu) = new_entry.menu {
-                old_entry.menu = Some(menu);
+            if let Some(promotions) = new_entry.promotions {
+                old_entry.promotions = Some(promotions);
             }
         } else {
             self.commerce.insert(commerce_id, new_entry);
         }
     }
+    /// Adds a menu event to the commerce's menu, chunked menus are assembled as their events arrive.
+    pub fn update_menu(&mut self, commerce_id: String, note: NostrNote) -> anyhow::Result<()> {
+        let entry = self.commerce.entry(commerce_id).or_default();
+        match entry.menu.as_mut() {
+            Some(menu) => menu.apply_note(note)?,
+            None => entry.menu = Some(ProductMenuIdb::try_from(note)?),
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CommerceRegistryEntry {
     pub profile: Option<NostrNote>,
-    pub menu: Option<NostrNote>,
+    pub menu: Option<ProductMenuIdb>,
+    pub promotions: Option<NostrNote>,
 }
 impl Default for CommerceRegistryEntry {
     fn default() -> Self {
         Self {
             profile: None,
             menu: None,
+            promotions: None,
         }
     }
 }
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use fuente::models::{CoordinateStrings, CourierTrack, DeliveryEta, OrderStatus};
+
+/// What the ETA of each live order is worked out from, and the last one sent.
+#[derive(Debug, Clone)]
+pub struct DeliveryTracking {
+    orders: HashMap<String, TrackedDelivery>,
+}
+impl Default for DeliveryTracking {
+    fn default() -> Self {
+        Self {
+            orders: HashMap::new(),
+        }
+    }
+}
+impl DeliveryTracking {
+    pub fn get_delivery(&self, order_id: &str) -> Option<&TrackedDelivery> {
+        self.orders.get(order_id)
+    }
+    /// Notes when the commerce started on the order, forgets it once it's done.
+    pub fn update_status(&mut self, order_id: &str, status: OrderStatus, now: i64) {
+        match status {
+            OrderStatus::Completed | OrderStatus::Canceled => self.remove_delivery(order_id),
+            OrderStatus::Preparing => {
+                let delivery = self.orders.entry(order_id.to_string()).or_default();
+                delivery.preparing_since.get_or_insert(now);
+            }
+            _ => {}
+        }
+    }
+    pub fn remove_delivery(&mut self, order_id: &str) {
+        self.orders.remove(order_id);
+    }
+    pub fn record_position(&mut self, order_id: &str, timestamp: i64, position: CoordinateStrings) {
+        self.orders
+            .entry(order_id.to_string())
+            .or_default()
+            .courier
+            .record(timestamp, position);
+    }
+    /// Keeps the estimate if the consumer hasn't heard about it yet.
+    pub fn publish(&mut self, eta: DeliveryEta) -> bool {
+        let delivery = self.orders.entry(eta.order_id.clone()).or_default();
+        if delivery
+            .published
+            .as_ref()
+            .is_some_and(|published| !eta.differs_from(published))
+        {
+            return false;
+        }
+        delivery.published = Some(eta);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TrackedDelivery {
+    pub preparing_since: Option<i64>,
+    pub courier: CourierTrack,
+    published: Option<DeliveryEta>,
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use anyhow::anyhow;
-use fuente::models::OrderInvoiceState;
+use fuente::models::{CourierTipStatus, OrderInvoiceState, OrderStatus};
 
 #[derive(Debug, Clone)]
 pub struct LiveOrders {
@@ -23,9 +23,38 @@ impl LiveOrders {
         order_id: String,
         order: OrderInvoiceState,
     ) -> anyhow::Result<()> {
+        // Finished orders stay finished when older states are read back, only
+        // the tips paid to the courier after delivery still move on
+        if self.orders.get(&order_id).is_some_and(|known| {
+            matches!(
+                known.order_status,
+                OrderStatus::Completed | OrderStatus::Canceled
+            ) && (known.order_status != order.order_status
+                || tip_progress(&order) <= tip_progress(known))
+        }) {
+            return Ok(());
+        }
         self.orders.insert(order_id, order);
         Ok(())
     }
+    pub fn has_orders_from(&self, consumer: &str) -> bool {
+        self.orders.values().any(|order| {
+            order.order.pubkey == consumer && order.order_status != OrderStatus::Canceled
+        })
+    }
+    /// The order the courier took and hasn't finished yet.
+    pub fn find_courier_order(&self, courier: &str) -> Option<&OrderInvoiceState> {
+        self.orders.values().find(|order| {
+            order
+                .courier
+                .as_ref()
+                .is_some_and(|note| note.pubkey == courier)
+                && !matches!(
+                    order.order_status,
+                    OrderStatus::Completed | OrderStatus::Canceled
+                )
+        })
+    }
     pub fn remove_order(&mut self, order_id: &str) -> anyhow::Result<()> {
         self.orders
             .remove(order_id)
@@ -33,3 +62,13 @@ impl LiveOrders {
         Ok(())
     }
 }
+/// Tip payouts only move forward, so the furthest along state is the newest.
+fn tip_progress(order: &OrderInvoiceState) -> (u64, u64, u8) {
+    let payout = match order.tip_payout {
+        None => 0,
+        Some(CourierTipStatus::Pending) => 1,
+        Some(CourierTipStatus::Failed) => 2,
+        Some(CourierTipStatus::Paid) => 3,
+    };
+    (order.courier_tip_sats, order.courier_tip_paid_sats, payout)
+}
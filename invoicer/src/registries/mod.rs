@@ -1,8 +1,14 @@
 mod businesses;
 mod couriers;
 mod customers;
+mod deliveries;
 mod live_orders;
+mod promotions;
+mod stock;
 pub use businesses::*;
 pub use couriers::*;
 pub use customers::*;
+pub use deliveries::*;
 pub use live_orders::*;
+pub use promotions::*;
+pub use stock::*;
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+/// Promotions claimed by orders, counted from the moment their invoice is created.
+#[derive(Debug, Clone)]
+pub struct PromotionRedemptions {
+    orders: HashMap<String, PromotionRedemption>,
+}
+impl Default for PromotionRedemptions {
+    fn default() -> Self {
+        Self {
+            orders: HashMap::new(),
+        }
+    }
+}
+impl PromotionRedemptions {
+    /// Orders holding each promotion, by promotion id.
+    pub fn counts(&self) -> HashMap<String, u32> {
+        let mut counts = HashMap::new();
+        for redemption in self.orders.values() {
+            *counts.entry(redemption.promotion_id.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+    pub fn has_orders_from(&self, consumer: &str) -> bool {
+        self.orders
+            .values()
+            .any(|redemption| redemption.consumer == consumer)
+    }
+    pub fn redeem(&mut self, order_id: String, promotion_id: String, consumer: String) {
+        self.orders.insert(
+            order_id,
+            PromotionRedemption {
+                promotion_id,
+                consumer,
+            },
+        );
+    }
+    pub fn release(&mut self, order_id: &str) -> bool {
+        self.orders.remove(order_id).is_some()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PromotionRedemption {
+    promotion_id: String,
+    consumer: String,
+}
@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use fuente::models::ProductOrder;
+
+/// Units held by paid orders, counted against the stock the commerce published.
+///
+/// Live orders hold their units until they are canceled. Completed orders keep
+/// them counted as sold until the commerce publishes a new count.
+#[derive(Debug, Clone)]
+pub struct StockReservations {
+    orders: HashMap<String, StockReservation>,
+    /// When the stock counts read on startup were published, completed orders
+    /// from before then are already left out of them.
+    counted_since: HashMap<String, i64>,
+    /// Commerces whose remaining stock has to be published again.
+    changed: HashSet<String>,
+    published: HashMap<String, HashMap<String, u32>>,
+}
+impl Default for StockReservations {
+    fn default() -> Self {
+        Self {
+            orders: HashMap::new(),
+            counted_since: HashMap::new(),
+            changed: HashSet::new(),
+            published: HashMap::new(),
+        }
+    }
+}
+impl StockReservations {
+    /// Units taken per product id for the commerce.
+    pub fn sold(&self, commerce_id: &str) -> HashMap<String, u32> {
+        let mut sold = HashMap::new();
+        for reservation in self
+            .orders
+            .values()
+            .filter(|reservation| reservation.commerce == commerce_id)
+        {
+            for (product_id, units) in reservation.units.iter() {
+                *sold.entry(product_id.clone()).or_insert(0) += units;
+            }
+        }
+        sold
+    }
+    pub fn reserve(
+        &mut self,
+        order_id: String,
+        commerce_id: String,
+        order: &ProductOrder,
+        created_at: i64,
+    ) {
+        self.changed.insert(commerce_id.clone());
+        self.orders.insert(
+            order_id,
+            StockReservation {
+                commerce: commerce_id,
+                units: order.quantities(),
+                created_at,
+                completed: false,
+            },
+        );
+    }
+    /// Reserves the units of a paid order read back on startup, unless it already holds them.
+    pub fn hold(
+        &mut self,
+        order_id: String,
+        commerce_id: String,
+        order: &ProductOrder,
+        created_at: i64,
+    ) {
+        if !self.orders.contains_key(&order_id) {
+            self.reserve(order_id, commerce_id, order, created_at);
+        }
+    }
+    /// Keeps the units of a delivered order counted as sold, orders from before
+    /// the stock count was published are already taken out of it.
+    pub fn complete(
+        &mut self,
+        order_id: String,
+        commerce_id: String,
+        order: &ProductOrder,
+        created_at: i64,
+    ) {
+        if self
+            .counted_since
+            .get(&commerce_id)
+            .is_some_and(|since| created_at < *since)
+        {
+            self.orders.remove(&order_id);
+            return;
+        }
+        self.hold(order_id.clone(), commerce_id, order, created_at);
+        if let Some(reservation) = self.orders.get_mut(&order_id) {
+            reservation.completed = true;
+        }
+    }
+    /// Gives back the units of an order that won't be delivered.
+    pub fn release(&mut self, order_id: &str) -> bool {
+        if self
+            .orders
+            .get(order_id)
+            .is_some_and(|reservation| reservation.completed)
+        {
+            return false;
+        }
+        match self.orders.remove(order_id) {
+            Some(reservation) => {
+                self.changed.insert(reservation.commerce);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Sets when the stock counts of the commerce were published, dropping the
+    /// completed orders they already account for.
+    pub fn count_since(&mut self, commerce_id: &str, published_at: i64) {
+        self.counted_since
+            .insert(commerce_id.to_string(), published_at);
+        self.orders.retain(|_, reservation| {
+            reservation.commerce != commerce_id
+                || !reservation.completed
+                || reservation.created_at >= published_at
+        });
+        self.changed.insert(commerce_id.to_string());
+    }
+    /// Forgets the units sold of a product once the commerce publishes a new count for it.
+    pub fn restock(&mut self, commerce_id: &str, product_id: &str) {
+        for reservation in self
+            .orders
+            .values_mut()
+            .filter(|reservation| reservation.commerce == commerce_id)
+        {
+            reservation.units.remove(product_id);
+        }
+        self.orders
+            .retain(|_, reservation| !reservation.completed || !reservation.units.is_empty());
+        self.changed.insert(commerce_id.to_string());
+    }
+    /// Commerces whose stock changed since the last call.
+    pub fn take_changed(&mut self) -> Vec<String> {
+        self.changed.drain().collect()
+    }
+    /// False if `remaining` is what was last published for the commerce.
+    pub fn publish(&mut self, commerce_id: &str, remaining: &HashMap<String, u32>) -> bool {
+        if self.published.get(commerce_id) == Some(remaining) {
+            return false;
+        }
+        self.published
+            .insert(commerce_id.to_string(), remaining.clone());
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StockReservation {
+    commerce: String,
+    units: HashMap<String, u32>,
+    created_at: i64,
+    completed: bool,
+}
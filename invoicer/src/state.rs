@@ -1,9 +1,12 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
 use anyhow::anyhow;
 use fuente::models::{
-    AdminConfiguration, AdminConfigurationType, AdminServerRequest, CommerceProfile,
-    OrderInvoiceState, ProductMenu,
+    apply_promotions, AdminConfiguration, AdminConfigurationType, AdminServerRequest,
+    AppliedPromotion, CommerceProfile, CommerceStock, CoordinateStrings, DeliveryProgress,
+    EtaEstimator, ExchangeRates, OrderInvoiceState, OrderPaymentStatus, OrderRequest, OrderStatus,
+    PrepTime, ProductMenu, Promotion, PromotionContext, PromotionList, StraightLineRouter,
+    TEST_PUB_KEY,
 };
 use nostro2::{
     keypair::NostrKeypair,
@@ -12,9 +15,13 @@ use nostro2::{
 use tokio::sync::RwLock;
 use tracing::info;
 
-use crate::registries::{
-    CommerceRegistry, CommerceRegistryEntry, ConsumerRegistry, ConsumerRegistryEntry,
-    CourierRegistry, CourierRegistryEntry, LiveOrders,
+use crate::{
+    invoicer::unix_timestamp,
+    registries::{
+        CommerceRegistry, CommerceRegistryEntry, ConsumerRegistry, ConsumerRegistryEntry,
+        CourierRegistry, CourierRegistryEntry, DeliveryTracking, LiveOrders, PromotionRedemptions,
+        StockReservations,
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -24,6 +31,13 @@ struct InvoicerState {
     commerce_registries: CommerceRegistry,
     live_orders: LiveOrders,
     admin_config: AdminConfiguration,
+    promotion_redemptions: PromotionRedemptions,
+    /// Consumers with a paid order, who no longer get first order promotions.
+    returning_consumers: HashSet<String>,
+    stock_reservations: StockReservations,
+    scheduled_releases: HashSet<String>,
+    deliveries: DeliveryTracking,
+    eta_estimator: EtaEstimator<StraightLineRouter>,
 }
 impl InvoicerState {
     fn read_whitelist() -> Vec<String> {
@@ -44,6 +58,12 @@ impl InvoicerState {
             commerce_registries: CommerceRegistry::default(),
             live_orders: LiveOrders::default(),
             admin_config,
+            promotion_redemptions: PromotionRedemptions::default(),
+            returning_consumers: HashSet::new(),
+            stock_reservations: StockReservations::default(),
+            scheduled_releases: HashSet::new(),
+            deliveries: DeliveryTracking::default(),
+            eta_estimator: EtaEstimator::new(StraightLineRouter::default()),
         }
     }
 }
@@ -85,8 +105,8 @@ impl InvoicerStateLock {
             .is_ok()
             && profiles.consumer_profiles.is_registered(pubkey)
     }
-    pub async fn exchange_rate(&self) -> f64 {
-        self.lock().await.admin_config.get_exchange_rate()
+    pub async fn exchange_rates(&self) -> ExchangeRates {
+        self.lock().await.admin_config.get_exchange_rates()
     }
     pub async fn find_commerce(
         &self,
@@ -107,7 +127,7 @@ impl InvoicerStateLock {
             .as_ref()
             .ok_or(anyhow!("No menu found"))?;
         let commerce_profile = CommerceProfile::try_from(profile.clone())?;
-        let product_menu = ProductMenu::try_from(menu.clone())?;
+        let product_menu = menu.menu();
         Ok((commerce_profile, product_menu))
     }
     pub async fn add_consumer_profile(&self, profile: NostrNote) -> anyhow::Result<()> {
@@ -132,17 +152,182 @@ impl InvoicerStateLock {
         );
         Ok(())
     }
+    /// Takes in the menu index, one of its categories or a legacy single-event menu.
     pub async fn add_commerce_menu(&self, menu: NostrNote) -> anyhow::Result<()> {
+        let mut profiles = self.lock().await;
+        let commerce_id = menu.pubkey.clone();
+        let old_menu = Self::menu_for(&profiles, &commerce_id).ok();
+        let published_at = menu.created_at;
+        profiles
+            .commerce_registries
+            .update_menu(commerce_id.clone(), menu)?;
+        let new_menu = Self::menu_for(&profiles, &commerce_id)?;
+        match old_menu {
+            None => profiles
+                .stock_reservations
+                .count_since(&commerce_id, published_at),
+            Some(old_menu) => {
+                // A new stock count replaces whatever was sold against the old one
+                for product in new_menu.categories().iter().flat_map(|c| c.products()) {
+                    let restocked = old_menu
+                        .find_product(&product.id())
+                        .is_some_and(|old| old.stock() != product.stock());
+                    if restocked {
+                        profiles
+                            .stock_reservations
+                            .restock(&commerce_id, &product.id());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    pub async fn add_commerce_promotions(&self, promotions: NostrNote) -> anyhow::Result<()> {
         let mut profiles = self.lock().await;
         profiles.commerce_registries.update_record(
-            menu.pubkey.clone(),
+            promotions.pubkey.clone(),
             CommerceRegistryEntry {
-                menu: Some(menu),
+                promotions: Some(promotions),
                 ..Default::default()
             },
         );
         Ok(())
     }
+    /// Picks the promotion the order qualifies for, out of the commerce's own
+    /// promotions and the platform-wide ones set by the admins, and counts it
+    /// against its usage limit right away.
+    pub async fn claim_promotion(
+        &self,
+        order: &OrderRequest,
+        order_id: &str,
+        consumer: &str,
+        now: i64,
+    ) -> anyhow::Result<Option<AppliedPromotion>> {
+        let mut state = self.lock().await;
+        let mut offered: Vec<(String, Promotion)> = state
+            .admin_config
+            .get_promotions()
+            .into_iter()
+            .map(|promotion| (TEST_PUB_KEY.to_string(), promotion))
+            .collect();
+        if let Some(note) = state
+            .commerce_registries
+            .get_commerce(&order.commerce)
+            .and_then(|entry| entry.promotions.as_ref())
+        {
+            let list = PromotionList::try_from(note)?;
+            offered.extend(
+                list.promotions
+                    .into_iter()
+                    .map(|promotion| (order.commerce.clone(), promotion)),
+            );
+        }
+        let context = PromotionContext {
+            now,
+            is_first_order: !state.returning_consumers.contains(consumer)
+                && !state.live_orders.has_orders_from(consumer)
+                && !state.promotion_redemptions.has_orders_from(consumer),
+            redemptions: state.promotion_redemptions.counts(),
+            rates: state.admin_config.get_exchange_rates(),
+        };
+        // Participants of a group order pay their lines in full on invoices of their own
+        let promotion = apply_promotions(
+            &offered,
+            order.promo_code.as_deref(),
+            &order.host_products(),
+            &context,
+        )?;
+        if let Some(promotion) = &promotion {
+            state.promotion_redemptions.redeem(
+                order_id.to_string(),
+                promotion.promotion_id.clone(),
+                consumer.to_string(),
+            );
+        }
+        Ok(promotion)
+    }
+    /// Gives back the promotion of an order that won't be paid.
+    pub async fn release_promotion(&self, order_id: &str) {
+        if self.lock().await.promotion_redemptions.release(order_id) {
+            info!("Released promotion held by order {}", order_id);
+        }
+    }
+    fn menu_for(state: &InvoicerState, commerce_id: &str) -> anyhow::Result<ProductMenu> {
+        state
+            .commerce_registries
+            .get_commerce(commerce_id)
+            .and_then(|entry| entry.menu.as_ref())
+            .map(|menu| menu.menu())
+            .ok_or(anyhow!("No menu found"))
+    }
+    pub async fn check_stock(&self, order: &OrderRequest) -> anyhow::Result<()> {
+        let state = self.lock_owned().await;
+        let menu = Self::menu_for(&state, &order.commerce)?;
+        menu.check_stock(
+            &order.products,
+            &state.stock_reservations.sold(&order.commerce),
+        )
+    }
+    /// Fails when the order wasn't priced with the menu windows active at `timestamp`.
+    pub async fn check_prices(
+        &self,
+        order: &OrderRequest,
+        timestamp: i64,
+        utc_offset_minutes: i32,
+    ) -> anyhow::Result<()> {
+        let state = self.lock_owned().await;
+        let menu = Self::menu_for(&state, &order.commerce)?;
+        if menu.price_order(&order.products, timestamp, utc_offset_minutes)? != order.products {
+            return Err(anyhow!("Prices changed, please review your order"));
+        }
+        Ok(())
+    }
+    /// Holds the ordered units once the consumer has paid, fails if another
+    /// order took the last of them in the meantime.
+    pub async fn reserve_stock(&self, invoice_state: &OrderInvoiceState) -> anyhow::Result<()> {
+        let order = invoice_state.get_order_request();
+        let mut state = self.lock().await;
+        let menu = Self::menu_for(&state, &order.commerce)?;
+        menu.check_stock(
+            &order.products,
+            &state.stock_reservations.sold(&order.commerce),
+        )?;
+        state.stock_reservations.reserve(
+            invoice_state.order_id(),
+            order.commerce,
+            &order.products,
+            invoice_state.order.created_at,
+        );
+        Ok(())
+    }
+    pub async fn release_stock(&self, order_id: &str) {
+        if self.lock().await.stock_reservations.release(order_id) {
+            info!("Released stock held by order {}", order_id);
+        }
+    }
+    /// Signed notes with the stock left at every commerce whose stock changed
+    /// since the last call.
+    pub async fn stock_updates(&self, keys: &NostrKeypair) -> Vec<NostrNote> {
+        let mut state = self.lock().await;
+        let mut notes = vec![];
+        for commerce_id in state.stock_reservations.take_changed() {
+            let Ok(menu) = Self::menu_for(&state, &commerce_id) else {
+                continue;
+            };
+            let stock = CommerceStock::new(
+                commerce_id.clone(),
+                &menu,
+                &state.stock_reservations.sold(&commerce_id),
+            );
+            if state
+                .stock_reservations
+                .publish(&commerce_id, &stock.remaining)
+            {
+                notes.push(stock.signed_note(keys));
+            }
+        }
+        notes
+    }
     pub async fn add_courier_profile(&self, profile: NostrNote) -> anyhow::Result<()> {
         let mut profiles = self.lock().await;
         profiles.courier_profiles.insert_courier(
@@ -157,17 +342,195 @@ impl InvoicerStateLock {
     pub async fn update_live_order(&self, order: NostrNote) -> anyhow::Result<()> {
         let mut orders = self.lock().await;
         let invoice_state = OrderInvoiceState::try_from(order)?;
+        // Older states of a finished order may still be read back
+        let known = orders.live_orders.get_order(&invoice_state.order_id());
+        let finished_as = |status: OrderStatus| {
+            invoice_state.order_status == status
+                || known
+                    .as_ref()
+                    .is_some_and(|known| known.order_status == status)
+        };
+        let (canceled, completed) = (
+            finished_as(OrderStatus::Canceled),
+            finished_as(OrderStatus::Completed),
+        );
+        let paid = matches!(
+            invoice_state.payment_status,
+            OrderPaymentStatus::PaymentReceived | OrderPaymentStatus::PaymentSuccess
+        );
+        if canceled {
+            orders.stock_reservations.release(&invoice_state.order_id());
+        } else if paid {
+            // Order states read back on startup hold the stock they took
+            let request = invoice_state.get_order_request();
+            let (order_id, created_at) = (invoice_state.order_id(), invoice_state.order.created_at);
+            if completed {
+                orders.stock_reservations.complete(
+                    order_id,
+                    request.commerce,
+                    &request.products,
+                    created_at,
+                );
+            } else {
+                orders.stock_reservations.hold(
+                    order_id,
+                    request.commerce,
+                    &request.products,
+                    created_at,
+                );
+            }
+        }
+        if canceled {
+            orders
+                .promotion_redemptions
+                .release(&invoice_state.order_id());
+        } else if let Some(promotion) = &invoice_state.promotion {
+            // Order states read back on startup count the promotions they used
+            orders.promotion_redemptions.redeem(
+                invoice_state.order_id(),
+                promotion.promotion_id.clone(),
+                invoice_state.order.pubkey.clone(),
+            );
+        }
+        if paid {
+            orders
+                .returning_consumers
+                .insert(invoice_state.order.pubkey.clone());
+        }
+        orders.deliveries.update_status(
+            &invoice_state.order_id(),
+            invoice_state.order_status,
+            unix_timestamp(),
+        );
         orders
             .live_orders
             .update_order_record(invoice_state.order_id(), invoice_state)?;
         Ok(())
     }
+    /// False if the release of the order was already armed.
+    pub async fn arm_scheduled_release(&self, order_id: &str) -> bool {
+        self.lock()
+            .await
+            .scheduled_releases
+            .insert(order_id.to_string())
+    }
+    /// When a scheduled order goes out to its commerce, at the start of its
+    /// slot if the commerce profile isn't known yet.
+    pub async fn scheduled_release_time(&self, order: &OrderInvoiceState) -> Option<i64> {
+        let request = OrderRequest::try_from(&order.order).ok()?;
+        let slot = request.scheduled_for?;
+        let state = self.lock().await;
+        let release_at = state
+            .commerce_registries
+            .get_commerce(&request.commerce)
+            .and_then(|entry| entry.profile.clone())
+            .and_then(|profile| CommerceProfile::try_from(profile).ok())
+            .map(|commerce| commerce.availability.release_time(&slot))
+            .unwrap_or(slot.start);
+        Some(release_at)
+    }
     pub async fn remove_live_order(&self, order_id: &str) -> anyhow::Result<()> {
-        self.lock().await.live_orders.remove_order(order_id)
+        let mut state = self.lock().await;
+        state.promotion_redemptions.release(order_id);
+        state.deliveries.remove_delivery(order_id);
+        state.live_orders.remove_order(order_id)
     }
     pub async fn find_live_order(&self, order_id: &str) -> Option<OrderInvoiceState> {
         self.lock_owned().await.live_orders.get_order(order_id)
     }
+    /// Keeps the prep time the commerce gave, sent along once the payment settles.
+    pub async fn set_preparation(
+        &self,
+        order_id: &str,
+        preparation: PrepTime,
+    ) -> anyhow::Result<()> {
+        let mut state = self.lock().await;
+        let mut order = state
+            .live_orders
+            .get_order(order_id)
+            .ok_or(anyhow!("Order not found"))?;
+        order.preparation = Some(preparation);
+        state
+            .live_orders
+            .update_order_record(order_id.to_string(), order)
+    }
+    /// Adds a position to the track of the order the courier is delivering,
+    /// returning its id.
+    pub async fn record_courier_position(
+        &self,
+        courier: &str,
+        timestamp: i64,
+        position: CoordinateStrings,
+    ) -> Option<String> {
+        let mut state = self.lock().await;
+        let order_id = state.live_orders.find_courier_order(courier)?.order_id();
+        state
+            .deliveries
+            .record_position(&order_id, timestamp, position);
+        Some(order_id)
+    }
+    /// Estimates when the order reaches the consumer, returning the note to
+    /// send them when the estimate changed since the last one.
+    pub async fn delivery_eta_update(
+        &self,
+        order_id: &str,
+        keys: &NostrKeypair,
+    ) -> anyhow::Result<Option<NostrNote>> {
+        let mut state = self.lock().await;
+        let order = state
+            .live_orders
+            .get_order(order_id)
+            .ok_or(anyhow!("Order not found"))?;
+        let order_req = order.get_order_request();
+        let commerce = state
+            .commerce_registries
+            .get_commerce(&order_req.commerce)
+            .and_then(|entry| entry.profile.as_ref())
+            .ok_or(anyhow!("No profile found"))?;
+        let commerce = CommerceProfile::try_from(commerce.clone())?;
+        let destination = order_req.address.coordinates();
+        let delivery = state.deliveries.get_delivery(order_id);
+        let progress = DeliveryProgress {
+            status: order.order_status,
+            commerce: &commerce,
+            destination: &destination,
+            preparing_since: order
+                .preparation
+                .map(|prep| prep.started_at)
+                .or(delivery.and_then(|delivery| delivery.preparing_since)),
+            prep_minutes: order.preparation.map(|prep| prep.minutes),
+            courier: delivery.map(|delivery| &delivery.courier),
+        };
+        let Some(eta) = state
+            .eta_estimator
+            .estimate(order.order_id(), &progress, unix_timestamp())
+        else {
+            return Ok(None);
+        };
+        if !state.deliveries.publish(eta.clone()) {
+            return Ok(None);
+        }
+        Ok(Some(eta.giftwrapped_eta(keys, order.order.pubkey.clone())?))
+    }
+    /// Records a held payment towards a split group order. Returns the order
+    /// once the payment completes it, so exactly one caller releases it.
+    pub async fn record_group_payment(
+        &self,
+        order_id: &str,
+        pubkey: &str,
+    ) -> anyhow::Result<Option<OrderInvoiceState>> {
+        let mut state = self.lock().await;
+        let mut order = state
+            .live_orders
+            .get_order(order_id)
+            .ok_or(anyhow!("Order not found"))?;
+        let was_paid = order.group_paid();
+        order.record_group_payment(pubkey);
+        state
+            .live_orders
+            .update_order_record(order_id.to_string(), order.clone())?;
+        Ok((!was_paid && order.group_paid()).then_some(order))
+    }
     pub async fn sign_updated_config(
         &self,
         admin_note: NostrNote,
@@ -185,6 +548,11 @@ impl InvoicerStateLock {
                     .set_exchange_rate(admin_req.config_str.parse()?);
                 bot_state.admin_config.sign_exchange_rate(signing_keys)?
             }
+            AdminConfigurationType::ExchangeRates => {
+                let rates: ExchangeRates = serde_json::from_str(&admin_req.config_str)?;
+                bot_state.admin_config.set_exchange_rates(rates);
+                bot_state.admin_config.sign_exchange_rates(signing_keys)?
+            }
             AdminConfigurationType::CommerceWhitelist => {
                 let whitelist: Vec<String> = serde_json::from_str(&admin_req.config_str)?;
                 bot_state.admin_config.set_commerce_whitelist(whitelist);
@@ -199,6 +567,11 @@ impl InvoicerStateLock {
                     .admin_config
                     .sign_couriers_whitelist(signing_keys)?
             }
+            AdminConfigurationType::Promotions => {
+                let promotions: Vec<Promotion> = serde_json::from_str(&admin_req.config_str)?;
+                bot_state.admin_config.set_promotions(promotions);
+                bot_state.admin_config.sign_promotions(signing_keys)?
+            }
             _ => return Err(anyhow!("Invalid config type")),
         };
         Ok(update)
@@ -231,6 +604,11 @@ impl InvoicerStateLock {
                 bot_state.admin_config.set_exchange_rate(rate);
                 info!("Exchange rate set to: {}", rate);
             }
+            AdminConfigurationType::ExchangeRates => {
+                let rates: ExchangeRates = serde_json::from_str(&new_config.content)?;
+                info!("Exchange rates set to: {:?}", rates.rates());
+                bot_state.admin_config.set_exchange_rates(rates);
+            }
             AdminConfigurationType::ConsumerBlacklist => {
                 let blacklist: Vec<String> = serde_json::from_str(&decrypted.unwrap())?;
                 bot_state.admin_config.set_consumer_blacklist(blacklist);
@@ -243,6 +621,10 @@ impl InvoicerStateLock {
                 let whitelist: Vec<String> = serde_json::from_str(&decrypted.unwrap())?;
                 bot_state.admin_config.set_admin_whitelist(whitelist);
             }
+            AdminConfigurationType::Promotions => {
+                let promotions: Vec<Promotion> = serde_json::from_str(&new_config.content)?;
+                bot_state.admin_config.set_promotions(promotions);
+            }
         }
         Ok(())
     }